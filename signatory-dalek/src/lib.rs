@@ -1,4 +1,4 @@
-//! Signatory Ed25519 provider for the [ed25519-dalek] crate.
+//! Signatory Ed25519 and X25519 provider for the [ed25519-dalek] crate.
 //!
 //! For a usage example, see the toplevel Signatory docs:
 //! <https://docs.rs/signatory/latest/signatory/ed25519/index.html>
@@ -13,16 +13,27 @@
     html_root_url = "https://docs.rs/signatory-dalek/0.16.0"
 )]
 
+#[cfg(feature = "batch")]
+extern crate alloc;
+
 #[cfg(test)]
 #[macro_use]
 extern crate signatory;
 
-use sha2::Sha512;
+mod x25519;
+
+pub use self::x25519::X25519Secret;
+
+#[cfg(feature = "batch")]
+use alloc::vec::Vec;
+use curve25519_dalek::{constants::ED25519_BASEPOINT_TABLE, scalar::Scalar};
+use sha2::{Digest, Sha512};
 use signatory::{
-    ed25519,
+    ed25519::{self, ContextVerifier as _},
     public_key::PublicKeyed,
     signature::{DigestSigner, DigestVerifier, Error, Signature, Signer, Verifier},
 };
+use zeroize::Zeroize;
 
 /// Ed25519 signature provider for ed25519-dalek
 pub struct Ed25519Signer(ed25519_dalek::Keypair);
@@ -47,49 +58,224 @@ impl Signer<ed25519::Signature> for Ed25519Signer {
     }
 }
 
-// TODO: tests!
+/// Ed25519ph with an empty context string
 impl DigestSigner<Sha512, ed25519::Signature> for Ed25519Signer {
     fn try_sign_digest(&self, digest: Sha512) -> Result<ed25519::Signature, Error> {
-        // TODO: context support
-        let context: Option<&'static [u8]> = None;
+        let signature = self.0.sign_prehashed(digest, None).to_bytes();
+        Ok(ed25519::Signature::from_bytes(&signature[..]).unwrap())
+    }
+}
 
-        let signature =
-            ed25519::Signature::from_bytes(&self.0.sign_prehashed(digest, context).to_bytes()[..])
-                .unwrap();
+impl ed25519::ContextSigner for Ed25519Signer {
+    fn try_sign_ctx(
+        &self,
+        context: ed25519::Context<'_>,
+        msg: &[u8],
+    ) -> Result<ed25519::Signature, Error> {
+        Ok(self.sign_with_context(context, false, msg))
+    }
 
-        Ok(signature)
+    fn try_sign_ph(
+        &self,
+        context: ed25519::Context<'_>,
+        digest: Sha512,
+    ) -> Result<ed25519::Signature, Error> {
+        Ok(self.sign_with_context(context, true, &digest.result()))
+    }
+}
+
+impl Ed25519Signer {
+    /// Sign a message (or digest) with Ed25519ctx (`prehashed = false`) or
+    /// Ed25519ph (`prehashed = true`). ed25519-dalek only implements Ed25519ph
+    /// with `'static` contexts.
+    ///
+    /// The secret scalar, the nonce and its hash are zeroized once signed.
+    /// Hashers don't implement `Zeroize`, so each hasher is created from the
+    /// context rather than cloned, as ed25519-dalek's signing does.
+    fn sign_with_context(
+        &self,
+        context: ed25519::Context<'_>,
+        prehashed: bool,
+        msg: &[u8],
+    ) -> ed25519::Signature {
+        let mut expanded_secret = ed25519_dalek::ExpandedSecretKey::from(&self.0.secret).to_bytes();
+
+        let mut scalar_bytes = [0u8; 32];
+        scalar_bytes.copy_from_slice(&expanded_secret[..32]);
+        let mut secret_scalar = Scalar::from_bits(scalar_bytes);
+
+        let mut nonce_hash = context
+            .hasher(prehashed)
+            .chain(&expanded_secret[32..])
+            .chain(msg)
+            .result();
+        let mut nonce_bytes = [0u8; 64];
+        nonce_bytes.copy_from_slice(&nonce_hash);
+        let mut r = Scalar::from_bytes_mod_order_wide(&nonce_bytes);
+
+        let big_r = (&r * &ED25519_BASEPOINT_TABLE).compress();
+        let k = Scalar::from_hash(
+            context
+                .hasher(prehashed)
+                .chain(big_r.as_bytes())
+                .chain(self.0.public.as_bytes())
+                .chain(msg),
+        );
+        let s = &(&k * &secret_scalar) + &r;
+
+        expanded_secret.zeroize();
+        scalar_bytes.zeroize();
+        secret_scalar.zeroize();
+        nonce_hash.as_mut_slice().zeroize();
+        nonce_bytes.zeroize();
+        r.zeroize();
+
+        let mut signature = [0u8; 64];
+        signature[..32].copy_from_slice(big_r.as_bytes());
+        signature[32..].copy_from_slice(s.as_bytes());
+        ed25519::Signature::from_bytes(&signature[..]).unwrap()
     }
 }
 
 /// Ed25519 verifier provider for ed25519-dalek
 #[derive(Clone, Debug, Eq, PartialEq)]
-pub struct Ed25519Verifier(ed25519_dalek::PublicKey);
+pub struct Ed25519Verifier(ed25519_dalek::PublicKey, Option<ed25519::VerificationMode>);
+
+impl Ed25519Verifier {
+    /// Create a verifier which verifies signatures in the given mode
+    pub fn with_mode(public_key: &ed25519::PublicKey, mode: ed25519::VerificationMode) -> Self {
+        Ed25519Verifier(
+            ed25519_dalek::PublicKey::from_bytes(public_key.as_ref()).unwrap(),
+            Some(mode),
+        )
+    }
+
+    /// Mode of Ed25519ctx and Ed25519ph verification, which ed25519-dalek's
+    /// own rules don't cover
+    fn context_mode(&self) -> ed25519::VerificationMode {
+        self.1.unwrap_or(ed25519::VerificationMode::Rfc8032)
+    }
+}
 
 impl<'a> From<&'a ed25519::PublicKey> for Ed25519Verifier {
+    /// Create a verifier with ed25519-dalek's own (non-strict) rules
     fn from(public_key: &'a ed25519::PublicKey) -> Self {
-        Ed25519Verifier(ed25519_dalek::PublicKey::from_bytes(public_key.as_ref()).unwrap())
+        Ed25519Verifier(
+            ed25519_dalek::PublicKey::from_bytes(public_key.as_ref()).unwrap(),
+            None,
+        )
     }
 }
 
 impl Verifier<ed25519::Signature> for Ed25519Verifier {
     fn verify(&self, msg: &[u8], sig: &ed25519::Signature) -> Result<(), Error> {
+        if let Some(mode) = self.1 {
+            let public_key = ed25519::PublicKey::new(*self.0.as_bytes());
+            mode.check_encodings(&public_key, sig)?;
+            if mode.is_cofactored() {
+                return ed25519::verify_cofactored(&public_key, msg, sig);
+            }
+        }
+
         let dalek_sig = ed25519_dalek::Signature::from_bytes(sig.as_ref()).unwrap();
         self.0.verify(msg, &dalek_sig).map_err(|_| Error::new())
     }
 }
 
-// TODO: tests!
+/// Ed25519ph with an empty context string, with ed25519-dalek's own rules
+/// for verifiers created `From` a public key
 impl DigestVerifier<Sha512, ed25519::Signature> for Ed25519Verifier {
     fn verify_digest(&self, digest: Sha512, sig: &ed25519::Signature) -> Result<(), Error> {
-        // TODO: context support
-        let context: Option<&'static [u8]> = None;
+        if self.1.is_some() {
+            return self.verify_ph(ed25519::Context::default(), digest, sig);
+        }
+
         let dalek_sig = ed25519_dalek::Signature::from_bytes(sig.as_ref()).unwrap();
         self.0
-            .verify_prehashed(digest, context, &dalek_sig)
+            .verify_prehashed(digest, None, &dalek_sig)
             .map_err(|_| Error::new())
     }
 }
 
+/// Ed25519ctx and Ed25519ph are verified by Signatory itself, in the
+/// verifier's mode (RFC 8032 for verifiers created `From` a public key)
+impl ed25519::ContextVerifier for Ed25519Verifier {
+    fn verify_ctx(
+        &self,
+        context: ed25519::Context<'_>,
+        msg: &[u8],
+        sig: &ed25519::Signature,
+    ) -> Result<(), Error> {
+        let public_key = ed25519::PublicKey::new(*self.0.as_bytes());
+        self.context_mode()
+            .verify_ctx(&public_key, context, msg, sig)
+    }
+
+    fn verify_ph(
+        &self,
+        context: ed25519::Context<'_>,
+        digest: Sha512,
+        sig: &ed25519::Signature,
+    ) -> Result<(), Error> {
+        let public_key = ed25519::PublicKey::new(*self.0.as_bytes());
+        self.context_mode()
+            .verify_ph(&public_key, context, digest, sig)
+    }
+}
+
+/// Batch verification with ed25519-dalek's `verify_batch`, which checks the
+/// cofactored equation: it is only used in the cofactored modes, once the
+/// encodings of every item are checked. Signatures are verified one at a
+/// time otherwise, and to find the invalid ones if the batch fails.
+#[cfg(feature = "batch")]
+impl ed25519::BatchVerifier for Ed25519Verifier {
+    /// ed25519-dalek's own rules are cofactorless, so its batch verification
+    /// isn't used
+    fn verify_batch(items: &[ed25519::BatchItem<'_>]) -> Result<(), ed25519::BatchError> {
+        ed25519::verify_batch_sequentially::<Self>(items)
+    }
+
+    fn verify_batch_with_mode(
+        mode: ed25519::VerificationMode,
+        items: &[ed25519::BatchItem<'_>],
+    ) -> Result<(), ed25519::BatchError> {
+        let with_mode = |public_key: &ed25519::PublicKey| Self::with_mode(public_key, mode);
+        if !mode.is_cofactored()
+            || items.iter().any(|(public_key, _, signature)| {
+                mode.check_encodings(public_key, signature).is_err()
+            })
+        {
+            return ed25519::verify_batch_with(items, with_mode);
+        }
+
+        let mut messages = Vec::with_capacity(items.len());
+        let mut signatures = Vec::with_capacity(items.len());
+        let mut public_keys = Vec::with_capacity(items.len());
+
+        for (public_key, msg, signature) in items {
+            match (
+                ed25519_dalek::PublicKey::from_bytes(public_key.as_ref()),
+                ed25519_dalek::Signature::from_bytes(signature.as_ref()),
+            ) {
+                (Ok(public_key), Ok(signature)) => {
+                    messages.push(*msg);
+                    signatures.push(signature);
+                    public_keys.push(public_key);
+                }
+                _ => break,
+            }
+        }
+
+        if public_keys.len() == items.len()
+            && ed25519_dalek::verify_batch(&messages, &signatures, &public_keys).is_ok()
+        {
+            return Ok(());
+        }
+
+        ed25519::verify_batch_with(items, with_mode)
+    }
+}
+
 /// Convert a Signatory seed into a Dalek keypair
 fn keypair_from_seed(seed: &ed25519::Seed) -> ed25519_dalek::Keypair {
     let secret = ed25519_dalek::SecretKey::from_bytes(seed.as_secret_slice()).unwrap();
@@ -101,4 +287,38 @@ fn keypair_from_seed(seed: &ed25519::Seed) -> ed25519_dalek::Keypair {
 mod tests {
     use super::{Ed25519Signer, Ed25519Verifier};
     ed25519_tests!(Ed25519Signer, Ed25519Verifier);
+    ed25519_context_signer_tests!(Ed25519Signer);
+    ed25519_context_verifier_tests!(Ed25519Verifier);
+    ed25519_slip10_tests!(Ed25519Signer);
+    ed25519_verification_mode_tests!(Ed25519Verifier);
+
+    #[test]
+    fn digest_signer_is_ed25519ph() {
+        use sha2::{Digest, Sha512};
+        use signatory::signature::{DigestSigner, DigestVerifier};
+
+        let vector = TEST_VECTORS
+            .iter()
+            .find(|vector| vector.alg == TestVectorAlgorithm::Ed25519ph)
+            .unwrap();
+        let signer = Ed25519Signer::from(&ed25519::Seed::from_bytes(vector.sk).unwrap());
+        let sig = signer.sign_digest(Sha512::new().chain(vector.msg));
+        assert_eq!(sig.as_ref(), vector.sig);
+
+        let public_key = ed25519::PublicKey::from_bytes(vector.pk).unwrap();
+        let verifier = Ed25519Verifier::from(&public_key);
+        assert!(verifier
+            .verify_digest(Sha512::new().chain(vector.msg), &sig)
+            .is_ok());
+        let verifier = Ed25519Verifier::with_mode(&public_key, ed25519::VerificationMode::Strict);
+        assert!(verifier
+            .verify_digest(Sha512::new().chain(vector.msg), &sig)
+            .is_ok());
+        assert!(verifier
+            .verify_digest(Sha512::new().chain(b"other message"), &sig)
+            .is_err());
+    }
+
+    #[cfg(feature = "batch")]
+    ed25519_batch_tests!(Ed25519Verifier);
 }
@@ -0,0 +1,86 @@
+//! X25519 provider for curve25519-dalek
+
+use curve25519_dalek::{
+    constants::ED25519_BASEPOINT_TABLE, edwards::CompressedEdwardsY, montgomery::MontgomeryPoint,
+    scalar::Scalar,
+};
+use signatory::{ed25519, public_key::PublicKeyed, signature::Error, x25519};
+use zeroize::Zeroize;
+
+/// X25519 secret key provider for curve25519-dalek
+pub struct X25519Secret([u8; x25519::SECRET_KEY_SIZE]);
+
+impl<'a> From<&'a x25519::SecretKey> for X25519Secret {
+    /// Create a new X25519Secret from a secret key, which is clamped
+    fn from(secret_key: &'a x25519::SecretKey) -> Self {
+        let mut bytes = [0u8; x25519::SECRET_KEY_SIZE];
+        bytes.copy_from_slice(secret_key.as_secret_slice());
+        bytes[0] &= 248;
+        bytes[31] &= 127;
+        bytes[31] |= 64;
+        X25519Secret(bytes)
+    }
+}
+
+impl<'a> From<&'a ed25519::Seed> for X25519Secret {
+    /// Create a new X25519Secret from an Ed25519 seed
+    fn from(seed: &'a ed25519::Seed) -> Self {
+        Self::from(&x25519::SecretKey::from_ed25519_seed(seed))
+    }
+}
+
+impl PublicKeyed<x25519::PublicKey> for X25519Secret {
+    fn public_key(&self) -> Result<x25519::PublicKey, Error> {
+        let point = &Scalar::from_bits(self.0) * &ED25519_BASEPOINT_TABLE;
+        Ok(x25519::PublicKey::new(point.to_montgomery().to_bytes()))
+    }
+}
+
+impl x25519::DiffieHellman for X25519Secret {
+    fn diffie_hellman(
+        &self,
+        public_key: &x25519::PublicKey,
+    ) -> Result<x25519::SharedSecret, Error> {
+        let shared_point = &MontgomeryPoint(*public_key.as_bytes()) * &Scalar::from_bits(self.0);
+
+        // Reject all-zero outputs, as libsodium does
+        if shared_point
+            .as_bytes()
+            .iter()
+            .fold(0, |acc, byte| acc | byte)
+            == 0
+        {
+            return Err(Error::new());
+        }
+
+        Ok(x25519::SharedSecret::new(shared_point.to_bytes()))
+    }
+}
+
+impl x25519::Ed25519PublicKeyConverter for X25519Secret {
+    /// Convert an Ed25519 public key, rejecting the same points as libsodium's
+    /// `crypto_sign_ed25519_pk_to_curve25519`
+    fn convert_public_key(public_key: &ed25519::PublicKey) -> Result<x25519::PublicKey, Error> {
+        let point = CompressedEdwardsY(*public_key.as_bytes())
+            .decompress()
+            .ok_or_else(Error::new)?;
+
+        if point.is_small_order() || !point.is_torsion_free() {
+            return Err(Error::new());
+        }
+
+        Ok(x25519::PublicKey::new(point.to_montgomery().to_bytes()))
+    }
+}
+
+impl Drop for X25519Secret {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::X25519Secret;
+    x25519_tests!(X25519Secret);
+}
@@ -0,0 +1,497 @@
+//! Ledger Cosmos app provider: ECDSA/secp256k1 signatures over the
+//! canonical sign-JSON documents used by Cosmos SDK transactions.
+//!
+//! The app hashes the JSON it is given with SHA-256 before signing, so
+//! messages passed to `Signer::try_sign` must be the (sorted, compact) JSON
+//! sign bytes rather than a digest.
+
+mod path;
+mod transport;
+
+pub use self::{
+    path::{Bip44Path, InvalidPath, COSMOS_COIN_TYPE},
+    transport::{ApduAnswer, ApduCommand, RetcodeError, Transport, RETCODE_OK},
+};
+
+use signatory::{
+    ecdsa::{
+        curve::secp256k1::{FixedSignature, Secp256k1},
+        LowS, PublicKey,
+    },
+    public_key::PublicKeyed,
+    signature::{Error, Signature, Signer},
+};
+use std::sync::Mutex;
+
+/// APDU class of the Cosmos app
+const CLA: u8 = 0x55;
+
+/// Instruction: get app version
+const INS_GET_VERSION: u8 = 0x00;
+
+/// Instruction: sign a JSON message with a secp256k1 key
+const INS_SIGN_SECP256K1: u8 = 0x02;
+
+/// Instruction: get secp256k1 public key and bech32 address
+const INS_GET_ADDR_SECP256K1: u8 = 0x04;
+
+/// P1 value marking the first chunk of a payload (the derivation path)
+const PAYLOAD_INIT: u8 = 0x00;
+
+/// P1 value marking an intermediate chunk of a payload
+const PAYLOAD_ADD: u8 = 0x01;
+
+/// P1 value marking the last chunk of a payload
+const PAYLOAD_LAST: u8 = 0x02;
+
+/// Maximum amount of message data sent in a single APDU
+const CHUNK_SIZE: usize = 250;
+
+/// Bech32 human readable part sent when requesting the public key
+/// (only affects the address the device computes alongside it)
+const DEFAULT_HRP: &str = "cosmos";
+
+/// Size of a compressed secp256k1 public key
+const COMPRESSED_PUBLIC_KEY_SIZE: usize = 33;
+
+/// Size of a secp256k1 scalar
+const SCALAR_SIZE: usize = 32;
+
+/// Version of the Cosmos app running on the device
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Version {
+    /// Whether the app is running in test mode
+    pub test_mode: bool,
+
+    /// Major version
+    pub major: u8,
+
+    /// Minor version
+    pub minor: u8,
+
+    /// Patch version
+    pub patch: u8,
+}
+
+/// ECDSA/secp256k1 signature provider for the Ledger Cosmos app
+pub struct Secp256k1LedgerCosmosSigner<T: Transport = ledger::LedgerApp> {
+    /// Transport used to talk to the device
+    transport: Mutex<T>,
+
+    /// BIP44 derivation path of the key used by this signer
+    path: Bip44Path,
+}
+
+impl Secp256k1LedgerCosmosSigner<ledger::LedgerApp> {
+    /// Connect to a Ledger device running the Cosmos app over USB, using
+    /// the key at the given derivation path
+    pub fn connect(path: Bip44Path) -> Result<Self, Error> {
+        let app = ledger::LedgerApp::new().map_err(Error::from_source)?;
+        Ok(Self::with_transport(app, path))
+    }
+}
+
+impl<T: Transport> Secp256k1LedgerCosmosSigner<T> {
+    /// Create a signer which uses the given APDU transport
+    pub fn with_transport(transport: T, path: Bip44Path) -> Self {
+        Self {
+            transport: Mutex::new(transport),
+            path,
+        }
+    }
+
+    /// Get the BIP44 derivation path of the key used by this signer
+    pub fn path(&self) -> Bip44Path {
+        self.path
+    }
+
+    /// Query the version of the Cosmos app running on the device
+    pub fn version(&self) -> Result<Version, Error> {
+        let response = self.exchange(INS_GET_VERSION, 0, Vec::new())?;
+
+        if response.len() < 4 {
+            return Err(Error::new());
+        }
+
+        Ok(Version {
+            test_mode: response[0] != 0,
+            major: response[1],
+            minor: response[2],
+            patch: response[3],
+        })
+    }
+
+    /// Get the public key and bech32 address (with the given human readable
+    /// part) for this signer's derivation path
+    pub fn public_key_and_address(&self, hrp: &str) -> Result<(Vec<u8>, String), Error> {
+        if hrp.is_empty() || hrp.len() > 83 {
+            return Err(Error::new());
+        }
+
+        let mut data = Vec::with_capacity(1 + hrp.len() + 20);
+        data.push(hrp.len() as u8);
+        data.extend_from_slice(hrp.as_bytes());
+        data.extend_from_slice(&self.path.to_bytes());
+
+        let response = self.exchange(INS_GET_ADDR_SECP256K1, 0, data)?;
+
+        if response.len() < COMPRESSED_PUBLIC_KEY_SIZE {
+            return Err(Error::new());
+        }
+
+        let (public_key, address) = response.split_at(COMPRESSED_PUBLIC_KEY_SIZE);
+        let address = String::from_utf8(address.to_vec()).map_err(Error::from_source)?;
+        Ok((public_key.to_vec(), address))
+    }
+
+    /// Ask the device to sign the given JSON message, returning an ASN.1
+    /// DER-encoded signature.
+    ///
+    /// The message is streamed to the device in chunks: the first APDU
+    /// carries the derivation path, and the remaining ones the message.
+    fn sign_der(&self, msg: &[u8]) -> Result<Vec<u8>, Error> {
+        if msg.is_empty() {
+            return Err(Error::new());
+        }
+
+        let transport = self.transport.lock().unwrap();
+        transport::exchange(
+            &*transport,
+            command(INS_SIGN_SECP256K1, PAYLOAD_INIT, self.path.to_bytes()),
+        )?;
+
+        let chunks = msg.chunks(CHUNK_SIZE);
+        let last_index = chunks.len() - 1;
+        let mut response = Vec::new();
+
+        for (i, chunk) in chunks.enumerate() {
+            let p1 = if i == last_index {
+                PAYLOAD_LAST
+            } else {
+                PAYLOAD_ADD
+            };
+
+            response =
+                transport::exchange(&*transport, command(INS_SIGN_SECP256K1, p1, chunk.to_vec()))?;
+        }
+
+        Ok(response)
+    }
+
+    /// Exchange a single APDU with the device
+    fn exchange(&self, ins: u8, p1: u8, data: Vec<u8>) -> Result<Vec<u8>, Error> {
+        let transport = self.transport.lock().unwrap();
+        transport::exchange(&*transport, command(ins, p1, data))
+    }
+}
+
+impl<T: Transport> PublicKeyed<PublicKey<Secp256k1>> for Secp256k1LedgerCosmosSigner<T> {
+    /// Returns the public key for this signer's derivation path
+    fn public_key(&self) -> Result<PublicKey<Secp256k1>, Error> {
+        let (public_key, _) = self.public_key_and_address(DEFAULT_HRP)?;
+        PublicKey::from_bytes(&public_key).ok_or_else(Error::new)
+    }
+}
+
+impl<T: Transport> Signer<FixedSignature> for Secp256k1LedgerCosmosSigner<T> {
+    /// Compute a compact, fixed-sized signature of the given sign-JSON
+    /// message, normalized to low-S as Cosmos requires
+    fn try_sign(&self, msg: &[u8]) -> Result<FixedSignature, Error> {
+        let der_signature = self.sign_der(msg)?;
+        let mut fixed_signature = [0u8; SCALAR_SIZE * 2];
+        der_to_fixed(&der_signature, &mut fixed_signature)?;
+        Ok(FixedSignature::from_bytes(&fixed_signature[..])?.normalize_s())
+    }
+}
+
+/// Build a command APDU for the Cosmos app
+fn command(ins: u8, p1: u8, data: Vec<u8>) -> ApduCommand {
+    ApduCommand {
+        cla: CLA,
+        ins,
+        p1,
+        p2: 0,
+        length: data.len() as u8,
+        data,
+    }
+}
+
+/// Convert an ASN.1 DER-encoded ECDSA signature (as returned by the device)
+/// into the fixed-sized `r || s` form
+fn der_to_fixed(der: &[u8], out: &mut [u8; SCALAR_SIZE * 2]) -> Result<(), Error> {
+    // SEQUENCE { INTEGER r, INTEGER s }
+    if der.len() < 8 || der[0] != 0x30 || usize::from(der[1]) != der.len() - 2 {
+        return Err(Error::new());
+    }
+
+    let (r, rest) = der_integer(&der[2..])?;
+    let (s, rest) = der_integer(rest)?;
+
+    if !rest.is_empty() {
+        return Err(Error::new());
+    }
+
+    out[SCALAR_SIZE - r.len()..SCALAR_SIZE].copy_from_slice(r);
+    out[SCALAR_SIZE * 2 - s.len()..].copy_from_slice(s);
+    Ok(())
+}
+
+/// Parse a DER INTEGER holding a positive ECDSA scalar, returning its value
+/// without the sign padding, along with the remaining input.
+///
+/// Only the minimal encoding is accepted: negative values, zero and
+/// redundant leading zero bytes are rejected.
+fn der_integer(input: &[u8]) -> Result<(&[u8], &[u8]), Error> {
+    if input.len() < 2 || input[0] != 0x02 {
+        return Err(Error::new());
+    }
+
+    let len = usize::from(input[1]);
+
+    if len == 0 || input.len() < 2 + len {
+        return Err(Error::new());
+    }
+
+    let (mut value, rest) = input[2..].split_at(len);
+
+    // a set high bit means a negative integer
+    if value[0] & 0x80 != 0 {
+        return Err(Error::new());
+    }
+
+    // a leading zero byte is only allowed to clear the sign of the next one
+    if value[0] == 0 {
+        if value.len() == 1 || value[1] & 0x80 == 0 {
+            return Err(Error::new());
+        }
+        value = &value[1..];
+    }
+
+    if value.len() > SCALAR_SIZE {
+        return Err(Error::new());
+    }
+
+    Ok((value, rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    /// Compressed encoding of the secp256k1 generator point
+    const PUBLIC_KEY: &[u8] =
+        b"\x02\x79\xBE\x66\x7E\xF9\xDC\xBB\xAC\x55\xA0\x62\x95\xCE\x87\x0B\x07\
+          \x02\x9B\xFC\xDB\x2D\xCE\x28\xD9\x59\xF2\x81\x5B\x16\xF8\x17\x98";
+
+    /// Mock transport which records commands and replays canned answers
+    struct MockTransport {
+        commands: Mutex<Vec<(u8, u8, u8, u8, Vec<u8>)>>,
+        answers: Mutex<VecDeque<(Vec<u8>, u16)>>,
+    }
+
+    impl MockTransport {
+        fn new(answers: Vec<(Vec<u8>, u16)>) -> Self {
+            Self {
+                commands: Mutex::new(vec![]),
+                answers: Mutex::new(answers.into_iter().collect()),
+            }
+        }
+    }
+
+    impl Transport for MockTransport {
+        fn exchange(&self, command: ApduCommand) -> Result<ApduAnswer, Error> {
+            assert_eq!(command.length as usize, command.data.len());
+            self.commands.lock().unwrap().push((
+                command.cla,
+                command.ins,
+                command.p1,
+                command.p2,
+                command.data,
+            ));
+
+            let (data, retcode) = self
+                .answers
+                .lock()
+                .unwrap()
+                .pop_front()
+                .expect("unexpected APDU");
+
+            Ok(ApduAnswer { data, retcode })
+        }
+    }
+
+    fn recorded_commands(
+        signer: Secp256k1LedgerCosmosSigner<MockTransport>,
+    ) -> Vec<(u8, u8, u8, u8, Vec<u8>)> {
+        let transport = signer.transport.into_inner().unwrap();
+        assert!(transport.answers.lock().unwrap().is_empty());
+        transport.commands.into_inner().unwrap()
+    }
+
+    #[test]
+    fn get_version() {
+        let transport = MockTransport::new(vec![(vec![0, 2, 1, 0], RETCODE_OK)]);
+        let signer = Secp256k1LedgerCosmosSigner::with_transport(transport, Bip44Path::default());
+
+        let version = signer.version().unwrap();
+        assert_eq!(
+            version,
+            Version {
+                test_mode: false,
+                major: 2,
+                minor: 1,
+                patch: 0
+            }
+        );
+    }
+
+    #[test]
+    fn get_public_key() {
+        let mut answer = PUBLIC_KEY.to_vec();
+        answer.extend_from_slice(b"cosmos1w34k53py5v5xyluazqpq65agyajavep2rflq6h");
+
+        let transport = MockTransport::new(vec![(answer, RETCODE_OK)]);
+        let path = Bip44Path::cosmos(0, 1);
+        let signer = Secp256k1LedgerCosmosSigner::with_transport(transport, path);
+
+        let public_key = signer.public_key().unwrap();
+        assert_eq!(public_key.as_bytes(), PUBLIC_KEY);
+
+        let commands = recorded_commands(signer);
+        let mut expected_data = vec![6];
+        expected_data.extend_from_slice(b"cosmos");
+        expected_data.extend_from_slice(&path.to_bytes());
+        assert_eq!(
+            commands,
+            vec![(CLA, INS_GET_ADDR_SECP256K1, 0, 0, expected_data)]
+        );
+    }
+
+    #[test]
+    fn sign_chunked_message() {
+        let msg = vec![b'x'; CHUNK_SIZE + 50];
+
+        // DER signature with a 33-byte (zero-prefixed) `r` and 31-byte `s`
+        let mut der_signature = vec![0x30, 0x44, 0x02, 0x21, 0x00];
+        der_signature.extend_from_slice(&[0x81; 32]);
+        der_signature.extend_from_slice(&[0x02, 0x1f]);
+        der_signature.extend_from_slice(&[0x42; 31]);
+
+        let transport = MockTransport::new(vec![
+            (vec![], RETCODE_OK),
+            (vec![], RETCODE_OK),
+            (der_signature, RETCODE_OK),
+        ]);
+
+        let signer = Secp256k1LedgerCosmosSigner::with_transport(transport, Bip44Path::default());
+        let signature: FixedSignature = signer.sign(&msg);
+
+        let mut expected_signature = [0x81; 64];
+        expected_signature[32] = 0;
+        for byte in &mut expected_signature[33..] {
+            *byte = 0x42;
+        }
+        assert_eq!(signature.as_ref(), &expected_signature[..]);
+
+        let commands = recorded_commands(signer);
+        assert_eq!(
+            commands,
+            vec![
+                (
+                    CLA,
+                    INS_SIGN_SECP256K1,
+                    PAYLOAD_INIT,
+                    0,
+                    Bip44Path::default().to_bytes()
+                ),
+                (
+                    CLA,
+                    INS_SIGN_SECP256K1,
+                    PAYLOAD_ADD,
+                    0,
+                    msg[..CHUNK_SIZE].to_vec()
+                ),
+                (
+                    CLA,
+                    INS_SIGN_SECP256K1,
+                    PAYLOAD_LAST,
+                    0,
+                    msg[CHUNK_SIZE..].to_vec()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn sign_normalizes_high_s() {
+        // DER signature with `r = 1` and `s = n - 1`
+        let mut der_signature = vec![0x30, 0x26, 0x02, 0x01, 0x01, 0x02, 0x21, 0x00];
+        der_signature.extend_from_slice(
+            b"\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFE\
+              \xBA\xAE\xDC\xE6\xAF\x48\xA0\x3B\xBF\xD2\x5E\x8C\xD0\x36\x41\x40",
+        );
+
+        let transport = MockTransport::new(vec![(vec![], RETCODE_OK), (der_signature, RETCODE_OK)]);
+
+        let signer = Secp256k1LedgerCosmosSigner::with_transport(transport, Bip44Path::default());
+        let signature: FixedSignature = signer.sign(b"{}");
+
+        let mut expected_signature = [0u8; 64];
+        expected_signature[31] = 1;
+        expected_signature[63] = 1;
+        assert_eq!(signature.as_ref(), &expected_signature[..]);
+    }
+
+    #[test]
+    fn sign_rejected_by_user() {
+        let transport = MockTransport::new(vec![(vec![], RETCODE_OK), (vec![], 0x6986)]);
+        let signer = Secp256k1LedgerCosmosSigner::with_transport(transport, Bip44Path::default());
+
+        let result: Result<FixedSignature, Error> = signer.try_sign(b"{}");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn reject_malformed_der_signature() {
+        let mut out = [0u8; 64];
+        assert!(der_to_fixed(&[0x30, 0x02, 0x02, 0x00], &mut out).is_err());
+        assert!(der_to_fixed(&[0x31, 0x06, 0x02, 0x01, 0x01, 0x02, 0x01, 0x01], &mut out).is_err());
+
+        let mut oversized = vec![0x30, 0x46, 0x02, 0x22];
+        oversized.extend_from_slice(&[0x01; 34]);
+        oversized.extend_from_slice(&[0x02, 0x20]);
+        oversized.extend_from_slice(&[0x01; 32]);
+        assert!(der_to_fixed(&oversized, &mut out).is_err());
+    }
+
+    #[test]
+    fn reject_non_minimal_der_integer() {
+        let mut out = [0u8; 64];
+        assert!(der_to_fixed(&[0x30, 0x06, 0x02, 0x01, 0x01, 0x02, 0x01, 0x01], &mut out).is_ok());
+
+        // redundant leading zero before a byte without the high bit set
+        let der = [0x30, 0x07, 0x02, 0x02, 0x00, 0x01, 0x02, 0x01, 0x01];
+        assert!(der_to_fixed(&der, &mut out).is_err());
+        let der = [0x30, 0x07, 0x02, 0x01, 0x01, 0x02, 0x02, 0x00, 0x7f];
+        assert!(der_to_fixed(&der, &mut out).is_err());
+    }
+
+    #[test]
+    fn reject_negative_der_integer() {
+        let mut out = [0u8; 64];
+        let der = [0x30, 0x06, 0x02, 0x01, 0x80, 0x02, 0x01, 0x01];
+        assert!(der_to_fixed(&der, &mut out).is_err());
+        let der = [0x30, 0x07, 0x02, 0x01, 0x01, 0x02, 0x02, 0xff, 0x01];
+        assert!(der_to_fixed(&der, &mut out).is_err());
+    }
+
+    #[test]
+    fn reject_zero_der_integer() {
+        let mut out = [0u8; 64];
+        let der = [0x30, 0x06, 0x02, 0x01, 0x00, 0x02, 0x01, 0x01];
+        assert!(der_to_fixed(&der, &mut out).is_err());
+        let der = [0x30, 0x06, 0x02, 0x01, 0x01, 0x02, 0x01, 0x00];
+        assert!(der_to_fixed(&der, &mut out).is_err());
+    }
+}
@@ -0,0 +1,194 @@
+//! BIP44 derivation paths as understood by the Ledger Cosmos app
+
+use std::{
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+/// Bit which marks a BIP32 child index as hardened
+const HARDENED: u32 = 0x8000_0000;
+
+/// SLIP-0044 coin type registered for the Cosmos Hub (ATOM)
+pub const COSMOS_COIN_TYPE: u32 = 118;
+
+/// BIP44 derivation path: `m / 44' / coin_type' / account' / change / address_index`
+///
+/// The Cosmos app only accepts paths of exactly this shape, with the
+/// purpose, coin type and account components hardened.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Bip44Path([u32; 5]);
+
+impl Bip44Path {
+    /// Create a BIP44 path from its (unhardened) components
+    pub fn new(coin_type: u32, account: u32, change: u32, address_index: u32) -> Self {
+        Bip44Path([
+            44 | HARDENED,
+            coin_type | HARDENED,
+            account | HARDENED,
+            change,
+            address_index,
+        ])
+    }
+
+    /// Path for the given account and address index using the Cosmos coin type,
+    /// i.e. `m/44'/118'/account'/0/address_index`
+    pub fn cosmos(account: u32, address_index: u32) -> Self {
+        Self::new(COSMOS_COIN_TYPE, account, 0, address_index)
+    }
+
+    /// Raw BIP32 child indexes for this path (with hardened bits set)
+    pub fn components(&self) -> &[u32; 5] {
+        &self.0
+    }
+
+    /// Serialize this path in the format expected by the Cosmos app:
+    /// each component as a little endian `u32`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.0.len() * 4);
+
+        for component in &self.0 {
+            bytes.extend_from_slice(&component.to_le_bytes());
+        }
+
+        bytes
+    }
+}
+
+impl Default for Bip44Path {
+    fn default() -> Self {
+        Self::cosmos(0, 0)
+    }
+}
+
+impl Display for Bip44Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "m")?;
+
+        for component in &self.0 {
+            if component & HARDENED == HARDENED {
+                write!(f, "/{}'", component & !HARDENED)?;
+            } else {
+                write!(f, "/{}", component)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for Bip44Path {
+    type Err = InvalidPath;
+
+    /// Parse a path like `m/44'/118'/0'/0/0`
+    fn from_str(s: &str) -> Result<Self, InvalidPath> {
+        let mut parts = s.split('/');
+
+        if parts.next() != Some("m") {
+            return Err(InvalidPath);
+        }
+
+        let mut components = [0u32; 5];
+        let mut count = 0;
+
+        for part in parts {
+            if count == components.len() {
+                return Err(InvalidPath);
+            }
+
+            let (digits, hardened) = if part.ends_with('\'') || part.ends_with('h') {
+                (&part[..part.len() - 1], true)
+            } else {
+                (part, false)
+            };
+
+            // reject signs, which `u32::from_str` accepts
+            if digits.is_empty() || !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+                return Err(InvalidPath);
+            }
+
+            let index = digits.parse::<u32>().map_err(|_| InvalidPath)?;
+
+            if index & HARDENED != 0 {
+                return Err(InvalidPath);
+            }
+
+            components[count] = if hardened { index | HARDENED } else { index };
+            count += 1;
+        }
+
+        // The Cosmos app requires the first three levels to be hardened and
+        // the last two not to be
+        let path = Bip44Path(components);
+
+        if count != components.len()
+            || path.0[0] != 44 | HARDENED
+            || path.0[1] & HARDENED == 0
+            || path.0[2] & HARDENED == 0
+            || path.0[3] & HARDENED != 0
+            || path.0[4] & HARDENED != 0
+        {
+            return Err(InvalidPath);
+        }
+
+        Ok(path)
+    }
+}
+
+/// Error parsing a BIP44 derivation path
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct InvalidPath;
+
+impl Display for InvalidPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid BIP44 derivation path")
+    }
+}
+
+impl std::error::Error for InvalidPath {}
+
+#[cfg(test)]
+mod tests {
+    use super::Bip44Path;
+
+    #[test]
+    fn parse_cosmos_path() {
+        let path = "m/44'/118'/0'/0/0".parse::<Bip44Path>().unwrap();
+        assert_eq!(path, Bip44Path::cosmos(0, 0));
+        assert_eq!(path.to_string(), "m/44'/118'/0'/0/0");
+    }
+
+    #[test]
+    fn serialize_path() {
+        let path = Bip44Path::cosmos(1, 5);
+        assert_eq!(
+            path.to_bytes(),
+            [
+                0x2c, 0x00, 0x00, 0x80, // 44'
+                0x76, 0x00, 0x00, 0x80, // 118'
+                0x01, 0x00, 0x00, 0x80, // 1'
+                0x00, 0x00, 0x00, 0x00, // 0
+                0x05, 0x00, 0x00, 0x00, // 5
+            ]
+        );
+    }
+
+    #[test]
+    fn reject_invalid_paths() {
+        for path in &[
+            "",
+            "m",
+            "m/44'/118'/0'/0",
+            "m/44'/118'/0'/0/0/0",
+            "m/45'/118'/0'/0/0",
+            "m/44/118'/0'/0/0",
+            "m/44'/118'/0/0/0",
+            "m/44'/118'/0'/0'/0",
+            "m/44'/118'/0'/0/x",
+            "m/44'/118'/0'/0/+0",
+            "m/44'/+118'/0'/0/0",
+            "44'/118'/0'/0/0",
+        ] {
+            assert!(path.parse::<Bip44Path>().is_err(), "accepted {:?}", path);
+        }
+    }
+}
@@ -0,0 +1,69 @@
+//! APDU transport used to talk to the Ledger Cosmos app
+
+pub use ledger::{ApduAnswer, ApduCommand};
+
+use signatory::signature::Error;
+use std::fmt::{self, Display};
+
+/// Return code signalling a successful APDU exchange
+pub const RETCODE_OK: u16 = 0x9000;
+
+/// Transport capable of exchanging APDUs with a Ledger device.
+///
+/// Implemented for [`ledger::LedgerApp`] (i.e. USB HID). Alternative
+/// implementations (e.g. mocks for testing) can be supplied to
+/// `Secp256k1LedgerCosmosSigner::with_transport`.
+pub trait Transport: Send {
+    /// Send a command APDU to the device and wait for its answer
+    fn exchange(&self, command: ApduCommand) -> Result<ApduAnswer, Error>;
+}
+
+impl Transport for ledger::LedgerApp {
+    fn exchange(&self, command: ApduCommand) -> Result<ApduAnswer, Error> {
+        ledger::LedgerApp::exchange(self, command).map_err(Error::from_source)
+    }
+}
+
+/// Error returned by the Ledger app as a non-success APDU return code
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct RetcodeError(pub u16);
+
+impl Display for RetcodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self.0 {
+            0x6400 => "execution error",
+            0x6700 => "wrong length",
+            0x6982 => "empty buffer",
+            0x6983 => "output buffer too small",
+            0x6984 => "data is invalid",
+            0x6985 => "conditions not satisfied (rejected by user?)",
+            0x6986 => "transaction rejected",
+            0x6a80 => "bad key handle",
+            0x6b00 => "invalid P1/P2",
+            0x6d00 => "instruction not supported",
+            0x6e00 => "app does not seem to be open",
+            0x6f00 => "unknown error",
+            0x6f01 => "sign verify error",
+            _ => "unexpected return code",
+        };
+
+        write!(f, "ledger error 0x{:04x}: {}", self.0, reason)
+    }
+}
+
+impl std::error::Error for RetcodeError {}
+
+/// Exchange the given APDU, returning the answer's data if the device
+/// reported success
+pub(super) fn exchange<T: Transport + ?Sized>(
+    transport: &T,
+    command: ApduCommand,
+) -> Result<Vec<u8>, Error> {
+    let answer = transport.exchange(command)?;
+
+    if answer.retcode == RETCODE_OK {
+        Ok(answer.data)
+    } else {
+        Err(Error::from_source(RetcodeError(answer.retcode)))
+    }
+}
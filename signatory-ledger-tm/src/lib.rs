@@ -1,4 +1,5 @@
 //! ledger-tm provider: Ledger Tendermint Validator app (Ed25519 signatures for amino votes)
+//! and Ledger Cosmos app (secp256k1 signatures for sign-JSON messages, see [`cosmos`])
 
 #![forbid(unsafe_code)]
 #![warn(missing_docs, rust_2018_idioms, unused_qualifications)]
@@ -7,6 +8,8 @@
     html_root_url = "https://docs.rs/signatory-ledger-tm/0.16.0"
 )]
 
+pub mod cosmos;
+
 use ledger_tendermint::ledgertm::TendermintValidatorApp;
 use signatory::{
     ed25519::{PublicKey, Signature},
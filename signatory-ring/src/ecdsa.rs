@@ -1,5 +1,6 @@
-//! ECDSA provider for the *ring* crate (supporting NIST P-256/P-384)
+//! ECDSA and ECDH provider for the *ring* crate (supporting NIST P-256/P-384)
 
+mod agreement;
 pub mod p256;
 pub mod p384;
 mod signer;
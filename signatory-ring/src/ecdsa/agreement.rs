@@ -0,0 +1,45 @@
+//! Generic *ring* ECDH key agreement
+
+use ring::{
+    agreement::{self, EphemeralPrivateKey, UnparsedPublicKey},
+    rand::SystemRandom,
+};
+use signatory::signature;
+
+/// Generic ephemeral ECDH secret which is wrapped with curve-specific types
+pub(super) struct EphemeralSecret {
+    /// *ring* ephemeral private key, which is consumed by key agreement
+    private_key: EphemeralPrivateKey,
+}
+
+impl EphemeralSecret {
+    /// Randomly generate an ephemeral secret
+    pub fn generate(alg: &'static agreement::Algorithm) -> Self {
+        let private_key = EphemeralPrivateKey::generate(alg, &SystemRandom::new()).unwrap();
+        Self { private_key }
+    }
+
+    /// Compute the public key for this secret (an uncompressed point)
+    pub fn public_key(&self) -> Result<agreement::PublicKey, signature::Error> {
+        self.private_key
+            .compute_public_key()
+            .map_err(|_| signature::Error::new())
+    }
+
+    /// Agree on a shared secret (the x-coordinate of the shared point) with
+    /// the owner of the given public key, which *ring* requires to be an
+    /// uncompressed point
+    pub fn agree<F, R>(self, public_key: &[u8], f: F) -> Result<R, signature::Error>
+    where
+        F: FnOnce(&[u8]) -> Option<R>,
+    {
+        let alg = self.private_key.algorithm();
+
+        agreement::agree_ephemeral(
+            self.private_key,
+            &UnparsedPublicKey::new(alg, public_key),
+            signature::Error::new(),
+            |shared_secret| f(shared_secret).ok_or_else(signature::Error::new),
+        )
+    }
+}
@@ -3,6 +3,7 @@
 pub use signatory::ecdsa::curve::nistp256::{Asn1Signature, FixedSignature, NistP256};
 
 use ring::{
+    agreement::ECDH_P256,
     rand::SystemRandom,
     signature::{
         UnparsedPublicKey, ECDSA_P256_SHA256_ASN1, ECDSA_P256_SHA256_ASN1_SIGNING,
@@ -10,6 +11,7 @@ use ring::{
     },
 };
 use signatory::{
+    ecdsa::{KeyAgreement, LowS, SharedSecret, VerificationMode},
     encoding::{
         self,
         pkcs8::{self, FromPkcs8, GeneratePkcs8},
@@ -18,7 +20,7 @@ use signatory::{
     signature::{self, Signature},
 };
 
-use super::signer::EcdsaSigner;
+use super::{agreement, signer::EcdsaSigner};
 
 /// NIST P-256 public key
 pub type PublicKey = signatory::ecdsa::PublicKey<NistP256>;
@@ -27,10 +29,11 @@ pub type PublicKey = signatory::ecdsa::PublicKey<NistP256>;
 pub struct Signer<S: Signature>(EcdsaSigner<S>);
 
 impl FromPkcs8 for Signer<Asn1Signature> {
-    /// Create a new ECDSA signer which produces fixed-width signatures from a PKCS#8 keypair
+    /// Create a new ECDSA signer which produces ASN.1 DER-encoded signatures from a PKCS#8 keypair
     fn from_pkcs8<K: AsRef<[u8]>>(secret_key: K) -> Result<Self, encoding::Error> {
+        // Signatures are computed in fixed-sized form, to be normalized to low-S
         Ok(Signer(EcdsaSigner::from_pkcs8(
-            &ECDSA_P256_SHA256_ASN1_SIGNING,
+            &ECDSA_P256_SHA256_FIXED_SIGNING,
             secret_key.as_ref(),
         )?))
     }
@@ -82,29 +85,43 @@ where
 }
 
 impl signature::Signer<Asn1Signature> for Signer<Asn1Signature> {
+    /// Compute a low-S ASN.1 DER-encoded signature of the given message
     fn try_sign(&self, msg: &[u8]) -> Result<Asn1Signature, signature::Error> {
-        self.0.sign(msg)
+        let signature: FixedSignature = self.0.sign(msg)?;
+        Ok(Asn1Signature::from(&signature.normalize_s()))
     }
 }
 
 impl signature::Signer<FixedSignature> for Signer<FixedSignature> {
+    /// Compute a low-S fixed-sized signature of the given message
     fn try_sign(&self, msg: &[u8]) -> Result<FixedSignature, signature::Error> {
-        self.0.sign(msg)
+        let signature: FixedSignature = self.0.sign(msg)?;
+        Ok(signature.normalize_s())
     }
 }
 
 /// NIST P-256 ECDSA verifier
 #[derive(Clone, Debug, Eq, PartialEq)]
-pub struct Verifier(PublicKey);
+pub struct Verifier(PublicKey, VerificationMode);
+
+impl Verifier {
+    /// Create a new verifier which accepts or rejects high-S signatures
+    /// according to the given mode
+    pub fn with_mode(public_key: &PublicKey, mode: VerificationMode) -> Self {
+        Verifier(*public_key, mode)
+    }
+}
 
 impl<'a> From<&'a PublicKey> for Verifier {
+    /// Create a new verifier which accepts high-S signatures (FIPS 186-4)
     fn from(public_key: &'a PublicKey) -> Self {
-        Verifier(*public_key)
+        Self::with_mode(public_key, VerificationMode::Fips186)
     }
 }
 
 impl signature::Verifier<Asn1Signature> for Verifier {
     fn verify(&self, msg: &[u8], signature: &Asn1Signature) -> Result<(), signature::Error> {
+        self.1.check(signature)?;
         UnparsedPublicKey::new(&ECDSA_P256_SHA256_ASN1, self.0.as_ref())
             .verify(msg, signature.as_ref())
             .map_err(|_| signature::Error::new())
@@ -113,20 +130,48 @@ impl signature::Verifier<Asn1Signature> for Verifier {
 
 impl signature::Verifier<FixedSignature> for Verifier {
     fn verify(&self, msg: &[u8], signature: &FixedSignature) -> Result<(), signature::Error> {
+        self.1.check(signature)?;
         UnparsedPublicKey::new(&ECDSA_P256_SHA256_FIXED, self.0.as_ref())
             .verify(msg, signature.as_ref())
             .map_err(|_| signature::Error::new())
     }
 }
 
+/// NIST P-256 ephemeral ECDH secret, which can only be used once
+pub struct EphemeralSecret(agreement::EphemeralSecret);
+
+impl EphemeralSecret {
+    /// Randomly generate a P-256 ephemeral secret
+    pub fn generate() -> Self {
+        EphemeralSecret(agreement::EphemeralSecret::generate(&ECDH_P256))
+    }
+}
+
+impl PublicKeyed<PublicKey> for EphemeralSecret {
+    fn public_key(&self) -> Result<PublicKey, signature::Error> {
+        PublicKey::from_bytes(self.0.public_key()?).ok_or_else(signature::Error::new)
+    }
+}
+
+impl KeyAgreement<NistP256> for EphemeralSecret {
+    /// Agree on a shared secret with the owner of the given public key, which
+    /// *ring* requires to be uncompressed
+    fn agree(self, public_key: &PublicKey) -> Result<SharedSecret<NistP256>, signature::Error> {
+        self.0.agree(public_key.as_ref(), |shared_secret| {
+            SharedSecret::from_bytes(shared_secret)
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{PublicKey, Signer, Verifier};
+    use super::{EphemeralSecret, PublicKey, Signer, Verifier};
     use signatory::{
         ecdsa::{
             curve::nistp256::{Asn1Signature, FixedSignature},
             generic_array::GenericArray,
             test_vectors::nistp256::SHA256_FIXED_SIZE_TEST_VECTORS,
+            KeyAgreement, LowS, VerificationMode,
         },
         encoding::FromPkcs8,
         public_key::PublicKeyed,
@@ -134,6 +179,28 @@ mod tests {
         test_vector::{TestVectorAlgorithm, ToPkcs8},
     };
 
+    /// Message signed by the low-S and high-S signatures below
+    const HIGH_S_MSG: &[u8] = b"example message";
+
+    /// Public key of the `[0x11; 32]` secret key
+    const HIGH_S_PUBLIC_KEY: &[u8] = b"\x04\x02\x17\xE6\x17\xF0\xB6\x44\x39\x28\x27\x8F\x96\x99\x9E\x69\
+                                       \xA2\x3A\x4F\x2C\x15\x2B\xDF\x6D\x6C\xDF\x66\xE5\xB8\x02\x82\xD4\
+                                       \xED\x19\x4A\x7D\xEB\xCB\x97\x71\x2D\x2D\xDA\x3C\xA8\x5A\xA8\x76\
+                                       \x5A\x56\xF4\x5F\xC7\x58\x59\x96\x52\xF2\x89\x7C\x65\x30\x6E\x57\
+                                       \x94";
+
+    /// Low-S signature of `HIGH_S_MSG`
+    const LOW_S_SIGNATURE: &[u8] = b"\x3A\xD3\x86\x1A\x95\x62\x13\x92\x51\x6B\xB5\x93\xEF\x05\x58\x3E\
+                                     \xD2\xE5\x86\x6F\x5C\xB6\x26\x0A\x30\x17\x23\x7F\xD8\x9B\x90\xAF\
+                                     \x44\x01\x36\x06\x25\x15\xAD\xBD\x84\xA5\x6D\xCE\x5B\xA2\xC2\xBB\
+                                     \x10\xBB\xF7\x86\x43\x71\xE1\xC8\x0B\xFA\x60\xA9\xBA\xC0\xA6\x7C";
+
+    /// High-S form of `LOW_S_SIGNATURE`
+    const HIGH_S_SIGNATURE: &[u8] = b"\x3A\xD3\x86\x1A\x95\x62\x13\x92\x51\x6B\xB5\x93\xEF\x05\x58\x3E\
+                                      \xD2\xE5\x86\x6F\x5C\xB6\x26\x0A\x30\x17\x23\x7F\xD8\x9B\x90\xAF\
+                                      \xBB\xFE\xC9\xF8\xDA\xEA\x52\x43\x7B\x5A\x92\x31\xA4\x5D\x3D\x44\
+                                      \xAC\x2B\x03\x27\x63\xA5\xBC\xBC\xE7\xBF\x6A\x19\x41\xA2\x7E\xD5";
+
     #[test]
     pub fn asn1_signature_roundtrip() {
         // TODO: DER test vectors
@@ -248,4 +315,73 @@ mod tests {
         let asn1_signature = Asn1Signature::from(&fixed_signature);
         assert!(verifier.verify(vector.msg, &asn1_signature).is_ok());
     }
+
+    #[test]
+    fn ephemeral_key_agreement() {
+        let alice = EphemeralSecret::generate();
+        let bob = EphemeralSecret::generate();
+        let alice_public_key = alice.public_key().unwrap();
+        let bob_public_key = bob.public_key().unwrap();
+
+        let alice_shared_secret = alice.agree(&bob_public_key).unwrap();
+        let bob_shared_secret = bob.agree(&alice_public_key).unwrap();
+        assert_eq!(
+            alice_shared_secret.as_secret_slice(),
+            bob_shared_secret.as_secret_slice()
+        );
+    }
+
+    #[test]
+    fn key_agreement_with_ecdsa_public_key() {
+        let vector = &SHA256_FIXED_SIZE_TEST_VECTORS[0];
+        let signer =
+            Signer::<FixedSignature>::from_pkcs8(&vector.to_pkcs8(TestVectorAlgorithm::NistP256))
+                .unwrap();
+        let public_key = signer.public_key().unwrap();
+        assert!(EphemeralSecret::generate().agree(&public_key).is_ok());
+
+        let mut tweaked_public_key = public_key.as_bytes().to_vec();
+        *tweaked_public_key.iter_mut().last().unwrap() ^= 42;
+        let tweaked_public_key = PublicKey::from_bytes(tweaked_public_key).unwrap();
+        assert!(EphemeralSecret::generate()
+            .agree(&tweaked_public_key)
+            .is_err());
+    }
+
+    #[test]
+    fn signatures_are_low_s() {
+        for vector in SHA256_FIXED_SIZE_TEST_VECTORS {
+            let pkcs8 = vector.to_pkcs8(TestVectorAlgorithm::NistP256);
+
+            let signature: FixedSignature = Signer::from_pkcs8(&pkcs8).unwrap().sign(vector.msg);
+            assert!(signature.is_low_s());
+
+            let signature: Asn1Signature = Signer::from_pkcs8(&pkcs8).unwrap().sign(vector.msg);
+            assert!(signature.is_low_s());
+        }
+    }
+
+    #[test]
+    fn high_s_signatures() {
+        let public_key = PublicKey::from_bytes(HIGH_S_PUBLIC_KEY).unwrap();
+        let low_s_signature = FixedSignature::from_bytes(LOW_S_SIGNATURE).unwrap();
+        let high_s_signature = FixedSignature::from_bytes(HIGH_S_SIGNATURE).unwrap();
+        assert!(low_s_signature.is_low_s());
+        assert!(!high_s_signature.is_low_s());
+        assert_eq!(high_s_signature.normalize_s().as_ref(), LOW_S_SIGNATURE);
+
+        let verifier = Verifier::from(&public_key);
+        assert!(verifier.verify(HIGH_S_MSG, &low_s_signature).is_ok());
+        assert!(verifier.verify(HIGH_S_MSG, &high_s_signature).is_ok());
+        assert!(verifier
+            .verify(HIGH_S_MSG, &Asn1Signature::from(&high_s_signature))
+            .is_ok());
+
+        let verifier = Verifier::with_mode(&public_key, VerificationMode::Strict);
+        assert!(verifier.verify(HIGH_S_MSG, &low_s_signature).is_ok());
+        assert!(verifier.verify(HIGH_S_MSG, &high_s_signature).is_err());
+        assert!(verifier
+            .verify(HIGH_S_MSG, &Asn1Signature::from(&high_s_signature))
+            .is_err());
+    }
 }
@@ -3,6 +3,7 @@
 pub use signatory::ecdsa::curve::nistp384::{Asn1Signature, FixedSignature, NistP384};
 
 use ring::{
+    agreement::ECDH_P384,
     rand::SystemRandom,
     signature::{
         UnparsedPublicKey, ECDSA_P384_SHA384_ASN1, ECDSA_P384_SHA384_ASN1_SIGNING,
@@ -10,6 +11,7 @@ use ring::{
     },
 };
 use signatory::{
+    ecdsa::{KeyAgreement, LowS, SharedSecret, VerificationMode},
     encoding::{
         self,
         pkcs8::{self, FromPkcs8, GeneratePkcs8},
@@ -18,7 +20,7 @@ use signatory::{
     signature::{self, Signature},
 };
 
-use super::signer::EcdsaSigner;
+use super::{agreement, signer::EcdsaSigner};
 
 /// NIST P-384 public key
 pub type PublicKey = signatory::ecdsa::PublicKey<NistP384>;
@@ -27,10 +29,11 @@ pub type PublicKey = signatory::ecdsa::PublicKey<NistP384>;
 pub struct Signer<S: Signature>(EcdsaSigner<S>);
 
 impl FromPkcs8 for Signer<Asn1Signature> {
-    /// Create a new ECDSA signer which produces fixed-width signatures from a PKCS#8 keypair
+    /// Create a new ECDSA signer which produces ASN.1 DER-encoded signatures from a PKCS#8 keypair
     fn from_pkcs8<K: AsRef<[u8]>>(secret_key: K) -> Result<Self, encoding::Error> {
+        // Signatures are computed in fixed-sized form, to be normalized to low-S
         Ok(Signer(EcdsaSigner::from_pkcs8(
-            &ECDSA_P384_SHA384_ASN1_SIGNING,
+            &ECDSA_P384_SHA384_FIXED_SIGNING,
             secret_key.as_ref(),
         )?))
     }
@@ -81,29 +84,43 @@ where
 }
 
 impl signature::Signer<Asn1Signature> for Signer<Asn1Signature> {
+    /// Compute a low-S ASN.1 DER-encoded signature of the given message
     fn try_sign(&self, msg: &[u8]) -> Result<Asn1Signature, signature::Error> {
-        self.0.sign(msg)
+        let signature: FixedSignature = self.0.sign(msg)?;
+        Ok(Asn1Signature::from(&signature.normalize_s()))
     }
 }
 
 impl signature::Signer<FixedSignature> for Signer<FixedSignature> {
+    /// Compute a low-S fixed-sized signature of the given message
     fn try_sign(&self, msg: &[u8]) -> Result<FixedSignature, signature::Error> {
-        self.0.sign(msg)
+        let signature: FixedSignature = self.0.sign(msg)?;
+        Ok(signature.normalize_s())
     }
 }
 
 /// NIST P-384 ECDSA verifier
 #[derive(Clone, Debug, Eq, PartialEq)]
-pub struct Verifier(PublicKey);
+pub struct Verifier(PublicKey, VerificationMode);
+
+impl Verifier {
+    /// Create a new verifier which accepts or rejects high-S signatures
+    /// according to the given mode
+    pub fn with_mode(public_key: &PublicKey, mode: VerificationMode) -> Self {
+        Verifier(*public_key, mode)
+    }
+}
 
 impl<'a> From<&'a PublicKey> for Verifier {
+    /// Create a new verifier which accepts high-S signatures (FIPS 186-4)
     fn from(public_key: &'a PublicKey) -> Self {
-        Verifier(*public_key)
+        Self::with_mode(public_key, VerificationMode::Fips186)
     }
 }
 
 impl signature::Verifier<Asn1Signature> for Verifier {
     fn verify(&self, msg: &[u8], signature: &Asn1Signature) -> Result<(), signature::Error> {
+        self.1.check(signature)?;
         UnparsedPublicKey::new(&ECDSA_P384_SHA384_ASN1, self.0.as_ref())
             .verify(msg, signature.as_ref())
             .map_err(|_| signature::Error::new())
@@ -112,20 +129,48 @@ impl signature::Verifier<Asn1Signature> for Verifier {
 
 impl signature::Verifier<FixedSignature> for Verifier {
     fn verify(&self, msg: &[u8], signature: &FixedSignature) -> Result<(), signature::Error> {
+        self.1.check(signature)?;
         UnparsedPublicKey::new(&ECDSA_P384_SHA384_FIXED, self.0.as_ref())
             .verify(msg, signature.as_ref())
             .map_err(|_| signature::Error::new())
     }
 }
 
+/// NIST P-384 ephemeral ECDH secret, which can only be used once
+pub struct EphemeralSecret(agreement::EphemeralSecret);
+
+impl EphemeralSecret {
+    /// Randomly generate a P-384 ephemeral secret
+    pub fn generate() -> Self {
+        EphemeralSecret(agreement::EphemeralSecret::generate(&ECDH_P384))
+    }
+}
+
+impl PublicKeyed<PublicKey> for EphemeralSecret {
+    fn public_key(&self) -> Result<PublicKey, signature::Error> {
+        PublicKey::from_bytes(self.0.public_key()?).ok_or_else(signature::Error::new)
+    }
+}
+
+impl KeyAgreement<NistP384> for EphemeralSecret {
+    /// Agree on a shared secret with the owner of the given public key, which
+    /// *ring* requires to be uncompressed
+    fn agree(self, public_key: &PublicKey) -> Result<SharedSecret<NistP384>, signature::Error> {
+        self.0.agree(public_key.as_ref(), |shared_secret| {
+            SharedSecret::from_bytes(shared_secret)
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{PublicKey, Signer, Verifier};
+    use super::{EphemeralSecret, PublicKey, Signer, Verifier};
     use signatory::{
         ecdsa::{
             curve::nistp384::{Asn1Signature, FixedSignature},
             generic_array::GenericArray,
             test_vectors::nistp384::SHA384_FIXED_SIZE_TEST_VECTORS,
+            KeyAgreement, LowS, VerificationMode,
         },
         encoding::FromPkcs8,
         public_key::PublicKeyed,
@@ -133,6 +178,34 @@ mod tests {
         test_vector::{TestVectorAlgorithm, ToPkcs8},
     };
 
+    /// Message signed by the low-S and high-S signatures below
+    const HIGH_S_MSG: &[u8] = b"example message";
+
+    /// Public key of the `[0x11; 48]` secret key
+    const HIGH_S_PUBLIC_KEY: &[u8] = b"\x04\x38\x6E\x76\x7E\xA5\xCB\x71\x6C\x9C\xD6\x20\xFF\x73\x42\x12\
+                                       \x9C\x89\x2A\x6F\xCC\xEF\xE6\x12\x14\x0C\x80\xBF\xF5\x9E\x94\x34\
+                                       \x68\x01\x9D\xDA\x16\xE5\x07\x9B\x0C\x1D\x90\x01\xD2\x3A\x62\x4B\
+                                       \x6D\xD0\x88\xD0\xC3\x82\x63\x94\x19\x47\x87\x40\x3E\x8A\x7D\x07\
+                                       \xE5\xE2\x2F\x7E\x9C\x0B\x8E\x80\xFA\x1F\xAF\xF5\xD2\x8B\x4B\xB5\
+                                       \x97\xB2\x67\xF0\xB8\x70\x23\xCA\x61\xFC\x84\x54\xBD\xDE\xFD\x2E\
+                                       \x0E";
+
+    /// Low-S signature of `HIGH_S_MSG`
+    const LOW_S_SIGNATURE: &[u8] = b"\x72\xCC\xDE\x33\x75\x37\x62\x24\x5E\x01\x5D\xA9\x2E\x48\xFA\x02\
+                                     \x84\x95\x52\x2D\xC4\x23\x56\xC7\xE3\xDF\x51\xDC\xF5\x6A\x5E\x19\
+                                     \xDE\x74\x2A\xCD\x3A\x19\xF7\x9A\xF3\x72\xDC\x97\x05\xF5\x60\xD8\
+                                     \x3C\xE5\x08\xF5\xE6\x97\x70\xAF\x48\x7D\x55\x4C\x8D\x1C\xAA\x96\
+                                     \x2C\x76\x7A\x49\xE5\xD6\xD3\xAC\xC5\x03\x83\x5B\xEB\x46\x4B\x58\
+                                     \xBC\xAE\x62\xF1\x76\xD2\xF7\x7A\x2C\x74\xE3\xED\xFB\x4F\xCC\x5B";
+
+    /// High-S form of `LOW_S_SIGNATURE`
+    const HIGH_S_SIGNATURE: &[u8] = b"\x72\xCC\xDE\x33\x75\x37\x62\x24\x5E\x01\x5D\xA9\x2E\x48\xFA\x02\
+                                      \x84\x95\x52\x2D\xC4\x23\x56\xC7\xE3\xDF\x51\xDC\xF5\x6A\x5E\x19\
+                                      \xDE\x74\x2A\xCD\x3A\x19\xF7\x9A\xF3\x72\xDC\x97\x05\xF5\x60\xD8\
+                                      \xC3\x1A\xF7\x0A\x19\x68\x8F\x50\xB7\x82\xAA\xB3\x72\xE3\x55\x69\
+                                      \xD3\x89\x85\xB6\x1A\x29\x2C\x53\x02\x5F\xCA\x26\x08\xF0\xE2\x86\
+                                      \x9B\x6B\xAA\xC0\xD1\xDD\xB0\x00\xC0\x77\x35\x7C\xD1\x75\x5D\x18";
+
     #[test]
     pub fn asn1_signature_roundtrip() {
         // TODO: DER test vectors
@@ -224,4 +297,73 @@ mod tests {
             assert!(verifier.verify(vector.msg, &asn1_signature).is_ok());
         }
     }
+
+    #[test]
+    fn ephemeral_key_agreement() {
+        let alice = EphemeralSecret::generate();
+        let bob = EphemeralSecret::generate();
+        let alice_public_key = alice.public_key().unwrap();
+        let bob_public_key = bob.public_key().unwrap();
+
+        let alice_shared_secret = alice.agree(&bob_public_key).unwrap();
+        let bob_shared_secret = bob.agree(&alice_public_key).unwrap();
+        assert_eq!(
+            alice_shared_secret.as_secret_slice(),
+            bob_shared_secret.as_secret_slice()
+        );
+    }
+
+    #[test]
+    fn key_agreement_with_ecdsa_public_key() {
+        let vector = &SHA384_FIXED_SIZE_TEST_VECTORS[0];
+        let signer =
+            Signer::<FixedSignature>::from_pkcs8(&vector.to_pkcs8(TestVectorAlgorithm::NistP384))
+                .unwrap();
+        let public_key = signer.public_key().unwrap();
+        assert!(EphemeralSecret::generate().agree(&public_key).is_ok());
+
+        let mut tweaked_public_key = public_key.as_bytes().to_vec();
+        *tweaked_public_key.iter_mut().last().unwrap() ^= 42;
+        let tweaked_public_key = PublicKey::from_bytes(tweaked_public_key).unwrap();
+        assert!(EphemeralSecret::generate()
+            .agree(&tweaked_public_key)
+            .is_err());
+    }
+
+    #[test]
+    fn signatures_are_low_s() {
+        for vector in SHA384_FIXED_SIZE_TEST_VECTORS {
+            let pkcs8 = vector.to_pkcs8(TestVectorAlgorithm::NistP384);
+
+            let signature: FixedSignature = Signer::from_pkcs8(&pkcs8).unwrap().sign(vector.msg);
+            assert!(signature.is_low_s());
+
+            let signature: Asn1Signature = Signer::from_pkcs8(&pkcs8).unwrap().sign(vector.msg);
+            assert!(signature.is_low_s());
+        }
+    }
+
+    #[test]
+    fn high_s_signatures() {
+        let public_key = PublicKey::from_bytes(HIGH_S_PUBLIC_KEY).unwrap();
+        let low_s_signature = FixedSignature::from_bytes(LOW_S_SIGNATURE).unwrap();
+        let high_s_signature = FixedSignature::from_bytes(HIGH_S_SIGNATURE).unwrap();
+        assert!(low_s_signature.is_low_s());
+        assert!(!high_s_signature.is_low_s());
+        assert_eq!(high_s_signature.normalize_s().as_ref(), LOW_S_SIGNATURE);
+
+        let verifier = Verifier::from(&public_key);
+        assert!(verifier.verify(HIGH_S_MSG, &low_s_signature).is_ok());
+        assert!(verifier.verify(HIGH_S_MSG, &high_s_signature).is_ok());
+        assert!(verifier
+            .verify(HIGH_S_MSG, &Asn1Signature::from(&high_s_signature))
+            .is_ok());
+
+        let verifier = Verifier::with_mode(&public_key, VerificationMode::Strict);
+        assert!(verifier.verify(HIGH_S_MSG, &low_s_signature).is_ok());
+        assert!(verifier.verify(HIGH_S_MSG, &high_s_signature).is_err());
+        assert!(verifier
+            .verify(HIGH_S_MSG, &Asn1Signature::from(&high_s_signature))
+            .is_err());
+    }
 }
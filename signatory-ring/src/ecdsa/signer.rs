@@ -49,13 +49,15 @@ where
         self.keypair.public_key().as_ref()
     }
 
-    /// Sign a message, returning the signature
-    pub fn sign(&self, msg: &[u8]) -> Result<S, signature::Error> {
+    /// Sign a message, returning a fixed-sized signature: keypairs always
+    /// use a `*_FIXED_SIGNING` algorithm, so signatures can be normalized to
+    /// low-S before being converted to `S`
+    pub fn sign<F: Signature>(&self, msg: &[u8]) -> Result<F, signature::Error> {
         let sig = self
             .keypair
             .sign(&self.csrng, msg)
             .map_err(|_| signature::Error::new())?;
 
-        S::from_bytes(sig)
+        F::from_bytes(sig)
     }
 }
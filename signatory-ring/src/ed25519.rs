@@ -1,6 +1,6 @@
 //! Ed25519 signer and verifier implementation for *ring*
 
-pub use signatory::ed25519::{PublicKey, Seed, Signature};
+pub use signatory::ed25519::{PublicKey, Seed, Signature, VerificationMode};
 
 use ring::{
     self,
@@ -60,24 +60,140 @@ impl signature::Signer<Signature> for Signer {
 
 /// Ed25519 verifier for *ring*
 #[derive(Clone, Debug, Eq, PartialEq)]
-pub struct Verifier(PublicKey);
+pub struct Verifier(PublicKey, Option<VerificationMode>);
+
+impl Verifier {
+    /// Create a verifier which verifies signatures in the given mode.
+    ///
+    /// The cofactored modes (RFC 8032 and ZIP-215) need the `cofactored`
+    /// feature: without it, verifiers in these modes reject all signatures.
+    pub fn with_mode(public_key: &PublicKey, mode: VerificationMode) -> Self {
+        Verifier(*public_key, Some(mode))
+    }
+
+    /// Mode of Ed25519ctx and Ed25519ph verification, which *ring*'s own
+    /// rules don't cover
+    #[cfg(feature = "cofactored")]
+    fn context_mode(&self) -> VerificationMode {
+        self.1.unwrap_or(VerificationMode::Rfc8032)
+    }
+}
 
 impl<'a> From<&'a PublicKey> for Verifier {
+    /// Create a verifier with *ring*'s own rules
     fn from(public_key: &'a PublicKey) -> Self {
-        Verifier(*public_key)
+        Verifier(*public_key, None)
     }
 }
 
 impl signature::Verifier<Signature> for Verifier {
     fn verify(&self, msg: &[u8], signature: &Signature) -> Result<(), signature::Error> {
+        if let Some(mode) = self.1 {
+            mode.check_encodings(&self.0, signature)?;
+            if mode.is_cofactored() {
+                return verify_cofactored(&self.0, msg, signature);
+            }
+        }
+
         UnparsedPublicKey::new(&ring::signature::ED25519, self.0.as_bytes())
             .verify(msg, signature.as_ref())
             .map_err(|_| signature::Error::new())
     }
 }
 
+/// Ed25519ctx and Ed25519ph, which *ring* doesn't implement, are verified by
+/// Signatory itself, in the verifier's mode (RFC 8032 for verifiers created
+/// `From` a public key)
+#[cfg(feature = "cofactored")]
+impl signatory::ed25519::ContextVerifier for Verifier {
+    fn verify_ctx(
+        &self,
+        context: signatory::ed25519::Context<'_>,
+        msg: &[u8],
+        signature: &Signature,
+    ) -> Result<(), signature::Error> {
+        self.context_mode()
+            .verify_ctx(&self.0, context, msg, signature)
+    }
+
+    fn verify_ph(
+        &self,
+        context: signatory::ed25519::Context<'_>,
+        digest: signatory::sha2::Sha512,
+        signature: &Signature,
+    ) -> Result<(), signature::Error> {
+        self.context_mode()
+            .verify_ph(&self.0, context, digest, signature)
+    }
+}
+
+/// Check the cofactored verification equation
+#[cfg(feature = "cofactored")]
+fn verify_cofactored(
+    public_key: &PublicKey,
+    msg: &[u8],
+    signature: &Signature,
+) -> Result<(), signature::Error> {
+    signatory::ed25519::verify_cofactored(public_key, msg, signature)
+}
+
+/// Without the `cofactored` feature the cofactored equation can't be
+/// checked, and *ring*'s cofactorless one would give different answers
+#[cfg(not(feature = "cofactored"))]
+fn verify_cofactored(
+    _public_key: &PublicKey,
+    _msg: &[u8],
+    _signature: &Signature,
+) -> Result<(), signature::Error> {
+    Err(signature::Error::new())
+}
+
+/// Sequential fallback: *ring* has no batch verification
+#[cfg(feature = "alloc")]
+impl signatory::ed25519::BatchVerifier for Verifier {
+    fn verify_batch(
+        items: &[signatory::ed25519::BatchItem<'_>],
+    ) -> Result<(), signatory::ed25519::BatchError> {
+        signatory::ed25519::verify_batch_sequentially::<Self>(items)
+    }
+
+    fn verify_batch_with_mode(
+        mode: VerificationMode,
+        items: &[signatory::ed25519::BatchItem<'_>],
+    ) -> Result<(), signatory::ed25519::BatchError> {
+        signatory::ed25519::verify_batch_with(items, |public_key| Self::with_mode(public_key, mode))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::{Signer, Verifier};
     ed25519_tests!(Signer, Verifier);
+    ed25519_slip10_tests!(Signer);
+
+    #[cfg(feature = "cofactored")]
+    ed25519_context_verifier_tests!(Verifier);
+    #[cfg(feature = "cofactored")]
+    ed25519_verification_mode_tests!(Verifier);
+    #[cfg(all(feature = "alloc", feature = "cofactored"))]
+    ed25519_batch_tests!(Verifier);
+
+    #[cfg(not(feature = "cofactored"))]
+    #[test]
+    fn cofactored_modes_need_feature() {
+        use super::VerificationMode;
+        let seed = ed25519::Seed::from_bytes([0x42; 32]).unwrap();
+        let signer = Signer::from(&seed);
+        let signature = signer.sign(b"ring");
+        let public_key = signatory::public_key::PublicKeyed::public_key(&signer).unwrap();
+
+        assert!(Verifier::with_mode(&public_key, VerificationMode::Strict)
+            .verify(b"ring", &signature)
+            .is_ok());
+        for &mode in &[VerificationMode::Rfc8032, VerificationMode::Zip215] {
+            assert!(Verifier::with_mode(&public_key, mode)
+                .verify(b"ring", &signature)
+                .is_err());
+        }
+    }
 }
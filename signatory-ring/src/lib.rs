@@ -16,7 +16,7 @@ extern crate std;
 #[macro_use]
 extern crate signatory;
 
-/// ECDSA signing and verification support
+/// ECDSA signing and verification, and ECDH key agreement support
 #[cfg(feature = "ecdsa")]
 pub mod ecdsa;
 
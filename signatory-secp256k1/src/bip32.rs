@@ -0,0 +1,863 @@
+//! BIP32 hierarchical deterministic keys: extended private and public keys
+//! (`xprv`/`xpub`), child key derivation and derivation paths.
+//!
+//! Described in BIP32: <https://github.com/bitcoin/bips/blob/master/bip-0032.mediawiki>
+//!
+//! Derived keys are `SecretKey`s and `PublicKey`s which work with
+//! `EcdsaSigner` and `EcdsaVerifier`. In the (astronomically unlikely) case
+//! that BIP32 says a child key is invalid and the next index should be used,
+//! derivation returns an error instead.
+//!
+//! Extended private keys zeroize their secret key and chain code on drop.
+
+use crate::{PublicKey, SecretKey};
+use hmac::{Hmac, Mac};
+use lazy_static::lazy_static;
+use ripemd160::Ripemd160;
+use secp256k1::{self, All, Secp256k1};
+#[cfg(feature = "bip39")]
+use signatory::bip39::Mnemonic;
+use signatory::{
+    sha2::{Digest, Sha256, Sha512},
+    signature::Error,
+};
+use std::fmt::{self, Debug, Display};
+use std::str::FromStr;
+use zeroize::{Zeroize, Zeroizing};
+
+/// Version bytes of mainnet extended private keys (`xprv`)
+pub const XPRV_VERSION: [u8; 4] = [0x04, 0x88, 0xad, 0xe4];
+
+/// Version bytes of mainnet extended public keys (`xpub`)
+pub const XPUB_VERSION: [u8; 4] = [0x04, 0x88, 0xb2, 0x1e];
+
+/// Flag of hardened child numbers
+const HARDENED_FLAG: u32 = 1 << 31;
+
+/// Size of serialized extended keys, before Base58Check encoding
+const SERIALIZED_SIZE: usize = 78;
+
+/// Order of the secp256k1 group, big endian
+const CURVE_ORDER: [u8; 32] = [
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe,
+    0xba, 0xae, 0xdc, 0xe6, 0xaf, 0x48, 0xa0, 0x3b, 0xbf, 0xd2, 0x5e, 0x8c, 0xd0, 0x36, 0x41, 0x41,
+];
+
+lazy_static! {
+    /// secp256k1 engine shared by all derivations, as creating one is costly
+    static ref ENGINE: Secp256k1<All> = Secp256k1::new();
+}
+
+/// Index of a child key, hardened or not
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
+pub struct ChildNumber(u32);
+
+impl ChildNumber {
+    /// Normal (non-hardened) child number, with an index below 2^31
+    pub fn normal(index: u32) -> Result<Self, Error> {
+        if index & HARDENED_FLAG == 0 {
+            Ok(ChildNumber(index))
+        } else {
+            Err(Error::new())
+        }
+    }
+
+    /// Hardened child number, with an index below 2^31
+    pub fn hardened(index: u32) -> Result<Self, Error> {
+        Self::normal(index).map(|child| ChildNumber(child.0 | HARDENED_FLAG))
+    }
+
+    /// Is this a hardened child number?
+    pub fn is_hardened(self) -> bool {
+        self.0 & HARDENED_FLAG != 0
+    }
+
+    /// Index of the child, without the hardened flag
+    pub fn index(self) -> u32 {
+        self.0 & !HARDENED_FLAG
+    }
+
+    /// Child number as serialized: the index, plus 2^31 if hardened
+    pub fn to_u32(self) -> u32 {
+        self.0
+    }
+}
+
+impl From<u32> for ChildNumber {
+    /// Child number from its serialization: hardened from 2^31 on
+    fn from(child_number: u32) -> Self {
+        ChildNumber(child_number)
+    }
+}
+
+/// Index followed by `'` if hardened, e.g. `44'`
+impl Display for ChildNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.index())?;
+        if self.is_hardened() {
+            write!(f, "'")?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse an index followed by `'`, `h` or `H` if hardened
+impl FromStr for ChildNumber {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let (index, hardened) = match s.chars().last() {
+            Some('\'') | Some('h') | Some('H') => (&s[..s.len() - 1], true),
+            _ => (s, false),
+        };
+        // reject signs, which `u32::from_str` accepts
+        if index.is_empty() || !index.bytes().all(|byte| byte.is_ascii_digit()) {
+            return Err(Error::new());
+        }
+        let index = index.parse().map_err(|_| Error::new())?;
+        if hardened {
+            Self::hardened(index)
+        } else {
+            Self::normal(index)
+        }
+    }
+}
+
+/// Derivation path from a master key, e.g. `m/44'/118'/0'/0/0`
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct DerivationPath(Vec<ChildNumber>);
+
+impl DerivationPath {
+    /// Path of the given child of the key at this path
+    pub fn child(&self, child_number: ChildNumber) -> Self {
+        let mut path = self.clone();
+        path.0.push(child_number);
+        path
+    }
+
+    /// Iterate over the child numbers of the path
+    pub fn iter(&self) -> impl Iterator<Item = &ChildNumber> {
+        self.0.iter()
+    }
+}
+
+impl From<Vec<ChildNumber>> for DerivationPath {
+    fn from(child_numbers: Vec<ChildNumber>) -> Self {
+        DerivationPath(child_numbers)
+    }
+}
+
+impl AsRef<[ChildNumber]> for DerivationPath {
+    fn as_ref(&self) -> &[ChildNumber] {
+        &self.0
+    }
+}
+
+impl Display for DerivationPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "m")?;
+        for child_number in &self.0 {
+            write!(f, "/{}", child_number)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for DerivationPath {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let mut components = s.split('/');
+        if components.next() != Some("m") {
+            return Err(Error::new());
+        }
+        components
+            .map(str::parse)
+            .collect::<Result<_, _>>()
+            .map(DerivationPath)
+    }
+}
+
+/// BIP32 extended private key
+#[derive(Clone)]
+pub struct ExtendedPrivateKey {
+    depth: u8,
+    parent_fingerprint: [u8; 4],
+    child_number: ChildNumber,
+    chain_code: [u8; 32],
+    /// Bytes of a valid secp256k1 secret key
+    secret_key: [u8; 32],
+    public_key: secp256k1::PublicKey,
+}
+
+impl ExtendedPrivateKey {
+    /// Master key of a seed (e.g. from a BIP39 mnemonic), of 16 to 64 bytes
+    pub fn new_master(seed: &[u8]) -> Result<Self, Error> {
+        if seed.len() < 16 || seed.len() > 64 {
+            return Err(Error::new());
+        }
+        let (secret_key, chain_code) = hmac_sha512(b"Bitcoin seed", &[seed]);
+        let (secret_key, chain_code) = (Zeroizing::new(secret_key), Zeroizing::new(chain_code));
+        Self::from_parts(0, [0; 4], ChildNumber(0), &chain_code, &secret_key[..])
+    }
+
+    /// Key at the given path from the master key of a seed
+    pub fn derive_from_path(seed: &[u8], path: &DerivationPath) -> Result<Self, Error> {
+        Self::new_master(seed)?.derive_path(path)
+    }
+
+    /// Key at the given path from the master key of the seed of a BIP39
+    /// mnemonic with a passphrase (empty if none)
+    #[cfg(feature = "bip39")]
+    pub fn from_mnemonic(
+        mnemonic: &Mnemonic,
+        passphrase: &str,
+        path: &DerivationPath,
+    ) -> Result<Self, Error> {
+        Self::derive_from_path(mnemonic.to_seed(passphrase).as_bytes(), path)
+    }
+
+    /// Child key with the given number
+    pub fn derive_child(&self, child_number: ChildNumber) -> Result<Self, Error> {
+        let depth = self.depth.checked_add(1).ok_or_else(Error::new)?;
+        let public_key = self.public_key.serialize();
+        let mut hardened_key = Zeroizing::new([0u8; 33]);
+        let key: &[u8] = if child_number.is_hardened() {
+            hardened_key[1..].copy_from_slice(&self.secret_key);
+            &hardened_key[..]
+        } else {
+            &public_key
+        };
+        let (tweak, chain_code) = hmac_sha512(
+            &self.chain_code,
+            &[key, &child_number.to_u32().to_be_bytes()[..]],
+        );
+        let (tweak, chain_code) = (Zeroizing::new(tweak), Zeroizing::new(chain_code));
+
+        // add the tweak here rather than with libsecp256k1, whose `SecretKey`
+        // copies can't be zeroized: `from_parts` only needs one briefly
+        if !is_valid_secret_key(&tweak) {
+            return Err(Error::new());
+        }
+        let mut secret_key = Zeroizing::new([0u8; 32]);
+        add_scalars(&tweak, &self.secret_key, &mut secret_key);
+        Self::from_parts(
+            depth,
+            fingerprint(&public_key),
+            child_number,
+            &chain_code,
+            &secret_key[..],
+        )
+    }
+
+    /// Key at the given path from this key
+    pub fn derive_path(&self, path: &DerivationPath) -> Result<Self, Error> {
+        path.iter().try_fold(self.clone(), |key, child_number| {
+            key.derive_child(*child_number)
+        })
+    }
+
+    /// Secret key, for use with `EcdsaSigner`
+    pub fn secret_key(&self) -> SecretKey {
+        SecretKey::from_bytes(&self.secret_key[..]).unwrap()
+    }
+
+    /// Compressed public key
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey::from_bytes(&self.public_key.serialize()[..]).unwrap()
+    }
+
+    /// Extended public key, which derives the public keys of normal children
+    pub fn extended_public_key(&self) -> ExtendedPublicKey {
+        ExtendedPublicKey {
+            depth: self.depth,
+            parent_fingerprint: self.parent_fingerprint,
+            child_number: self.child_number,
+            chain_code: self.chain_code,
+            public_key: self.public_key,
+        }
+    }
+
+    /// Depth of the key: 0 for master keys
+    pub fn depth(&self) -> u8 {
+        self.depth
+    }
+
+    /// Child number of the key
+    pub fn child_number(&self) -> ChildNumber {
+        self.child_number
+    }
+
+    /// Fingerprint of the parent key
+    pub fn parent_fingerprint(&self) -> [u8; 4] {
+        self.parent_fingerprint
+    }
+
+    /// Fingerprint of the key: the first 4 bytes of the HASH160 of its
+    /// public key
+    pub fn fingerprint(&self) -> [u8; 4] {
+        fingerprint(&self.public_key.serialize())
+    }
+
+    /// Extended private key from its parts, checking the secret key is valid
+    fn from_parts(
+        depth: u8,
+        parent_fingerprint: [u8; 4],
+        child_number: ChildNumber,
+        chain_code: &[u8; 32],
+        secret_key: &[u8],
+    ) -> Result<Self, Error> {
+        // libsecp256k1 needs a (non-zeroizable) `SecretKey` to validate it
+        // and compute the public key, so do it once here rather than on use
+        let public_key = secp256k1::PublicKey::from_secret_key(
+            &ENGINE,
+            &secp256k1::SecretKey::from_slice(secret_key).map_err(Error::from_source)?,
+        );
+        let mut key = Self {
+            depth,
+            parent_fingerprint,
+            child_number,
+            chain_code: *chain_code,
+            secret_key: [0u8; 32],
+            public_key,
+        };
+        key.secret_key.copy_from_slice(secret_key);
+        Ok(key)
+    }
+}
+
+impl Drop for ExtendedPrivateKey {
+    fn drop(&mut self) {
+        self.chain_code.zeroize();
+        self.secret_key.zeroize();
+    }
+}
+
+/// Base58Check `xprv` serialization
+impl Display for ExtendedPrivateKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut key = [0u8; 33];
+        key[1..].copy_from_slice(&self.secret_key);
+        let encoded = serialize(
+            XPRV_VERSION,
+            self.depth,
+            self.parent_fingerprint,
+            self.child_number,
+            &self.chain_code,
+            &key,
+        );
+        key.zeroize();
+        write!(f, "{}", encoded)
+    }
+}
+
+impl FromStr for ExtendedPrivateKey {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let (depth, parent_fingerprint, child_number, mut chain_code, mut key) =
+            deserialize(s, XPRV_VERSION)?;
+        let result = if key[0] == 0 {
+            Self::from_parts(
+                depth,
+                parent_fingerprint,
+                child_number,
+                &chain_code,
+                &key[1..],
+            )
+        } else {
+            Err(Error::new())
+        };
+        chain_code.zeroize();
+        key.zeroize();
+        result
+    }
+}
+
+impl Debug for ExtendedPrivateKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExtendedPrivateKey")
+            .field("depth", &self.depth)
+            .field("parent_fingerprint", &self.parent_fingerprint)
+            .field("child_number", &self.child_number)
+            .finish()
+    }
+}
+
+/// BIP32 extended public key
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ExtendedPublicKey {
+    depth: u8,
+    parent_fingerprint: [u8; 4],
+    child_number: ChildNumber,
+    chain_code: [u8; 32],
+    public_key: secp256k1::PublicKey,
+}
+
+impl ExtendedPublicKey {
+    /// Public key of the child with the given number, which can't be
+    /// hardened
+    pub fn derive_child(&self, child_number: ChildNumber) -> Result<Self, Error> {
+        if child_number.is_hardened() {
+            return Err(Error::new());
+        }
+        let parent_key = self.public_key.serialize();
+        let (tweak, chain_code) = hmac_sha512(
+            &self.chain_code,
+            &[&parent_key[..], &child_number.to_u32().to_be_bytes()],
+        );
+        let mut public_key = self.public_key;
+        public_key
+            .add_exp_assign(&ENGINE, &tweak)
+            .map_err(Error::from_source)?;
+
+        Ok(Self {
+            depth: self.depth.checked_add(1).ok_or_else(Error::new)?,
+            parent_fingerprint: fingerprint(&parent_key),
+            child_number,
+            chain_code,
+            public_key,
+        })
+    }
+
+    /// Public key at the given path (of normal child numbers) from this key
+    pub fn derive_path(&self, path: &DerivationPath) -> Result<Self, Error> {
+        path.iter().try_fold(self.clone(), |key, child_number| {
+            key.derive_child(*child_number)
+        })
+    }
+
+    /// Compressed public key, for use with `EcdsaVerifier`
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey::from_bytes(&self.public_key.serialize()[..]).unwrap()
+    }
+
+    /// Depth of the key: 0 for master keys
+    pub fn depth(&self) -> u8 {
+        self.depth
+    }
+
+    /// Child number of the key
+    pub fn child_number(&self) -> ChildNumber {
+        self.child_number
+    }
+
+    /// Fingerprint of the parent key
+    pub fn parent_fingerprint(&self) -> [u8; 4] {
+        self.parent_fingerprint
+    }
+
+    /// Fingerprint of the key: the first 4 bytes of the HASH160 of its
+    /// public key
+    pub fn fingerprint(&self) -> [u8; 4] {
+        fingerprint(&self.public_key.serialize())
+    }
+}
+
+/// Base58Check `xpub` serialization
+impl Display for ExtendedPublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let encoded = serialize(
+            XPUB_VERSION,
+            self.depth,
+            self.parent_fingerprint,
+            self.child_number,
+            &self.chain_code,
+            &self.public_key.serialize(),
+        );
+        write!(f, "{}", encoded)
+    }
+}
+
+impl FromStr for ExtendedPublicKey {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let (depth, parent_fingerprint, child_number, chain_code, key) =
+            deserialize(s, XPUB_VERSION)?;
+        Ok(Self {
+            depth,
+            parent_fingerprint,
+            child_number,
+            chain_code,
+            public_key: secp256k1::PublicKey::from_slice(&key).map_err(Error::from_source)?,
+        })
+    }
+}
+
+/// Derive the secret key at a path (e.g. `m/44'/0'/0'/0/0`) from a BIP39
+/// mnemonic with a passphrase (empty if none)
+#[cfg(feature = "bip39")]
+pub fn secret_key_from_mnemonic(
+    mnemonic: &Mnemonic,
+    passphrase: &str,
+    path: &DerivationPath,
+) -> Result<SecretKey, Error> {
+    Ok(ExtendedPrivateKey::from_mnemonic(mnemonic, passphrase, path)?.secret_key())
+}
+
+/// Split `HMAC-SHA512(key, data)` into a key (or tweak) and a chain code
+fn hmac_sha512(key: &[u8], data: &[&[u8]]) -> ([u8; 32], [u8; 32]) {
+    let mut mac = Hmac::<Sha512>::new_varkey(key).unwrap();
+    for bytes in data {
+        mac.input(*bytes);
+    }
+    let mut output = mac.result().code();
+    let mut left = [0u8; 32];
+    let mut right = [0u8; 32];
+    left.copy_from_slice(&output[..32]);
+    right.copy_from_slice(&output[32..]);
+    output.as_mut_slice().zeroize();
+    (left, right)
+}
+
+/// Subtract the curve order from a big endian scalar into `out`, returning
+/// whether the scalar is at least the curve order (i.e. nothing was borrowed)
+fn sub_curve_order(scalar: &[u8; 32], out: &mut [u8; 32]) -> bool {
+    let mut borrow = 0i16;
+    for i in (0..32).rev() {
+        let diff = i16::from(scalar[i]) - i16::from(CURVE_ORDER[i]) - borrow;
+        out[i] = diff as u8;
+        borrow = i16::from(diff < 0);
+    }
+    borrow == 0
+}
+
+/// Whether a big endian scalar is a valid secret key, i.e. in `[1, n)`
+fn is_valid_secret_key(scalar: &[u8; 32]) -> bool {
+    let mut diff = Zeroizing::new([0u8; 32]);
+    scalar.iter().any(|&byte| byte != 0) && !sub_curve_order(scalar, &mut diff)
+}
+
+/// `(a + b) mod n` of big endian scalars lower than the curve order
+fn add_scalars(a: &[u8; 32], b: &[u8; 32], out: &mut [u8; 32]) {
+    let mut carry = 0u16;
+    for i in (0..32).rev() {
+        let sum = u16::from(a[i]) + u16::from(b[i]) + carry;
+        out[i] = sum as u8;
+        carry = sum >> 8;
+    }
+    let mut reduced = Zeroizing::new([0u8; 32]);
+    if sub_curve_order(out, &mut reduced) || carry != 0 {
+        out.copy_from_slice(&reduced[..]);
+    }
+}
+
+/// First 4 bytes of `RIPEMD160(SHA256(public key))`
+fn fingerprint(public_key: &[u8; 33]) -> [u8; 4] {
+    let hash = Ripemd160::digest(&Sha256::digest(public_key));
+    let mut fingerprint = [0u8; 4];
+    fingerprint.copy_from_slice(&hash[..4]);
+    fingerprint
+}
+
+fn serialize(
+    version: [u8; 4],
+    depth: u8,
+    parent_fingerprint: [u8; 4],
+    child_number: ChildNumber,
+    chain_code: &[u8; 32],
+    key: &[u8; 33],
+) -> String {
+    let mut bytes = Vec::with_capacity(SERIALIZED_SIZE);
+    bytes.extend_from_slice(&version);
+    bytes.push(depth);
+    bytes.extend_from_slice(&parent_fingerprint);
+    bytes.extend_from_slice(&child_number.to_u32().to_be_bytes());
+    bytes.extend_from_slice(chain_code);
+    bytes.extend_from_slice(key);
+    let encoded = bs58::encode(&bytes).with_check().into_string();
+    bytes.as_mut_slice().zeroize();
+    encoded
+}
+
+/// Decode a Base58Check extended key with the given version into its depth,
+/// parent fingerprint, child number, chain code and key
+fn deserialize(
+    s: &str,
+    version: [u8; 4],
+) -> Result<(u8, [u8; 4], ChildNumber, [u8; 32], [u8; 33]), Error> {
+    let mut bytes = bs58::decode(s)
+        .with_check(None)
+        .into_vec()
+        .map_err(|_| Error::new())?;
+    let result = parse_extended_key(&bytes, version);
+    bytes.as_mut_slice().zeroize();
+    result
+}
+
+/// Split the raw bytes of an extended key with the given version
+fn parse_extended_key(
+    bytes: &[u8],
+    version: [u8; 4],
+) -> Result<(u8, [u8; 4], ChildNumber, [u8; 32], [u8; 33]), Error> {
+    if bytes.len() != SERIALIZED_SIZE || bytes[..4] != version {
+        return Err(Error::new());
+    }
+    let depth = bytes[4];
+    let mut parent_fingerprint = [0u8; 4];
+    parent_fingerprint.copy_from_slice(&bytes[5..9]);
+    let mut child_number = [0u8; 4];
+    child_number.copy_from_slice(&bytes[9..13]);
+    let child_number = ChildNumber(u32::from_be_bytes(child_number));
+    // master keys have no parent
+    if depth == 0 && (parent_fingerprint != [0; 4] || child_number.to_u32() != 0) {
+        return Err(Error::new());
+    }
+    let mut chain_code = [0u8; 32];
+    chain_code.copy_from_slice(&bytes[13..45]);
+    let mut key = [0u8; 33];
+    key.copy_from_slice(&bytes[45..]);
+    Ok((depth, parent_fingerprint, child_number, chain_code, key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EcdsaSigner;
+    #[cfg(feature = "bip39")]
+    use signatory::bip39::Language;
+    use signatory::public_key::PublicKeyed;
+
+    fn from_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len() / 2)
+            .map(|i| u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap())
+            .collect()
+    }
+
+    /// Check the derivation of a seed along a path against the expected
+    /// `(xpub, xprv)` of the master key and each child
+    fn check_vector(seed: &str, path: &str, expected: &[(&str, &str)]) {
+        let path: DerivationPath = path.parse().unwrap();
+        let mut key = ExtendedPrivateKey::new_master(&from_hex(seed)).unwrap();
+        let mut child_numbers = path.iter();
+        for (i, (xpub, xprv)) in expected.iter().enumerate() {
+            if i > 0 {
+                let child_number = *child_numbers.next().unwrap();
+                let parent = key.extended_public_key();
+                key = key.derive_child(child_number).unwrap();
+                if !child_number.is_hardened() {
+                    assert_eq!(
+                        parent.derive_child(child_number).unwrap(),
+                        key.extended_public_key()
+                    );
+                }
+            }
+            assert_eq!(key.to_string(), **xprv);
+            assert_eq!(key.extended_public_key().to_string(), **xpub);
+            assert_eq!(
+                xprv.parse::<ExtendedPrivateKey>().unwrap().to_string(),
+                *xprv
+            );
+            assert_eq!(
+                xpub.parse::<ExtendedPublicKey>().unwrap(),
+                key.extended_public_key()
+            );
+        }
+        assert_eq!(
+            ExtendedPrivateKey::derive_from_path(&from_hex(seed), &path)
+                .unwrap()
+                .to_string(),
+            expected.last().unwrap().1
+        );
+    }
+
+    #[test]
+    fn bip32_test_vector_1() {
+        check_vector(
+            "000102030405060708090a0b0c0d0e0f",
+            "m/0'/1/2'/2/1000000000",
+            &[
+                (
+                    "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8",
+                    "xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHi",
+                ),
+                (
+                    "xpub68Gmy5EdvgibQVfPdqkBBCHxA5htiqg55crXYuXoQRKfDBFA1WEjWgP6LHhwBZeNK1VTsfTFUHCdrfp1bgwQ9xv5ski8PX9rL2dZXvgGDnw",
+                    "xprv9uHRZZhk6KAJC1avXpDAp4MDc3sQKNxDiPvvkX8Br5ngLNv1TxvUxt4cV1rGL5hj6KCesnDYUhd7oWgT11eZG7XnxHrnYeSvkzY7d2bhkJ7",
+                ),
+                (
+                    "xpub6ASuArnXKPbfEwhqN6e3mwBcDTgzisQN1wXN9BJcM47sSikHjJf3UFHKkNAWbWMiGj7Wf5uMash7SyYq527Hqck2AxYysAA7xmALppuCkwQ",
+                    "xprv9wTYmMFdV23N2TdNG573QoEsfRrWKQgWeibmLntzniatZvR9BmLnvSxqu53Kw1UmYPxLgboyZQaXwTCg8MSY3H2EU4pWcQDnRnrVA1xe8fs",
+                ),
+                (
+                    "xpub6D4BDPcP2GT577Vvch3R8wDkScZWzQzMMUm3PWbmWvVJrZwQY4VUNgqFJPMM3No2dFDFGTsxxpG5uJh7n7epu4trkrX7x7DogT5Uv6fcLW5",
+                    "xprv9z4pot5VBttmtdRTWfWQmoH1taj2axGVzFqSb8C9xaxKymcFzXBDptWmT7FwuEzG3ryjH4ktypQSAewRiNMjANTtpgP4mLTj34bhnZX7UiM",
+                ),
+                (
+                    "xpub6FHa3pjLCk84BayeJxFW2SP4XRrFd1JYnxeLeU8EqN3vDfZmbqBqaGJAyiLjTAwm6ZLRQUMv1ZACTj37sR62cfN7fe5JnJ7dh8zL4fiyLHV",
+                    "xprvA2JDeKCSNNZky6uBCviVfJSKyQ1mDYahRjijr5idH2WwLsEd4Hsb2Tyh8RfQMuPh7f7RtyzTtdrbdqqsunu5Mm3wDvUAKRHSC34sJ7in334",
+                ),
+                (
+                    "xpub6H1LXWLaKsWFhvm6RVpEL9P4KfRZSW7abD2ttkWP3SSQvnyA8FSVqNTEcYFgJS2UaFcxupHiYkro49S8yGasTvXEYBVPamhGW6cFJodrTHy",
+                    "xprvA41z7zogVVwxVSgdKUHDy1SKmdb533PjDz7J6N6mV6uS3ze1ai8FHa8kmHScGpWmj4WggLyQjgPie1rFSruoUihUZREPSL39UNdE3BBDu76",
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn bip32_test_vector_2() {
+        check_vector(
+            "fffcf9f6f3f0edeae7e4e1dedbd8d5d2cfccc9c6c3c0bdbab7b4b1aeaba8a5a29f9c999693908d8a8784817e7b7875726f6c696663605d5a5754514e4b484542",
+            "m/0/2147483647'/1/2147483646'/2",
+            &[
+                (
+                    "xpub661MyMwAqRbcFW31YEwpkMuc5THy2PSt5bDMsktWQcFF8syAmRUapSCGu8ED9W6oDMSgv6Zz8idoc4a6mr8BDzTJY47LJhkJ8UB7WEGuduB",
+                    "xprv9s21ZrQH143K31xYSDQpPDxsXRTUcvj2iNHm5NUtrGiGG5e2DtALGdso3pGz6ssrdK4PFmM8NSpSBHNqPqm55Qn3LqFtT2emdEXVYsCzC2U",
+                ),
+                (
+                    "xpub69H7F5d8KSRgmmdJg2KhpAK8SR3DjMwAdkxj3ZuxV27CprR9LgpeyGmXUbC6wb7ERfvrnKZjXoUmmDznezpbZb7ap6r1D3tgFxHmwMkQTPH",
+                    "xprv9vHkqa6EV4sPZHYqZznhT2NPtPCjKuDKGY38FBWLvgaDx45zo9WQRUT3dKYnjwih2yJD9mkrocEZXo1ex8G81dwSM1fwqWpWkeS3v86pgKt",
+                ),
+                (
+                    "xpub6ASAVgeehLbnwdqV6UKMHVzgqAG8Gr6riv3Fxxpj8ksbH9ebxaEyBLZ85ySDhKiLDBrQSARLq1uNRts8RuJiHjaDMBU4Zn9h8LZNnBC5y4a",
+                    "xprv9wSp6B7kry3Vj9m1zSnLvN3xH8RdsPP1Mh7fAaR7aRLcQMKTR2vidYEeEg2mUCTAwCd6vnxVrcjfy2kRgVsFawNzmjuHc2YmYRmagcEPdU9",
+                ),
+                (
+                    "xpub6DF8uhdarytz3FWdA8TvFSvvAh8dP3283MY7p2V4SeE2wyWmG5mg5EwVvmdMVCQcoNJxGoWaU9DCWh89LojfZ537wTfunKau47EL2dhHKon",
+                    "xprv9zFnWC6h2cLgpmSA46vutJzBcfJ8yaJGg8cX1e5StJh45BBciYTRXSd25UEPVuesF9yog62tGAQtHjXajPPdbRCHuWS6T8XA2ECKADdw4Ef",
+                ),
+                (
+                    "xpub6ERApfZwUNrhLCkDtcHTcxd75RbzS1ed54G1LkBUHQVHQKqhMkhgbmJbZRkrgZw4koxb5JaHWkY4ALHY2grBGRjaDMzQLcgJvLJuZZvRcEL",
+                    "xprvA1RpRA33e1JQ7ifknakTFpgNXPmW2YvmhqLQYMmrj4xJXXWYpDPS3xz7iAxn8L39njGVyuoseXzU6rcxFLJ8HFsTjSyQbLYnMpCqE2VbFWc",
+                ),
+                (
+                    "xpub6FnCn6nSzZAw5Tw7cgR9bi15UV96gLZhjDstkXXxvCLsUXBGXPdSnLFbdpq8p9HmGsApME5hQTZ3emM2rnY5agb9rXpVGyy3bdW6EEgAtqt",
+                    "xprvA2nrNbFZABcdryreWet9Ea4LvTJcGsqrMzxHx98MMrotbir7yrKCEXw7nadnHM8Dq38EGfSh6dqA9QWTyefMLEcBYJUuekgW4BYPJcr9E7j",
+                ),
+            ],
+        );
+    }
+
+    /// Retention of leading zeros of private keys
+    #[test]
+    fn bip32_test_vector_3() {
+        check_vector(
+            "4b381541583be4423346c643850da4b320e46a87ae3d2a4e6da11eba819cd4acba45d239319ac14f863b8d5ab5a0d0c64d2e8a1e7d1457df2e5a3c51c73235be",
+            "m/0'",
+            &[
+                (
+                    "xpub661MyMwAqRbcEZVB4dScxMAdx6d4nFc9nvyvH3v4gJL378CSRZiYmhRoP7mBy6gSPSCYk6SzXPTf3ND1cZAceL7SfJ1Z3GC8vBgp2epUt13",
+                    "xprv9s21ZrQH143K25QhxbucbDDuQ4naNntJRi4KUfWT7xo4EKsHt2QJDu7KXp1A3u7Bi1j8ph3EGsZ9Xvz9dGuVrtHHs7pXeTzjuxBrCmmhgC6",
+                ),
+                (
+                    "xpub68NZiKmJWnxxS6aaHmn81bvJeTESw724CRDs6HbuccFQN9Ku14VQrADWgqbhhTHBaohPX4CjNLf9fq9MYo6oDaPPLPxSb7gwQN3ih19Zm4Y",
+                    "xprv9uPDJpEQgRQfDcW7BkF7eTya6RPxXeJCqCJGHuCJ4GiRVLzkTXBAJMu2qaMWPrS7AANYqdq6vcBcBUdJCVVFceUvJFjaPdGZ2y9WACViL4L",
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn derivation_paths() {
+        let path: DerivationPath = "m/44'/118'/0'/0/0".parse().unwrap();
+        assert_eq!(
+            path.as_ref(),
+            &[
+                ChildNumber::hardened(44).unwrap(),
+                ChildNumber::hardened(118).unwrap(),
+                ChildNumber::hardened(0).unwrap(),
+                ChildNumber::normal(0).unwrap(),
+                ChildNumber::normal(0).unwrap(),
+            ]
+        );
+        assert_eq!(path.to_string(), "m/44'/118'/0'/0/0");
+        assert_eq!(
+            "m/44h/118H/0"
+                .parse::<DerivationPath>()
+                .unwrap()
+                .to_string(),
+            "m/44'/118'/0"
+        );
+        assert_eq!(
+            "m".parse::<DerivationPath>().unwrap(),
+            DerivationPath::default()
+        );
+
+        for invalid in &[
+            "",
+            "44'/0",
+            "m/",
+            "m/-1",
+            "m/+1",
+            "m/2147483648",
+            "m/0''",
+            "m//0",
+        ] {
+            assert!(invalid.parse::<DerivationPath>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn derived_keys_sign() {
+        let path = "m/44'/118'/0'/0/0".parse().unwrap();
+        let key = ExtendedPrivateKey::derive_from_path(&[0x42; 32], &path).unwrap();
+        let signer = EcdsaSigner::from(&key.secret_key());
+        assert_eq!(signer.public_key().unwrap(), key.public_key());
+        assert_eq!(key.depth(), 5);
+        assert_eq!(key.child_number(), ChildNumber::normal(0).unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "bip39")]
+    fn mnemonic_secret_key() {
+        let mnemonic = Mnemonic::from_phrase(
+            Language::English,
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+        )
+        .unwrap();
+        let path = "m/44'/0'/0'/0/0".parse().unwrap();
+        let secret_key = secret_key_from_mnemonic(&mnemonic, "", &path).unwrap();
+        assert_eq!(
+            secret_key.as_secret_slice(),
+            &from_hex("e284129cc0922579a535bbf4d1a3b25773090d28c909bc0fed73b5e0222cc372")[..]
+        );
+    }
+
+    #[test]
+    fn hardened_public_derivation_fails() {
+        let key = ExtendedPrivateKey::new_master(&[0x42; 32]).unwrap();
+        assert!(key
+            .extended_public_key()
+            .derive_child(ChildNumber::hardened(0).unwrap())
+            .is_err());
+    }
+
+    #[test]
+    fn invalid_extended_keys() {
+        let xprv = "xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHi";
+        let xpub = "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8";
+        // wrong version
+        assert!(xprv.parse::<ExtendedPublicKey>().is_err());
+        assert!(xpub.parse::<ExtendedPrivateKey>().is_err());
+        // bad checksum
+        let mut tampered = xprv.to_owned();
+        tampered.pop();
+        tampered.push('j');
+        assert!(tampered.parse::<ExtendedPrivateKey>().is_err());
+    }
+
+    #[test]
+    fn scalar_arithmetic() {
+        let mut one = [0u8; 32];
+        one[31] = 1;
+        let mut order_minus_one = CURVE_ORDER;
+        order_minus_one[31] -= 1;
+
+        assert!(is_valid_secret_key(&one));
+        assert!(is_valid_secret_key(&order_minus_one));
+        assert!(!is_valid_secret_key(&[0u8; 32]));
+        assert!(!is_valid_secret_key(&CURVE_ORDER));
+        assert!(!is_valid_secret_key(&[0xff; 32]));
+
+        // (n - 1) + 1 = 0 and (n - 1) + (n - 1) = n - 2 (with a carry out)
+        let mut sum = [0u8; 32];
+        add_scalars(&order_minus_one, &one, &mut sum);
+        assert_eq!(sum, [0u8; 32]);
+        add_scalars(&order_minus_one, &order_minus_one, &mut sum);
+        let mut order_minus_two = CURVE_ORDER;
+        order_minus_two[31] -= 2;
+        assert_eq!(sum, order_minus_two);
+    }
+}
@@ -0,0 +1,81 @@
+//! ECDH key agreement over secp256k1 with static (i.e. reusable) keys.
+//!
+//! Shared secrets are the ones computed by libsecp256k1's `secp256k1_ecdh`:
+//! the SHA-256 hash of the compressed shared point, rather than its bare
+//! x-coordinate as is usual for ECDH.
+
+use crate::{PublicKey, SecretKey};
+use secp256k1::{self, ecdh, Secp256k1, SignOnly};
+use signatory::{
+    ecdsa::{curve::Secp256k1 as Curve, KeyAgreement, SharedSecret},
+    public_key::PublicKeyed,
+    signature::Error,
+};
+
+/// Static ECDH secret for the secp256k1 crate, which can agree on any
+/// number of shared secrets
+pub struct StaticSecret {
+    /// ECDSA secret key
+    secret_key: secp256k1::SecretKey,
+
+    /// secp256k1 engine
+    engine: Secp256k1<SignOnly>,
+}
+
+impl<'a> From<&'a SecretKey> for StaticSecret {
+    /// Create a new static ECDH secret from the given `SecretKey`
+    fn from(secret_key: &'a SecretKey) -> StaticSecret {
+        let secret_key = secp256k1::SecretKey::from_slice(secret_key.as_secret_slice()).unwrap();
+        let engine = Secp256k1::signing_only();
+        StaticSecret { secret_key, engine }
+    }
+}
+
+impl PublicKeyed<PublicKey> for StaticSecret {
+    /// Return the public key that corresponds to this secret
+    fn public_key(&self) -> Result<PublicKey, Error> {
+        let public_key = secp256k1::PublicKey::from_secret_key(&self.engine, &self.secret_key);
+        PublicKey::from_bytes(&public_key.serialize()[..]).ok_or_else(Error::new)
+    }
+}
+
+impl<'a> KeyAgreement<Curve> for &'a StaticSecret {
+    /// Compute the SHA-256 hash of the (compressed) point shared with the
+    /// owner of the given public key
+    fn agree(self, public_key: &PublicKey) -> Result<SharedSecret<Curve>, Error> {
+        let public_key =
+            secp256k1::PublicKey::from_slice(public_key.as_ref()).map_err(|_| Error::new())?;
+        let shared_secret = ecdh::SharedSecret::new(&public_key, &self.secret_key);
+        SharedSecret::from_bytes(&shared_secret[..]).ok_or_else(Error::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Shared secret of the `[0x11; 32]` and `[0x22; 32]` secret keys
+    const SHARED_SECRET: &[u8] = b"\xB3\x6B\x6D\x19\x59\x82\xC5\xBE\x87\x4D\x6D\x54\x2D\xC2\x68\x23\
+                                   \x43\x79\xE1\xAE\x4F\xF1\x70\x94\x02\x13\x5B\x7D\xE5\xCF\x07\x66";
+
+    #[test]
+    fn static_key_agreement() {
+        let alice = StaticSecret::from(&SecretKey::from_bytes(&[0x11; 32]).unwrap());
+        let bob = StaticSecret::from(&SecretKey::from_bytes(&[0x22; 32]).unwrap());
+
+        let alice_public_key = alice.public_key().unwrap();
+        let bob_public_key = bob.public_key().unwrap();
+        assert_eq!(
+            alice_public_key.as_ref(),
+            &b"\x03\x4F\x35\x5B\xDC\xB7\xCC\x0A\xF7\x28\xEF\x3C\xCE\xB9\x61\x5D\x90\
+               \x68\x4B\xB5\xB2\xCA\x5F\x85\x9A\xB0\xF0\xB7\x04\x07\x58\x71\xAA"[..]
+        );
+
+        let shared_secret = (&alice).agree(&bob_public_key).unwrap();
+        assert_eq!(shared_secret.as_secret_slice(), SHARED_SECRET);
+
+        // Static secrets can be used again
+        let shared_secret = (&bob).agree(&alice_public_key).unwrap();
+        assert_eq!(shared_secret.as_secret_slice(), SHARED_SECRET);
+    }
+}
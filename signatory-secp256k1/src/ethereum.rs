@@ -0,0 +1,363 @@
+//! Ethereum support: Keccak-256 message digests for `personal_sign`
+//! (EIP-191) and typed data (EIP-712), and addresses (EIP-55).
+//!
+//! Ethereum signs Keccak-256 digests with recoverable signatures: use
+//! `DigestSigner<Keccak256, RecoverableSignature>` with the digests computed
+//! here, and `recover_address` in place of `ecrecover`. Ethereum encodes the
+//! recovery ID of signatures as `v = 27 + recovery ID` (or with the chain ID
+//! for transactions, see EIP-155).
+
+use crate::{recover_public_key_from_digest, PublicKey};
+use signatory::{
+    ecdsa::{generic_array::typenum::U32, RecoverableSignature},
+    signature::{digest::Digest, Error},
+};
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+pub use sha3::Keccak256;
+
+/// Size of an Ethereum address
+pub const ADDRESS_SIZE: usize = 20;
+
+/// Ethereum address: the last 20 bytes of the Keccak-256 digest of an
+/// uncompressed public key
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Address([u8; ADDRESS_SIZE]);
+
+impl Address {
+    /// Create an address from its bytes
+    pub fn new(bytes: [u8; ADDRESS_SIZE]) -> Self {
+        Address(bytes)
+    }
+
+    /// Address of a public key (compressed or uncompressed)
+    pub fn from_public_key(public_key: &PublicKey) -> Result<Self, Error> {
+        let public_key =
+            secp256k1::PublicKey::from_slice(public_key.as_bytes()).map_err(Error::from_source)?;
+        // the digest of the point without its 0x04 tag
+        let digest = Keccak256::digest(&public_key.serialize_uncompressed()[1..]);
+        let mut bytes = [0u8; ADDRESS_SIZE];
+        bytes.copy_from_slice(&digest[32 - ADDRESS_SIZE..]);
+        Ok(Address(bytes))
+    }
+
+    /// Bytes of the address
+    pub fn as_bytes(&self) -> &[u8; ADDRESS_SIZE] {
+        &self.0
+    }
+
+    /// Address with the mixed-case checksum of EIP-55, without `0x`
+    fn to_checksum_hex(&self) -> String {
+        let hex: String = self.0.iter().map(|byte| format!("{:02x}", byte)).collect();
+        let digest = Keccak256::digest(hex.as_bytes());
+        hex.chars()
+            .enumerate()
+            .map(|(i, c)| {
+                let nibble = (digest[i / 2] >> (4 * (1 - i % 2))) & 0xf;
+                if nibble >= 8 {
+                    c.to_ascii_uppercase()
+                } else {
+                    c
+                }
+            })
+            .collect()
+    }
+}
+
+/// `0x`-prefixed address with the EIP-55 checksum
+impl Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x{}", self.to_checksum_hex())
+    }
+}
+
+/// Parse a hex address, with or without `0x`. Mixed-case addresses must
+/// have a valid EIP-55 checksum.
+impl FromStr for Address {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let hex = if s.starts_with("0x") { &s[2..] } else { s };
+        // reject signs, which `u8::from_str_radix` accepts
+        if hex.len() != 2 * ADDRESS_SIZE || !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+            return Err(Error::new());
+        }
+        let mut bytes = [0u8; ADDRESS_SIZE];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).map_err(|_| Error::new())?;
+        }
+        let address = Address(bytes);
+        let mixed_case = hex.chars().any(|c| c.is_ascii_lowercase())
+            && hex.chars().any(|c| c.is_ascii_uppercase());
+        if mixed_case && address.to_checksum_hex() != hex {
+            return Err(Error::new());
+        }
+        Ok(address)
+    }
+}
+
+/// Recover the address which signed a 32-byte digest from a recoverable
+/// signature, i.e. `ecrecover`
+pub fn recover_address<D>(digest: D, signature: &RecoverableSignature) -> Result<Address, Error>
+where
+    D: Digest<OutputSize = U32>,
+{
+    Address::from_public_key(&recover_public_key_from_digest(digest, signature)?)
+}
+
+/// Digest signed by `personal_sign` (EIP-191 version `0x45`):
+/// `keccak256("\x19Ethereum Signed Message:\n" || len(message) || message)`
+pub fn personal_message_digest(message: &[u8]) -> Keccak256 {
+    Keccak256::new()
+        .chain(b"\x19Ethereum Signed Message:\n")
+        .chain(message.len().to_string().as_bytes())
+        .chain(message)
+}
+
+/// Digest signed for EIP-712 typed data (EIP-191 version `0x01`):
+/// `keccak256("\x19\x01" || domainSeparator || hashStruct(message))`
+pub fn typed_data_digest(domain_separator: &[u8; 32], struct_hash: &[u8; 32]) -> Keccak256 {
+    Keccak256::new()
+        .chain(b"\x19\x01")
+        .chain(&domain_separator[..])
+        .chain(&struct_hash[..])
+}
+
+/// EIP-712 `typeHash` of a struct type: the Keccak-256 digest of its
+/// encoding, e.g. `Mail(Person from,Person to,string contents)Person(string
+/// name,address wallet)`
+pub fn type_hash(encoded_type: &str) -> [u8; 32] {
+    keccak256(encoded_type.as_bytes())
+}
+
+/// EIP-712 `hashStruct`: the Keccak-256 digest of the type hash followed by
+/// the encoding of each member, in order (see the `encode_*` functions, and
+/// `hash_struct` itself for struct members)
+pub fn hash_struct(type_hash: &[u8; 32], encoded_members: &[[u8; 32]]) -> [u8; 32] {
+    let mut hasher = Keccak256::new().chain(&type_hash[..]);
+    for member in encoded_members {
+        hasher.input(&member[..]);
+    }
+    finalize(hasher)
+}
+
+/// EIP-712 encoding of a `string` member
+pub fn encode_string(value: &str) -> [u8; 32] {
+    encode_bytes(value.as_bytes())
+}
+
+/// EIP-712 encoding of a `bytes` member
+pub fn encode_bytes(value: &[u8]) -> [u8; 32] {
+    keccak256(value)
+}
+
+/// EIP-712 encoding of an unsigned integer member (`uint8` to `uint256`),
+/// given as big endian bytes (e.g. `u64::to_be_bytes`) of at most 32 bytes
+pub fn encode_uint(value: &[u8]) -> Result<[u8; 32], Error> {
+    if value.len() > 32 {
+        return Err(Error::new());
+    }
+    Ok(pad_left(value))
+}
+
+/// EIP-712 encoding of a `bool` member
+pub fn encode_bool(value: bool) -> [u8; 32] {
+    pad_left(&[value as u8])
+}
+
+/// EIP-712 encoding of an `address` member
+pub fn encode_address(value: &Address) -> [u8; 32] {
+    pad_left(value.as_bytes())
+}
+
+/// EIP-712 domain, with all its optional fields but the salt
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Eip712Domain {
+    /// Name of the signing domain, e.g. the DApp
+    pub name: String,
+    /// Current major version of the signing domain
+    pub version: String,
+    /// EIP-155 chain ID
+    pub chain_id: u64,
+    /// Address of the contract verifying the signature
+    pub verifying_contract: Address,
+}
+
+impl Eip712Domain {
+    /// `domainSeparator`: `hashStruct` of the domain
+    pub fn separator(&self) -> [u8; 32] {
+        hash_struct(
+            &type_hash(
+                "EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)",
+            ),
+            &[
+                encode_string(&self.name),
+                encode_string(&self.version),
+                pad_left(&self.chain_id.to_be_bytes()),
+                encode_address(&self.verifying_contract),
+            ],
+        )
+    }
+}
+
+/// Zero-extend a big endian value of at most 32 bytes to 32 bytes
+fn pad_left(value: &[u8]) -> [u8; 32] {
+    let mut padded = [0u8; 32];
+    padded[32 - value.len()..].copy_from_slice(value);
+    padded
+}
+
+fn finalize(hasher: Keccak256) -> [u8; 32] {
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(&hasher.result());
+    hash
+}
+
+fn keccak256(data: &[u8]) -> [u8; 32] {
+    finalize(Keccak256::new().chain(data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EcdsaSigner, SecretKey};
+    use signatory::{public_key::PublicKeyed, signature::DigestSigner};
+
+    fn from_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len() / 2)
+            .map(|i| u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn address_of_public_key() {
+        // EIP-155 example
+        let signer = EcdsaSigner::from(&SecretKey::from_bytes([0x46; 32]).unwrap());
+        let address = Address::from_public_key(&signer.public_key().unwrap()).unwrap();
+        assert_eq!(
+            address.to_string(),
+            "0x9d8A62f656a8d1615C1294fd71e9CFb3E4855A4F"
+        );
+    }
+
+    #[test]
+    fn checksum_addresses() {
+        // EIP-55 examples
+        for checksummed in &[
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+            "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+            "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+        ] {
+            let address: Address = checksummed.parse().unwrap();
+            assert_eq!(&address.to_string(), checksummed);
+            let lower_case: Address = checksummed.to_lowercase().parse().unwrap();
+            assert_eq!(lower_case, address);
+        }
+        assert!("0x5AAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"
+            .parse::<Address>()
+            .is_err());
+        assert!("0x5aaeb6053f3e94c9b9a09f33669435e7ef1bea"
+            .parse::<Address>()
+            .is_err());
+        // signs are not hex digits
+        assert!("0x+aaeb6053f3e94c9b9a09f33669435e7ef1beaed"
+            .parse::<Address>()
+            .is_err());
+    }
+
+    #[test]
+    fn uint_encoding() {
+        let mut expected = [0u8; 32];
+        expected[30] = 0x01;
+        expected[31] = 0x02;
+        assert_eq!(encode_uint(&[1, 2]).unwrap(), expected);
+        assert_eq!(encode_uint(&[0xff; 32]).unwrap(), [0xff; 32]);
+        assert!(encode_uint(&[0; 33]).is_err());
+        expected[30] = 0;
+        expected[31] = 1;
+        assert_eq!(encode_bool(true), expected);
+    }
+
+    #[test]
+    fn typed_data() {
+        // the `Mail` example of EIP-712
+        let domain = Eip712Domain {
+            name: "Ether Mail".to_owned(),
+            version: "1".to_owned(),
+            chain_id: 1,
+            verifying_contract: "0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC"
+                .parse()
+                .unwrap(),
+        };
+        assert_eq!(
+            domain.separator().to_vec(),
+            from_hex("f2cee375fa42b42143804025fc449deafd50cc031ca257e0b194a650a912090f")
+        );
+
+        let person_type = type_hash("Person(string name,address wallet)");
+        let person = |name: &str, wallet: &str| {
+            hash_struct(
+                &person_type,
+                &[
+                    encode_string(name),
+                    encode_address(&wallet.parse().unwrap()),
+                ],
+            )
+        };
+        let mail = hash_struct(
+            &type_hash(
+                "Mail(Person from,Person to,string contents)Person(string name,address wallet)",
+            ),
+            &[
+                person("Cow", "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826"),
+                person("Bob", "0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB"),
+                encode_string("Hello, Bob!"),
+            ],
+        );
+        let digest = typed_data_digest(&domain.separator(), &mail);
+        assert_eq!(
+            digest.clone().result().to_vec(),
+            from_hex("be609aee343fb3c4b28e1df9e632fca64fcfaede20f02e86244efddf30957bd2")
+        );
+
+        // signed by Cow
+        let signer = EcdsaSigner::from(&SecretKey::from_bytes(keccak256(b"cow")).unwrap());
+        let signature: RecoverableSignature = signer.sign_digest(digest.clone());
+        assert_eq!(
+            &signature.as_ref()[..64],
+            &from_hex(concat!(
+                "4355c47d63924e8a72e509b65029052eb6c299d53a04e167c5775fd466751c9d",
+                "07299936d304c153f6443dfa05f40ff007d72911b6f72307f996231605b91562"
+            ))[..]
+        );
+        assert_eq!(signature.recovery_id() + 27, 28);
+        assert_eq!(
+            recover_address(digest, &signature).unwrap().to_string(),
+            "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826"
+        );
+    }
+
+    #[test]
+    fn personal_sign() {
+        let signer = EcdsaSigner::from(&SecretKey::from_bytes([0x46; 32]).unwrap());
+        let address = Address::from_public_key(&signer.public_key().unwrap()).unwrap();
+
+        let message = b"hello world";
+        let signature: RecoverableSignature = signer.sign_digest(personal_message_digest(message));
+        assert_eq!(
+            recover_address(personal_message_digest(message), &signature).unwrap(),
+            address
+        );
+        assert_ne!(
+            recover_address(personal_message_digest(b"goodbye"), &signature).ok(),
+            Some(address)
+        );
+        // personal messages are not signed as raw Keccak-256 digests
+        assert_ne!(
+            recover_address(Keccak256::new().chain(&message[..]), &signature).ok(),
+            Some(address)
+        );
+    }
+}
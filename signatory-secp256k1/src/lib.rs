@@ -1,4 +1,4 @@
-//! ECDSA provider for the `secp256k1` crate (a.k.a. secp256k1-rs)
+//! ECDSA, ECDH and BIP340 Schnorr provider for the `secp256k1` crate (a.k.a. secp256k1-rs)
 
 #![forbid(unsafe_code)]
 #![warn(missing_docs, rust_2018_idioms, unused_qualifications)]
@@ -7,9 +7,26 @@
     html_root_url = "https://docs.rs/signatory-secp256k1/0.16.0"
 )]
 
-use secp256k1::{self, Secp256k1, SignOnly, VerifyOnly};
+#[cfg(feature = "bip32")]
+pub mod bip32;
+#[cfg(feature = "ecdh")]
+pub mod ecdh;
+#[cfg(feature = "ethereum")]
+pub mod ethereum;
+#[cfg(feature = "schnorr")]
+pub mod schnorr;
+
+use secp256k1::{
+    self,
+    recovery::{RecoverableSignature as RawRecoverableSignature, RecoveryId},
+    Secp256k1, SignOnly, VerifyOnly,
+};
 use signatory::{
-    ecdsa::curve::secp256k1::{Asn1Signature, FixedSignature},
+    ecdsa::{
+        curve::secp256k1::{Asn1Signature, FixedSignature},
+        generic_array::typenum::U32,
+        RecoverableSignature, VerificationMode,
+    },
     public_key::PublicKeyed,
     sha2::Sha256,
     signature::{digest::Digest, DigestSigner, DigestVerifier, Error, Signature, Signer, Verifier},
@@ -48,16 +65,23 @@ impl PublicKeyed<PublicKey> for EcdsaSigner {
     }
 }
 
-impl DigestSigner<Sha256, Asn1Signature> for EcdsaSigner {
-    /// Compute an ASN.1 DER-encoded signature of the given 32-byte SHA-256 digest
-    fn try_sign_digest(&self, digest: Sha256) -> Result<Asn1Signature, Error> {
+impl<D> DigestSigner<D, Asn1Signature> for EcdsaSigner
+where
+    D: Digest<OutputSize = U32>,
+{
+    /// Compute an ASN.1 DER-encoded signature of the given 32-byte digest
+    /// (SHA-256, or e.g. Keccak-256 for Ethereum)
+    fn try_sign_digest(&self, digest: D) -> Result<Asn1Signature, Error> {
         Ok(Asn1Signature::from_bytes(self.raw_sign_digest(digest)?.serialize_der()).unwrap())
     }
 }
 
-impl DigestSigner<Sha256, FixedSignature> for EcdsaSigner {
-    /// Compute a compact, fixed-sized signature of the given 32-byte SHA-256 digest
-    fn try_sign_digest(&self, digest: Sha256) -> Result<FixedSignature, Error> {
+impl<D> DigestSigner<D, FixedSignature> for EcdsaSigner
+where
+    D: Digest<OutputSize = U32>,
+{
+    /// Compute a compact, fixed-sized signature of the given 32-byte digest
+    fn try_sign_digest(&self, digest: D) -> Result<FixedSignature, Error> {
         Ok(
             FixedSignature::from_bytes(&self.raw_sign_digest(digest)?.serialize_compact()[..])
                 .unwrap(),
@@ -65,12 +89,32 @@ impl DigestSigner<Sha256, FixedSignature> for EcdsaSigner {
     }
 }
 
+impl<D> DigestSigner<D, RecoverableSignature> for EcdsaSigner
+where
+    D: Digest<OutputSize = U32>,
+{
+    /// Compute a recoverable signature of the given 32-byte digest
+    fn try_sign_digest(&self, digest: D) -> Result<RecoverableSignature, Error> {
+        let msg = digest_message(digest)?;
+        let (recovery_id, signature) = self
+            .engine
+            .sign_recoverable(&msg, &self.secret_key)
+            .serialize_compact();
+
+        RecoverableSignature::new(
+            &FixedSignature::from_bytes(&signature[..])?,
+            recovery_id.to_i32() as u8,
+        )
+    }
+}
+
 impl EcdsaSigner {
     /// Sign a digest and produce a `secp256k1::Signature`
-    fn raw_sign_digest(&self, digest: Sha256) -> Result<secp256k1::Signature, Error> {
-        let msg = secp256k1::Message::from_slice(digest.result().as_slice())
-            .map_err(Error::from_source)?;
-
+    fn raw_sign_digest<D>(&self, digest: D) -> Result<secp256k1::Signature, Error>
+    where
+        D: Digest<OutputSize = U32>,
+    {
+        let msg = digest_message(digest)?;
         Ok(self.engine.sign(&msg, &self.secret_key))
     }
 }
@@ -83,18 +127,39 @@ pub struct EcdsaVerifier {
 
     /// ECDSA engine
     engine: Secp256k1<VerifyOnly>,
+
+    /// Whether high-S signatures are accepted
+    mode: VerificationMode,
 }
 
-impl<'a> From<&'a PublicKey> for EcdsaVerifier {
-    fn from(public_key: &'a PublicKey) -> Self {
+impl EcdsaVerifier {
+    /// Create a new verifier which accepts or rejects high-S signatures
+    /// according to the given mode
+    pub fn with_mode(public_key: &PublicKey, mode: VerificationMode) -> Self {
         let public_key = secp256k1::PublicKey::from_slice(public_key.as_bytes()).unwrap();
         let engine = Secp256k1::verification_only();
-        EcdsaVerifier { public_key, engine }
+        EcdsaVerifier {
+            public_key,
+            engine,
+            mode,
+        }
+    }
+}
+
+impl<'a> From<&'a PublicKey> for EcdsaVerifier {
+    /// Create a new verifier which rejects high-S signatures, like
+    /// libsecp256k1 itself
+    fn from(public_key: &'a PublicKey) -> Self {
+        Self::with_mode(public_key, VerificationMode::Strict)
     }
 }
 
-impl DigestVerifier<Sha256, Asn1Signature> for EcdsaVerifier {
-    fn verify_digest(&self, digest: Sha256, signature: &Asn1Signature) -> Result<(), Error> {
+impl<D> DigestVerifier<D, Asn1Signature> for EcdsaVerifier
+where
+    D: Digest<OutputSize = U32>,
+{
+    fn verify_digest(&self, digest: D, signature: &Asn1Signature) -> Result<(), Error> {
+        self.mode.check(signature)?;
         self.raw_verify_digest(
             digest,
             secp256k1::Signature::from_der(signature.as_slice()).map_err(Error::from_source)?,
@@ -102,8 +167,12 @@ impl DigestVerifier<Sha256, Asn1Signature> for EcdsaVerifier {
     }
 }
 
-impl DigestVerifier<Sha256, FixedSignature> for EcdsaVerifier {
-    fn verify_digest(&self, digest: Sha256, signature: &FixedSignature) -> Result<(), Error> {
+impl<D> DigestVerifier<D, FixedSignature> for EcdsaVerifier
+where
+    D: Digest<OutputSize = U32>,
+{
+    fn verify_digest(&self, digest: D, signature: &FixedSignature) -> Result<(), Error> {
+        self.mode.check(signature)?;
         self.raw_verify_digest(
             digest,
             secp256k1::Signature::from_compact(signature.as_slice()).map_err(Error::from_source)?,
@@ -111,11 +180,29 @@ impl DigestVerifier<Sha256, FixedSignature> for EcdsaVerifier {
     }
 }
 
+impl<D> DigestVerifier<D, RecoverableSignature> for EcdsaVerifier
+where
+    D: Digest<OutputSize = U32>,
+{
+    fn verify_digest(&self, digest: D, signature: &RecoverableSignature) -> Result<(), Error> {
+        DigestVerifier::<D, FixedSignature>::verify_digest(
+            self,
+            digest,
+            &signature.to_fixed_signature(),
+        )
+    }
+}
+
 impl EcdsaVerifier {
-    /// Verify a digest against a `secp256k1::Signature`
-    fn raw_verify_digest(&self, digest: Sha256, sig: secp256k1::Signature) -> Result<(), Error> {
-        let msg = secp256k1::Message::from_slice(digest.result().as_slice())
-            .map_err(Error::from_source)?;
+    /// Verify a digest against a `secp256k1::Signature`, which has already
+    /// been checked against this verifier's mode (libsecp256k1 only verifies
+    /// low-S signatures, so it gets normalized)
+    fn raw_verify_digest<D>(&self, digest: D, mut sig: secp256k1::Signature) -> Result<(), Error>
+    where
+        D: Digest<OutputSize = U32>,
+    {
+        let msg = digest_message(digest)?;
+        sig.normalize_s();
 
         self.engine
             .verify(&msg, &sig, &self.public_key)
@@ -123,20 +210,82 @@ impl EcdsaVerifier {
     }
 }
 
+/// Recover the public key which signed the given message (with SHA-256)
+/// from a recoverable signature, i.e. `ecrecover`.
+///
+/// The signature is not checked against any expected signer: compare the
+/// recovered public key with the expected one.
+pub fn recover_public_key(
+    msg: &[u8],
+    signature: &RecoverableSignature,
+) -> Result<PublicKey, Error> {
+    recover_public_key_from_digest(Sha256::new().chain(msg), signature)
+}
+
+/// Recover the public key which signed the given 32-byte digest (e.g.
+/// Keccak-256 for Ethereum) from a recoverable signature
+pub fn recover_public_key_from_digest<D>(
+    digest: D,
+    signature: &RecoverableSignature,
+) -> Result<PublicKey, Error>
+where
+    D: Digest<OutputSize = U32>,
+{
+    let msg = digest_message(digest)?;
+    let recovery_id =
+        RecoveryId::from_i32(i32::from(signature.recovery_id())).map_err(Error::from_source)?;
+    let signature = RawRecoverableSignature::from_compact(&signature.as_ref()[..64], recovery_id)
+        .map_err(Error::from_source)?;
+    let public_key = Secp256k1::verification_only()
+        .recover(&msg, &signature)
+        .map_err(Error::from_source)?;
+
+    PublicKey::from_bytes(&public_key.serialize()[..]).ok_or_else(Error::new)
+}
+
+/// Message to sign or verify from its 32-byte digest
+fn digest_message<D>(digest: D) -> Result<secp256k1::Message, Error>
+where
+    D: Digest<OutputSize = U32>,
+{
+    secp256k1::Message::from_slice(digest.result().as_slice()).map_err(Error::from_source)
+}
+
 // TODO: test against actual test vectors, rather than just checking if signatures roundtrip
 #[cfg(test)]
 mod tests {
-    use super::{EcdsaSigner, EcdsaVerifier, PublicKey, SecretKey};
+    use super::{recover_public_key, EcdsaSigner, EcdsaVerifier, PublicKey, SecretKey};
     use signatory::{
         self,
         ecdsa::{
             curve::secp256k1::{Asn1Signature, FixedSignature},
             test_vectors::secp256k1::SHA256_FIXED_SIZE_TEST_VECTORS,
+            LowS, RecoverableSignature, VerificationMode,
         },
         public_key::PublicKeyed,
         signature::{Signature, Signer, Verifier},
     };
 
+    /// Message signed by the low-S and high-S signatures below
+    const HIGH_S_MSG: &[u8] = b"example message";
+
+    /// Public key of the `[0x11; 32]` secret key
+    const HIGH_S_PUBLIC_KEY: &[u8] = b"\x03\x4F\x35\x5B\xDC\xB7\xCC\x0A\xF7\x28\xEF\x3C\xCE\xB9\x61\x5D\
+                                       \x90\x68\x4B\xB5\xB2\xCA\x5F\x85\x9A\xB0\xF0\xB7\x04\x07\x58\x71\
+                                       \xAA";
+
+    /// Low-S signature of `HIGH_S_MSG`
+    const LOW_S_SIGNATURE: &[u8] = b"\x24\x65\x3E\xAC\x43\x44\x88\x00\x2C\xC0\x6B\xBF\xB7\xF1\x0F\xE1\
+                                     \x89\x91\xE3\x5F\x9F\xE4\x30\x2D\xBE\xA6\xD2\x35\x3D\xC0\xAB\x1C\
+                                     \x0B\x20\x24\x19\xDB\x0B\x73\x5B\x63\x0C\x92\xF2\x91\x42\x4B\x9A\
+                                     \x04\x40\x3C\xAC\x54\x4F\xE1\xA2\x9E\x3D\x88\x91\x92\x4A\x18\x23";
+
+    /// High-S form of `LOW_S_SIGNATURE`
+    const HIGH_S_SIGNATURE: &[u8] = b"\x24\x65\x3E\xAC\x43\x44\x88\x00\x2C\xC0\x6B\xBF\xB7\xF1\x0F\xE1\
+                                      \x89\x91\xE3\x5F\x9F\xE4\x30\x2D\xBE\xA6\xD2\x35\x3D\xC0\xAB\x1C\
+                                      \xF4\xDF\xDB\xE6\x24\xF4\x8C\xA4\x9C\xF3\x6D\x0D\x6E\xBD\xB4\x64\
+                                      \xB6\x6E\xA0\x3A\x5A\xF8\xBE\x99\x21\x94\xD5\xFB\x3D\xEC\x29\x1E";
+
     #[test]
     pub fn asn1_signature_roundtrip() {
         let vector = &SHA256_FIXED_SIZE_TEST_VECTORS[0];
@@ -205,4 +354,79 @@ mod tests {
             "expected bad signature to cause validation error!"
         );
     }
+
+    #[test]
+    pub fn recoverable_signature_vectors() {
+        for vector in SHA256_FIXED_SIZE_TEST_VECTORS {
+            let signer = EcdsaSigner::from(&SecretKey::from_bytes(vector.sk).unwrap());
+            let public_key = PublicKey::from_bytes(vector.pk).unwrap();
+
+            let signature: RecoverableSignature = signer.sign(vector.msg);
+            assert_eq!(&signature.as_ref()[..64], vector.sig);
+            assert_eq!(
+                recover_public_key(vector.msg, &signature).unwrap(),
+                public_key
+            );
+
+            EcdsaVerifier::from(&public_key)
+                .verify(vector.msg, &signature)
+                .unwrap();
+        }
+    }
+
+    #[test]
+    pub fn recovers_other_key_from_tweaked_signature() {
+        let vector = &SHA256_FIXED_SIZE_TEST_VECTORS[0];
+        let signer = EcdsaSigner::from(&SecretKey::from_bytes(vector.sk).unwrap());
+
+        let signature: RecoverableSignature = signer.sign(vector.msg);
+        let mut tweaked_signature = signature.as_ref().to_vec();
+        tweaked_signature[64] ^= 1;
+        let tweaked_signature = RecoverableSignature::from_bytes(tweaked_signature).unwrap();
+
+        assert!(recover_public_key(vector.msg, &tweaked_signature)
+            .map(|public_key| public_key != signer.public_key().unwrap())
+            .unwrap_or(true));
+        assert!(recover_public_key(b"another message", &signature)
+            .map(|public_key| public_key != signer.public_key().unwrap())
+            .unwrap_or(true));
+        assert!(RecoverableSignature::from_bytes(&[0u8; 64][..]).is_err());
+    }
+
+    #[test]
+    pub fn signatures_are_low_s() {
+        for vector in SHA256_FIXED_SIZE_TEST_VECTORS {
+            let signer = EcdsaSigner::from(&SecretKey::from_bytes(vector.sk).unwrap());
+
+            let signature: FixedSignature = signer.sign(vector.msg);
+            assert!(signature.is_low_s());
+
+            let signature: Asn1Signature = signer.sign(vector.msg);
+            assert!(signature.is_low_s());
+        }
+    }
+
+    #[test]
+    pub fn high_s_signatures() {
+        let public_key = PublicKey::from_bytes(HIGH_S_PUBLIC_KEY).unwrap();
+        let low_s_signature = FixedSignature::from_bytes(LOW_S_SIGNATURE).unwrap();
+        let high_s_signature = FixedSignature::from_bytes(HIGH_S_SIGNATURE).unwrap();
+        assert!(low_s_signature.is_low_s());
+        assert!(!high_s_signature.is_low_s());
+        assert_eq!(high_s_signature.normalize_s().as_ref(), LOW_S_SIGNATURE);
+
+        let verifier = EcdsaVerifier::from(&public_key);
+        assert!(verifier.verify(HIGH_S_MSG, &low_s_signature).is_ok());
+        assert!(verifier.verify(HIGH_S_MSG, &high_s_signature).is_err());
+        assert!(verifier
+            .verify(HIGH_S_MSG, &Asn1Signature::from(&high_s_signature))
+            .is_err());
+
+        let verifier = EcdsaVerifier::with_mode(&public_key, VerificationMode::Fips186);
+        assert!(verifier.verify(HIGH_S_MSG, &low_s_signature).is_ok());
+        assert!(verifier.verify(HIGH_S_MSG, &high_s_signature).is_ok());
+        assert!(verifier
+            .verify(HIGH_S_MSG, &Asn1Signature::from(&high_s_signature))
+            .is_ok());
+    }
 }
@@ -0,0 +1,434 @@
+//! BIP340 Schnorr signatures, built from the secp256k1 crate's scalar and
+//! point operations.
+//!
+//! Signatures use auxiliary randomness from the OS when signing through the
+//! `Signer` trait, or the caller's with `SchnorrSigner::sign_with_aux_rand`
+//! (which is also what makes signing testable).
+
+use crate::SecretKey;
+use secp256k1::{self, All, Secp256k1};
+use signatory::{
+    public_key::PublicKeyed,
+    schnorr::{Signature, XOnlyPublicKey},
+    sha2::{Digest, Sha256},
+    signature::{Error, Signature as _, Signer, Verifier},
+};
+
+/// Order of the secp256k1 group
+const CURVE_ORDER: [u8; 32] = [
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe,
+    0xba, 0xae, 0xdc, 0xe6, 0xaf, 0x48, 0xa0, 0x3b, 0xbf, 0xd2, 0x5e, 0x8c, 0xd0, 0x36, 0x41, 0x41,
+];
+
+/// Tag byte of compressed points with an even Y coordinate
+const EVEN_Y: u8 = 0x02;
+
+/// BIP340 Schnorr signature provider for the secp256k1 crate
+pub struct SchnorrSigner {
+    /// Secret key, negated if needed so its public key has an even Y
+    secret_key: secp256k1::SecretKey,
+
+    /// x-only public key
+    public_key: XOnlyPublicKey,
+
+    /// secp256k1 engine
+    engine: Secp256k1<All>,
+}
+
+impl<'a> From<&'a SecretKey> for SchnorrSigner {
+    /// Create a new BIP340 signer from the given `SecretKey`
+    fn from(secret_key: &'a SecretKey) -> SchnorrSigner {
+        let engine = Secp256k1::new();
+        let mut secret_key =
+            secp256k1::SecretKey::from_slice(secret_key.as_secret_slice()).unwrap();
+        let point = secp256k1::PublicKey::from_secret_key(&engine, &secret_key).serialize();
+        if point[0] != EVEN_Y {
+            secret_key = secp256k1::SecretKey::from_slice(&negate(&secret_key[..])).unwrap();
+        }
+        let public_key = XOnlyPublicKey::from_bytes(&point[1..]).unwrap();
+        SchnorrSigner {
+            secret_key,
+            public_key,
+            engine,
+        }
+    }
+}
+
+impl PublicKeyed<XOnlyPublicKey> for SchnorrSigner {
+    /// Return the x-only public key of this signer
+    fn public_key(&self) -> Result<XOnlyPublicKey, Error> {
+        Ok(self.public_key)
+    }
+}
+
+impl Signer<Signature> for SchnorrSigner {
+    /// Sign a message with auxiliary randomness from the OS
+    fn try_sign(&self, msg: &[u8]) -> Result<Signature, Error> {
+        let mut aux_rand = [0u8; 32];
+        getrandom::getrandom(&mut aux_rand).map_err(|_| Error::new())?;
+        self.sign_with_aux_rand(msg, &aux_rand)
+    }
+}
+
+impl SchnorrSigner {
+    /// Sign a message with the given auxiliary randomness, as specified by
+    /// BIP340. All-zero auxiliary randomness gives deterministic signatures.
+    pub fn sign_with_aux_rand(&self, msg: &[u8], aux_rand: &[u8; 32]) -> Result<Signature, Error> {
+        let mut masked_key = tagged_hash(b"BIP0340/aux", &[&aux_rand[..]]);
+        for (byte, key_byte) in masked_key.iter_mut().zip(self.secret_key[..].iter()) {
+            *byte ^= key_byte;
+        }
+        let nonce = reduce(tagged_hash(
+            b"BIP0340/nonce",
+            &[&masked_key[..], self.public_key.as_ref(), msg],
+        ));
+        let mut nonce = secp256k1::SecretKey::from_slice(&nonce).map_err(Error::from_source)?;
+        let nonce_point = secp256k1::PublicKey::from_secret_key(&self.engine, &nonce).serialize();
+        if nonce_point[0] != EVEN_Y {
+            nonce = secp256k1::SecretKey::from_slice(&negate(&nonce[..])).unwrap();
+        }
+
+        // s = k + e * d
+        let challenge = challenge(&nonce_point[1..], &self.public_key, msg);
+        let mut s = self.secret_key;
+        s.mul_assign(&challenge).map_err(Error::from_source)?;
+        s.add_assign(&nonce[..]).map_err(Error::from_source)?;
+
+        let mut bytes = [0u8; 64];
+        bytes[..32].copy_from_slice(&nonce_point[1..]);
+        bytes[32..].copy_from_slice(&s[..]);
+        let signature = Signature::new(bytes);
+
+        // guard against faults producing signatures which leak the key
+        raw_verify(&self.engine, &self.public_key, msg, &signature)?;
+        Ok(signature)
+    }
+}
+
+/// BIP340 Schnorr verifier provider for the secp256k1 crate
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SchnorrVerifier {
+    /// x-only public key
+    public_key: XOnlyPublicKey,
+
+    /// secp256k1 engine
+    engine: Secp256k1<All>,
+}
+
+impl<'a> From<&'a XOnlyPublicKey> for SchnorrVerifier {
+    fn from(public_key: &'a XOnlyPublicKey) -> Self {
+        SchnorrVerifier {
+            public_key: *public_key,
+            engine: Secp256k1::new(),
+        }
+    }
+}
+
+impl Verifier<Signature> for SchnorrVerifier {
+    fn verify(&self, msg: &[u8], signature: &Signature) -> Result<(), Error> {
+        raw_verify(&self.engine, &self.public_key, msg, signature)
+    }
+}
+
+/// Check that `R = s * G - e * P` has an even Y coordinate and the X
+/// coordinate of the signature
+fn raw_verify(
+    engine: &Secp256k1<All>,
+    public_key: &XOnlyPublicKey,
+    msg: &[u8],
+    signature: &Signature,
+) -> Result<(), Error> {
+    let mut point = [EVEN_Y; 33];
+    point[1..].copy_from_slice(public_key.as_ref());
+    let public_key_point = secp256k1::PublicKey::from_slice(&point).map_err(Error::from_source)?;
+
+    let s = signature.s();
+    if s >= &CURVE_ORDER[..] {
+        return Err(Error::new());
+    }
+    let challenge = challenge(signature.r(), public_key, msg);
+
+    // -e * P, or the point at infinity (None) when e is zero
+    let minus_e_p = if is_zero(&challenge) {
+        None
+    } else {
+        let mut minus_e_p = public_key_point;
+        minus_e_p
+            .mul_assign(engine, &negate(&challenge))
+            .map_err(Error::from_source)?;
+        Some(minus_e_p)
+    };
+
+    // s * G, or the point at infinity
+    let s_g = if is_zero(s) {
+        None
+    } else {
+        let s = secp256k1::SecretKey::from_slice(s).map_err(Error::from_source)?;
+        Some(secp256k1::PublicKey::from_secret_key(engine, &s))
+    };
+
+    let nonce_point = match (s_g, minus_e_p) {
+        (Some(s_g), Some(minus_e_p)) => s_g.combine(&minus_e_p).map_err(Error::from_source)?,
+        (Some(point), None) | (None, Some(point)) => point,
+        (None, None) => return Err(Error::new()),
+    };
+
+    let nonce_point = nonce_point.serialize();
+    if nonce_point[0] == EVEN_Y && &nonce_point[1..] == signature.r() {
+        Ok(())
+    } else {
+        Err(Error::new())
+    }
+}
+
+/// `e = int(hash_BIP0340/challenge(r || P || m)) mod n`
+fn challenge(r: &[u8], public_key: &XOnlyPublicKey, msg: &[u8]) -> [u8; 32] {
+    reduce(tagged_hash(
+        b"BIP0340/challenge",
+        &[r, public_key.as_ref(), msg],
+    ))
+}
+
+/// `SHA256(SHA256(tag) || SHA256(tag) || x)`
+fn tagged_hash(tag: &[u8], data: &[&[u8]]) -> [u8; 32] {
+    let tag_hash = Sha256::digest(tag);
+    let mut hasher = Sha256::new().chain(&tag_hash).chain(&tag_hash);
+    for bytes in data {
+        hasher.input(*bytes);
+    }
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(&hasher.result());
+    hash
+}
+
+/// Reduce a 256-bit big-endian integer modulo the group order, which is
+/// more than half of 2^256
+fn reduce(x: [u8; 32]) -> [u8; 32] {
+    if x >= CURVE_ORDER {
+        sub(&x, &CURVE_ORDER)
+    } else {
+        x
+    }
+}
+
+/// Negate a nonzero scalar modulo the group order
+fn negate(x: &[u8]) -> [u8; 32] {
+    sub(&CURVE_ORDER, x)
+}
+
+/// Big-endian `a - b`, where `a >= b`
+fn sub(a: &[u8], b: &[u8]) -> [u8; 32] {
+    let mut result = [0u8; 32];
+    let mut borrow = 0i16;
+    for i in (0..32).rev() {
+        let mut diff = i16::from(a[i]) - i16::from(b[i]) - borrow;
+        borrow = if diff < 0 { 1 } else { 0 };
+        diff += borrow * 256;
+        result[i] = diff as u8;
+    }
+    result
+}
+
+fn is_zero(x: &[u8]) -> bool {
+    x.iter().all(|byte| *byte == 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use signatory::signature::Signature as _;
+
+    /// BIP340 test vector
+    struct TestVector {
+        secret_key: Option<&'static str>,
+        public_key: &'static str,
+        aux_rand: Option<&'static str>,
+        msg: &'static str,
+        signature: &'static str,
+        valid: bool,
+    }
+
+    /// Test vectors 0 to 14 of BIP340 (`test-vectors.csv`)
+    const TEST_VECTORS: &[TestVector] = &[
+        TestVector {
+            secret_key: Some("0000000000000000000000000000000000000000000000000000000000000003"),
+            public_key: "F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9",
+            aux_rand: Some("0000000000000000000000000000000000000000000000000000000000000000"),
+            msg: "0000000000000000000000000000000000000000000000000000000000000000",
+            signature: "E907831F80848D1069A5371B402410364BDF1C5F8307B0084C55F1CE2DCA821525F66A4A85EA8B71E482A74F382D2CE5EBEEE8FDB2172F477DF4900D310536C0",
+            valid: true,
+        },
+        TestVector {
+            secret_key: Some("B7E151628AED2A6ABF7158809CF4F3C762E7160F38B4DA56A784D9045190CFEF"),
+            public_key: "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+            aux_rand: Some("0000000000000000000000000000000000000000000000000000000000000001"),
+            msg: "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+            signature: "6896BD60EEAE296DB48A229FF71DFE071BDE413E6D43F917DC8DCF8C78DE33418906D11AC976ABCCB20B091292BFF4EA897EFCB639EA871CFA95F6DE339E4B0A",
+            valid: true,
+        },
+        TestVector {
+            secret_key: Some("C90FDAA22168C234C4C6628B80DC1CD129024E088A67CC74020BBEA63B14E5C9"),
+            public_key: "DD308AFEC5777E13121FA72B9CC1B7CC0139715309B086C960E18FD969774EB8",
+            aux_rand: Some("C87AA53824B4D7AE2EB035A2B5BBBCCC080E76CDC6D1692C4B0B62D798E6D906"),
+            msg: "7E2D58D8B3BCDF1ABADEC7829054F90DDA9805AAB56C77333024B9D0A508B75C",
+            signature: "5831AAEED7B44BB74E5EAB94BA9D4294C49BCF2A60728D8B4C200F50DD313C1BAB745879A5AD954A72C45A91C3A51D3C7ADEA98D82F8481E0E1E03674A6F3FB7",
+            valid: true,
+        },
+        // fails if msg is reduced modulo p or n
+        TestVector {
+            secret_key: Some("0B432B2677937381AEF05BB02A66ECD012773062CF3FA2549E44F58ED2401710"),
+            public_key: "25D1DFF95105F5253C4022F628A996AD3A0D95FBF21D468A1B33F8C160D8F517",
+            aux_rand: Some("FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF"),
+            msg: "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF",
+            signature: "7EB0509757E246F19449885651611CB965ECC1A187DD51B64FDA1EDC9637D5EC97582B9CB13DB3933705B32BA982AF5AF25FD78881EBB32771FC5922EFC66EA3",
+            valid: true,
+        },
+        TestVector {
+            secret_key: None,
+            public_key: "D69C3509BB99E412E68B0FE8544E72837DFA30746D8BE2AA65975F29D22DC7B9",
+            aux_rand: None,
+            msg: "4DF3C3F68FCC83B27E9D42C90431A72499F17875C81A599B566C9889B9696703",
+            signature: "00000000000000000000003B78CE563F89A0ED9414F5AA28AD0D96D6795F9C6376AFB1548AF603B3EB45C9F8207DEE1060CB71C04E80F593060B07D28308D7F4",
+            valid: true,
+        },
+        // public key not on the curve
+        TestVector {
+            secret_key: None,
+            public_key: "EEFDEA4CDB677750A420FEE807EACF21EB9898AE79B9768766E4FAA04A2D4A34",
+            aux_rand: None,
+            msg: "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+            signature: "6CFF5C3BA86C69EA4B7376F31A9BCB4F74C1976089B2D9963DA2E5543E17776969E89B4C5564D00349106B8497785DD7D1D713A8AE82B32FA79D5F7FC407D39B",
+            valid: false,
+        },
+        // has_even_y(R) is false
+        TestVector {
+            secret_key: None,
+            public_key: "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+            aux_rand: None,
+            msg: "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+            signature: "FFF97BD5755EEEA420453A14355235D382F6472F8568A18B2F057A14602975563CC27944640AC607CD107AE10923D9EF7A73C643E166BE5EBEAFA34B1AC553E2",
+            valid: false,
+        },
+        // negated message
+        TestVector {
+            secret_key: None,
+            public_key: "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+            aux_rand: None,
+            msg: "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+            signature: "1FA62E331EDBC21C394792D2AB1100A7B432B013DF3F6FF4F99FCB33E0E1515F28890B3EDB6E7189B630448B515CE4F8622A954CFE545735AAEA5134FCCDB2BD",
+            valid: false,
+        },
+        // negated s value
+        TestVector {
+            secret_key: None,
+            public_key: "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+            aux_rand: None,
+            msg: "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+            signature: "6CFF5C3BA86C69EA4B7376F31A9BCB4F74C1976089B2D9963DA2E5543E177769961764B3AA9B2FFCB6EF947B6887A226E8D7C93E00C5ED0C1834FF0D0C2E6DA6",
+            valid: false,
+        },
+        // sG - eP is infinite (with x(inf) defined as 0)
+        TestVector {
+            secret_key: None,
+            public_key: "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+            aux_rand: None,
+            msg: "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+            signature: "0000000000000000000000000000000000000000000000000000000000000000123DDA8328AF9C23A94C1FEECFD123BA4FB73476F0D594DCB65C6425BD186051",
+            valid: false,
+        },
+        // sG - eP is infinite (with x(inf) defined as 1)
+        TestVector {
+            secret_key: None,
+            public_key: "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+            aux_rand: None,
+            msg: "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+            signature: "00000000000000000000000000000000000000000000000000000000000000017615FBAF5AE28864013C099742DEADB4DBA87F11AC6754F93780D5A1837CF197",
+            valid: false,
+        },
+        // sig[0:32] is not an X coordinate on the curve
+        TestVector {
+            secret_key: None,
+            public_key: "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+            aux_rand: None,
+            msg: "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+            signature: "4A298DACAE57395A15D0795DDBFD1DCB564DA82B0F269BC70A74F8220429BA1D69E89B4C5564D00349106B8497785DD7D1D713A8AE82B32FA79D5F7FC407D39B",
+            valid: false,
+        },
+        // sig[0:32] is equal to the field size
+        TestVector {
+            secret_key: None,
+            public_key: "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+            aux_rand: None,
+            msg: "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+            signature: "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFC2F69E89B4C5564D00349106B8497785DD7D1D713A8AE82B32FA79D5F7FC407D39B",
+            valid: false,
+        },
+        // sig[32:64] is equal to the curve order
+        TestVector {
+            secret_key: None,
+            public_key: "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659",
+            aux_rand: None,
+            msg: "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+            signature: "6CFF5C3BA86C69EA4B7376F31A9BCB4F74C1976089B2D9963DA2E5543E177769FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141",
+            valid: false,
+        },
+        // public key is not a valid X coordinate because it exceeds the field size
+        TestVector {
+            secret_key: None,
+            public_key: "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFC30",
+            aux_rand: None,
+            msg: "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89",
+            signature: "6CFF5C3BA86C69EA4B7376F31A9BCB4F74C1976089B2D9963DA2E5543E17776969E89B4C5564D00349106B8497785DD7D1D713A8AE82B32FA79D5F7FC407D39B",
+            valid: false,
+        },
+    ];
+
+    fn from_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len() / 2)
+            .map(|i| u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn bip340_sign() {
+        for vector in TEST_VECTORS {
+            let secret_key = match vector.secret_key {
+                Some(secret_key) => secret_key,
+                None => continue,
+            };
+            let signer = SchnorrSigner::from(&SecretKey::from_bytes(from_hex(secret_key)).unwrap());
+            assert_eq!(
+                signer.public_key().unwrap().as_ref(),
+                &from_hex(vector.public_key)[..]
+            );
+
+            let mut aux_rand = [0u8; 32];
+            aux_rand.copy_from_slice(&from_hex(vector.aux_rand.unwrap()));
+            let signature = signer
+                .sign_with_aux_rand(&from_hex(vector.msg), &aux_rand)
+                .unwrap();
+            assert_eq!(signature.as_ref(), &from_hex(vector.signature)[..]);
+        }
+    }
+
+    #[test]
+    fn bip340_verify() {
+        for (i, vector) in TEST_VECTORS.iter().enumerate() {
+            let public_key = XOnlyPublicKey::from_bytes(from_hex(vector.public_key)).unwrap();
+            let signature = Signature::from_bytes(from_hex(vector.signature)).unwrap();
+            let result =
+                SchnorrVerifier::from(&public_key).verify(&from_hex(vector.msg), &signature);
+            assert_eq!(result.is_ok(), vector.valid, "test vector {}", i);
+        }
+    }
+
+    #[test]
+    fn sign_and_verify() {
+        let signer = SchnorrSigner::from(&SecretKey::generate());
+        let verifier = SchnorrVerifier::from(&signer.public_key().unwrap());
+        let msg = b"arbitrary length messages are signed as they are";
+        let signature: Signature = signer.sign(msg);
+        assert!(verifier.verify(msg, &signature).is_ok());
+        assert!(verifier.verify(b"another message", &signature).is_err());
+    }
+}
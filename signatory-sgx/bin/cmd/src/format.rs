@@ -0,0 +1,220 @@
+//! Encodings of keys, signatures and data accepted and printed by the cli
+
+use signatory::ecdsa::curve::{nistp256, secp256k1, NistP256, Secp256k1};
+use signatory::ed25519;
+use signatory::signature::{Signature, Verifier};
+use signatory_dalek::Ed25519Verifier;
+use signatory_ring::ecdsa::p256;
+use signatory_secp256k1::EcdsaVerifier as Secp256k1Verifier;
+use signatory_sgx::der::{pem_decode, pem_encode, public_key_to_spki, secret_key_from_pkcs8};
+use signatory_sgx::error::Error;
+use signatory_sgx::protocol::KeyAlgorithm;
+use std::str::FromStr;
+use subtle_encoding::{bech32, Base64, Encoding, Hex};
+
+/// Encoding of binary data: signatures, messages and public keys
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Format {
+    /// the bytes themselves
+    Raw,
+    Hex,
+    Base64,
+}
+
+impl FromStr for Format {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "raw" => Ok(Format::Raw),
+            "hex" => Ok(Format::Hex),
+            "base64" => Ok(Format::Base64),
+            _ => Err(Error::new(format!(
+                "unknown format: {} (raw, hex, base64)",
+                s
+            ))),
+        }
+    }
+}
+
+impl Format {
+    /// Encode data as text, which raw data isn't
+    pub fn encode(self, data: &[u8]) -> Result<String, Error> {
+        match self {
+            Format::Raw => Err(Error::new("raw data can't be printed as text")),
+            Format::Hex => Ok(Hex::lower_case().encode_to_string(data).unwrap()),
+            Format::Base64 => Ok(Base64::default().encode_to_string(data).unwrap()),
+        }
+    }
+
+    pub fn decode(self, s: &str) -> Result<Vec<u8>, Error> {
+        match self {
+            Format::Raw => Ok(s.as_bytes().to_vec()),
+            Format::Hex => Hex::lower_case()
+                .decode(s.trim().to_ascii_lowercase().as_bytes())
+                .map_err(|_| Error::new("invalid hex")),
+            Format::Base64 => Base64::default()
+                .decode(s.trim().as_bytes())
+                .map_err(|_| Error::new("invalid base64")),
+        }
+    }
+}
+
+/// Export format of public keys
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PublicKeyFormat {
+    Hex,
+    Base64,
+    Bech32,
+    /// PEM-encoded SubjectPublicKeyInfo
+    Pem,
+}
+
+impl FromStr for PublicKeyFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "hex" => Ok(PublicKeyFormat::Hex),
+            "base64" => Ok(PublicKeyFormat::Base64),
+            "bech32" => Ok(PublicKeyFormat::Bech32),
+            "pem" => Ok(PublicKeyFormat::Pem),
+            _ => Err(Error::new(format!(
+                "unknown public key format: {} (hex, base64, bech32, pem)",
+                s
+            ))),
+        }
+    }
+}
+
+impl PublicKeyFormat {
+    /// Encode a public key; bech32 needs a human-readable part
+    pub fn encode(
+        self,
+        algorithm: KeyAlgorithm,
+        public_key: &[u8],
+        hrp: Option<&str>,
+    ) -> Result<String, Error> {
+        match self {
+            PublicKeyFormat::Hex => Format::Hex.encode(public_key),
+            PublicKeyFormat::Base64 => Format::Base64.encode(public_key),
+            PublicKeyFormat::Bech32 => {
+                let hrp = hrp.ok_or_else(|| Error::new("bech32 needs --hrp"))?;
+                Ok(bech32::encode(hrp, public_key))
+            }
+            PublicKeyFormat::Pem => Ok(pem_encode(
+                "PUBLIC KEY",
+                &public_key_to_spki(algorithm, public_key),
+            )),
+        }
+    }
+}
+
+/// Encoding of a secret key to import
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SecretKeyFormat {
+    Base64,
+    Hex,
+    /// PKCS#8 document, DER or PEM
+    Pkcs8,
+    /// Tendermint `priv_validator_key.json`
+    PrivValidatorJson,
+}
+
+impl FromStr for SecretKeyFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "base64" => Ok(SecretKeyFormat::Base64),
+            "hex" => Ok(SecretKeyFormat::Hex),
+            "pkcs8" => Ok(SecretKeyFormat::Pkcs8),
+            "priv-validator-json" => Ok(SecretKeyFormat::PrivValidatorJson),
+            _ => Err(Error::new(format!(
+                "unknown key type: {} (base64, hex, pkcs8, priv-validator-json)",
+                s
+            ))),
+        }
+    }
+}
+
+impl SecretKeyFormat {
+    /// Decode a secret key to the form `SgxClient::import_secret` takes,
+    /// along with its algorithm when the encoding tells it
+    pub fn decode(self, key: &[u8]) -> Result<(Option<KeyAlgorithm>, Vec<u8>), Error> {
+        let text = || std::str::from_utf8(key).map_err(|_| Error::new("invalid key"));
+        match self {
+            SecretKeyFormat::Base64 => Ok((None, Format::Base64.decode(text()?)?)),
+            SecretKeyFormat::Hex => Ok((None, Format::Hex.decode(text()?)?)),
+            SecretKeyFormat::Pkcs8 => {
+                let der = if key.starts_with(b"-----BEGIN") {
+                    pem_decode("PRIVATE KEY", text()?)?
+                } else {
+                    key.to_vec()
+                };
+                let (algorithm, secret) = secret_key_from_pkcs8(&der)?;
+                Ok((Some(algorithm), secret))
+            }
+            SecretKeyFormat::PrivValidatorJson => {
+                let (algorithm, secret) = priv_validator_key(text()?)?;
+                Ok((Some(algorithm), secret))
+            }
+        }
+    }
+}
+
+/// Secret key of a Tendermint `priv_validator_key.json`
+fn priv_validator_key(json: &str) -> Result<(KeyAlgorithm, Vec<u8>), Error> {
+    let value: serde_json::Value = serde_json::from_str(json)
+        .map_err(|e| Error::new(format!("invalid priv_validator_key.json: {}", e)))?;
+    let priv_key = &value["priv_key"];
+    let algorithm = match priv_key["type"].as_str() {
+        Some("tendermint/PrivKeyEd25519") => KeyAlgorithm::Ed25519,
+        Some("tendermint/PrivKeySecp256k1") => KeyAlgorithm::Secp256k1,
+        Some(key_type) => {
+            return Err(Error::new(format!(
+                "unsupported validator key type: {}",
+                key_type
+            )))
+        }
+        None => return Err(Error::new("priv_validator_key.json has no priv_key.type")),
+    };
+    let secret = priv_key["value"]
+        .as_str()
+        .ok_or_else(|| Error::new("priv_validator_key.json has no priv_key.value"))?;
+    Ok((algorithm, Format::Base64.decode(secret)?))
+}
+
+/// Check a signature made by the enclave, returning whether it is valid
+pub fn verify(
+    algorithm: KeyAlgorithm,
+    public_key: &[u8],
+    msg: &[u8],
+    sig_raw: &[u8],
+) -> Result<bool, Error> {
+    let invalid_public_key = || Error::new("invalid public key");
+    let valid = match algorithm {
+        KeyAlgorithm::Ed25519 => {
+            let public_key =
+                ed25519::PublicKey::from_bytes(public_key).ok_or_else(invalid_public_key)?;
+            ed25519::Signature::from_bytes(sig_raw)
+                .and_then(|sig| Ed25519Verifier::from(&public_key).verify(msg, &sig))
+                .is_ok()
+        }
+        KeyAlgorithm::Secp256k1 => {
+            let public_key = signatory::ecdsa::PublicKey::<Secp256k1>::from_bytes(public_key)
+                .ok_or_else(invalid_public_key)?;
+            secp256k1::FixedSignature::from_bytes(sig_raw)
+                .and_then(|sig| Secp256k1Verifier::from(&public_key).verify(msg, &sig))
+                .is_ok()
+        }
+        KeyAlgorithm::NistP256 => {
+            let public_key = signatory::ecdsa::PublicKey::<NistP256>::from_bytes(public_key)
+                .ok_or_else(invalid_public_key)?;
+            nistp256::FixedSignature::from_bytes(sig_raw)
+                .and_then(|sig| p256::Verifier::from(&public_key).verify(msg, &sig))
+                .is_ok()
+        }
+    };
+    Ok(valid)
+}
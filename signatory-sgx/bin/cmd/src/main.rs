@@ -1,18 +1,36 @@
-use crossbeam_channel::{unbounded, Receiver};
-use signatory::public_key::PublicKeyed;
-use signatory::signature::Signer;
+mod format;
+
+use crate::format::{verify, Format, PublicKeyFormat, SecretKeyFormat};
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use serde_json::{json, Value};
+use signatory_sgx::backup::{KeyBackup, RecoverySecretKey};
+use signatory_sgx::daemon::{bind_tcp, bind_unix_socket, Daemon};
 use signatory_sgx::error::Error;
-use signatory_sgx::protocol::{KeyType, SecretKeyEncoding};
-use signatory_sgx::provider::SgxSigner;
+use signatory_sgx::key_file::StoredKey;
+use signatory_sgx::keystore::{KeyStore, KeyStoreClient};
+use signatory_sgx::protocol::{Decode, KeyAlgorithm};
+use signatory_sgx::provider::{SgxClient, SgxSigner};
 use signatory_sgx::server::{run_server, stop_server, C2S};
-use std::path::PathBuf;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::process;
 use std::thread;
 use std::thread::JoinHandle;
 use structopt::StructOpt;
-use subtle_encoding::encoding::Encoding;
 
 #[derive(Debug, StructOpt)]
 #[structopt(name = "client", about = "client for sgx server")]
+pub struct Opt {
+    /// print results as JSON, for scripts
+    #[structopt(long, global = true)]
+    json: bool,
+    #[structopt(subcommand)]
+    cmd: CMD,
+}
+
+#[derive(Debug, StructOpt)]
 pub enum CMD {
     /// create a new secret key and public key
     Keygen {
@@ -22,6 +40,13 @@ pub enum CMD {
         /// set secret file path
         #[structopt(short, long, parse(from_os_str))]
         key_file: PathBuf,
+        /// set the key algorithm (ed25519, secp256k1, nistp256)
+        #[structopt(long, default_value = "ed25519")]
+        algorithm: KeyAlgorithm,
+        /// also write a backup of the key, encrypted to the recovery key
+        /// configured in the enclave, to this file
+        #[structopt(long, parse(from_os_str))]
+        backup_file: Option<PathBuf>,
     },
     /// import a secret key into sgx
     Import {
@@ -33,23 +58,36 @@ pub enum CMD {
         key_file: PathBuf,
         /// set the secret key
         #[structopt(short, long)]
-        key: String,
-        /// set the secret key type(base64)
+        key: Option<String>,
+        /// read the secret key from this file instead (`-` for stdin)
+        #[structopt(long, parse(from_os_str))]
+        input: Option<PathBuf>,
+        /// set the secret key type (base64, hex, pkcs8, priv-validator-json)
         #[structopt(long, default_value = "base64")]
-        key_type: String,
+        key_type: SecretKeyFormat,
+        /// set the key algorithm (ed25519, secp256k1, nistp256), which
+        /// pkcs8 and priv-validator-json keys tell themselves
+        #[structopt(long)]
+        algorithm: Option<KeyAlgorithm>,
     },
 
     /// get public key of a secret key file
-    Pubkey {
+    Publickey {
         /// set sgxs file path
         #[structopt(short, long, parse(from_os_str))]
         sgx_file: PathBuf,
         /// set secret file path
         #[structopt(short, long, parse(from_os_str))]
         key_file: PathBuf,
+        /// set the output format (hex, base64, bech32, pem)
+        #[structopt(long, default_value = "base64")]
+        format: PublicKeyFormat,
+        /// set the human-readable part of bech32 public keys
+        #[structopt(long)]
+        hrp: Option<String>,
     },
 
-    /// sign a string example
+    /// sign a string, a file or stdin
     Sign {
         /// set sgxs file path
         #[structopt(short, long, parse(from_os_str))]
@@ -58,88 +96,602 @@ pub enum CMD {
         #[structopt(short, long, parse(from_os_str))]
         key_file: PathBuf,
         /// sign data
-        #[structopt(short, long, default_value = "hello world")]
-        data: String,
+        #[structopt(short, long)]
+        data: Option<String>,
+        /// sign the content of this file instead (`-` or nothing for stdin)
+        #[structopt(long, parse(from_os_str))]
+        input: Option<PathBuf>,
+        /// set the signature format (raw, hex, base64)
+        #[structopt(long, default_value = "base64")]
+        output_format: Format,
     },
+
+    /// sign each line of a file, printing one signature per line
+    SignBatch {
+        /// set sgxs file path
+        #[structopt(short, long, parse(from_os_str))]
+        sgx_file: PathBuf,
+        /// set secret file path
+        #[structopt(short, long, parse(from_os_str))]
+        key_file: PathBuf,
+        /// set the file of newline-delimited messages (`-` for stdin)
+        #[structopt(long, parse(from_os_str))]
+        input: PathBuf,
+        /// set the encoding of the messages (raw, hex, base64)
+        #[structopt(long, default_value = "raw")]
+        input_format: Format,
+        /// set the signature format (hex, base64)
+        #[structopt(long, default_value = "base64")]
+        output_format: Format,
+    },
+
+    /// verify a signature; the enclave is only needed for --key-file
+    Verify {
+        /// set sgxs file path, to read the public key of --key-file
+        #[structopt(short, long, parse(from_os_str))]
+        sgx_file: Option<PathBuf>,
+        /// take the public key from this secret file
+        #[structopt(short, long, parse(from_os_str))]
+        key_file: Option<PathBuf>,
+        /// set the public key instead (see --public-key-format)
+        #[structopt(long)]
+        public_key: Option<String>,
+        /// set the public key format (hex, base64)
+        #[structopt(long, default_value = "base64")]
+        public_key_format: Format,
+        /// set the key algorithm of --public-key (ed25519, secp256k1, nistp256)
+        #[structopt(long, default_value = "ed25519")]
+        algorithm: KeyAlgorithm,
+        /// set the signature
+        #[structopt(long)]
+        signature: String,
+        /// set the signature format (hex, base64)
+        #[structopt(long, default_value = "base64")]
+        signature_format: Format,
+        /// signed data
+        #[structopt(short, long)]
+        data: Option<String>,
+        /// signed content of this file instead (`-` or nothing for stdin)
+        #[structopt(long, parse(from_os_str))]
+        input: Option<PathBuf>,
+    },
+
+    /// keep the enclave loaded and serve signing requests on a socket
+    Daemon {
+        /// set sgxs file path
+        #[structopt(short, long, parse(from_os_str))]
+        sgx_file: PathBuf,
+        /// set unix domain socket path to listen on
+        #[structopt(long, default_value = "signatory-sgx.sock", parse(from_os_str))]
+        socket: PathBuf,
+        /// also listen on this loopback tcp address (e.g. 127.0.0.1:26659);
+        /// tcp clients are not authenticated
+        #[structopt(long)]
+        tcp: Option<String>,
+    },
+
+    /// describe a sealed key file, without the enclave
+    Inspect {
+        /// set secret file path
+        #[structopt(short, long, parse(from_os_str))]
+        key_file: PathBuf,
+    },
+
+    /// upgrade a legacy sealed key file to the current format
+    Upgrade {
+        /// set sgxs file path
+        #[structopt(short, long, parse(from_os_str))]
+        sgx_file: PathBuf,
+        /// set secret file path
+        #[structopt(short, long, parse(from_os_str))]
+        key_file: PathBuf,
+    },
+
+    /// generate a recovery key for key backups, to be kept offline; the
+    /// enclave is built with its public key (SIGNATORY_SGX_RECOVERY_KEY)
+    RecoveryKeygen {
+        /// set the file to write the recovery secret key to
+        #[structopt(long, parse(from_os_str))]
+        recovery_key_file: PathBuf,
+    },
+
+    /// import a key from its backup into the enclave (e.g. on a new CPU)
+    Recover {
+        /// set sgxs file path
+        #[structopt(short, long, parse(from_os_str))]
+        sgx_file: PathBuf,
+        /// set the backup file written by `keygen --backup-file`
+        #[structopt(long, parse(from_os_str))]
+        backup_file: PathBuf,
+        /// set the recovery secret key file
+        #[structopt(long, parse(from_os_str))]
+        recovery_key_file: PathBuf,
+        /// set secret file path to store the recovered key
+        #[structopt(short, long, parse(from_os_str))]
+        key_file: PathBuf,
+    },
+
+    /// re-seal every key of a key directory under the current enclave and
+    /// platform security version (e.g. after an enclave or microcode upgrade)
+    Migrate {
+        /// set sgxs file path
+        #[structopt(short, long, parse(from_os_str))]
+        sgx_file: PathBuf,
+        /// set the key directory
+        #[structopt(long, parse(from_os_str))]
+        key_dir: PathBuf,
+    },
+}
+
+/// Result of a command: a message, and the same as fields for `--json`
+pub struct Output {
+    text: String,
+    json: Value,
+    /// whether the command succeeded, i.e. the exit status
+    success: bool,
+}
+
+impl Output {
+    fn new<S: Into<String>>(text: S, json: Value) -> Self {
+        Self {
+            text: text.into(),
+            json,
+            success: true,
+        }
+    }
+
+    /// Output of a command which failed, but has something to report
+    fn failure<S: Into<String>>(text: S, json: Value) -> Self {
+        Self {
+            success: false,
+            ..Self::new(text, json)
+        }
+    }
+
+    fn print(&self, json: bool) {
+        if json {
+            println!("{}", self.json);
+        } else if !self.text.is_empty() {
+            println!("{}", self.text);
+        }
+    }
 }
 
 fn start_server(client2server_rx: Receiver<C2S>, sgx_file: PathBuf) -> JoinHandle<()> {
     thread::spawn(move || {
         if let Err(e) = run_server(client2server_rx, sgx_file) {
-            println!("error: {:?}", e.what);
+            eprintln!("error: {:?}", e.what);
         };
     })
 }
 
+/// Load the enclave, run `f` with a channel to it, then unload it
+fn with_enclave<T, F>(sgx_file: &Path, f: F) -> Result<T, Error>
+where
+    F: FnOnce(Sender<C2S>) -> Result<T, Error>,
+{
+    let (client2server_tx, client2server_rx) = unbounded::<C2S>();
+    let t = start_server(client2server_rx, sgx_file.to_path_buf());
+    let result = f(client2server_tx.clone());
+    stop_server(client2server_tx);
+    let _ = t.join().unwrap();
+    result
+}
+
+/// Read a string argument, or else the content of a file or stdin
+fn read_input(data: &Option<String>, input: &Option<PathBuf>) -> Result<Vec<u8>, Error> {
+    match (data, input) {
+        (Some(_), Some(_)) => Err(Error::new("give either --data or --input")),
+        (Some(data), None) => Ok(data.clone().into_bytes()),
+        (None, Some(path)) if path != Path::new("-") => Ok(fs::read(path)?),
+        (None, _) => {
+            let mut bytes = vec![];
+            io::stdin().read_to_end(&mut bytes)?;
+            Ok(bytes)
+        }
+    }
+}
+
+fn inspect(key_file: &PathBuf) -> Result<Output, Error> {
+    match StoredKey::read(key_file)? {
+        StoredKey::Current(key_file) => {
+            let pubkey_str = Format::Base64.encode(&key_file.public_key)?;
+            let text = format!(
+                "algorithm: {:?}\npublic key (unverified): {}\n\
+                 created at: {} (seconds since the UNIX epoch)\n\
+                 key policy: {:?}\nisvsvn: {}\ncpusvn: {:?}",
+                key_file.algorithm,
+                pubkey_str,
+                key_file.created_at,
+                key_file.key_policy,
+                key_file.isvsvn,
+                key_file.cpusvn
+            );
+            let json = json!({
+                "legacy": false,
+                "algorithm": key_file.algorithm.to_string(),
+                "public_key": pubkey_str,
+                "created_at": key_file.created_at,
+                "key_policy": key_file.key_policy.bits(),
+                "isvsvn": key_file.isvsvn,
+                "cpusvn": Format::Hex.encode(&key_file.cpusvn)?,
+            });
+            Ok(Output::new(text, json))
+        }
+        StoredKey::Legacy(sealed_signer) => {
+            let seal_data = sealed_signer.seal_data();
+            let text = format!(
+                "legacy key file, run `upgrade` to add its public key\n\
+                 algorithm: {:?}\nisvsvn: {}\ncpusvn: {:?}",
+                sealed_signer.algorithm(),
+                seal_data.isvsvn(),
+                seal_data.cpusvn()
+            );
+            let json = json!({
+                "legacy": true,
+                "algorithm": sealed_signer.algorithm().to_string(),
+                "isvsvn": seal_data.isvsvn(),
+                "cpusvn": Format::Hex.encode(&seal_data.cpusvn())?,
+            });
+            Ok(Output::new(text, json))
+        }
+    }
+}
+
+/// Algorithm and public key of a key file, asking the enclave: the public key
+/// in the header of a key file isn't authenticated
+fn public_key(sgx_file: &PathBuf, key_file: &PathBuf) -> Result<(KeyAlgorithm, Vec<u8>), Error> {
+    with_enclave(sgx_file, |client2server_tx| {
+        let signer = SgxSigner::new(client2server_tx, key_file);
+        Ok((signer.key_algorithm()?, signer.get_pubkey()?))
+    })
+}
+
 impl CMD {
-    pub fn execute(&self) -> Result<(), Error> {
-        let (client2server_tx, client2server_rx) = unbounded::<C2S>();
-        let t = match self {
+    /// Run the command, returning what to print (nothing for raw output)
+    pub fn execute(&self) -> Result<Option<Output>, Error> {
+        let output = match self {
             // generate key pair
-            CMD::Keygen { sgx_file, key_file } => {
-                let t = start_server(client2server_rx, sgx_file.clone());
-                let signer = SgxSigner::new(client2server_tx.clone(), key_file);
-                let keypair = signer.keygen()?;
-                let pubkey_str = signer.store_key(&keypair)?;
-                println!(
-                    "stored secret key in file: {:?}, the public key is: {}",
-                    key_file, pubkey_str
-                );
-                t
+            CMD::Keygen {
+                sgx_file,
+                key_file,
+                algorithm,
+                backup_file,
+            } => {
+                // create the backup file before generating the key, so that
+                // an existing file is never overwritten
+                let mut backup_out = match backup_file {
+                    Some(backup_file) => Some(
+                        fs::OpenOptions::new()
+                            .write(true)
+                            .create_new(true)
+                            .mode(0o600)
+                            .open(backup_file)?,
+                    ),
+                    None => None,
+                };
+                let result = with_enclave(sgx_file, |client2server_tx| {
+                    let signer =
+                        SgxSigner::new(client2server_tx, key_file).with_algorithm(*algorithm);
+                    let key_pair = match backup_out.as_mut() {
+                        Some(backup_out) => {
+                            let (key_pair, backup) = signer.keygen_with_backup()?;
+                            backup_out.write_all(&backup)?;
+                            backup_out.sync_all()?;
+                            key_pair
+                        }
+                        None => signer.keygen()?,
+                    };
+                    signer.store_key(&key_pair)?;
+                    Ok(key_pair)
+                });
+                let key_pair = match (result, backup_file) {
+                    (Err(e), Some(backup_file)) => {
+                        let _ = fs::remove_file(backup_file);
+                        return Err(e);
+                    }
+                    (result, _) => result?,
+                };
+                let pubkey_str = Format::Base64.encode(&key_pair.pubkey)?;
+                Output::new(
+                    format!(
+                        "stored secret key in file: {:?}, the public key is: {}",
+                        key_file, pubkey_str
+                    ),
+                    json!({
+                        "key_file": key_file,
+                        "algorithm": algorithm.to_string(),
+                        "public_key": pubkey_str,
+                    }),
+                )
             }
             // import a secret key
             CMD::Import {
                 sgx_file,
                 key_file,
                 key,
+                input,
                 key_type,
+                algorithm,
             } => {
-                let t = start_server(client2server_rx, sgx_file.clone());
-                let signer = SgxSigner::new(client2server_tx.clone(), key_file);
-                let ktype: KeyType;
-                if key_type == "base64" {
-                    ktype = KeyType::Base64;
-                } else {
-                    return Err(Error::new("error key_type"));
-                }
-                signer.import(ktype, key)?;
-                println!("import success");
-                t
+                let key = match (key, input) {
+                    (Some(_), Some(_)) => return Err(Error::new("give either --key or --input")),
+                    (None, None) => return Err(Error::new("give --key or --input")),
+                    (key, input) => read_input(key, input)?,
+                };
+                let (key_algorithm, secret) = key_type.decode(&key)?;
+                let algorithm = match (key_algorithm, *algorithm) {
+                    (Some(a), Some(b)) if a != b => {
+                        return Err(Error::new(format!("the key is a {} key, not {}", a, b)))
+                    }
+                    (Some(a), _) | (None, Some(a)) => a,
+                    (None, None) => KeyAlgorithm::default(),
+                };
+                let key_pair = with_enclave(sgx_file, |client2server_tx| {
+                    let signer =
+                        SgxSigner::new(client2server_tx, key_file).with_algorithm(algorithm);
+                    let key_pair = signer.import_secret(secret)?;
+                    signer.store_key(&key_pair)?;
+                    Ok(key_pair)
+                })?;
+                let pubkey_str = Format::Base64.encode(&key_pair.pubkey)?;
+                Output::new(
+                    format!(
+                        "imported secret key to file: {:?}, the public key is: {}",
+                        key_file, pubkey_str
+                    ),
+                    json!({
+                        "key_file": key_file,
+                        "algorithm": algorithm.to_string(),
+                        "public_key": pubkey_str,
+                    }),
+                )
             }
             // get public key from a secret file
-            CMD::Publickey { sgx_file, key_file } => {
-                let t = start_server(client2server_rx, sgx_file.clone());
-                let signer = SgxSigner::new(client2server_tx.clone(), key_file);
-                let pubkey = signer.public_key().unwrap();
-                let encoder = SecretKeyEncoding::default();
-                let pubkey_str = encoder.encode_to_string(pubkey.as_bytes()).unwrap();
-                println!("public key: {}", pubkey_str);
-                t
+            CMD::Publickey {
+                sgx_file,
+                key_file,
+                format,
+                hrp,
+            } => {
+                let (algorithm, pubkey) = public_key(sgx_file, key_file)?;
+                let pubkey_str =
+                    format.encode(algorithm, &pubkey, hrp.as_ref().map(String::as_str))?;
+                let text = if *format == PublicKeyFormat::Pem {
+                    pubkey_str.trim_end().to_owned()
+                } else {
+                    format!("public key: {}", pubkey_str)
+                };
+                Output::new(
+                    text,
+                    json!({
+                        "algorithm": algorithm.to_string(),
+                        "public_key": pubkey_str,
+                    }),
+                )
             }
-            // sign a string
+            // sign data
             CMD::Sign {
                 sgx_file,
                 key_file,
                 data,
+                input,
+                output_format,
             } => {
-                let t = start_server(client2server_rx, sgx_file.clone());
-                let signer = SgxSigner::new(client2server_tx.clone(), key_file);
-                let data_raw: Vec<u8> = data.clone().into_bytes();
-                let signed_result = signer.try_sign(&data_raw).unwrap();
-                println!("signed result: {:?}", signed_result);
-                t
+                let data = read_input(data, input)?;
+                let signature = with_enclave(sgx_file, |client2server_tx| {
+                    SgxSigner::new(client2server_tx, key_file).sign_msg(&data)
+                })?;
+                if *output_format == Format::Raw {
+                    io::stdout().write_all(&signature)?;
+                    return Ok(None);
+                }
+                let signature_str = output_format.encode(&signature)?;
+                Output::new(
+                    format!("signature: {}", signature_str),
+                    json!({ "signature": signature_str }),
+                )
+            }
+            // sign each line of a file
+            CMD::SignBatch {
+                sgx_file,
+                key_file,
+                input,
+                input_format,
+                output_format,
+            } => {
+                let input = String::from_utf8(read_input(&None, &Some(input.clone()))?)
+                    .map_err(|_| Error::new("the input is not UTF-8"))?;
+                let messages = input
+                    .lines()
+                    .map(|line| input_format.decode(line.trim_end_matches('\r')))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                let signatures = with_enclave(sgx_file, |client2server_tx| {
+                    let signer = SgxSigner::new(client2server_tx, key_file);
+                    messages
+                        .iter()
+                        .map(|msg| output_format.encode(&signer.sign_msg(msg)?))
+                        .collect::<Result<Vec<_>, Error>>()
+                })?;
+                Output::new(signatures.join("\n"), json!({ "signatures": signatures }))
+            }
+            // verify a signature
+            CMD::Verify {
+                sgx_file,
+                key_file,
+                public_key,
+                public_key_format,
+                algorithm,
+                signature,
+                signature_format,
+                data,
+                input,
+            } => {
+                let (algorithm, pubkey) = match (key_file, public_key) {
+                    (Some(key_file), None) => {
+                        let sgx_file = sgx_file.as_ref().ok_or_else(|| {
+                            Error::new("--key-file needs --sgx-file to read its public key")
+                        })?;
+                        public_key(sgx_file, key_file)?
+                    }
+                    (None, Some(public_key)) => (*algorithm, public_key_format.decode(public_key)?),
+                    _ => return Err(Error::new("give either --key-file or --public-key")),
+                };
+                let signature = signature_format.decode(signature)?;
+                let data = read_input(data, input)?;
+                if verify(algorithm, &pubkey, &data, &signature)? {
+                    Output::new("signature is valid", json!({ "valid": true }))
+                } else {
+                    Output::failure("invalid signature", json!({ "valid": false }))
+                }
+            }
+            // run as a daemon until killed
+            CMD::Daemon {
+                sgx_file,
+                socket,
+                tcp,
+            } => {
+                with_enclave(sgx_file, |client2server_tx| {
+                    let daemon = Daemon::new(client2server_tx);
+                    let unix_listener = bind_unix_socket(socket)?;
+                    if let Some(addr) = tcp {
+                        let tcp_listener = bind_tcp(addr.as_str())?;
+                        let daemon = daemon.clone();
+                        thread::spawn(move || daemon.serve_tcp(tcp_listener));
+                    }
+                    eprintln!("listening on {:?}", socket);
+                    daemon.serve_unix(unix_listener)
+                })?;
+                return Ok(None);
+            }
+            CMD::Inspect { key_file } => inspect(key_file)?,
+            // upgrade a legacy key file
+            CMD::Upgrade { sgx_file, key_file } => {
+                let upgraded = with_enclave(sgx_file, |client2server_tx| {
+                    SgxClient::new(client2server_tx).upgrade_key_file(key_file)
+                })?;
+                Output::new(
+                    format!(
+                        "key file {:?} is in the current format, algorithm: {:?}",
+                        key_file, upgraded.algorithm
+                    ),
+                    json!({
+                        "key_file": key_file,
+                        "algorithm": upgraded.algorithm.to_string(),
+                    }),
+                )
+            }
+            // generate a recovery key
+            CMD::RecoveryKeygen { recovery_key_file } => {
+                let recovery_secret_key = RecoverySecretKey::generate();
+                fs::OpenOptions::new()
+                    .write(true)
+                    .create_new(true)
+                    .mode(0o600)
+                    .open(recovery_key_file)?
+                    .write_all(recovery_secret_key.as_bytes())?;
+                let recovery_key = recovery_secret_key.public_key();
+                Output::new(
+                    format!(
+                        "stored recovery secret key in file: {:?}, the recovery public key is: {}",
+                        recovery_key_file, recovery_key
+                    ),
+                    json!({
+                        "recovery_key_file": recovery_key_file,
+                        "recovery_public_key": recovery_key.to_string(),
+                    }),
+                )
+            }
+            // import a key from its backup
+            CMD::Recover {
+                sgx_file,
+                backup_file,
+                recovery_key_file,
+                key_file,
+            } => {
+                let backup = KeyBackup::decode(&fs::read(backup_file)?)?;
+                let recovery_secret_key =
+                    RecoverySecretKey::from_bytes(&fs::read(recovery_key_file)?)?;
+                let key_pair = with_enclave(sgx_file, |client2server_tx| {
+                    let signer = SgxSigner::new(client2server_tx, key_file);
+                    let key_pair = signer.client().recover(&backup, &recovery_secret_key)?;
+                    signer.store_key(&key_pair)?;
+                    Ok(key_pair)
+                })?;
+                let pubkey_str = Format::Base64.encode(&key_pair.pubkey)?;
+                Output::new(
+                    format!(
+                        "recovered secret key to file: {:?}, the public key is: {}",
+                        key_file, pubkey_str
+                    ),
+                    json!({
+                        "key_file": key_file,
+                        "algorithm": backup.algorithm.to_string(),
+                        "public_key": pubkey_str,
+                    }),
+                )
+            }
+            // re-seal all keys of a key directory
+            CMD::Migrate { sgx_file, key_dir } => {
+                let (resealed, failed) = with_enclave(sgx_file, |client2server_tx| {
+                    let client = KeyStoreClient::new(
+                        SgxClient::new(client2server_tx),
+                        KeyStore::open(key_dir)?,
+                    );
+                    let mut resealed = vec![];
+                    let mut failed = vec![];
+                    for key_id in client.list_keys()? {
+                        match client.reseal_key(&key_id) {
+                            Ok(()) => resealed.push(key_id.to_string()),
+                            Err(e) => failed.push((key_id.to_string(), e.what)),
+                        }
+                    }
+                    Ok((resealed, failed))
+                })?;
+                let mut lines: Vec<String> = resealed
+                    .iter()
+                    .map(|key_id| format!("resealed key {}", key_id))
+                    .collect();
+                lines.extend(
+                    failed
+                        .iter()
+                        .map(|(key_id, what)| format!("failed to reseal key {}: {}", key_id, what)),
+                );
+                let failures: Value = failed
+                    .iter()
+                    .map(|(key_id, what)| json!({ "key_id": key_id, "error": what }))
+                    .collect();
+                let json = json!({ "resealed": resealed, "failed": failures });
+                if failed.is_empty() {
+                    Output::new(lines.join("\n"), json)
+                } else {
+                    lines.push(format!("failed to reseal {} key(s)", failed.len()));
+                    Output::failure(lines.join("\n"), json)
+                }
             }
         };
-        stop_server(client2server_tx);
-        let _ = t.join().unwrap();
-        Ok(())
+        Ok(Some(output))
     }
 }
 
 fn main() {
     env_logger::init();
-    let cmd = CMD::from_args();
-    if let Err(e) = cmd.execute() {
-        println!("error: {}", e.what);
-    };
+    let opt = Opt::from_args();
+    match opt.cmd.execute() {
+        Ok(Some(output)) => {
+            output.print(opt.json);
+            if !output.success {
+                process::exit(1);
+            }
+        }
+        Ok(None) => {}
+        Err(e) => {
+            if opt.json {
+                println!("{}", json!({ "error": e.what }));
+            } else {
+                eprintln!("error: {}", e.what);
+            }
+            process::exit(1);
+        }
+    }
 }
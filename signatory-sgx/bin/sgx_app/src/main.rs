@@ -1,30 +1,28 @@
 mod logger;
-use signatory_sgx::backend::serve;
-use signatory_sgx::error::ErrorKind;
-use signatory_sgx::protocol::{Encode, Response};
-use std::io::prelude::*;
+use signatory_sgx::backend::{run_with_config, EnclaveConfig};
+use signatory_sgx::backup::RecoveryPublicKey;
+use signatory_sgx::policy::SigningPolicy;
 use std::net::TcpStream;
 
+#[cfg(not(feature = "tendermint-policy"))]
+const SIGNING_POLICY: SigningPolicy = SigningPolicy::Unrestricted;
+#[cfg(feature = "tendermint-policy")]
+const SIGNING_POLICY: SigningPolicy = SigningPolicy::Tendermint;
+
+/// Recovery key given at build time (hex), so that it is part of the
+/// enclave measurement and can't be swapped by the host
+fn recovery_key() -> Option<RecoveryPublicKey> {
+    option_env!("SIGNATORY_SGX_RECOVERY_KEY")
+        .map(|key| key.parse().expect("invalid SIGNATORY_SGX_RECOVERY_KEY"))
+}
+
 fn main() {
     logger::init().expect("init log failed");
-    loop {
-        let mut stream = TcpStream::connect("sgx").expect("failed to connect sgx");
-
-        if let Err(e) = serve(&mut stream) {
-            if e.kind == ErrorKind::Stop {
-                break;
-            }
-            log::error!("error to handle request: {:?}", e);
-            let _ = Response::Error(e.what.to_string())
-                .encode(true)
-                .map(|data| {
-                    let _ = stream.write(&data);
-                })
-                .map_err(|e| {
-                    log::error!("encode data failed: {}", e);
-                });
-        } else {
-            log::info!("handle request success!");
-        }
+    let config = EnclaveConfig {
+        signing_policy: SIGNING_POLICY,
+        recovery_key: recovery_key(),
+    };
+    if let Err(e) = run_with_config(|| TcpStream::connect("sgx"), config) {
+        log::error!("failed to connect sgx: {:?}", e);
     }
 }
@@ -0,0 +1,445 @@
+//! Remote attestation of keys held by the enclave.
+//!
+//! The enclave creates an SGX report targeted at a quoting enclave, with
+//! `reportdata` binding the SHA-256 digest of the key algorithm and public
+//! key (see `protocol::public_key_reportdata`). The quoting enclave turns the report
+//! into a quote which a relying party can check with `AttestationPolicy`:
+//! the quote must be authentic (checked by a `QuoteVerifier`), come from the
+//! expected enclave (MRENCLAVE and/or MRSIGNER, ISVPRODID, minimum ISVSVN,
+//! not in debug mode) and bind the attested public key.
+//!
+//! On hardware, quotes are EPID quotes obtained through AESM
+//! (`AesmQuotingEnclave`); checking their signature requires the Intel
+//! Attestation Service and is left to a `QuoteVerifier` implementation. In
+//! simulation mode, `SimQuotingEnclave` signs report bodies with an Ed25519
+//! key and `SimQuoteVerifier` checks them.
+
+use crate::error::Error;
+use crate::keystore::{KeyId, KeyStoreClient};
+use crate::protocol::{public_key_reportdata, Decode, Encode, KeyAlgorithm, Request, Response};
+use crate::provider::{Connection, SgxClient, SgxSigner};
+use crate::seal_signer::SealedSigner;
+use aesm_client::AesmClient;
+use serde::{Deserialize, Serialize};
+use sgx_isa::{AttributesFlags, Report};
+use std::path::Path;
+
+/// Size of an SGX report body, i.e. a report without `keyid` and `mac`
+pub const REPORT_BODY_SIZE: usize = 384;
+
+/// Size of an SGX report
+const REPORT_SIZE: usize = 432;
+
+/// Offset of the report body in an EPID quote
+const EPID_QUOTE_BODY_OFFSET: usize = 48;
+
+/// Quote of an SGX report
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Quote {
+    /// EPID quote produced by the platform's quoting enclave
+    Epid(Vec<u8>),
+
+    /// Report body signed by a `SimQuotingEnclave`
+    Simulated { body: Vec<u8>, signature: Vec<u8> },
+}
+
+impl Quote {
+    /// The quoted report body
+    pub fn report_body(&self) -> Result<&[u8], Error> {
+        let body = match self {
+            Quote::Epid(quote) => quote
+                .get(EPID_QUOTE_BODY_OFFSET..EPID_QUOTE_BODY_OFFSET + REPORT_BODY_SIZE)
+                .ok_or_else(|| Error::new("EPID quote too short"))?,
+            Quote::Simulated { body, .. } => body.as_slice(),
+        };
+        if body.len() != REPORT_BODY_SIZE {
+            return Err(Error::new("invalid report body size"));
+        }
+        Ok(body)
+    }
+
+    /// Parse the quoted report body (`keyid` and `mac` are zeroed)
+    pub fn report(&self) -> Result<Report, Error> {
+        let mut report = self.report_body()?.to_vec();
+        report.resize(REPORT_SIZE, 0);
+        Report::try_copy_from(&report).ok_or_else(|| Error::new("invalid report body"))
+    }
+}
+
+impl Encode for Quote {}
+impl<'de> Decode<'de> for Quote {}
+
+/// Evidence that a public key belongs to a key held by the enclave
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Attestation {
+    pub algorithm: KeyAlgorithm,
+    pub public_key: Vec<u8>,
+    pub quote: Quote,
+}
+
+impl Encode for Attestation {}
+impl<'de> Decode<'de> for Attestation {}
+
+/// Turns enclave reports into quotes
+pub trait QuotingEnclave {
+    /// `Targetinfo` of the quoting enclave, which reports must target
+    fn target_info(&self) -> Result<Vec<u8>, Error>;
+
+    /// Quote a report targeted at this quoting enclave
+    fn quote(&self, report: &[u8]) -> Result<Quote, Error>;
+}
+
+/// The platform's EPID quoting enclave, through AESM
+pub struct AesmQuotingEnclave {
+    client: AesmClient,
+    spid: Vec<u8>,
+    sig_rl: Vec<u8>,
+}
+
+impl AesmQuotingEnclave {
+    /// Create a quoting enclave client for the given service provider ID
+    pub fn new(spid: [u8; 16]) -> Self {
+        Self {
+            client: AesmClient::new(),
+            spid: spid.to_vec(),
+            sig_rl: vec![],
+        }
+    }
+
+    /// Set the signature revocation list of the platform's EPID group
+    pub fn with_sig_rl(mut self, sig_rl: Vec<u8>) -> Self {
+        self.sig_rl = sig_rl;
+        self
+    }
+}
+
+impl QuotingEnclave for AesmQuotingEnclave {
+    fn target_info(&self) -> Result<Vec<u8>, Error> {
+        let quote_info = self
+            .client
+            .init_quote()
+            .map_err(|e| Error::new(format!("init quote error: {:?}", e)))?;
+        Ok(quote_info.target_info().to_vec())
+    }
+
+    fn quote(&self, report: &[u8]) -> Result<Quote, Error> {
+        let quote_info = self
+            .client
+            .init_quote()
+            .map_err(|e| Error::new(format!("init quote error: {:?}", e)))?;
+        let quote = self
+            .client
+            .get_quote(
+                &quote_info,
+                report.to_vec(),
+                self.spid.clone(),
+                self.sig_rl.clone(),
+            )
+            .map_err(|e| Error::new(format!("get quote error: {:?}", e)))?;
+        Ok(Quote::Epid(quote.quote().to_vec()))
+    }
+}
+
+/// Checks that a quote was produced by a genuine quoting enclave
+pub trait QuoteVerifier {
+    fn verify_quote(&self, quote: &Quote) -> Result<(), Error>;
+}
+
+/// What a relying party expects from an attested enclave
+#[derive(Clone, Debug, Default)]
+pub struct AttestationPolicy {
+    /// Expected enclave measurement
+    pub mrenclave: Option<[u8; 32]>,
+
+    /// Expected enclave signer
+    pub mrsigner: Option<[u8; 32]>,
+
+    /// Expected product ID
+    pub isvprodid: Option<u16>,
+
+    /// Minimum security version of the enclave
+    pub min_isvsvn: u16,
+
+    /// Accept enclaves running in debug mode, whose memory is not protected
+    pub allow_debug: bool,
+}
+
+impl AttestationPolicy {
+    /// Verify an attestation, returning the attested report.
+    ///
+    /// At least one of MRENCLAVE and MRSIGNER must be pinned by the policy.
+    pub fn verify<V: QuoteVerifier>(
+        &self,
+        verifier: &V,
+        attestation: &Attestation,
+    ) -> Result<Report, Error> {
+        if self.mrenclave.is_none() && self.mrsigner.is_none() {
+            return Err(Error::new("the policy pins neither MRENCLAVE nor MRSIGNER"));
+        }
+
+        verifier.verify_quote(&attestation.quote)?;
+        let report = attestation.quote.report()?;
+
+        if let Some(mrenclave) = self.mrenclave {
+            if report.mrenclave != mrenclave {
+                return Err(Error::new("MRENCLAVE mismatch"));
+            }
+        }
+
+        if let Some(mrsigner) = self.mrsigner {
+            if report.mrsigner != mrsigner {
+                return Err(Error::new("MRSIGNER mismatch"));
+            }
+        }
+
+        if let Some(isvprodid) = self.isvprodid {
+            if report.isvprodid != isvprodid {
+                return Err(Error::new("ISVPRODID mismatch"));
+            }
+        }
+
+        if report.isvsvn < self.min_isvsvn {
+            return Err(Error::new(format!(
+                "ISVSVN {} is lower than the minimum {}",
+                report.isvsvn, self.min_isvsvn
+            )));
+        }
+
+        if !self.allow_debug && report.attributes.flags.contains(AttributesFlags::DEBUG) {
+            return Err(Error::new("the enclave runs in debug mode"));
+        }
+
+        let reportdata = public_key_reportdata(attestation.algorithm, &attestation.public_key);
+        if report.reportdata[..] != reportdata[..] {
+            return Err(Error::new("the report does not bind the key"));
+        }
+
+        Ok(report)
+    }
+}
+
+impl<C: Connection> SgxClient<C> {
+    /// Attest a sealed key with the given quoting enclave
+    pub fn attest<Q: QuotingEnclave>(
+        &self,
+        sealed_signer: SealedSigner,
+        quoting_enclave: &Q,
+    ) -> Result<Attestation, Error> {
+        let algorithm = sealed_signer.algorithm();
+        let request = Request::Attest {
+            sealed_signer,
+            target_info: quoting_enclave.target_info()?,
+        };
+        match self.send(request)? {
+            Response::Report { public_key, report } => Ok(Attestation {
+                algorithm,
+                public_key,
+                quote: quoting_enclave.quote(&report)?,
+            }),
+            Response::Error(s) => Err(Error::new(s)),
+            _ => Err(Error::new("response error")),
+        }
+    }
+}
+
+impl<P: AsRef<Path>, C: Connection> SgxSigner<P, C> {
+    /// Attest the key of this signer with the given quoting enclave
+    pub fn attest<Q: QuotingEnclave>(&self, quoting_enclave: &Q) -> Result<Attestation, Error> {
+        self.client()
+            .attest(self.get_sealed_signer()?, quoting_enclave)
+    }
+}
+
+impl<C: Connection> KeyStoreClient<C> {
+    /// Attest a key of the store with the given quoting enclave
+    pub fn attest<Q: QuotingEnclave>(
+        &self,
+        key_id: &KeyId,
+        quoting_enclave: &Q,
+    ) -> Result<Attestation, Error> {
+        self.client()
+            .attest(self.store().load(key_id)?, quoting_enclave)
+    }
+}
+
+#[cfg(feature = "sim")]
+pub use self::simulated::{SimQuoteVerifier, SimQuotingEnclave};
+
+#[cfg(feature = "sim")]
+mod simulated {
+    use super::{Quote, QuoteVerifier, QuotingEnclave, REPORT_BODY_SIZE};
+    use crate::error::Error;
+    use crate::sim::{verify_report, QE_MRENCLAVE};
+    use sgx_isa::{Report, Targetinfo};
+    use signatory::ed25519;
+    use signatory::public_key::PublicKeyed;
+    use signatory::signature::{Signature, Signer, Verifier};
+    use signatory_dalek::{Ed25519Signer, Ed25519Verifier};
+
+    /// Simulated quoting enclave, signing report bodies with an Ed25519 key
+    pub struct SimQuotingEnclave {
+        signer: Ed25519Signer,
+    }
+
+    impl SimQuotingEnclave {
+        pub fn new(seed: &ed25519::Seed) -> Self {
+            Self {
+                signer: Ed25519Signer::from(seed),
+            }
+        }
+
+        /// Public key relying parties use to check quotes (see
+        /// `SimQuoteVerifier`)
+        pub fn public_key(&self) -> ed25519::PublicKey {
+            self.signer.public_key().unwrap()
+        }
+    }
+
+    impl QuotingEnclave for SimQuotingEnclave {
+        fn target_info(&self) -> Result<Vec<u8>, Error> {
+            let targetinfo = Targetinfo {
+                measurement: QE_MRENCLAVE,
+                ..Default::default()
+            };
+            Ok(targetinfo.as_ref().to_vec())
+        }
+
+        fn quote(&self, report: &[u8]) -> Result<Quote, Error> {
+            let report =
+                Report::try_copy_from(report).ok_or_else(|| Error::new("invalid report"))?;
+            if !verify_report(&report, &QE_MRENCLAVE) {
+                return Err(Error::new("invalid report MAC"));
+            }
+            let body = report.as_ref()[..REPORT_BODY_SIZE].to_vec();
+            let signature: ed25519::Signature = self.signer.sign(&body);
+            Ok(Quote::Simulated {
+                body,
+                signature: signature.as_ref().to_vec(),
+            })
+        }
+    }
+
+    /// Checks quotes of a `SimQuotingEnclave`
+    pub struct SimQuoteVerifier {
+        verifier: Ed25519Verifier,
+    }
+
+    impl SimQuoteVerifier {
+        pub fn new(qe_public_key: &ed25519::PublicKey) -> Self {
+            Self {
+                verifier: Ed25519Verifier::from(qe_public_key),
+            }
+        }
+    }
+
+    impl QuoteVerifier for SimQuoteVerifier {
+        fn verify_quote(&self, quote: &Quote) -> Result<(), Error> {
+            match quote {
+                Quote::Simulated { body, signature } => {
+                    let signature = ed25519::Signature::from_bytes(signature)
+                        .map_err(|_| Error::new("invalid quote signature"))?;
+                    self.verifier
+                        .verify(body, &signature)
+                        .map_err(|_| Error::new("invalid quote signature"))
+                }
+                Quote::Epid(_) => Err(Error::new("not a simulated quote")),
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "sim"))]
+mod tests {
+    use super::*;
+    use crate::server::{run_sim_server, stop_server, C2S};
+    use crate::sim;
+    use crossbeam_channel::unbounded;
+    use signatory::ed25519;
+    use std::thread;
+
+    fn policy() -> AttestationPolicy {
+        let platform = sim::platform();
+        AttestationPolicy {
+            mrenclave: Some(platform.mrenclave),
+            mrsigner: Some(platform.mrsigner),
+            isvprodid: Some(platform.isvprodid),
+            min_isvsvn: platform.isvsvn,
+            allow_debug: false,
+        }
+    }
+
+    #[test]
+    fn test_attestation() {
+        let (client2server_tx, client2server_rx) = unbounded::<C2S>();
+        let server = thread::spawn(move || run_sim_server(client2server_rx).unwrap());
+        let client = SgxClient::new(client2server_tx.clone());
+
+        let quoting_enclave = SimQuotingEnclave::new(&ed25519::Seed::generate());
+        let verifier = SimQuoteVerifier::new(&quoting_enclave.public_key());
+
+        for &algorithm in &[KeyAlgorithm::Ed25519, KeyAlgorithm::Secp256k1] {
+            let key_pair = client.keygen(algorithm).unwrap();
+            let attestation = client
+                .attest(key_pair.sealed_privkey, &quoting_enclave)
+                .unwrap();
+            assert_eq!(attestation.algorithm, algorithm);
+            assert_eq!(attestation.public_key, key_pair.pubkey);
+
+            // survives a round trip to the relying party
+            let attestation = Attestation::decode(&attestation.encode().unwrap()).unwrap();
+            assert!(policy().verify(&verifier, &attestation).is_ok());
+
+            // substituted public key
+            let mut forged = attestation.clone();
+            forged.public_key[1] ^= 1;
+            assert!(policy().verify(&verifier, &forged).is_err());
+
+            // substituted algorithm
+            let mut forged = attestation.clone();
+            forged.algorithm = KeyAlgorithm::NistP256;
+            assert!(policy().verify(&verifier, &forged).is_err());
+
+            // tampered report body
+            let mut forged = attestation.clone();
+            if let Quote::Simulated { body, .. } = &mut forged.quote {
+                body[0] ^= 1;
+            }
+            assert!(policy().verify(&verifier, &forged).is_err());
+        }
+
+        let attestation = client
+            .attest(
+                client.keygen(KeyAlgorithm::Ed25519).unwrap().sealed_privkey,
+                &quoting_enclave,
+            )
+            .unwrap();
+
+        // quotes of another quoting enclave
+        let other_verifier =
+            SimQuoteVerifier::new(&SimQuotingEnclave::new(&ed25519::Seed::generate()).public_key());
+        assert!(policy().verify(&other_verifier, &attestation).is_err());
+
+        let mut wrong_enclave = policy();
+        wrong_enclave.mrenclave = Some([0; 32]);
+        assert!(wrong_enclave.verify(&verifier, &attestation).is_err());
+
+        let mut newer_enclave = policy();
+        newer_enclave.min_isvsvn += 1;
+        assert!(newer_enclave.verify(&verifier, &attestation).is_err());
+
+        assert!(AttestationPolicy::default()
+            .verify(&verifier, &attestation)
+            .is_err());
+
+        drop(client);
+        stop_server(client2server_tx);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_quoting_enclave_rejects_untargeted_report() {
+        let quoting_enclave = SimQuotingEnclave::new(&ed25519::Seed::generate());
+        let report = sim::report_for_self();
+        assert!(quoting_enclave.quote(report.as_ref()).is_err());
+    }
+}
@@ -1,11 +1,118 @@
-use crate::error::Error;
-use crate::protocol::{get_data_from_stream, Decode, Encode, KeyPair, Request, Response};
-use crate::seal_signer::SealedSigner;
-use log::{debug, info};
-use std::io::prelude::*;
-use std::net::TcpStream;
-
-fn handle_request(raw_data: &[u8]) -> Result<Response, Error> {
+use crate::backup::RecoveryPublicKey;
+use crate::error::{Error, ErrorKind};
+use crate::framing::{read_frame, write_frame, Frame};
+use crate::policy::{ConsensusPolicy, SigningPolicy};
+use crate::protocol::{
+    key_handle, public_key_reportdata, Decode, Encode, KeyHandle, KeyPair, Request, Response,
+};
+use crate::seal_signer::{SealedSigner, UnsealedSigner};
+#[cfg(feature = "sim")]
+use crate::sim::report_for_target;
+use log::{debug, error, info};
+#[cfg(not(feature = "sim"))]
+use sgx_isa::Report;
+use sgx_isa::Targetinfo;
+use std::collections::HashMap;
+use std::io::{self, prelude::*};
+use std::time::{Duration, Instant};
+
+/// Unsealed keys kept in enclave memory, each until its time-to-live expires.
+///
+/// Keys are cached under the digest of their sealed form (see
+/// `protocol::key_handle`) rather than a name chosen by the client, so a
+/// client can neither use nor replace a key it doesn't hold.
+#[derive(Default)]
+pub struct KeyCache {
+    keys: HashMap<KeyHandle, CachedKey>,
+}
+
+struct CachedKey {
+    signer: UnsealedSigner,
+    expires_at: Instant,
+}
+
+impl KeyCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Unseal a key and cache it under its key handle for `ttl` (loading a
+    /// key again only extends its time-to-live)
+    pub fn load(
+        &mut self,
+        sealed_signer: &SealedSigner,
+        ttl: Duration,
+    ) -> Result<&UnsealedSigner, Error> {
+        let key_handle = key_handle(sealed_signer)?;
+        let signer = sealed_signer.get_signer()?;
+        let expires_at = Instant::now() + ttl;
+        self.keys
+            .insert(key_handle, CachedKey { signer, expires_at });
+        Ok(&self.keys[&key_handle].signer)
+    }
+
+    /// Get a cached key, if it is loaded and has not expired yet
+    pub fn get(&mut self, key_handle: &KeyHandle) -> Option<&UnsealedSigner> {
+        self.purge_expired();
+        self.keys.get(key_handle).map(|key| &key.signer)
+    }
+
+    /// Drop a cached key, returning whether it was loaded
+    pub fn evict(&mut self, key_handle: &KeyHandle) -> bool {
+        self.keys.remove(key_handle).is_some()
+    }
+
+    fn purge_expired(&mut self) {
+        let now = Instant::now();
+        self.keys.retain(|_, key| key.expires_at > now);
+    }
+}
+
+/// Configuration of the enclave, fixed when it is built
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EnclaveConfig {
+    pub signing_policy: SigningPolicy,
+    /// Recovery key to which keys generated with a backup are encrypted
+    pub recovery_key: Option<RecoveryPublicKey>,
+}
+
+/// Everything the enclave keeps in memory between requests
+#[derive(Default)]
+pub struct EnclaveState {
+    pub key_cache: KeyCache,
+    pub signing_policy: SigningPolicy,
+    pub consensus_policy: ConsensusPolicy,
+    pub recovery_key: Option<RecoveryPublicKey>,
+}
+
+impl EnclaveState {
+    pub fn new(signing_policy: SigningPolicy) -> Self {
+        Self::with_config(EnclaveConfig {
+            signing_policy,
+            ..EnclaveConfig::default()
+        })
+    }
+
+    pub fn with_config(config: EnclaveConfig) -> Self {
+        Self {
+            signing_policy: config.signing_policy,
+            recovery_key: config.recovery_key,
+            ..Self::default()
+        }
+    }
+
+    /// Refuse requests to sign arbitrary bytes unless the policy allows it
+    fn check_raw_signing(&self) -> Result<(), Error> {
+        match self.signing_policy {
+            SigningPolicy::Unrestricted => Ok(()),
+            SigningPolicy::Tendermint => Err(Error::new(
+                "signing arbitrary data is disabled by the signing policy",
+            )),
+        }
+    }
+}
+
+fn handle_request(raw_data: &[u8], state: &mut EnclaveState) -> Result<Response, Error> {
     debug!("handle raw data: {:?}", raw_data);
     let request =
         Request::decode(raw_data).map_err(|e| Error::new(format!("invalid request: {:?}", e)))?;
@@ -14,24 +121,39 @@ fn handle_request(raw_data: &[u8]) -> Result<Response, Error> {
             info!("get ping, return pong");
             Ok(Response::Pong)
         }
-        Request::KeyGen => {
-            info!("generate keypair");
-            let sealed_privkey = SealedSigner::new()?;
+        Request::KeyGen(algorithm) => {
+            info!("generate {:?} keypair", algorithm);
+            let sealed_privkey = SealedSigner::generate(algorithm)?;
             debug!("sealed signer: {:?}", sealed_privkey);
-            let pubkey = sealed_privkey.get_public_key()?;
-            let raw_pubkey = pubkey.into_bytes().to_vec();
+            let raw_pubkey = sealed_privkey.get_public_key()?;
             let key_pair = KeyPair {
                 sealed_privkey,
                 pubkey: raw_pubkey,
             };
             Ok(Response::KeyPair(key_pair))
         }
-        Request::Import(raw_key_pair) => {
-            info!("import key");
-            let sealed_privkey = SealedSigner::import(raw_key_pair)?;
+        Request::KeyGenWithBackup(algorithm) => {
+            info!("generate {:?} keypair with a backup", algorithm);
+            let recovery_key = state
+                .recovery_key
+                .ok_or_else(|| Error::new("no recovery key is configured in the enclave"))?;
+            let sealed_privkey = SealedSigner::generate(algorithm)?;
+            let raw_pubkey = sealed_privkey.get_public_key()?;
+            let backup = sealed_privkey.backup(&recovery_key, &raw_pubkey)?;
+            let key_pair = KeyPair {
+                sealed_privkey,
+                pubkey: raw_pubkey,
+            };
+            Ok(Response::KeyPairWithBackup {
+                key_pair,
+                backup: backup.encode()?,
+            })
+        }
+        Request::Import(algorithm, raw_secret_key) => {
+            info!("import {:?} key", algorithm);
+            let sealed_privkey = SealedSigner::import_key(algorithm, &raw_secret_key)?;
             debug!("sealed signer: {:?}", sealed_privkey);
-            let pubkey = sealed_privkey.get_public_key()?;
-            let raw_pubkey = pubkey.into_bytes().to_vec();
+            let raw_pubkey = sealed_privkey.get_public_key()?;
             let key_pair = KeyPair {
                 sealed_privkey,
                 pubkey: raw_pubkey,
@@ -41,26 +163,181 @@ fn handle_request(raw_data: &[u8]) -> Result<Response, Error> {
         Request::GetPublicKey(sealed_signer) => {
             info!("get public key");
             debug!("get sealed signer: {:?}", sealed_signer);
-            let pubkey = sealed_signer.get_public_key()?;
-            let raw_pubkey = pubkey.into_bytes().to_vec();
+            let raw_pubkey = sealed_signer.get_public_key()?;
             Ok(Response::PublicKey(raw_pubkey))
         }
         Request::Sign((sealed_signer, raw_data)) => {
             info!("sign data");
+            state.check_raw_signing()?;
             let sig = sealed_signer.try_sign(&raw_data)?;
             Ok(Response::Signed(sig))
         }
+        Request::LoadKey {
+            sealed_signer,
+            ttl_secs,
+        } => {
+            info!("load {:?} key", sealed_signer.algorithm());
+            let signer = state
+                .key_cache
+                .load(&sealed_signer, Duration::from_secs(ttl_secs))?;
+            Ok(Response::PublicKey(signer.public_key()?))
+        }
+        Request::GetLoadedPublicKey(key_handle) => {
+            info!("get public key of loaded key");
+            match state.key_cache.get(&key_handle) {
+                Some(signer) => Ok(Response::PublicKey(signer.public_key()?)),
+                None => Ok(Response::KeyNotLoaded(key_handle)),
+            }
+        }
+        Request::SignWithKey { key_handle, data } => {
+            info!("sign data with loaded key");
+            state.check_raw_signing()?;
+            match state.key_cache.get(&key_handle) {
+                Some(signer) => Ok(Response::Signed(signer.try_sign(&data)?)),
+                None => Ok(Response::KeyNotLoaded(key_handle)),
+            }
+        }
+        Request::EvictKey(key_handle) => {
+            info!("evict loaded key");
+            state.key_cache.evict(&key_handle);
+            Ok(Response::KeyEvicted)
+        }
+        Request::Attest {
+            sealed_signer,
+            target_info,
+        } => {
+            info!("create report for key");
+            let targetinfo = Targetinfo::try_copy_from(&target_info)
+                .ok_or_else(|| Error::new("invalid target info"))?;
+            let signer = sealed_signer.get_signer()?;
+            let public_key = signer.public_key()?;
+            let report = report_for_target(
+                &targetinfo,
+                &public_key_reportdata(signer.algorithm(), &public_key),
+            );
+            Ok(Response::Report {
+                public_key,
+                report: report.as_ref().to_vec(),
+            })
+        }
+        Request::Reseal(sealed_signer) => {
+            info!("reseal {:?} key", sealed_signer.algorithm());
+            let sealed_privkey = sealed_signer.reseal()?;
+            let raw_pubkey = sealed_privkey.get_public_key()?;
+            let key_pair = KeyPair {
+                sealed_privkey,
+                pubkey: raw_pubkey,
+            };
+            Ok(Response::KeyPair(key_pair))
+        }
+        Request::PolicySign {
+            sealed_signer,
+            sealed_state,
+            sign_bytes,
+        } => {
+            info!("sign consensus message");
+            let signer = sealed_signer.get_signer()?;
+            let (signature, sealed_state) = state.consensus_policy.sign(
+                &signer,
+                sealed_state.as_ref().map(Vec::as_slice),
+                &sign_bytes,
+            )?;
+            Ok(Response::PolicySigned {
+                signature,
+                sealed_state,
+            })
+        }
+        Request::InitPolicy {
+            sealed_signer,
+            chain_id,
+            height,
+            round,
+        } => {
+            info!(
+                "initialise policy on chain {} at height {} round {}",
+                chain_id, height, round
+            );
+            let signer = sealed_signer.get_signer()?;
+            let sealed_state = state
+                .consensus_policy
+                .init(&signer, &chain_id, height, round)?;
+            Ok(Response::PolicyInitialized { sealed_state })
+        }
     }
 }
 
-pub fn serve(stream: &mut TcpStream) -> Result<(), Error> {
-    let request_raw_data = get_data_from_stream(stream)?;
-    if request_raw_data.is_empty() {
-        return Err(Error::stop());
-    }
-    let response = handle_request(&request_raw_data)?;
+#[cfg(not(feature = "sim"))]
+fn report_for_target(targetinfo: &Targetinfo, reportdata: &[u8; 64]) -> Report {
+    Report::for_target(targetinfo, reportdata)
+}
+
+/// Read one request frame from the stream and answer it.
+///
+/// Errors while handling the request are sent back as `Response::Error`
+/// with the same request id; only framing and I/O errors are returned.
+pub fn serve<S: Read + Write>(stream: &mut S, state: &mut EnclaveState) -> Result<(), Error> {
+    let request_frame = read_frame(stream)?.ok_or_else(Error::stop)?;
+    let response = handle_request(&request_frame.payload, state).unwrap_or_else(|e| {
+        error!("error to handle request: {:?}", e);
+        Response::Error(e.what)
+    });
     debug!("send response to client: {:?}", response);
-    let response_raw_data = response.encode(true)?;
-    let _ = stream.write(&response_raw_data)?;
-    Ok(())
+    let response_frame = Frame::new(request_frame.request_id, response.encode()?);
+    write_frame(stream, &response_frame)
+}
+
+/// Serve requests until the host closes the connection.
+///
+/// If the connection breaks or a malformed frame is received, the stream can
+/// no longer be trusted to be in sync: it is dropped and a new one is opened
+/// with `connect`.
+pub fn run<S, F>(connect: F) -> Result<(), Error>
+where
+    S: Read + Write,
+    F: FnMut() -> io::Result<S>,
+{
+    run_with_policy(connect, SigningPolicy::Unrestricted)
+}
+
+/// Same as `run`, enforcing the given signing policy
+pub fn run_with_policy<S, F>(connect: F, signing_policy: SigningPolicy) -> Result<(), Error>
+where
+    S: Read + Write,
+    F: FnMut() -> io::Result<S>,
+{
+    run_with_config(
+        connect,
+        EnclaveConfig {
+            signing_policy,
+            ..EnclaveConfig::default()
+        },
+    )
+}
+
+/// Same as `run`, with the given configuration
+pub fn run_with_config<S, F>(mut connect: F, config: EnclaveConfig) -> Result<(), Error>
+where
+    S: Read + Write,
+    F: FnMut() -> io::Result<S>,
+{
+    let mut state = EnclaveState::with_config(config);
+    loop {
+        let mut stream = connect()?;
+
+        loop {
+            match serve(&mut stream, &mut state) {
+                Ok(()) => info!("handle request success!"),
+                Err(ref e) if e.kind == ErrorKind::Stop => return Ok(()),
+                Err(e) => {
+                    error!("invalid request frame, reconnecting: {:?}", e);
+                    // request id 0 is never used by clients, so this cannot
+                    // be mistaken for the answer to a request
+                    let _ = Response::Error(e.what)
+                        .encode()
+                        .and_then(|data| write_frame(&mut stream, &Frame::new(0, data)));
+                    break;
+                }
+            }
+        }
+    }
 }
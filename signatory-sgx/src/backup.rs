@@ -0,0 +1,238 @@
+//! Encrypted backups of keys generated in the enclave.
+//!
+//! Sealed keys can only be unsealed on the platform which sealed them, so a
+//! key generated in the enclave is lost with its CPU. When the enclave is
+//! configured with the public key of an operator's recovery key, keys can
+//! be generated along with a backup of their secret, encrypted to that
+//! recovery key: an ephemeral X25519 key agreement with the recovery key,
+//! HKDF-SHA256 and AES-256-GCM-SIV, binding the algorithm and public key of
+//! the backed up key.
+//!
+//! The recovery secret key is meant to be kept offline; `KeyBackup::decrypt`
+//! is used by the recovery tool to import the key into a new enclave.
+
+use crate::error::Error;
+use crate::protocol::{Decode, Encode, KeyAlgorithm};
+use aead::{generic_array::GenericArray, Aead, NewAead, Payload};
+use aes_gcm_siv::Aes256GcmSiv;
+use hkdf::Hkdf;
+use rand::random;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::fmt;
+use std::str::FromStr;
+use x25519_dalek::{x25519, X25519_BASEPOINT_BYTES};
+
+/// Domain separation of backups, used as HKDF info and as part of the AAD
+const BACKUP_DOMAIN: &[u8] = b"signatory-sgx key backup";
+
+/// Version of the backup format
+const BACKUP_VERSION: u8 = 1;
+
+/// X25519 public key of an operator's recovery key
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct RecoveryPublicKey(pub [u8; 32]);
+
+impl fmt::Display for RecoveryPublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for byte in self.0.iter() {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse the hex encoding of a recovery public key
+impl FromStr for RecoveryPublicKey {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let s = s.trim();
+        // reject signs, which `u8::from_str_radix` accepts
+        if s.len() != 64 || !s.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+            return Err(Error::new("a recovery public key is 64 hex digits"));
+        }
+        let mut bytes = [0u8; 32];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&s[2 * i..2 * i + 2], 16)
+                .map_err(|_| Error::new("invalid recovery public key"))?;
+        }
+        Ok(RecoveryPublicKey(bytes))
+    }
+}
+
+/// X25519 secret key of an operator's recovery key
+pub struct RecoverySecretKey([u8; 32]);
+
+impl RecoverySecretKey {
+    pub fn generate() -> Self {
+        RecoverySecretKey(random())
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() != 32 {
+            return Err(Error::new("a recovery secret key is 32 bytes"));
+        }
+        let mut secret = [0u8; 32];
+        secret.copy_from_slice(bytes);
+        Ok(RecoverySecretKey(secret))
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    pub fn public_key(&self) -> RecoveryPublicKey {
+        RecoveryPublicKey(x25519(self.0, X25519_BASEPOINT_BYTES))
+    }
+}
+
+/// Secret of a key encrypted to a recovery key
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct KeyBackup {
+    version: u8,
+    pub algorithm: KeyAlgorithm,
+    /// Encoded public key of the backed up key, as returned by the enclave
+    pub public_key: Vec<u8>,
+    /// The recovery key the secret is encrypted to
+    pub recovery_key: RecoveryPublicKey,
+    ephemeral_public_key: [u8; 32],
+    nonce: [u8; 12],
+    ciphertext: Vec<u8>,
+}
+
+impl Encode for KeyBackup {}
+impl<'de> Decode<'de> for KeyBackup {}
+
+impl KeyBackup {
+    /// Encrypt the secret of a key, in the form `SealedSigner::import_key`
+    /// takes, to a recovery key
+    pub fn encrypt(
+        recovery_key: &RecoveryPublicKey,
+        algorithm: KeyAlgorithm,
+        public_key: &[u8],
+        secret: &[u8],
+    ) -> Result<Self, Error> {
+        let ephemeral_secret: [u8; 32] = random();
+        let ephemeral_public_key = x25519(ephemeral_secret, X25519_BASEPOINT_BYTES);
+        let shared_secret = x25519(ephemeral_secret, recovery_key.0);
+        let mut backup = Self {
+            version: BACKUP_VERSION,
+            algorithm,
+            public_key: public_key.to_vec(),
+            recovery_key: *recovery_key,
+            ephemeral_public_key,
+            nonce: random(),
+            ciphertext: vec![],
+        };
+        let aad = backup.aad();
+        let payload = Payload {
+            msg: secret,
+            aad: &aad,
+        };
+        backup.ciphertext = backup
+            .cipher(&shared_secret)?
+            .encrypt(GenericArray::from_slice(&backup.nonce), payload)
+            .map_err(|e| Error::new(format!("encrypt key backup failed: {:?}", e)))?;
+        Ok(backup)
+    }
+
+    /// Decrypt the secret of the key with the recovery secret key
+    pub fn decrypt(&self, recovery_secret_key: &RecoverySecretKey) -> Result<Vec<u8>, Error> {
+        if self.version != BACKUP_VERSION {
+            return Err(Error::new(format!(
+                "unsupported key backup version {}",
+                self.version
+            )));
+        }
+        if recovery_secret_key.public_key() != self.recovery_key {
+            return Err(Error::new(
+                "the backup is encrypted to another recovery key",
+            ));
+        }
+        let shared_secret = x25519(recovery_secret_key.0, self.ephemeral_public_key);
+        let aad = self.aad();
+        let payload = Payload {
+            msg: &self.ciphertext,
+            aad: &aad,
+        };
+        self.cipher(&shared_secret)?
+            .decrypt(GenericArray::from_slice(&self.nonce), payload)
+            .map_err(|_| Error::new("invalid key backup"))
+    }
+
+    /// Cipher keyed with the shared secret of the ephemeral and recovery keys
+    fn cipher(&self, shared_secret: &[u8; 32]) -> Result<Aes256GcmSiv, Error> {
+        // a low order recovery or ephemeral key gives an all-zero secret
+        if shared_secret.iter().all(|byte| *byte == 0) {
+            return Err(Error::new("invalid recovery or ephemeral key"));
+        }
+        let mut salt = self.ephemeral_public_key.to_vec();
+        salt.extend_from_slice(&self.recovery_key.0);
+        let mut key = [0u8; 32];
+        Hkdf::<Sha256>::new(Some(&salt), shared_secret)
+            .expand(BACKUP_DOMAIN, &mut key)
+            .map_err(|_| Error::new("derive key backup key failed"))?;
+        Ok(Aes256GcmSiv::new(GenericArray::clone_from_slice(&key)))
+    }
+
+    fn aad(&self) -> Vec<u8> {
+        let algorithm = self.algorithm.to_string();
+        let mut aad = BACKUP_DOMAIN.to_vec();
+        aad.push(self.version);
+        aad.push(algorithm.len() as u8);
+        aad.extend_from_slice(algorithm.as_bytes());
+        aad.extend_from_slice(&self.public_key);
+        aad
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backup_and_recover() {
+        let recovery_secret_key = RecoverySecretKey::generate();
+        let recovery_key = recovery_secret_key.public_key();
+        assert_eq!(
+            recovery_key
+                .to_string()
+                .parse::<RecoveryPublicKey>()
+                .unwrap(),
+            recovery_key
+        );
+        let mut signed = recovery_key.to_string();
+        signed.replace_range(..2, "+f");
+        assert!(signed.parse::<RecoveryPublicKey>().is_err());
+
+        let secret = [0x42; 32];
+        let backup =
+            KeyBackup::encrypt(&recovery_key, KeyAlgorithm::Ed25519, b"public key", &secret)
+                .unwrap();
+        let decoded = KeyBackup::decode(&backup.encode().unwrap()).unwrap();
+        assert_eq!(decoded.decrypt(&recovery_secret_key).unwrap(), secret);
+
+        // only the recovery key can decrypt the backup
+        assert!(decoded.decrypt(&RecoverySecretKey::generate()).is_err());
+
+        // the algorithm and public key can't be swapped
+        let mut tampered = decoded.clone();
+        tampered.public_key = b"other public key".to_vec();
+        assert!(tampered.decrypt(&recovery_secret_key).is_err());
+        let mut tampered = decoded;
+        tampered.algorithm = KeyAlgorithm::Secp256k1;
+        assert!(tampered.decrypt(&recovery_secret_key).is_err());
+    }
+
+    #[test]
+    fn test_low_order_recovery_key() {
+        let result = KeyBackup::encrypt(
+            &RecoveryPublicKey([0; 32]),
+            KeyAlgorithm::Ed25519,
+            b"public key",
+            &[0x42; 32],
+        );
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,362 @@
+//! Long-running signing daemon.
+//!
+//! The daemon keeps a single enclave loaded (see `server::run_server`) and
+//! accepts clients on a Unix domain socket and/or a TCP socket. Clients speak
+//! the framed `Request`/`Response` protocol (see `framing`); each client is
+//! served on its own thread, up to `MAX_CLIENTS` at a time, and requests
+//! from all clients are handed to the enclave one at a time.
+//!
+//! The socket API gives full access to the keys the enclave can unseal, so
+//! the Unix socket is only accessible by the owner of the daemon. The TCP
+//! listener doesn't authenticate its clients, so it can only be bound to a
+//! loopback address.
+
+use crate::error::Error;
+use crate::framing::{read_frame, write_frame, Frame};
+use crate::protocol::{Encode, Response};
+use crate::provider::{Connection, SgxSigner};
+use crate::server::C2S;
+use crossbeam_channel::{unbounded, Sender};
+use log::{debug, error, info, warn};
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+#[cfg(unix)]
+use std::{
+    ffi::OsString,
+    fs::{self, DirBuilder},
+    os::unix::fs::{DirBuilderExt, FileTypeExt, PermissionsExt},
+    os::unix::net::{UnixListener, UnixStream},
+    process,
+};
+
+/// Maximum number of clients served at a time (across all listeners of a
+/// daemon); clients connecting beyond it are disconnected
+pub const MAX_CLIENTS: usize = 64;
+
+/// Accepts clients and forwards their requests to the enclave server
+#[derive(Clone)]
+pub struct Daemon {
+    client2server_tx: Sender<C2S>,
+    clients: Arc<AtomicUsize>,
+}
+
+/// Slot of a connected client, freed when the client thread ends
+struct ClientSlot(Arc<AtomicUsize>);
+
+impl Drop for ClientSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl Daemon {
+    /// Create a daemon forwarding requests to the server listening on
+    /// `client2server_tx`
+    pub fn new(client2server_tx: Sender<C2S>) -> Self {
+        Self {
+            client2server_tx,
+            clients: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Serve clients connecting to the given Unix domain socket listener
+    /// (see `bind_unix_socket`)
+    #[cfg(unix)]
+    pub fn serve_unix(&self, listener: UnixListener) -> Result<(), Error> {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => self.spawn_client(stream),
+                Err(e) => error!("accept unix socket client error: {:?}", e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Serve clients connecting to the given TCP listener
+    pub fn serve_tcp(&self, listener: TcpListener) -> Result<(), Error> {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    info!("accept tcp client: {:?}", stream.peer_addr());
+                    self.spawn_client(stream)
+                }
+                Err(e) => error!("accept tcp client error: {:?}", e),
+            }
+        }
+        Ok(())
+    }
+
+    fn spawn_client<S: Read + Write + Send + 'static>(&self, stream: S) {
+        if self.clients.fetch_add(1, Ordering::SeqCst) >= MAX_CLIENTS {
+            self.clients.fetch_sub(1, Ordering::SeqCst);
+            warn!("refuse client: {} clients connected already", MAX_CLIENTS);
+            return;
+        }
+        let slot = ClientSlot(self.clients.clone());
+        let client2server_tx = self.client2server_tx.clone();
+        thread::spawn(move || {
+            let _slot = slot;
+            if let Err(e) = handle_client(stream, client2server_tx) {
+                error!("client error: {:?}", e.what);
+            }
+        });
+    }
+}
+
+/// Forward request frames from a client to the enclave server until the
+/// client disconnects.
+///
+/// A malformed frame, e.g. one of another protocol version, is answered with
+/// an error and the client is disconnected.
+fn handle_client<S: Read + Write>(
+    mut stream: S,
+    client2server_tx: Sender<C2S>,
+) -> Result<(), Error> {
+    let (tx, rx) = unbounded();
+    loop {
+        let frame = match read_frame(&mut stream) {
+            Ok(Some(frame)) => frame,
+            Ok(None) => break,
+            Err(e) => {
+                // request id 0 is never used by clients
+                let _ = Response::Error(e.what.clone())
+                    .encode()
+                    .and_then(|data| write_frame(&mut stream, &Frame::new(0, data)));
+                return Err(e);
+            }
+        };
+        debug!("forward request {}", frame.request_id);
+        client2server_tx.send((tx.clone(), frame.to_bytes()?))?;
+        let response = rx.recv()?;
+        stream.write_all(&response)?;
+        stream.flush()?;
+    }
+    debug!("client disconnected");
+    Ok(())
+}
+
+/// Bind a Unix domain socket only accessible by the current user.
+///
+/// The socket is bound inside a new directory only accessible by the current
+/// user, restricted to mode 0600 and only then renamed to `path`, so no other
+/// user can connect in between. A stale socket left at `path` by a previous
+/// daemon (i.e. refusing connections) is replaced, but a socket a daemon is
+/// listening on, or any other kind of file, is left alone and an error is
+/// returned.
+#[cfg(unix)]
+pub fn bind_unix_socket<P: AsRef<Path>>(path: P) -> Result<UnixListener, Error> {
+    let path = path.as_ref();
+    if let Ok(metadata) = fs::symlink_metadata(path) {
+        if !metadata.file_type().is_socket() {
+            return Err(Error::new(format!(
+                "{} already exists and is not a socket",
+                path.display()
+            )));
+        }
+        match UnixStream::connect(path) {
+            Ok(_) => {
+                return Err(Error::new(format!(
+                    "another daemon is listening on {}",
+                    path.display()
+                )));
+            }
+            Err(ref e) if e.kind() == std::io::ErrorKind::ConnectionRefused => {
+                info!("replace stale socket: {}", path.display());
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| Error::new(format!("invalid socket path: {}", path.display())))?;
+    let mut dir_name = OsString::from(".");
+    dir_name.push(file_name);
+    dir_name.push(format!(".{}.d", process::id()));
+    let private_dir = path.with_file_name(dir_name);
+    DirBuilder::new().mode(0o700).create(&private_dir)?;
+
+    let private_path = private_dir.join("socket");
+    let result = UnixListener::bind(&private_path).and_then(|listener| {
+        fs::set_permissions(&private_path, fs::Permissions::from_mode(0o600))?;
+        fs::rename(&private_path, path)?;
+        Ok(listener)
+    });
+    let _ = fs::remove_file(&private_path);
+    fs::remove_dir(&private_dir)?;
+
+    let listener = result?;
+    info!("listen on unix socket: {}", path.display());
+    Ok(listener)
+}
+
+/// Bind a TCP listener to a loopback address.
+///
+/// Clients of the TCP listener aren't authenticated, so any other address is
+/// refused: use the Unix domain socket, or a tunnel ending on the loopback
+/// interface, to reach the daemon from elsewhere.
+pub fn bind_tcp<A: ToSocketAddrs>(addr: A) -> Result<TcpListener, Error> {
+    let addrs: Vec<SocketAddr> = addr.to_socket_addrs()?.collect();
+    if addrs.is_empty() {
+        return Err(Error::new("no address to listen on"));
+    }
+    if let Some(addr) = addrs.iter().find(|addr| !addr.ip().is_loopback()) {
+        return Err(Error::new(format!(
+            "refusing to listen on non-loopback address {}: tcp clients are not authenticated",
+            addr
+        )));
+    }
+    let listener = TcpListener::bind(&addrs[..])?;
+    info!("listen on tcp: {:?}", listener.local_addr());
+    Ok(listener)
+}
+
+/// Client side of a daemon socket
+pub struct StreamConnection<S: Read + Write + Send> {
+    stream: Mutex<S>,
+}
+
+impl<S: Read + Write + Send> StreamConnection<S> {
+    pub fn new(stream: S) -> Self {
+        Self {
+            stream: Mutex::new(stream),
+        }
+    }
+}
+
+impl<S: Read + Write + Send> Connection for StreamConnection<S> {
+    fn exchange(&self, request_frame: Vec<u8>) -> Result<Vec<u8>, Error> {
+        let mut stream = self
+            .stream
+            .lock()
+            .map_err(|_| Error::new("connection lock poisoned"))?;
+        stream.write_all(&request_frame)?;
+        stream.flush()?;
+        let response = read_frame(&mut *stream)?
+            .ok_or_else(|| Error::new("the daemon closed the connection"))?;
+        response.to_bytes()
+    }
+}
+
+#[cfg(unix)]
+impl<P: AsRef<Path>> SgxSigner<P, StreamConnection<UnixStream>> {
+    /// Connect to a daemon listening on the given Unix domain socket
+    pub fn connect_unix<S: AsRef<Path>>(
+        socket_path: S,
+        sealed_signer_path: P,
+    ) -> Result<Self, Error> {
+        let stream = UnixStream::connect(socket_path)?;
+        Ok(Self::with_connection(
+            StreamConnection::new(stream),
+            sealed_signer_path,
+        ))
+    }
+}
+
+impl<P: AsRef<Path>> SgxSigner<P, StreamConnection<TcpStream>> {
+    /// Connect to a daemon listening on the given TCP address
+    pub fn connect_tcp<A: ToSocketAddrs>(addr: A, sealed_signer_path: P) -> Result<Self, Error> {
+        let stream = TcpStream::connect(addr)?;
+        stream.set_nodelay(true)?;
+        Ok(Self::with_connection(
+            StreamConnection::new(stream),
+            sealed_signer_path,
+        ))
+    }
+}
+
+#[cfg(all(test, unix, feature = "sim"))]
+mod tests {
+    use super::*;
+    use crate::server::run_sim_server;
+    use signatory::ed25519;
+    use signatory::public_key::PublicKeyed;
+    use signatory::signature::{Signer, Verifier};
+    use signatory_dalek::Ed25519Verifier;
+    use std::path::PathBuf;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "signatory-sgx-daemon-{}-{}",
+            std::process::id(),
+            name
+        ));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_concurrent_clients() {
+        let (client2server_tx, client2server_rx) = unbounded::<C2S>();
+        thread::spawn(move || run_sim_server(client2server_rx).unwrap());
+
+        let socket_path = temp_path("socket");
+        let listener = bind_unix_socket(&socket_path).unwrap();
+        let mode = fs::metadata(&socket_path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        let daemon = Daemon::new(client2server_tx);
+        thread::spawn(move || daemon.serve_unix(listener).unwrap());
+
+        let key_path = temp_path("key");
+        let signer = SgxSigner::connect_unix(&socket_path, key_path.clone()).unwrap();
+        let keypair = signer.keygen().unwrap();
+        signer.store_key(&keypair).unwrap();
+
+        let clients: Vec<_> = (0..4)
+            .map(|i| {
+                let socket_path = socket_path.clone();
+                let key_path = key_path.clone();
+                thread::spawn(move || {
+                    let signer = SgxSigner::connect_unix(&socket_path, key_path).unwrap();
+                    let public_key: ed25519::PublicKey = signer.public_key().unwrap();
+                    for j in 0..8 {
+                        let msg = format!("message {} from client {}", j, i);
+                        let signature: ed25519::Signature = signer.sign(msg.as_bytes());
+                        assert!(Ed25519Verifier::from(&public_key)
+                            .verify(msg.as_bytes(), &signature)
+                            .is_ok());
+                    }
+                })
+            })
+            .collect();
+
+        for client in clients {
+            client.join().unwrap();
+        }
+
+        fs::remove_file(&key_path).unwrap();
+        fs::remove_file(&socket_path).unwrap();
+    }
+
+    #[test]
+    fn test_refuse_non_loopback_tcp_address() {
+        assert!(bind_tcp("0.0.0.0:0").is_err());
+        let listener = bind_tcp("127.0.0.1:0").unwrap();
+        assert!(listener.local_addr().unwrap().ip().is_loopback());
+    }
+
+    #[test]
+    fn test_refuse_to_replace_live_socket() {
+        let path = temp_path("live-socket");
+        let listener = bind_unix_socket(&path).unwrap();
+        assert!(bind_unix_socket(&path).is_err());
+
+        // once the daemon is gone, its socket is stale
+        drop(listener);
+        let _listener = bind_unix_socket(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_refuse_to_replace_regular_file() {
+        let path = temp_path("regular-file");
+        fs::write(&path, b"not a socket").unwrap();
+        assert!(bind_unix_socket(&path).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
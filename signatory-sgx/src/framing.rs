@@ -0,0 +1,225 @@
+//! Framing of the messages exchanged between the host and the enclave.
+//!
+//! Every request and response travels in a frame with a fixed 18-byte header:
+//!
+//! ```text
+//! +---------+-------------+----------------+----------------+---------+
+//! | magic   | version     | request id     | payload length | payload |
+//! | 4 bytes | u16 (LE)    | u64 (LE)       | u32 (LE)       |         |
+//! +---------+-------------+----------------+----------------+---------+
+//! ```
+//!
+//! A response carries the request id of the request it answers, so that a
+//! client can tell whether it is in sync with the enclave.
+
+use crate::error::Error;
+use std::io::{self, Read, Write};
+
+/// Magic bytes at the beginning of every frame
+pub const MAGIC: [u8; 4] = *b"SGXS";
+
+/// Version of the framing and of the request/response protocol.
+///
+/// Must be bumped whenever the header or the encoding of `Request` or
+/// `Response` changes, so that peers built from different versions refuse
+/// each other's frames instead of misinterpreting them.
+pub const PROTOCOL_VERSION: u16 = 2;
+
+/// Size of the frame header in bytes
+pub const HEADER_SIZE: usize = 18;
+
+/// Maximum size of a frame payload in bytes
+pub const MAX_FRAME_SIZE: u32 = 1 << 20;
+
+/// A single framed message
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Frame {
+    /// ID of the request, echoed in the response
+    pub request_id: u64,
+
+    /// Serialized `Request` or `Response`
+    pub payload: Vec<u8>,
+}
+
+impl Frame {
+    /// Create a new frame
+    pub fn new(request_id: u64, payload: Vec<u8>) -> Self {
+        Self {
+            request_id,
+            payload,
+        }
+    }
+
+    /// Serialize this frame, header included
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        let len = self.payload_len()?;
+        let mut bytes = Vec::with_capacity(HEADER_SIZE + self.payload.len());
+        bytes.extend_from_slice(&MAGIC);
+        bytes.extend_from_slice(&PROTOCOL_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&self.request_id.to_le_bytes());
+        bytes.extend_from_slice(&len.to_le_bytes());
+        bytes.extend_from_slice(&self.payload);
+        Ok(bytes)
+    }
+
+    /// Parse a frame from a buffer which must contain exactly one frame
+    pub fn from_bytes(mut bytes: &[u8]) -> Result<Self, Error> {
+        let frame = read_frame(&mut bytes)?.ok_or_else(|| Error::new("empty frame"))?;
+        if !bytes.is_empty() {
+            return Err(Error::new("trailing data after frame"));
+        }
+        Ok(frame)
+    }
+
+    fn payload_len(&self) -> Result<u32, Error> {
+        if self.payload.len() > MAX_FRAME_SIZE as usize {
+            return Err(Error::new(format!(
+                "frame payload too large: {} bytes (max {})",
+                self.payload.len(),
+                MAX_FRAME_SIZE
+            )));
+        }
+        Ok(self.payload.len() as u32)
+    }
+}
+
+/// Write a frame to the given stream
+pub fn write_frame<W: Write>(stream: &mut W, frame: &Frame) -> Result<(), Error> {
+    stream.write_all(&frame.to_bytes()?)?;
+    stream.flush()?;
+    Ok(())
+}
+
+/// Read a frame from the given stream.
+///
+/// Returns `Ok(None)` if the stream is closed before the first byte of the
+/// frame, and an error if it is closed in the middle of a frame.
+pub fn read_frame<R: Read>(stream: &mut R) -> Result<Option<Frame>, Error> {
+    let mut header = [0u8; HEADER_SIZE];
+    if !read_header(stream, &mut header)? {
+        return Ok(None);
+    }
+
+    if header[..4] != MAGIC {
+        return Err(Error::new("invalid frame magic"));
+    }
+
+    let version = u16::from_le_bytes([header[4], header[5]]);
+    if version != PROTOCOL_VERSION {
+        return Err(Error::new(format!(
+            "unsupported protocol version: {} (expected {})",
+            version, PROTOCOL_VERSION
+        )));
+    }
+
+    let mut request_id = [0u8; 8];
+    request_id.copy_from_slice(&header[6..14]);
+    let mut len = [0u8; 4];
+    len.copy_from_slice(&header[14..18]);
+    let len = u32::from_le_bytes(len);
+
+    if len > MAX_FRAME_SIZE {
+        return Err(Error::new(format!(
+            "frame payload too large: {} bytes (max {})",
+            len, MAX_FRAME_SIZE
+        )));
+    }
+
+    let mut payload = vec![0u8; len as usize];
+    stream
+        .read_exact(&mut payload)
+        .map_err(|e| Error::new(format!("read frame payload failed: {}", e)))?;
+
+    Ok(Some(Frame::new(u64::from_le_bytes(request_id), payload)))
+}
+
+/// Fill `header`, returning `false` on a clean end of stream
+fn read_header<R: Read>(stream: &mut R, header: &mut [u8]) -> Result<bool, Error> {
+    let mut filled = 0;
+    while filled < header.len() {
+        match stream.read(&mut header[filled..]) {
+            Ok(0) if filled == 0 => return Ok(false),
+            Ok(0) => return Err(Error::new("stream closed in the middle of a frame header")),
+            Ok(n) => filled += n,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reader returning at most one byte per `read` call
+    struct ShortReader<'a>(&'a [u8]);
+
+    impl<'a> Read for ShortReader<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.0.is_empty() || buf.is_empty() {
+                return Ok(0);
+            }
+            buf[0] = self.0[0];
+            self.0 = &self.0[1..];
+            Ok(1)
+        }
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let frame = Frame::new(42, b"hello world".to_vec());
+        let bytes = frame.to_bytes().unwrap();
+        assert_eq!(bytes.len(), HEADER_SIZE + 11);
+        assert_eq!(Frame::from_bytes(&bytes).unwrap(), frame);
+    }
+
+    #[test]
+    fn test_short_reads() {
+        let first = Frame::new(1, vec![0xab; 300]);
+        let second = Frame::new(2, vec![]);
+        let mut bytes = first.to_bytes().unwrap();
+        bytes.extend(second.to_bytes().unwrap());
+
+        let mut reader = ShortReader(&bytes);
+        assert_eq!(read_frame(&mut reader).unwrap(), Some(first));
+        assert_eq!(read_frame(&mut reader).unwrap(), Some(second));
+        assert_eq!(read_frame(&mut reader).unwrap(), None);
+    }
+
+    #[test]
+    fn test_truncated_frame() {
+        let bytes = Frame::new(1, vec![1, 2, 3]).to_bytes().unwrap();
+        assert!(read_frame(&mut &bytes[..HEADER_SIZE - 1]).is_err());
+        assert!(read_frame(&mut &bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_oversized_frame() {
+        let frame = Frame::new(1, vec![0; MAX_FRAME_SIZE as usize + 1]);
+        assert!(frame.to_bytes().is_err());
+
+        let mut bytes = Frame::new(1, vec![]).to_bytes().unwrap();
+        bytes[14..18].copy_from_slice(&(MAX_FRAME_SIZE + 1).to_le_bytes());
+        assert!(read_frame(&mut &bytes[..]).is_err());
+    }
+
+    #[test]
+    fn test_bad_magic() {
+        let mut bytes = Frame::new(1, vec![]).to_bytes().unwrap();
+        bytes[0] ^= 0xff;
+        assert!(read_frame(&mut &bytes[..]).is_err());
+    }
+
+    #[test]
+    fn test_version_mismatch() {
+        let mut bytes = Frame::new(1, vec![]).to_bytes().unwrap();
+        bytes[4..6].copy_from_slice(&(PROTOCOL_VERSION + 1).to_le_bytes());
+        assert!(read_frame(&mut &bytes[..]).is_err());
+
+        // frames of the previous version, whose payload encoding differs
+        bytes[4..6].copy_from_slice(&(PROTOCOL_VERSION - 1).to_le_bytes());
+        assert!(read_frame(&mut &bytes[..]).is_err());
+        assert!(Frame::from_bytes(&bytes).is_err());
+    }
+}
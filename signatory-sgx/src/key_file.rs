@@ -0,0 +1,441 @@
+//! Sealed key file format.
+//!
+//! A key file is a fixed header describing the key, followed by the sealed
+//! key itself, so that the key can be identified without the enclave:
+//!
+//! | field        | size         | content                                        |
+//! |--------------|--------------|------------------------------------------------|
+//! | magic        | 8            | `SGXSKEY\0`                                    |
+//! | version      | 2            | format version, little endian (currently 1)    |
+//! | algorithm    | 1            | 0: Ed25519, 1: secp256k1, 2: NIST P-256        |
+//! | key policy   | 2            | `Keypolicy` of the sealing key, little endian  |
+//! | ISVSVN       | 2            | enclave security version at seal time, LE      |
+//! | CPUSVN       | 16           | platform security version at seal time         |
+//! | created at   | 8            | seconds since the UNIX epoch, little endian    |
+//! | public key   | 2 + n        | length (LE) and encoded public key             |
+//! | payload      | 4 + n        | length (LE) and sealed key                     |
+//!
+//! The payload of a version 1 file is the bincode encoding of `SealedSigner`.
+//! A change of the payload encoding must bump the version, and readers
+//! refuse versions they don't know instead of misinterpreting them.
+//!
+//! The header is not authenticated: `KeyFile::from_bytes` checks it against
+//! the parts of the sealed key readable outside the enclave, but only the
+//! enclave can check the public key. Use `SgxClient::check_key_file` (or
+//! `SgxClient::read_key_file`) before trusting `KeyFile::public_key`.
+//!
+//! Files written before this format existed are the base64 encoded payload
+//! only. They can still be read (`StoredKey::Legacy`) and are upgraded with
+//! `SgxClient::upgrade_key_file`, which asks the enclave for the public key.
+
+use crate::error::Error;
+use crate::protocol::{Decode, Encode, KeyAlgorithm, KeyPair};
+use crate::provider::{get_data_from_file, write_file_atomically, Connection, SgxClient};
+use crate::seal_data::{CpuSvn, IsvSvn, SEAL_KEY_POLICY};
+use crate::seal_signer::SealedSigner;
+use sgx_isa::Keypolicy;
+use std::convert::TryFrom;
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Magic bytes at the start of every key file; the NUL byte can never
+/// appear in a legacy (base64) key file
+pub const MAGIC: [u8; 8] = *b"SGXSKEY\0";
+
+/// Current version of the key file format
+pub const KEY_FILE_VERSION: u16 = 1;
+
+/// Size of the fixed part of the header, up to the public key
+const FIXED_HEADER_SIZE: usize = 8 + 2 + 1 + 2 + 2 + 16 + 8;
+
+/// A sealed key with its description
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyFile {
+    pub algorithm: KeyAlgorithm,
+    /// Key policy of the sealing key
+    pub key_policy: Keypolicy,
+    /// Enclave security version the key is sealed under
+    pub isvsvn: IsvSvn,
+    /// Platform security version the key is sealed under
+    pub cpusvn: CpuSvn,
+    /// Seconds since the UNIX epoch
+    pub created_at: u64,
+    /// Encoded public key, as returned by the enclave; it's only known to
+    /// match the sealed key once checked with `SgxClient::check_key_file`
+    pub public_key: Vec<u8>,
+    pub sealed_signer: SealedSigner,
+}
+
+impl KeyFile {
+    /// Describe a key pair returned by the enclave, created now
+    pub fn new(key_pair: &KeyPair) -> Self {
+        Self::with_created_at(key_pair, now())
+    }
+
+    /// Describe a key pair returned by the enclave, created at `created_at`
+    /// (seconds since the UNIX epoch)
+    pub fn with_created_at(key_pair: &KeyPair, created_at: u64) -> Self {
+        let sealed_signer = key_pair.sealed_privkey.clone();
+        Self {
+            algorithm: sealed_signer.algorithm(),
+            key_policy: SEAL_KEY_POLICY,
+            isvsvn: sealed_signer.seal_data().isvsvn(),
+            cpusvn: sealed_signer.seal_data().cpusvn(),
+            created_at,
+            public_key: key_pair.pubkey.clone(),
+            sealed_signer,
+        }
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        let payload = self.sealed_signer.encode()?;
+        let public_key_len =
+            u16::try_from(self.public_key.len()).map_err(|_| Error::new("public key too long"))?;
+        let payload_len =
+            u32::try_from(payload.len()).map_err(|_| Error::new("sealed key too long"))?;
+
+        let mut bytes =
+            Vec::with_capacity(FIXED_HEADER_SIZE + 2 + self.public_key.len() + 4 + payload.len());
+        bytes.extend_from_slice(&MAGIC);
+        bytes.extend_from_slice(&KEY_FILE_VERSION.to_le_bytes());
+        bytes.push(algorithm_tag(self.algorithm));
+        bytes.extend_from_slice(&self.key_policy.bits().to_le_bytes());
+        bytes.extend_from_slice(&self.isvsvn.to_le_bytes());
+        bytes.extend_from_slice(&self.cpusvn);
+        bytes.extend_from_slice(&self.created_at.to_le_bytes());
+        bytes.extend_from_slice(&public_key_len.to_le_bytes());
+        bytes.extend_from_slice(&self.public_key);
+        bytes.extend_from_slice(&payload_len.to_le_bytes());
+        bytes.extend_from_slice(&payload);
+        Ok(bytes)
+    }
+
+    /// Parse a key file, checking the header describes the sealed key as far
+    /// as possible without the enclave (see `SgxClient::check_key_file`)
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let mut reader = Reader(bytes);
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(Error::new("not a sealed key file"));
+        }
+        let version = reader.u16()?;
+        if version != KEY_FILE_VERSION {
+            return Err(Error::new(format!(
+                "unsupported key file version {}",
+                version
+            )));
+        }
+        let algorithm = algorithm_from_tag(reader.u8()?)?;
+        let key_policy =
+            Keypolicy::from_bits(reader.u16()?).ok_or_else(|| Error::new("invalid key policy"))?;
+        let isvsvn = reader.u16()?;
+        let mut cpusvn = CpuSvn::default();
+        cpusvn.copy_from_slice(reader.take(cpusvn.len())?);
+        let created_at = reader.u64()?;
+        let public_key_len = reader.u16()?;
+        let public_key = reader.take(usize::from(public_key_len))?.to_vec();
+        let payload_len = reader.u32()?;
+        let payload = reader.take(payload_len as usize)?;
+        if !reader.0.is_empty() {
+            return Err(Error::new("trailing data after sealed key"));
+        }
+
+        let sealed_signer = SealedSigner::decode(payload)?;
+        let seal_data = sealed_signer.seal_data();
+        // keys are always sealed under `SEAL_KEY_POLICY`
+        if key_policy != SEAL_KEY_POLICY
+            || sealed_signer.algorithm() != algorithm
+            || seal_data.isvsvn() != isvsvn
+            || seal_data.cpusvn() != cpusvn
+        {
+            return Err(Error::new("key file header does not match the sealed key"));
+        }
+
+        Ok(Self {
+            algorithm,
+            key_policy,
+            isvsvn,
+            cpusvn,
+            created_at,
+            public_key,
+            sealed_signer,
+        })
+    }
+
+    /// Read a key file; legacy key files are refused (see `StoredKey::read`)
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        match StoredKey::read(path)? {
+            StoredKey::Current(key_file) => Ok(key_file),
+            StoredKey::Legacy(_) => Err(Error::new("legacy key file, it needs to be upgraded")),
+        }
+    }
+
+    /// Write the key file, atomically replacing any existing file at `path`
+    /// (see `provider::write_file_atomically`). The file is only readable by
+    /// its owner.
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        write_file_atomically(&self.to_bytes()?, path)?;
+        Ok(())
+    }
+}
+
+/// Contents of a sealed key file, in the current or the legacy format
+#[derive(Clone, Debug, PartialEq)]
+pub enum StoredKey {
+    Current(KeyFile),
+    /// Base64 encoded `SealedSigner`, with no description
+    Legacy(SealedSigner),
+}
+
+impl StoredKey {
+    /// Read a key file in either format
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let bytes = fs::read(path)?;
+        if bytes.starts_with(&MAGIC) {
+            return KeyFile::from_bytes(&bytes).map(StoredKey::Current);
+        }
+        // legacy files are read the way they were written
+        let payload = get_data_from_file(path)?;
+        SealedSigner::decode(&payload).map(StoredKey::Legacy)
+    }
+
+    pub fn sealed_signer(&self) -> &SealedSigner {
+        match self {
+            StoredKey::Current(key_file) => &key_file.sealed_signer,
+            StoredKey::Legacy(sealed_signer) => sealed_signer,
+        }
+    }
+
+    pub fn into_sealed_signer(self) -> SealedSigner {
+        match self {
+            StoredKey::Current(key_file) => key_file.sealed_signer,
+            StoredKey::Legacy(sealed_signer) => sealed_signer,
+        }
+    }
+}
+
+impl<C: Connection> SgxClient<C> {
+    /// Check the public key in the header of a key file is the one of the
+    /// sealed key, asking the enclave to unseal it
+    pub fn check_key_file(&self, key_file: &KeyFile) -> Result<(), Error> {
+        let pubkey = self.get_public_key(key_file.sealed_signer.clone())?;
+        if pubkey != key_file.public_key {
+            return Err(Error::new("key file header does not match the sealed key"));
+        }
+        Ok(())
+    }
+
+    /// Read a key file and check its header with the enclave; legacy key
+    /// files are refused (see `KeyFile::read`)
+    pub fn read_key_file<P: AsRef<Path>>(&self, path: P) -> Result<KeyFile, Error> {
+        let key_file = KeyFile::read(path)?;
+        self.check_key_file(&key_file)?;
+        Ok(key_file)
+    }
+
+    /// Upgrade a legacy key file to the current format in place, asking the
+    /// enclave for the public key. The creation time of a legacy key is
+    /// taken from the file's modification time. Files already in the current
+    /// format are left alone.
+    pub fn upgrade_key_file<P: AsRef<Path>>(&self, path: P) -> Result<KeyFile, Error> {
+        let path = path.as_ref();
+        let sealed_signer = match StoredKey::read(path)? {
+            StoredKey::Current(key_file) => return Ok(key_file),
+            StoredKey::Legacy(sealed_signer) => sealed_signer,
+        };
+        let pubkey = self.get_public_key(sealed_signer.clone())?;
+        let created_at = fs::metadata(path)?
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map(|since_epoch| since_epoch.as_secs())
+            .unwrap_or_else(now);
+        let key_pair = KeyPair {
+            sealed_privkey: sealed_signer,
+            pubkey,
+        };
+        let key_file = KeyFile::with_created_at(&key_pair, created_at);
+        key_file.write(path)?;
+        Ok(key_file)
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since_epoch| since_epoch.as_secs())
+        .unwrap_or(0)
+}
+
+fn algorithm_tag(algorithm: KeyAlgorithm) -> u8 {
+    match algorithm {
+        KeyAlgorithm::Ed25519 => 0,
+        KeyAlgorithm::Secp256k1 => 1,
+        KeyAlgorithm::NistP256 => 2,
+    }
+}
+
+fn algorithm_from_tag(tag: u8) -> Result<KeyAlgorithm, Error> {
+    match tag {
+        0 => Ok(KeyAlgorithm::Ed25519),
+        1 => Ok(KeyAlgorithm::Secp256k1),
+        2 => Ok(KeyAlgorithm::NistP256),
+        _ => Err(Error::new(format!("unknown key algorithm {}", tag))),
+    }
+}
+
+/// Reads the fields of a key file
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if self.0.len() < len {
+            return Err(Error::new("truncated key file"));
+        }
+        let (head, tail) = self.0.split_at(len);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, Error> {
+        let mut bytes = [0u8; 2];
+        bytes.copy_from_slice(self.take(2)?);
+        Ok(u16::from_le_bytes(bytes))
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        let mut bytes = [0u8; 4];
+        bytes.copy_from_slice(self.take(4)?);
+        Ok(u32::from_le_bytes(bytes))
+    }
+
+    fn u64(&mut self) -> Result<u64, Error> {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(bytes))
+    }
+}
+
+#[cfg(all(test, feature = "sim"))]
+mod tests {
+    use super::*;
+    use crate::provider::store_data_to_file;
+
+    fn key_pair(algorithm: KeyAlgorithm) -> KeyPair {
+        let sealed_privkey = SealedSigner::generate(algorithm).unwrap();
+        let pubkey = sealed_privkey.get_public_key().unwrap();
+        KeyPair {
+            sealed_privkey,
+            pubkey,
+        }
+    }
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "signatory-sgx-key-file-{}-{}",
+            std::process::id(),
+            name
+        ));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for &algorithm in &[
+            KeyAlgorithm::Ed25519,
+            KeyAlgorithm::Secp256k1,
+            KeyAlgorithm::NistP256,
+        ] {
+            let key_file = KeyFile::new(&key_pair(algorithm));
+            assert_eq!(key_file.algorithm, algorithm);
+            let bytes = key_file.to_bytes().unwrap();
+            assert_eq!(KeyFile::from_bytes(&bytes).unwrap(), key_file);
+        }
+    }
+
+    #[test]
+    fn test_reject_invalid() {
+        let bytes = KeyFile::new(&key_pair(KeyAlgorithm::Ed25519))
+            .to_bytes()
+            .unwrap();
+
+        let mut future_version = bytes.clone();
+        future_version[8] = 2;
+        assert!(KeyFile::from_bytes(&future_version).is_err());
+
+        let mut wrong_algorithm = bytes.clone();
+        wrong_algorithm[10] = algorithm_tag(KeyAlgorithm::Secp256k1);
+        assert!(KeyFile::from_bytes(&wrong_algorithm).is_err());
+
+        let mut wrong_key_policy = bytes.clone();
+        wrong_key_policy[11..13].copy_from_slice(&Keypolicy::MRENCLAVE.bits().to_le_bytes());
+        assert!(KeyFile::from_bytes(&wrong_key_policy).is_err());
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(KeyFile::from_bytes(&trailing).is_err());
+
+        for len in 0..bytes.len() {
+            assert!(KeyFile::from_bytes(&bytes[..len]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_read_legacy() {
+        let key_pair = key_pair(KeyAlgorithm::Ed25519);
+        let path = temp_path("legacy");
+        store_data_to_file(&key_pair.sealed_privkey.encode().unwrap(), &path).unwrap();
+
+        assert!(KeyFile::read(&path).is_err());
+        match StoredKey::read(&path).unwrap() {
+            StoredKey::Legacy(sealed_signer) => {
+                assert_eq!(sealed_signer, key_pair.sealed_privkey)
+            }
+            StoredKey::Current(_) => panic!("legacy key file read as current"),
+        }
+
+        let key_file = KeyFile::new(&key_pair);
+        key_file.write(&path).unwrap();
+        assert_eq!(KeyFile::read(&path).unwrap(), key_file);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_upgrade_legacy() {
+        use crate::server::{run_sim_server, stop_server, C2S};
+        use crossbeam_channel::unbounded;
+        use std::thread;
+
+        let (client2server_tx, client2server_rx) = unbounded::<C2S>();
+        let server = thread::spawn(move || run_sim_server(client2server_rx).unwrap());
+        let client = SgxClient::new(client2server_tx.clone());
+
+        let legacy_key_pair = key_pair(KeyAlgorithm::Secp256k1);
+        let path = temp_path("upgrade");
+        store_data_to_file(&legacy_key_pair.sealed_privkey.encode().unwrap(), &path).unwrap();
+
+        let key_file = client.upgrade_key_file(&path).unwrap();
+        assert_eq!(key_file.public_key, legacy_key_pair.pubkey);
+        assert_eq!(key_file.algorithm, KeyAlgorithm::Secp256k1);
+        assert!(key_file.created_at > 0);
+        assert_eq!(KeyFile::read(&path).unwrap(), key_file);
+        // upgrading again is a no-op
+        assert_eq!(client.upgrade_key_file(&path).unwrap(), key_file);
+
+        assert_eq!(client.read_key_file(&path).unwrap(), key_file);
+        let mut forged = key_file.clone();
+        forged.public_key = key_pair(KeyAlgorithm::Secp256k1).pubkey;
+        assert!(client.check_key_file(&forged).is_err());
+
+        drop(client);
+        stop_server(client2server_tx);
+        server.join().unwrap();
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,595 @@
+//! Directory of sealed keys on the host, indexed by key ID.
+//!
+//! Every key is stored as `<key id>.sealed` (see `key_file`) in the key store
+//! directory. The enclave never sees the directory: `KeyStoreClient` loads
+//! the sealed key into the enclave's key cache on first use (and again
+//! whenever the cache entry expired), then refers to it by its key handle
+//! (the digest of the sealed key, see `protocol::key_handle`) in signing
+//! requests.
+
+use crate::error::Error;
+use crate::key_file::{KeyFile, StoredKey};
+use crate::protocol::{key_handle, KeyAlgorithm, KeyHandle, KeyType, Request, Response};
+use crate::provider::{
+    check_algorithm, create_file, ChannelConnection, Connection, NistP256PublicKey,
+    Secp256k1PublicKey, SgxClient,
+};
+use crate::seal_signer::SealedSigner;
+use signatory::ecdsa::curve::{nistp256, secp256k1};
+use signatory::ed25519;
+use signatory::public_key::PublicKeyed;
+use signatory::signature::{Error as SigError, Signature, Signer};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Extension of the sealed key files in a key store
+const SEALED_KEY_EXTENSION: &str = "sealed";
+
+/// Maximum length of a key ID
+pub const MAX_KEY_ID_LEN: usize = 64;
+
+/// Default time a loaded key stays cached in the enclave
+pub const DEFAULT_KEY_TTL: Duration = Duration::from_secs(600);
+
+/// Name of a key in a `KeyStore`.
+///
+/// Key IDs are 1 to 64 characters from `[A-Za-z0-9_.-]` and don't start with
+/// a `.`, so that they are always safe to use as file names.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct KeyId(String);
+
+impl KeyId {
+    /// Create a key ID, checking it is valid
+    pub fn new<S: Into<String>>(id: S) -> Result<Self, Error> {
+        let id = id.into();
+        let valid_char = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.';
+        if id.is_empty()
+            || id.len() > MAX_KEY_ID_LEN
+            || id.starts_with('.')
+            || !id.chars().all(valid_char)
+        {
+            return Err(Error::new(format!("invalid key id: {:?}", id)));
+        }
+        Ok(KeyId(id))
+    }
+
+    /// Key ID derived from an encoded public key (as returned by the
+    /// enclave): the hex encoding of the first 8 bytes of the Ed25519 key or
+    /// of the ECDSA point's x coordinate
+    pub fn fingerprint(public_key: &[u8]) -> Result<Self, Error> {
+        let key_bytes = match public_key.len() {
+            ed25519::PUBLIC_KEY_SIZE => public_key,
+            // SEC1 encoded point, skip the tag
+            33 | 65 => &public_key[1..],
+            _ => return Err(Error::new("invalid public key")),
+        };
+        let hex = subtle_encoding::hex::encode(&key_bytes[..8]);
+        Ok(KeyId(String::from_utf8(hex).unwrap()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for KeyId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Directory of sealed keys
+#[derive(Clone, Debug)]
+pub struct KeyStore {
+    dir: PathBuf,
+}
+
+impl KeyStore {
+    /// Open the key store in `dir`, creating the directory if needed
+    pub fn open<P: Into<PathBuf>>(dir: P) -> Result<Self, Error> {
+        let dir = dir.into();
+        if !dir.exists() {
+            fs::create_dir_all(&dir)?;
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                fs::set_permissions(&dir, fs::Permissions::from_mode(0o700))?;
+            }
+        }
+        if !dir.is_dir() {
+            return Err(Error::new(format!("{} is not a directory", dir.display())));
+        }
+        Ok(Self { dir })
+    }
+
+    /// Path of the sealed key file for `key_id`
+    pub fn path(&self, key_id: &KeyId) -> PathBuf {
+        self.dir
+            .join(format!("{}.{}", key_id.as_str(), SEALED_KEY_EXTENSION))
+    }
+
+    /// Store a new sealed key; an existing key is never overwritten
+    pub fn store(&self, key_id: &KeyId, key_file: &KeyFile) -> Result<(), Error> {
+        create_file(&key_file.to_bytes()?, self.path(key_id)).map_err(|e| {
+            if e.kind() == io::ErrorKind::AlreadyExists {
+                Error::new(format!("key {} already exists", key_id))
+            } else {
+                e.into()
+            }
+        })
+    }
+
+    /// Read a key file, in the current or the legacy format
+    pub fn read(&self, key_id: &KeyId) -> Result<StoredKey, Error> {
+        let path = self.path(key_id);
+        if !path.exists() {
+            return Err(Error::new(format!("key {} not found", key_id)));
+        }
+        StoredKey::read(path)
+    }
+
+    /// Load a sealed key
+    pub fn load(&self, key_id: &KeyId) -> Result<SealedSigner, Error> {
+        self.read(key_id).map(StoredKey::into_sealed_signer)
+    }
+
+    /// Replace an existing sealed key, atomically
+    pub fn replace(&self, key_id: &KeyId, key_file: &KeyFile) -> Result<(), Error> {
+        let path = self.path(key_id);
+        if !path.exists() {
+            return Err(Error::new(format!("key {} not found", key_id)));
+        }
+        key_file.write(path)
+    }
+
+    /// IDs of all keys in the store, sorted
+    pub fn list(&self) -> Result<Vec<KeyId>, Error> {
+        let mut key_ids = vec![];
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some(SEALED_KEY_EXTENSION) {
+                continue;
+            }
+            if let Some(key_id) = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| KeyId::new(stem).ok())
+            {
+                key_ids.push(key_id);
+            }
+        }
+        key_ids.sort();
+        Ok(key_ids)
+    }
+
+    /// Delete a key
+    pub fn delete(&self, key_id: &KeyId) -> Result<(), Error> {
+        let path = self.path(key_id);
+        if !path.exists() {
+            return Err(Error::new(format!("key {} not found", key_id)));
+        }
+        fs::remove_file(path)?;
+        Ok(())
+    }
+
+    /// Rename a key; fails if a key named `to` already exists
+    pub fn rename(&self, from: &KeyId, to: &KeyId) -> Result<(), Error> {
+        let from_path = self.path(from);
+        let to_path = self.path(to);
+        if !from_path.exists() {
+            return Err(Error::new(format!("key {} not found", from)));
+        }
+        if to_path.exists() {
+            return Err(Error::new(format!("key {} already exists", to)));
+        }
+        fs::rename(from_path, to_path)?;
+        Ok(())
+    }
+}
+
+/// Client managing the keys of a `KeyStore` and signing with them in the
+/// enclave
+pub struct KeyStoreClient<C: Connection = ChannelConnection> {
+    client: SgxClient<C>,
+    store: KeyStore,
+    ttl: Duration,
+}
+
+impl<C: Connection> KeyStoreClient<C> {
+    pub fn new(client: SgxClient<C>, store: KeyStore) -> Self {
+        Self {
+            client,
+            store,
+            ttl: DEFAULT_KEY_TTL,
+        }
+    }
+
+    /// Set how long loaded keys stay cached in the enclave
+    pub fn with_key_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    pub fn client(&self) -> &SgxClient<C> {
+        &self.client
+    }
+
+    pub fn store(&self) -> &KeyStore {
+        &self.store
+    }
+
+    /// Generate a new key in the enclave and store it as `key_id`,
+    /// returning its encoded public key
+    pub fn keygen(&self, key_id: &KeyId, algorithm: KeyAlgorithm) -> Result<Vec<u8>, Error> {
+        let key_pair = self.client.keygen(algorithm)?;
+        self.store.store(key_id, &KeyFile::new(&key_pair))?;
+        Ok(key_pair.pubkey)
+    }
+
+    /// Import a key into the enclave and store it as `key_id`, returning its
+    /// encoded public key
+    pub fn import<S: AsRef<str>>(
+        &self,
+        key_id: &KeyId,
+        algorithm: KeyAlgorithm,
+        key_type: KeyType,
+        key_str: S,
+    ) -> Result<Vec<u8>, Error> {
+        let key_pair = self.client.import(algorithm, key_type, key_str)?;
+        self.store.store(key_id, &KeyFile::new(&key_pair))?;
+        Ok(key_pair.pubkey)
+    }
+
+    pub fn list_keys(&self) -> Result<Vec<KeyId>, Error> {
+        self.store.list()
+    }
+
+    /// Delete a key from the store and drop it from the enclave's cache
+    pub fn delete_key(&self, key_id: &KeyId) -> Result<(), Error> {
+        let key_handle = key_handle(&self.store.load(key_id)?)?;
+        self.store.delete(key_id)?;
+        self.evict(key_handle)
+    }
+
+    /// Rename a key (the enclave caches keys by their sealed form, so a
+    /// loaded key stays loaded)
+    pub fn rename_key(&self, from: &KeyId, to: &KeyId) -> Result<(), Error> {
+        self.store.rename(from, to)
+    }
+
+    /// Seal a key again under the current security version of the enclave
+    /// and platform, replacing it in the store (upgraded to the current key
+    /// file format, see `key_file`). The public key is checked to be
+    /// unchanged before the old sealed key is replaced.
+    pub fn reseal_key(&self, key_id: &KeyId) -> Result<(), Error> {
+        let stored_key = self.store.read(key_id)?;
+        let old_pubkey = self
+            .client
+            .get_public_key(stored_key.sealed_signer().clone())?;
+        let old_key_handle = key_handle(stored_key.sealed_signer())?;
+        let created_at = match &stored_key {
+            StoredKey::Current(key_file) => Some(key_file.created_at),
+            StoredKey::Legacy(_) => None,
+        };
+        let key_pair = self.client.reseal(stored_key.into_sealed_signer())?;
+        if key_pair.pubkey != old_pubkey {
+            return Err(Error::new(format!("public key of {} changed", key_id)));
+        }
+        let key_file = match created_at {
+            Some(created_at) => KeyFile::with_created_at(&key_pair, created_at),
+            None => KeyFile::new(&key_pair),
+        };
+        self.store.replace(key_id, &key_file)?;
+        self.evict(old_key_handle)
+    }
+
+    /// Load a key into the enclave's cache, returning its encoded public key
+    pub fn load_key(&self, key_id: &KeyId) -> Result<Vec<u8>, Error> {
+        self.load_sealed_key(self.store.load(key_id)?)
+    }
+
+    /// Encoded public key of a key
+    pub fn public_key(&self, key_id: &KeyId) -> Result<Vec<u8>, Error> {
+        match self.send_with_key(key_id, Request::GetLoadedPublicKey)? {
+            Response::PublicKey(pubkey_raw) => Ok(pubkey_raw),
+            Response::Error(s) => Err(Error::new(s)),
+            _ => Err(Error::new("response error")),
+        }
+    }
+
+    /// Sign a message with a key, returning the encoded signature
+    pub fn sign(&self, key_id: &KeyId, msg: &[u8]) -> Result<Vec<u8>, Error> {
+        match self.send_with_key(key_id, |key_handle| Request::SignWithKey {
+            key_handle,
+            data: msg.to_vec(),
+        })? {
+            Response::Signed(sig_raw) => Ok(sig_raw),
+            Response::Error(s) => Err(Error::new(s)),
+            _ => Err(Error::new("response error")),
+        }
+    }
+
+    fn load_sealed_key(&self, sealed_signer: SealedSigner) -> Result<Vec<u8>, Error> {
+        let request = Request::LoadKey {
+            sealed_signer,
+            ttl_secs: self.ttl.as_secs(),
+        };
+        match self.client.send(request)? {
+            Response::PublicKey(pubkey_raw) => Ok(pubkey_raw),
+            Response::Error(s) => Err(Error::new(s)),
+            _ => Err(Error::new("response error")),
+        }
+    }
+
+    /// Send a request referring to the handle of a loaded key, loading the
+    /// key and retrying once if the enclave doesn't have it cached
+    fn send_with_key<F>(&self, key_id: &KeyId, request: F) -> Result<Response, Error>
+    where
+        F: Fn(KeyHandle) -> Request,
+    {
+        let sealed_signer = self.store.load(key_id)?;
+        let key_handle = key_handle(&sealed_signer)?;
+        match self.client.send(request(key_handle))? {
+            Response::KeyNotLoaded(_) => {
+                self.load_sealed_key(sealed_signer)?;
+                self.client.send(request(key_handle))
+            }
+            response => Ok(response),
+        }
+    }
+
+    fn evict(&self, key_handle: KeyHandle) -> Result<(), Error> {
+        match self.client.send(Request::EvictKey(key_handle))? {
+            Response::KeyEvicted => Ok(()),
+            Response::Error(s) => Err(Error::new(s)),
+            _ => Err(Error::new("response error")),
+        }
+    }
+}
+
+/// Signer for a key in a `KeyStore`
+pub struct KeyStoreSigner<C: Connection = ChannelConnection> {
+    client: Arc<KeyStoreClient<C>>,
+    key_id: KeyId,
+    algorithm: KeyAlgorithm,
+}
+
+impl<C: Connection> KeyStoreSigner<C> {
+    pub fn new(client: Arc<KeyStoreClient<C>>, key_id: KeyId) -> Result<Self, Error> {
+        let algorithm = client.store.load(&key_id)?.algorithm();
+        Ok(Self {
+            client,
+            key_id,
+            algorithm,
+        })
+    }
+
+    pub fn key_id(&self) -> &KeyId {
+        &self.key_id
+    }
+
+    pub fn algorithm(&self) -> KeyAlgorithm {
+        self.algorithm
+    }
+
+    fn public_key_for(&self, algorithm: KeyAlgorithm) -> Result<Vec<u8>, SigError> {
+        check_algorithm(self.algorithm, algorithm)
+            .and_then(|()| self.client.public_key(&self.key_id))
+            .map_err(SigError::from_source)
+    }
+
+    fn sign_for(&self, algorithm: KeyAlgorithm, msg: &[u8]) -> Result<Vec<u8>, SigError> {
+        check_algorithm(self.algorithm, algorithm)
+            .and_then(|()| self.client.sign(&self.key_id, msg))
+            .map_err(SigError::from_source)
+    }
+}
+
+impl<C: Connection> PublicKeyed<ed25519::PublicKey> for KeyStoreSigner<C> {
+    fn public_key(&self) -> Result<ed25519::PublicKey, SigError> {
+        let pubkey_raw = self.public_key_for(KeyAlgorithm::Ed25519)?;
+        ed25519::PublicKey::from_bytes(&pubkey_raw).ok_or_else(SigError::new)
+    }
+}
+
+impl<C: Connection> Signer<ed25519::Signature> for KeyStoreSigner<C> {
+    fn try_sign(&self, msg: &[u8]) -> Result<ed25519::Signature, SigError> {
+        let signature_raw = self.sign_for(KeyAlgorithm::Ed25519, msg)?;
+        ed25519::Signature::from_bytes(&signature_raw[..])
+    }
+}
+
+impl<C: Connection> PublicKeyed<Secp256k1PublicKey> for KeyStoreSigner<C> {
+    fn public_key(&self) -> Result<Secp256k1PublicKey, SigError> {
+        let pubkey_raw = self.public_key_for(KeyAlgorithm::Secp256k1)?;
+        Secp256k1PublicKey::from_bytes(&pubkey_raw).ok_or_else(SigError::new)
+    }
+}
+
+impl<C: Connection> Signer<secp256k1::FixedSignature> for KeyStoreSigner<C> {
+    fn try_sign(&self, msg: &[u8]) -> Result<secp256k1::FixedSignature, SigError> {
+        let signature_raw = self.sign_for(KeyAlgorithm::Secp256k1, msg)?;
+        secp256k1::FixedSignature::from_bytes(&signature_raw[..])
+    }
+}
+
+impl<C: Connection> PublicKeyed<NistP256PublicKey> for KeyStoreSigner<C> {
+    fn public_key(&self) -> Result<NistP256PublicKey, SigError> {
+        let pubkey_raw = self.public_key_for(KeyAlgorithm::NistP256)?;
+        NistP256PublicKey::from_bytes(&pubkey_raw).ok_or_else(SigError::new)
+    }
+}
+
+impl<C: Connection> Signer<nistp256::FixedSignature> for KeyStoreSigner<C> {
+    fn try_sign(&self, msg: &[u8]) -> Result<nistp256::FixedSignature, SigError> {
+        let signature_raw = self.sign_for(KeyAlgorithm::NistP256, msg)?;
+        nistp256::FixedSignature::from_bytes(&signature_raw[..])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "signatory-sgx-keystore-{}-{}",
+            std::process::id(),
+            name
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_key_id() {
+        assert!(KeyId::new("validator-1.ed25519_key").is_ok());
+        assert!(KeyId::new("").is_err());
+        assert!(KeyId::new(".hidden").is_err());
+        assert!(KeyId::new("../escape").is_err());
+        assert!(KeyId::new("a/b").is_err());
+        assert!(KeyId::new("x".repeat(MAX_KEY_ID_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn test_path_stays_in_store() {
+        let store = KeyStore::open(temp_dir("path")).unwrap();
+        let key_id = KeyId::new("validator").unwrap();
+        assert_eq!(store.path(&key_id).parent(), Some(store.dir.as_path()));
+        fs::remove_dir_all(&store.dir).unwrap();
+    }
+
+    #[cfg(feature = "sim")]
+    #[test]
+    fn test_keystore_client() {
+        use crate::server::{run_sim_server, stop_server, C2S};
+        use crossbeam_channel::unbounded;
+        use signatory::signature::Verifier;
+        use signatory_dalek::Ed25519Verifier;
+        use std::thread;
+
+        let (client2server_tx, client2server_rx) = unbounded::<C2S>();
+        let server = thread::spawn(move || run_sim_server(client2server_rx).unwrap());
+
+        let dir = temp_dir("client");
+        let client = Arc::new(KeyStoreClient::new(
+            SgxClient::new(client2server_tx.clone()),
+            KeyStore::open(&dir).unwrap(),
+        ));
+
+        let alice = KeyId::new("alice").unwrap();
+        let bob = KeyId::new("bob").unwrap();
+        let alice_public_key = client.keygen(&alice, KeyAlgorithm::Ed25519).unwrap();
+        let alice_public_key = ed25519::PublicKey::from_bytes(&alice_public_key).unwrap();
+        let bob_public_key = client.keygen(&bob, KeyAlgorithm::Secp256k1).unwrap();
+        assert!(client.keygen(&alice, KeyAlgorithm::Ed25519).is_err());
+        assert_eq!(
+            client.list_keys().unwrap(),
+            vec![alice.clone(), bob.clone()]
+        );
+
+        // keys are loaded on first use
+        let signer = KeyStoreSigner::new(Arc::clone(&client), alice.clone()).unwrap();
+        let public_key: ed25519::PublicKey = signer.public_key().unwrap();
+        assert_eq!(public_key, alice_public_key);
+        let msg = b"hello world";
+        let signature: ed25519::Signature = signer.sign(msg);
+        assert!(Ed25519Verifier::from(&alice_public_key)
+            .verify(msg, &signature)
+            .is_ok());
+        // an Ed25519 key can't produce ECDSA signatures
+        assert!(Signer::<secp256k1::FixedSignature>::try_sign(&signer, msg).is_err());
+
+        // loaded keys can only be used by clients holding their sealed form
+        match client
+            .client()
+            .send(Request::SignWithKey {
+                key_handle: [0; 32],
+                data: msg.to_vec(),
+            })
+            .unwrap()
+        {
+            Response::KeyNotLoaded(_) => (),
+            response => panic!("unexpected response: {:?}", response),
+        }
+
+        let carol = KeyId::new("carol").unwrap();
+        client.rename_key(&bob, &carol).unwrap();
+        assert!(client.rename_key(&alice, &carol).is_err());
+        assert_eq!(client.public_key(&carol).unwrap(), bob_public_key);
+        assert!(client.public_key(&bob).is_err());
+
+        client.delete_key(&alice).unwrap();
+        assert!(client.sign(&alice, msg).is_err());
+        assert_eq!(client.list_keys().unwrap(), vec![carol]);
+
+        drop(signer);
+        drop(client);
+        stop_server(client2server_tx);
+        server.join().unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "sim")]
+    #[test]
+    fn test_reseal_after_upgrade() {
+        use crate::seal_signer::verify;
+        use crate::server::{run_sim_server, run_sim_server_on_platform, stop_server, C2S};
+        use crate::sim;
+        use crossbeam_channel::unbounded;
+        use std::thread;
+
+        let (client2server_tx, client2server_rx) = unbounded::<C2S>();
+        let server = thread::spawn(move || run_sim_server(client2server_rx).unwrap());
+
+        let dir = temp_dir("reseal");
+        let client = KeyStoreClient::new(
+            SgxClient::new(client2server_tx.clone()),
+            KeyStore::open(&dir).unwrap(),
+        );
+        let key_id = KeyId::new("validator").unwrap();
+        let public_key = client.keygen(&key_id, KeyAlgorithm::Secp256k1).unwrap();
+        let old_key_file = match client.store().read(&key_id).unwrap() {
+            StoredKey::Current(key_file) => key_file,
+            StoredKey::Legacy(_) => panic!("new key stored in the legacy format"),
+        };
+        let old_isvsvn = old_key_file.isvsvn;
+        drop(client);
+        stop_server(client2server_tx);
+        server.join().unwrap();
+
+        // simulate an enclave upgrade
+        let mut platform = sim::platform();
+        platform.isvsvn += 1;
+        let (client2server_tx, client2server_rx) = unbounded::<C2S>();
+        let upgraded = platform.clone();
+        let server =
+            thread::spawn(move || run_sim_server_on_platform(client2server_rx, upgraded).unwrap());
+        let client = KeyStoreClient::new(
+            SgxClient::new(client2server_tx.clone()),
+            KeyStore::open(&dir).unwrap(),
+        );
+
+        client.reseal_key(&key_id).unwrap();
+        let resealed = KeyFile::read(client.store().path(&key_id)).unwrap();
+        assert_eq!(resealed.isvsvn, platform.isvsvn);
+        assert!(resealed.isvsvn > old_isvsvn);
+        assert_eq!(resealed.public_key, old_key_file.public_key);
+        assert_eq!(resealed.created_at, old_key_file.created_at);
+        assert_eq!(client.list_keys().unwrap(), vec![key_id.clone()]);
+
+        assert_eq!(client.public_key(&key_id).unwrap(), public_key);
+        let msg = b"hello world";
+        let signature = client.sign(&key_id, msg).unwrap();
+        assert!(verify(KeyAlgorithm::Secp256k1, &public_key, msg, &signature).is_ok());
+
+        drop(client);
+        stop_server(client2server_tx);
+        server.join().unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
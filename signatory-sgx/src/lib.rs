@@ -1,6 +1,21 @@
-#[cfg(feature = "sgx")]
+#[cfg(feature = "std")]
+pub mod attestation;
+#[cfg(any(feature = "sgx", feature = "sim"))]
 pub mod backend;
+#[cfg(feature = "backup")]
+pub mod backup;
+#[cfg(feature = "std")]
+pub mod daemon;
+#[cfg(feature = "std")]
+pub mod der;
 pub mod error;
+pub mod framing;
+#[cfg(feature = "std")]
+pub mod key_file;
+#[cfg(feature = "std")]
+pub mod keystore;
+#[cfg(any(feature = "sgx", feature = "sim"))]
+pub mod policy;
 pub mod protocol;
 #[cfg(feature = "std")]
 pub mod provider;
@@ -8,3 +23,6 @@ pub mod seal_data;
 pub mod seal_signer;
 #[cfg(feature = "std")]
 pub mod server;
+#[cfg(feature = "sim")]
+pub mod sim;
+pub mod tendermint;
@@ -0,0 +1,410 @@
+//! Signing policy enforced inside the enclave.
+//!
+//! With `SigningPolicy::Tendermint` the enclave refuses to sign arbitrary
+//! bytes: keys can only sign Tendermint votes and proposals
+//! (`Request::PolicySign`), and never two conflicting messages for the same
+//! height, round and step of a chain (see `tendermint::HighWaterMark`).
+//!
+//! A key can't sign for a chain until the operator initialises the policy
+//! of that (key, chain ID) pair (`Request::InitPolicy`) with the last height
+//! and round the key may have signed at: the enclave has no way to know what
+//! the key signed before, so nothing at or below that height and round is
+//! ever signed.
+//!
+//! The high-water mark of every (key, chain ID) pair is kept in enclave
+//! memory, and sealed and handed to the host with each signature so that it
+//! survives enclave restarts. To detect the host rolling back to an older
+//! sealed state, each state records the value of a monotonic counter
+//! incremented with every signature, and a state is only accepted if it
+//! matches the counter's current value. The state and the counter are only
+//! updated once the message is signed.
+//!
+//! SGX on this target has no platform monotonic counters, so the counters
+//! are a stand-in: in simulation mode they are the simulated platform's
+//! counters (see `sim::read_counter`), which survive enclave restarts. In an
+//! enclave they only live as long as the enclave, so **rollback protection
+//! does not survive an enclave restart on hardware**: a restarted enclave
+//! can't tell the latest sealed state from an older one, and refuses them
+//! all. The operator has to initialise the policy again, with a height and
+//! round at least as high as anything the key signed before the restart.
+
+use crate::error::Error;
+use crate::protocol::{Decode, Encode};
+use crate::seal_data::{seal_key, unseal_key, Label, SealData};
+use crate::seal_signer::{get_algo, UnsealedSigner};
+use crate::tendermint::{ConsensusMessage, HighWaterMark};
+use aead::{generic_array::GenericArray, Aead, Payload};
+use rand::random;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+/// Domain separation of the sealed policy state
+const POLICY_STATE_AAD: &[u8] = b"signatory-sgx tendermint policy state";
+
+/// What the enclave is willing to sign
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SigningPolicy {
+    /// Anything the host asks for
+    Unrestricted,
+    /// Only non-conflicting Tendermint votes and proposals
+    Tendermint,
+}
+
+impl Default for SigningPolicy {
+    fn default() -> Self {
+        SigningPolicy::Unrestricted
+    }
+}
+
+/// High-water mark of a key on a chain
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct PolicyState {
+    public_key: Vec<u8>,
+    chain_id: String,
+    /// Height and round the policy was initialised with: the key may have
+    /// signed anything up to them before
+    initial_height: i64,
+    initial_round: i64,
+    /// Latest message signed since the policy was initialised
+    high_water_mark: Option<HighWaterMark>,
+    counter: u64,
+}
+
+impl PolicyState {
+    /// Check that signing `message` can't be a double sign
+    fn check(&self, message: &ConsensusMessage) -> Result<(), Error> {
+        if (message.height, message.round) <= (self.initial_height, self.initial_round) {
+            return Err(Error::new(format!(
+                "refusing to sign at height {} round {}: the policy was initialised at height {} round {}",
+                message.height, message.round, self.initial_height, self.initial_round
+            )));
+        }
+        match &self.high_water_mark {
+            Some(high_water_mark) => high_water_mark.check(message),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Encode for PolicyState {}
+impl<'de> Decode<'de> for PolicyState {}
+
+/// `PolicyState` sealed to the enclave
+#[derive(Serialize, Deserialize)]
+struct SealedPolicyState {
+    sealed_state: Vec<u8>,
+    seal_data: SealData,
+    label: Label,
+}
+
+impl Encode for SealedPolicyState {}
+impl<'de> Decode<'de> for SealedPolicyState {}
+
+impl SealedPolicyState {
+    fn seal(state: &PolicyState) -> Result<Self, Error> {
+        let label: Label = random();
+        let (seal_key, seal_data) = seal_key(label);
+        let aad = aad(label);
+        let plaintext = state.encode()?;
+        let payload = Payload {
+            msg: &plaintext,
+            aad: &aad,
+        };
+        let nonce = GenericArray::from_slice(&seal_data.nonce);
+        let sealed_state = get_algo(&seal_key)
+            .encrypt(nonce, payload)
+            .map_err(|e| Error::new(format!("seal policy state failed: {:?}", e)))?;
+        Ok(Self {
+            sealed_state,
+            seal_data,
+            label,
+        })
+    }
+
+    fn unseal(&self) -> Result<PolicyState, Error> {
+        let seal_key = unseal_key(self.label, &self.seal_data)?;
+        let aad = aad(self.label);
+        let payload = Payload {
+            msg: &self.sealed_state,
+            aad: &aad,
+        };
+        let nonce = GenericArray::from_slice(&self.seal_data.nonce);
+        let plaintext = get_algo(&seal_key)
+            .decrypt(nonce, payload)
+            .map_err(|_| Error::new("invalid sealed policy state"))?;
+        PolicyState::decode(&plaintext)
+    }
+}
+
+fn aad(label: Label) -> Vec<u8> {
+    let mut aad = POLICY_STATE_AAD.to_vec();
+    aad.extend_from_slice(&label);
+    aad
+}
+
+/// Name of the state and counter of a key on a chain
+fn state_name(public_key: &[u8], chain_id: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.input(&(public_key.len() as u64).to_le_bytes());
+    hasher.input(public_key);
+    hasher.input(chain_id.as_bytes());
+    hasher
+        .result()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Monotonic counters, one per state (see the module documentation)
+#[derive(Default)]
+struct MonotonicCounters {
+    #[cfg(not(feature = "sim"))]
+    values: HashMap<String, u64>,
+}
+
+impl MonotonicCounters {
+    /// Current value of a counter, or `None` if it isn't known: counters
+    /// living in enclave memory are lost when the enclave restarts
+    #[cfg(not(feature = "sim"))]
+    fn read(&self, name: &str) -> Option<u64> {
+        self.values.get(name).cloned()
+    }
+
+    /// Start counting from `value` with a counter that isn't known
+    #[cfg(not(feature = "sim"))]
+    fn restore(&mut self, name: &str, value: u64) {
+        self.values.insert(name.to_owned(), value);
+    }
+
+    #[cfg(not(feature = "sim"))]
+    fn increment(&mut self, name: &str) -> u64 {
+        let value = self.values.entry(name.to_owned()).or_insert(0);
+        *value += 1;
+        *value
+    }
+
+    #[cfg(feature = "sim")]
+    fn read(&self, name: &str) -> Option<u64> {
+        Some(crate::sim::read_counter(name))
+    }
+
+    #[cfg(feature = "sim")]
+    fn restore(&mut self, _name: &str, _value: u64) {
+        unreachable!("simulated platform counters are always known")
+    }
+
+    #[cfg(feature = "sim")]
+    fn increment(&mut self, name: &str) -> u64 {
+        crate::sim::increment_counter(name)
+    }
+}
+
+/// Double-sign protection for Tendermint validators
+#[derive(Default)]
+pub struct ConsensusPolicy {
+    states: HashMap<String, PolicyState>,
+    counters: MonotonicCounters,
+}
+
+impl ConsensusPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Initialise the policy of a key on a chain, which refuses to sign
+    /// anything at or below `height` and `round`, returning the sealed state
+    /// to pass along with the first signing request.
+    ///
+    /// The operator must choose `height` and `round` at least as high as
+    /// anything the key may have signed on the chain. This is refused while
+    /// the enclave holds the state of the key on the chain already.
+    pub fn init(
+        &mut self,
+        signer: &UnsealedSigner,
+        chain_id: &str,
+        height: i64,
+        round: i64,
+    ) -> Result<Vec<u8>, Error> {
+        let public_key = signer.public_key()?;
+        let name = state_name(&public_key, chain_id);
+        if self.states.contains_key(&name) {
+            return Err(Error::new(
+                "the policy of this key and chain is initialised already",
+            ));
+        }
+
+        // states sealed before are superseded by this one
+        if self.counters.read(&name).is_none() {
+            self.counters.restore(&name, 0);
+        }
+        let state = PolicyState {
+            public_key,
+            chain_id: chain_id.to_owned(),
+            initial_height: height,
+            initial_round: round,
+            high_water_mark: None,
+            counter: self.counters.increment(&name),
+        };
+        let sealed_state = SealedPolicyState::seal(&state)?.encode()?;
+        self.states.insert(name, state);
+        Ok(sealed_state)
+    }
+
+    /// Sign a vote or proposal if it doesn't conflict with anything signed
+    /// before, returning the signature and the new sealed state.
+    ///
+    /// `sealed_state` is the state returned with the previous signature (or
+    /// by `init`); it is only needed when the enclave restarted since then.
+    pub fn sign(
+        &mut self,
+        signer: &UnsealedSigner,
+        sealed_state: Option<&[u8]>,
+        sign_bytes: &[u8],
+    ) -> Result<(Vec<u8>, Vec<u8>), Error> {
+        let message = ConsensusMessage::parse(sign_bytes)?;
+        let public_key = signer.public_key()?;
+        let name = state_name(&public_key, &message.chain_id);
+
+        if !self.states.contains_key(&name) {
+            let sealed_state = sealed_state.ok_or_else(|| {
+                Error::new("no policy state for this key and chain: the policy must be initialised")
+            })?;
+            let state = SealedPolicyState::decode(sealed_state)?.unseal()?;
+            if state.public_key != public_key || state.chain_id != message.chain_id {
+                return Err(Error::new("the policy state is for another key or chain"));
+            }
+            match self.counters.read(&name) {
+                Some(counter) if counter == state.counter => {}
+                Some(_) => {
+                    return Err(Error::new(
+                        "stale policy state: the host rolled back the sealed state",
+                    ));
+                }
+                None => {
+                    return Err(Error::new(
+                        "the enclave restarted and can't tell whether the policy state is the \
+                         latest one: the policy must be initialised again",
+                    ));
+                }
+            }
+            self.states.insert(name.clone(), state);
+        }
+
+        let state = &self.states[&name];
+        state.check(&message)?;
+        let counter = self
+            .counters
+            .read(&name)
+            .ok_or_else(|| Error::new("the policy counter is unknown"))?;
+
+        // the new state is sealed before the signature leaves the enclave;
+        // if the host then loses it, the key is locked rather than exposed.
+        // Nothing is updated unless the message is signed.
+        let next_state = PolicyState {
+            high_water_mark: Some(HighWaterMark::new(&message)),
+            counter: counter + 1,
+            ..state.clone()
+        };
+        let sealed_state = SealedPolicyState::seal(&next_state)?.encode()?;
+        let signature = signer.try_sign(sign_bytes)?;
+        self.counters.increment(&name);
+        self.states.insert(name, next_state);
+        Ok((signature, sealed_state))
+    }
+}
+
+#[cfg(all(test, feature = "sim"))]
+mod tests {
+    use super::*;
+    use crate::protocol::KeyAlgorithm;
+    use crate::seal_signer::SealedSigner;
+
+    fn vote(height: u8, round: u8, precommit: bool, block: u8) -> Vec<u8> {
+        let mut msg = vec![0x08, if precommit { 0x02 } else { 0x01 }];
+        msg.extend_from_slice(&[0x11, height, 0, 0, 0, 0, 0, 0, 0]);
+        msg.extend_from_slice(&[0x19, round, 0, 0, 0, 0, 0, 0, 0]);
+        msg.extend_from_slice(&[0x22, 0x04, 0x0a, 0x02, block, block]);
+        msg.extend_from_slice(&[0x32, 0x04]);
+        msg.extend_from_slice(b"test");
+        let mut sign_bytes = vec![msg.len() as u8];
+        sign_bytes.extend_from_slice(&msg);
+        sign_bytes
+    }
+
+    #[test]
+    fn test_double_sign_and_rollback() {
+        let signer = SealedSigner::generate(KeyAlgorithm::Ed25519)
+            .unwrap()
+            .get_signer()
+            .unwrap();
+        let mut policy = ConsensusPolicy::new();
+
+        // nothing is signed before the policy is initialised
+        assert!(policy.sign(&signer, None, &vote(1, 0, false, 1)).is_err());
+        let state_0 = policy.init(&signer, "test", 0, 0).unwrap();
+        assert!(policy.init(&signer, "test", 0, 0).is_err());
+
+        let (_, state_1) = policy.sign(&signer, None, &vote(1, 0, false, 1)).unwrap();
+        let (_, state_2) = policy.sign(&signer, None, &vote(1, 0, true, 1)).unwrap();
+        assert!(policy.sign(&signer, None, &vote(1, 0, true, 2)).is_err());
+        assert!(policy.sign(&signer, None, &vote(1, 0, false, 1)).is_err());
+        // the same vote may be signed again
+        let (_, state_3) = policy.sign(&signer, None, &vote(1, 0, true, 1)).unwrap();
+
+        // after a restart, the latest sealed state is required
+        let mut restarted = ConsensusPolicy::new();
+        assert!(restarted
+            .sign(&signer, None, &vote(2, 0, false, 1))
+            .is_err());
+        assert!(restarted
+            .sign(&signer, Some(&state_0), &vote(2, 0, false, 1))
+            .is_err());
+        assert!(restarted
+            .sign(&signer, Some(&state_1), &vote(2, 0, false, 1))
+            .is_err());
+        assert!(restarted
+            .sign(&signer, Some(&state_2), &vote(2, 0, false, 1))
+            .is_err());
+        assert!(restarted
+            .sign(&signer, Some(&state_3), &vote(1, 0, true, 2))
+            .is_err());
+        assert!(restarted
+            .sign(&signer, Some(&state_3), &vote(2, 0, false, 1))
+            .is_ok());
+
+        // states of other keys are refused
+        let other_signer = SealedSigner::generate(KeyAlgorithm::Ed25519)
+            .unwrap()
+            .get_signer()
+            .unwrap();
+        assert!(ConsensusPolicy::new()
+            .sign(&other_signer, Some(&state_3), &vote(3, 0, false, 1))
+            .is_err());
+    }
+
+    #[test]
+    fn test_init_height_and_round() {
+        let signer = SealedSigner::generate(KeyAlgorithm::Ed25519)
+            .unwrap()
+            .get_signer()
+            .unwrap();
+        let mut policy = ConsensusPolicy::new();
+        let state = policy.init(&signer, "test", 2, 0).unwrap();
+
+        assert!(policy.sign(&signer, None, &vote(1, 5, true, 1)).is_err());
+        assert!(policy.sign(&signer, None, &vote(2, 0, true, 1)).is_err());
+        // a refused message doesn't change the state
+        let mut restarted = ConsensusPolicy::new();
+        assert!(restarted
+            .sign(&signer, Some(&state), &vote(2, 1, false, 1))
+            .is_ok());
+        assert!(policy.sign(&signer, None, &vote(2, 1, false, 1)).is_ok());
+
+        // the policy is initialised per chain
+        let mut other_chain = vote(3, 0, false, 1);
+        let len = other_chain.len();
+        other_chain[len - 1] = b'x';
+        assert!(policy.sign(&signer, None, &other_chain).is_err());
+    }
+}
@@ -1,24 +1,110 @@
 use crate::error::Error;
+use crate::framing::MAX_FRAME_SIZE;
 use crate::seal_signer::SealedSigner;
 use serde::{Deserialize, Serialize};
-use std::io::{Error as IoError, Read, Write};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::str::FromStr;
 
 pub type DataType = Vec<u8>;
 #[cfg(feature = "std")]
 pub type SecretKeyEncoding = subtle_encoding::Base64;
 
+/// Encoding of a secret key passed to `SgxClient::import`
 #[derive(Debug)]
 pub enum KeyType {
     Base64,
 }
 
+/// Algorithm of a key held by the enclave
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum KeyAlgorithm {
+    /// Ed25519 signatures
+    Ed25519,
+    /// ECDSA over secp256k1 with SHA-256, fixed-size signatures
+    Secp256k1,
+    /// ECDSA over NIST P-256 with SHA-256, fixed-size signatures
+    NistP256,
+}
+
+impl Default for KeyAlgorithm {
+    fn default() -> Self {
+        KeyAlgorithm::Ed25519
+    }
+}
+
+impl fmt::Display for KeyAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            KeyAlgorithm::Ed25519 => "ed25519",
+            KeyAlgorithm::Secp256k1 => "secp256k1",
+            KeyAlgorithm::NistP256 => "nistp256",
+        })
+    }
+}
+
+impl FromStr for KeyAlgorithm {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s.to_ascii_lowercase().as_str() {
+            "ed25519" => Ok(KeyAlgorithm::Ed25519),
+            "secp256k1" => Ok(KeyAlgorithm::Secp256k1),
+            "nistp256" | "p256" | "p-256" => Ok(KeyAlgorithm::NistP256),
+            _ => Err(Error::new(format!("unknown key algorithm: {}", s))),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub enum Request {
     Ping,
-    KeyGen,
+    KeyGen(KeyAlgorithm),
     GetPublicKey(SealedSigner),
-    Import(Vec<u8>), // return Response::KeyPair
+    Import(KeyAlgorithm, Vec<u8>), // return Response::KeyPair
     Sign((SealedSigner, DataType)),
+    // unseal a key and keep it in the enclave for `ttl_secs` seconds under
+    // its `key_handle`, return Response::PublicKey
+    LoadKey {
+        sealed_signer: SealedSigner,
+        ttl_secs: u64,
+    },
+    GetLoadedPublicKey(KeyHandle), // return Response::PublicKey or Response::KeyNotLoaded
+    SignWithKey {
+        key_handle: KeyHandle,
+        data: DataType,
+    }, // return Response::Signed or Response::KeyNotLoaded
+    EvictKey(KeyHandle),           // return Response::KeyEvicted
+    // report of the enclave for the quoting enclave described by
+    // `target_info`, binding the key's public key, return Response::Report
+    Attest {
+        sealed_signer: SealedSigner,
+        target_info: Vec<u8>,
+    },
+    // seal a key again under the current ISVSVN/CPUSVN, refusing downgrades,
+    // return Response::KeyPair
+    Reseal(SealedSigner),
+    // sign Tendermint vote or proposal sign bytes subject to the double-sign
+    // policy, passing the sealed state returned with the previous signature,
+    // return Response::PolicySigned
+    PolicySign {
+        sealed_signer: SealedSigner,
+        sealed_state: Option<Vec<u8>>,
+        sign_bytes: DataType,
+    },
+    // initialise the double-sign policy of a key on a chain, refusing to sign
+    // anything at or below `height` and `round` (see `policy`),
+    // return Response::PolicyInitialized
+    InitPolicy {
+        sealed_signer: SealedSigner,
+        chain_id: String,
+        height: i64,
+        round: i64,
+    },
+    // generate a key along with a backup of its secret encrypted to the
+    // recovery key configured in the enclave (see `backup`),
+    // return Response::KeyPairWithBackup
+    KeyGenWithBackup(KeyAlgorithm),
 }
 
 impl Encode for Request {}
@@ -31,60 +117,73 @@ pub enum Response {
     PublicKey(Vec<u8>),
     Signed(DataType),
     Error(String),
+    KeyNotLoaded(KeyHandle),
+    KeyEvicted,
+    Report {
+        public_key: Vec<u8>,
+        report: Vec<u8>,
+    },
+    PolicySigned {
+        signature: DataType,
+        sealed_state: Vec<u8>,
+    },
+    KeyPairWithBackup {
+        key_pair: KeyPair,
+        // encoded `backup::KeyBackup`
+        backup: Vec<u8>,
+    },
+    PolicyInitialized {
+        sealed_state: Vec<u8>,
+    },
 }
 
 impl Encode for Response {}
 impl<'de> Decode<'de> for Response {}
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct KeyPair {
-    pub sealed_privkey: SealedSigner,
-    pub pubkey: Vec<u8>,
+/// Handle of a key loaded into the enclave: the SHA-256 digest of the
+/// encoded `SealedSigner`, so that only clients holding the sealed key can
+/// use it (the daemon is shared by all local clients)
+pub type KeyHandle = [u8; 32];
+
+/// Handle under which the enclave caches a sealed key once it's loaded
+pub fn key_handle(sealed_signer: &SealedSigner) -> Result<KeyHandle, Error> {
+    let mut key_handle = [0u8; 32];
+    key_handle.copy_from_slice(&Sha256::digest(&sealed_signer.encode()?));
+    Ok(key_handle)
 }
 
-pub fn get_data_from_stream<T: Read>(stream: &mut T) -> Result<Vec<u8>, Error> {
-    let mut len_info = [0_u8; 8];
-    let _ = stream.read(&mut len_info)?;
-    let data_len = usize::from_le_bytes(len_info);
-    let mut data = vec![0_u8; data_len];
-    let _ = stream.read(&mut data)?;
-    Ok(data)
+/// `reportdata` of a report attesting a key: the SHA-256 digest of the
+/// algorithm name, a zero byte and the encoded public key, followed by zeros
+pub fn public_key_reportdata(algorithm: KeyAlgorithm, public_key: &[u8]) -> [u8; 64] {
+    let digest = Sha256::new()
+        .chain(algorithm.to_string().as_bytes())
+        .chain(&[0u8])
+        .chain(public_key)
+        .result();
+    let mut reportdata = [0u8; 64];
+    reportdata[..32].copy_from_slice(&digest);
+    reportdata
 }
 
-pub fn send_data_to_stream<W: Write>(
-    stream: &mut W,
-    data: &[u8],
-    with_len_info: bool,
-) -> Result<usize, IoError> {
-    let l1 = if with_len_info {
-        let data_len: [u8; 8] = data.len().to_le_bytes();
-        stream.write(&data_len)?
-    } else {
-        0
-    };
-    let l2 = stream.write(&data[..])?;
-    Ok(l1 + l2)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KeyPair {
+    pub sealed_privkey: SealedSigner,
+    pub pubkey: Vec<u8>,
 }
 
 pub trait Encode: Serialize {
-    fn encode(&self, with_len_info: bool) -> Result<Vec<u8>, Error> {
-        let data = bincode::serialize(self)
-            .map_err(|e| Error::new(format!("serialize seal signer failed with error: {:?}", e)))?;
-        // the first 8 bits is the length info of the serialized data
-        if with_len_info {
-            let len_data: [u8; 8] = data.len().to_le_bytes();
-            let mut result = len_data.to_vec();
-            result.extend_from_slice(&data);
-            Ok(result)
-        } else {
-            Ok(data)
-        }
+    fn encode(&self) -> Result<Vec<u8>, Error> {
+        bincode::serialize(self)
+            .map_err(|e| Error::new(format!("serialize seal signer failed with error: {:?}", e)))
     }
 }
 
 pub trait Decode<'de>: Deserialize<'de> {
     fn decode(encoded: &'de [u8]) -> Result<Self, Error> {
-        bincode::deserialize(encoded)
+        // never trust length prefixes inside the data beyond the frame size
+        bincode::config()
+            .limit(u64::from(MAX_FRAME_SIZE))
+            .deserialize(encoded)
             .map_err(|e| Error::new(format!("deserialize with error: {:?}", e)))
     }
 }
@@ -1,15 +1,33 @@
+#[cfg(feature = "backup")]
+use crate::backup::{KeyBackup, RecoverySecretKey};
 use crate::error::Error;
-use crate::protocol::{Decode, Encode, KeyPair, KeyType, Request, Response, SecretKeyEncoding};
+use crate::framing::Frame;
+use crate::key_file::{KeyFile, StoredKey};
+use crate::protocol::{
+    Decode, Encode, KeyAlgorithm, KeyPair, KeyType, Request, Response, SecretKeyEncoding,
+};
 use crate::seal_signer::SealedSigner;
 use crate::server::C2S;
-use crossbeam_channel::{unbounded, Receiver, Sender};
+use crossbeam_channel::{unbounded, Sender};
 use log::debug;
+use signatory::ecdsa::curve::{nistp256, secp256k1, NistP256, Secp256k1};
 use signatory::ed25519;
 use signatory::public_key::PublicKeyed;
 use signatory::signature::{Error as SigError, Signature, Signer};
+use std::ffi::OsString;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
 use std::path::Path;
+use std::process;
+use std::sync::atomic::{AtomicU64, Ordering};
 use subtle_encoding::encoding::Encoding;
 
+/// ECDSA over secp256k1 public key
+pub type Secp256k1PublicKey = signatory::ecdsa::PublicKey<Secp256k1>;
+
+/// ECDSA over NIST P-256 public key
+pub type NistP256PublicKey = signatory::ecdsa::PublicKey<NistP256>;
+
 #[inline]
 pub fn store_data_to_file<P: AsRef<Path>>(data: &[u8], file_path: P) -> Result<(), Error> {
     let encoder = SecretKeyEncoding::default();
@@ -37,53 +55,149 @@ pub fn encode_to_string(raw_data: &[u8]) -> Result<String, Error> {
     Ok(result)
 }
 
-pub struct SgxSigner<P: AsRef<Path>> {
-    tx: Sender<Vec<u8>>,
-    rx: Receiver<Vec<u8>>,
+/// Counter making the names of temporary files unique within the process
+static TEMP_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Write `data` to `file_path`, atomically replacing any existing file.
+///
+/// The data goes to a new temporary file next to `file_path`, which is
+/// synced to disk before it's renamed over `file_path`; the directory is
+/// synced after the rename. The file is only readable by its owner.
+pub fn write_file_atomically<P: AsRef<Path>>(data: &[u8], file_path: P) -> io::Result<()> {
+    let file_path = file_path.as_ref();
+    let file_name = file_path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no file name"))?;
+    let mut tmp_name = OsString::from(".");
+    tmp_name.push(file_name);
+    tmp_name.push(format!(
+        ".{}.{}.tmp",
+        process::id(),
+        TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let tmp_path = file_path.with_file_name(tmp_name);
+
+    let written = write_new_file(data, &tmp_path).and_then(|()| fs::rename(&tmp_path, file_path));
+    if let Err(e) = written {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(e);
+    }
+    sync_parent_dir(file_path)
+}
+
+/// Write `data` to a new file at `file_path`, failing with
+/// `io::ErrorKind::AlreadyExists` if there is a file there already. The file
+/// is synced to disk, along with its directory, and only readable by its
+/// owner.
+pub fn create_file<P: AsRef<Path>>(data: &[u8], file_path: P) -> io::Result<()> {
+    let file_path = file_path.as_ref();
+    write_new_file(data, file_path)?;
+    sync_parent_dir(file_path)
+}
+
+/// Create the file, write and sync it, removing it again if that fails
+fn write_new_file(data: &[u8], file_path: &Path) -> io::Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(file_path)?;
+    let written = file.write_all(data).and_then(|()| file.sync_all());
+    if written.is_err() {
+        let _ = fs::remove_file(file_path);
+    }
+    written
+}
+
+/// Sync the directory of `file_path`, so that a file created or renamed in
+/// it survives a crash
+fn sync_parent_dir(file_path: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        let dir = match file_path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        fs::File::open(dir)?.sync_all()?;
+    }
+    #[cfg(not(unix))]
+    let _ = file_path;
+    Ok(())
+}
+
+/// Transport used by `SgxSigner` to exchange frames with the enclave
+pub trait Connection: Send + Sync {
+    /// Send a request frame and return the response frame
+    fn exchange(&self, request_frame: Vec<u8>) -> Result<Vec<u8>, Error>;
+}
+
+/// Connection to a server running in the same process (see `server::run_server`)
+pub struct ChannelConnection {
     client2server_tx: Sender<C2S>,
-    sealed_signer_path: P,
 }
 
-impl<P: AsRef<Path>> SgxSigner<P> {
-    pub fn new(client2server_tx: Sender<C2S>, sealed_signer_path: P) -> Self {
-        let (tx, rx) = unbounded::<Vec<u8>>();
-        Self {
-            tx,
-            rx,
-            client2server_tx,
-            sealed_signer_path,
-        }
+impl ChannelConnection {
+    pub fn new(client2server_tx: Sender<C2S>) -> Self {
+        Self { client2server_tx }
     }
+}
 
-    fn send(&self, request: Request) -> Result<Response, Error> {
-        debug!("send request {:?}", request);
-        let request_rawdata = request.encode(true)?;
+impl Connection for ChannelConnection {
+    fn exchange(&self, request_frame: Vec<u8>) -> Result<Vec<u8>, Error> {
+        // a reply channel per request, so that concurrent callers never
+        // receive each other's responses
+        let (tx, rx) = unbounded::<Vec<u8>>();
         self.client2server_tx
-            .send((self.tx.clone(), request_rawdata))
+            .send((tx, request_frame))
             .map_err(|e| Error::new(format!("send data error: {:?}", e)))?;
-        let data = self
-            .rx
-            .recv()
-            .map_err(|e| Error::new(format!("receive data error: {:?}", e)))?;
-        Response::decode(&data[8..]) // remove the first 8 bits info
+        rx.recv()
+            .map_err(|e| Error::new(format!("receive data error: {:?}", e)))
     }
+}
 
-    pub fn store_key(&self, key_pair: &KeyPair) -> Result<String, Error> {
-        // dangerous to use the old secret_key path
-        if self.sealed_signer_path.as_ref().exists() {
-            return Err(Error::new("secret key path already exist"));
+/// Client of the enclave request/response protocol, not bound to any key
+pub struct SgxClient<C: Connection = ChannelConnection> {
+    connection: C,
+    next_request_id: AtomicU64,
+}
+
+impl SgxClient {
+    pub fn new(client2server_tx: Sender<C2S>) -> Self {
+        Self::with_connection(ChannelConnection::new(client2server_tx))
+    }
+}
+
+impl<C: Connection> SgxClient<C> {
+    /// Create a client talking to the enclave over the given connection
+    pub fn with_connection(connection: C) -> Self {
+        Self {
+            connection,
+            // request id 0 is reserved for errors not tied to a request
+            next_request_id: AtomicU64::new(1),
         }
-        let secret_raw_data = key_pair.sealed_privkey.encode(false)?;
-        store_data_to_file(&secret_raw_data, &self.sealed_signer_path)?;
-        let pubkey_str = encode_to_string(&key_pair.pubkey)?;
-        Ok(pubkey_str)
     }
 
-    #[inline]
-    fn get_sealed_signer(&self) -> Result<SealedSigner, Error> {
-        let sgx_secret_raw = get_data_from_file(self.sealed_signer_path.as_ref())?;
-        let signer = SealedSigner::decode(&sgx_secret_raw)?;
-        Ok(signer)
+    /// Send a request to the enclave and wait for its response
+    pub fn send(&self, request: Request) -> Result<Response, Error> {
+        debug!("send request {:?}", request);
+        let request_id = self.next_request_id.fetch_add(1, Ordering::SeqCst);
+        let request_frame = Frame::new(request_id, request.encode()?);
+        let data = self.connection.exchange(request_frame.to_bytes()?)?;
+        let response_frame = Frame::from_bytes(&data)?;
+        let response = Response::decode(&response_frame.payload)?;
+        if response_frame.request_id != request_id {
+            return match response {
+                Response::Error(s) => Err(Error::new(s)),
+                _ => Err(Error::new(format!(
+                    "response id mismatch: expected {}, got {}",
+                    request_id, response_frame.request_id
+                ))),
+            };
+        }
+        Ok(response)
     }
 
     pub fn ping(&self) -> Result<(), Error> {
@@ -96,8 +210,8 @@ impl<P: AsRef<Path>> SgxSigner<P> {
         }
     }
 
-    pub fn keygen(&self) -> Result<KeyPair, Error> {
-        let request = Request::KeyGen;
+    pub fn keygen(&self, algorithm: KeyAlgorithm) -> Result<KeyPair, Error> {
+        let request = Request::KeyGen(algorithm);
         match self.send(request)? {
             Response::Error(s) => Err(Error::new(s)),
             Response::KeyPair(keypair) => Ok(keypair),
@@ -105,7 +219,12 @@ impl<P: AsRef<Path>> SgxSigner<P> {
         }
     }
 
-    pub fn import<S: AsRef<str>>(&self, key_type: KeyType, key_str: S) -> Result<KeyPair, Error> {
+    pub fn import<S: AsRef<str>>(
+        &self,
+        algorithm: KeyAlgorithm,
+        key_type: KeyType,
+        key_str: S,
+    ) -> Result<KeyPair, Error> {
         let key_pair_raw = match key_type {
             KeyType::Base64 => {
                 let encoder = SecretKeyEncoding::default();
@@ -115,7 +234,17 @@ impl<P: AsRef<Path>> SgxSigner<P> {
                 raw
             }
         };
-        let request = Request::Import(key_pair_raw);
+        self.import_secret(algorithm, key_pair_raw)
+    }
+
+    /// Import a raw secret key, in the form `SealedSigner::import_key` takes
+    /// for its algorithm
+    pub fn import_secret(
+        &self,
+        algorithm: KeyAlgorithm,
+        secret: Vec<u8>,
+    ) -> Result<KeyPair, Error> {
+        let request = Request::Import(algorithm, secret);
         match self.send(request)? {
             Response::Error(s) => Err(Error::new(s)),
             Response::KeyPair(keypair) => Ok(keypair),
@@ -123,8 +252,196 @@ impl<P: AsRef<Path>> SgxSigner<P> {
         }
     }
 
+    /// Generate a key along with a backup of its secret, encrypted to the
+    /// recovery key configured in the enclave (see `backup`). The backup is
+    /// returned encoded, for `recover`.
+    #[cfg(feature = "backup")]
+    pub fn keygen_with_backup(&self, algorithm: KeyAlgorithm) -> Result<(KeyPair, Vec<u8>), Error> {
+        let request = Request::KeyGenWithBackup(algorithm);
+        match self.send(request)? {
+            Response::Error(s) => Err(Error::new(s)),
+            Response::KeyPairWithBackup { key_pair, backup } => Ok((key_pair, backup)),
+            _ => Err(Error::new("error kind of response")),
+        }
+    }
+
+    /// Import a key from its backup into this enclave, decrypting the backup
+    /// on the host with the recovery secret key
+    #[cfg(feature = "backup")]
+    pub fn recover(
+        &self,
+        backup: &KeyBackup,
+        recovery_secret_key: &RecoverySecretKey,
+    ) -> Result<KeyPair, Error> {
+        let secret = backup.decrypt(recovery_secret_key)?;
+        let key_pair = self.import_secret(backup.algorithm, secret)?;
+        if key_pair.pubkey != backup.public_key {
+            return Err(Error::new("the recovered key doesn't match the backup"));
+        }
+        Ok(key_pair)
+    }
+
+    /// Ask the enclave for the public key of a sealed key
+    pub fn get_public_key(&self, sealed_signer: SealedSigner) -> Result<Vec<u8>, Error> {
+        let request = Request::GetPublicKey(sealed_signer);
+        match self.send(request)? {
+            Response::Error(s) => Err(Error::new(s)),
+            Response::PublicKey(pubkey) => Ok(pubkey),
+            _ => Err(Error::new("error kind of response")),
+        }
+    }
+
+    /// Seal a key again under the current security version of the enclave
+    /// and platform, e.g. after an enclave or microcode upgrade
+    pub fn reseal(&self, sealed_signer: SealedSigner) -> Result<KeyPair, Error> {
+        let request = Request::Reseal(sealed_signer);
+        match self.send(request)? {
+            Response::Error(s) => Err(Error::new(s)),
+            Response::KeyPair(keypair) => Ok(keypair),
+            _ => Err(Error::new("error kind of response")),
+        }
+    }
+
+    /// Initialise the enclave's double-sign policy of a key on a chain (see
+    /// `policy`), returning the sealed policy state to pass along with the
+    /// first signing request. Nothing at or below `height` and `round` is
+    /// signed afterwards.
+    pub fn init_policy(
+        &self,
+        sealed_signer: SealedSigner,
+        chain_id: &str,
+        height: i64,
+        round: i64,
+    ) -> Result<Vec<u8>, Error> {
+        let request = Request::InitPolicy {
+            sealed_signer,
+            chain_id: chain_id.to_owned(),
+            height,
+            round,
+        };
+        match self.send(request)? {
+            Response::PolicyInitialized { sealed_state } => Ok(sealed_state),
+            Response::Error(s) => Err(Error::new(s)),
+            _ => Err(Error::new("error kind of response")),
+        }
+    }
+
+    /// Sign Tendermint vote or proposal sign bytes subject to the enclave's
+    /// double-sign policy (see `policy`), returning the signature and the
+    /// new sealed policy state to pass along with the next request
+    pub fn policy_sign(
+        &self,
+        sealed_signer: SealedSigner,
+        sealed_state: Option<Vec<u8>>,
+        sign_bytes: &[u8],
+    ) -> Result<(Vec<u8>, Vec<u8>), Error> {
+        let request = Request::PolicySign {
+            sealed_signer,
+            sealed_state,
+            sign_bytes: sign_bytes.to_vec(),
+        };
+        match self.send(request)? {
+            Response::PolicySigned {
+                signature,
+                sealed_state,
+            } => Ok((signature, sealed_state)),
+            Response::Error(s) => Err(Error::new(s)),
+            _ => Err(Error::new("error kind of response")),
+        }
+    }
+}
+
+pub struct SgxSigner<P: AsRef<Path>, C: Connection = ChannelConnection> {
+    client: SgxClient<C>,
+    sealed_signer_path: P,
+    algorithm: KeyAlgorithm,
+}
+
+impl<P: AsRef<Path>> SgxSigner<P> {
+    pub fn new(client2server_tx: Sender<C2S>, sealed_signer_path: P) -> Self {
+        Self::with_connection(ChannelConnection::new(client2server_tx), sealed_signer_path)
+    }
+}
+
+impl<P: AsRef<Path>, C: Connection> SgxSigner<P, C> {
+    /// Create a signer talking to the enclave over the given connection
+    pub fn with_connection(connection: C, sealed_signer_path: P) -> Self {
+        Self {
+            client: SgxClient::with_connection(connection),
+            sealed_signer_path,
+            algorithm: KeyAlgorithm::default(),
+        }
+    }
+
+    /// Set the algorithm of keys generated or imported by this signer
+    /// (Ed25519 by default)
+    pub fn with_algorithm(mut self, algorithm: KeyAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// Client used by this signer to talk to the enclave
+    pub fn client(&self) -> &SgxClient<C> {
+        &self.client
+    }
+
+    fn send(&self, request: Request) -> Result<Response, Error> {
+        self.client.send(request)
+    }
+
+    pub fn store_key(&self, key_pair: &KeyPair) -> Result<String, Error> {
+        // dangerous to use the old secret_key path
+        if self.sealed_signer_path.as_ref().exists() {
+            return Err(Error::new("secret key path already exist"));
+        }
+        KeyFile::new(key_pair).write(&self.sealed_signer_path)?;
+        let pubkey_str = encode_to_string(&key_pair.pubkey)?;
+        Ok(pubkey_str)
+    }
+
+    #[inline]
+    pub(crate) fn get_sealed_signer(&self) -> Result<SealedSigner, Error> {
+        let stored_key = StoredKey::read(self.sealed_signer_path.as_ref())?;
+        Ok(stored_key.into_sealed_signer())
+    }
+
+    pub fn ping(&self) -> Result<(), Error> {
+        self.client.ping()
+    }
+
+    pub fn keygen(&self) -> Result<KeyPair, Error> {
+        self.client.keygen(self.algorithm)
+    }
+
+    /// Generate a key along with its encoded backup (see
+    /// `SgxClient::keygen_with_backup`)
+    #[cfg(feature = "backup")]
+    pub fn keygen_with_backup(&self) -> Result<(KeyPair, Vec<u8>), Error> {
+        self.client.keygen_with_backup(self.algorithm)
+    }
+
+    pub fn import<S: AsRef<str>>(&self, key_type: KeyType, key_str: S) -> Result<KeyPair, Error> {
+        self.client.import(self.algorithm, key_type, key_str)
+    }
+
+    /// Import a raw secret key for the algorithm of this signer
+    pub fn import_secret(&self, secret: Vec<u8>) -> Result<KeyPair, Error> {
+        self.client.import_secret(self.algorithm, secret)
+    }
+
+    /// Get the sealed key, checking it is a key for the given algorithm
+    fn get_sealed_signer_for(&self, algorithm: KeyAlgorithm) -> Result<SealedSigner, Error> {
+        let sealed_signer = self.get_sealed_signer()?;
+        check_algorithm(sealed_signer.algorithm(), algorithm)?;
+        Ok(sealed_signer)
+    }
+
     pub fn get_pubkey(&self) -> Result<Vec<u8>, Error> {
         let sealed_signer = self.get_sealed_signer()?;
+        self.request_pubkey(sealed_signer)
+    }
+
+    fn request_pubkey(&self, sealed_signer: SealedSigner) -> Result<Vec<u8>, Error> {
         let request = Request::GetPublicKey(sealed_signer);
         let response = self.send(request)?;
         debug!("response: {:?}", response);
@@ -135,8 +452,56 @@ impl<P: AsRef<Path>> SgxSigner<P> {
         }
     }
 
+    /// Algorithm of the stored key
+    pub fn key_algorithm(&self) -> Result<KeyAlgorithm, Error> {
+        Ok(self.get_sealed_signer()?.algorithm())
+    }
+
     pub fn sign_msg(&self, msg: &[u8]) -> Result<Vec<u8>, Error> {
         let sealed_signer = self.get_sealed_signer()?;
+        self.request_sign(sealed_signer, msg)
+    }
+
+    /// Initialise the enclave's double-sign policy of the key on a chain,
+    /// saving the sealed policy state to `state_path` (see
+    /// `SgxClient::init_policy`). This is needed before the first signature,
+    /// and again whenever the enclave restarted on hardware.
+    pub fn init_consensus<Q: AsRef<Path>>(
+        &self,
+        state_path: Q,
+        chain_id: &str,
+        height: i64,
+        round: i64,
+    ) -> Result<(), Error> {
+        let sealed_state =
+            self.client
+                .init_policy(self.get_sealed_signer()?, chain_id, height, round)?;
+        write_file_atomically(&sealed_state, state_path)?;
+        Ok(())
+    }
+
+    /// Sign Tendermint vote or proposal sign bytes subject to the enclave's
+    /// double-sign policy. The sealed policy state is read from and saved
+    /// to `state_path`, before the signature is returned.
+    pub fn sign_consensus<Q: AsRef<Path>>(
+        &self,
+        state_path: Q,
+        sign_bytes: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        let state_path = state_path.as_ref();
+        let sealed_state = if state_path.exists() {
+            Some(fs::read(state_path)?)
+        } else {
+            None
+        };
+        let (signature, sealed_state) =
+            self.client
+                .policy_sign(self.get_sealed_signer()?, sealed_state, sign_bytes)?;
+        write_file_atomically(&sealed_state, state_path)?;
+        Ok(signature)
+    }
+
+    fn request_sign(&self, sealed_signer: SealedSigner, msg: &[u8]) -> Result<Vec<u8>, Error> {
         let request = Request::Sign((sealed_signer, msg.to_vec()));
         let response = self.send(request)?;
         debug!("response: {:?}", response);
@@ -148,31 +513,101 @@ impl<P: AsRef<Path>> SgxSigner<P> {
     }
 }
 
-impl<P> PublicKeyed<ed25519::PublicKey> for SgxSigner<P>
+impl<P, C> PublicKeyed<ed25519::PublicKey> for SgxSigner<P, C>
 where
     P: AsRef<Path> + Send + Sync,
+    C: Connection,
 {
     fn public_key(&self) -> Result<ed25519::PublicKey, SigError> {
-        let pubkey_raw = self.get_pubkey().map_err(SigError::from_source)?;
-        let pubkey = ed25519::PublicKey::from_bytes(&pubkey_raw);
-        if let Some(p) = pubkey {
-            Ok(p)
-        } else {
-            Err(SigError::new())
-        }
+        let pubkey_raw = self
+            .get_sealed_signer_for(KeyAlgorithm::Ed25519)
+            .and_then(|sealed_signer| self.request_pubkey(sealed_signer))
+            .map_err(SigError::from_source)?;
+        ed25519::PublicKey::from_bytes(&pubkey_raw).ok_or_else(SigError::new)
     }
 }
 
-impl<P> Signer<ed25519::Signature> for SgxSigner<P>
+impl<P, C> Signer<ed25519::Signature> for SgxSigner<P, C>
 where
     P: AsRef<Path> + Send + Sync,
+    C: Connection,
 {
     fn try_sign(&self, msg: &[u8]) -> Result<ed25519::Signature, SigError> {
-        let signature_raw = self.sign_msg(msg).map_err(SigError::from_source)?;
-        let signature = ed25519::Signature::from_bytes(&signature_raw[..])?;
-        Ok(signature)
+        let signature_raw = self
+            .get_sealed_signer_for(KeyAlgorithm::Ed25519)
+            .and_then(|sealed_signer| self.request_sign(sealed_signer, msg))
+            .map_err(SigError::from_source)?;
+        ed25519::Signature::from_bytes(&signature_raw[..])
     }
 }
+
+impl<P, C> PublicKeyed<Secp256k1PublicKey> for SgxSigner<P, C>
+where
+    P: AsRef<Path> + Send + Sync,
+    C: Connection,
+{
+    fn public_key(&self) -> Result<Secp256k1PublicKey, SigError> {
+        let pubkey_raw = self
+            .get_sealed_signer_for(KeyAlgorithm::Secp256k1)
+            .and_then(|sealed_signer| self.request_pubkey(sealed_signer))
+            .map_err(SigError::from_source)?;
+        Secp256k1PublicKey::from_bytes(&pubkey_raw).ok_or_else(SigError::new)
+    }
+}
+
+impl<P, C> Signer<secp256k1::FixedSignature> for SgxSigner<P, C>
+where
+    P: AsRef<Path> + Send + Sync,
+    C: Connection,
+{
+    fn try_sign(&self, msg: &[u8]) -> Result<secp256k1::FixedSignature, SigError> {
+        let signature_raw = self
+            .get_sealed_signer_for(KeyAlgorithm::Secp256k1)
+            .and_then(|sealed_signer| self.request_sign(sealed_signer, msg))
+            .map_err(SigError::from_source)?;
+        secp256k1::FixedSignature::from_bytes(&signature_raw[..])
+    }
+}
+
+impl<P, C> PublicKeyed<NistP256PublicKey> for SgxSigner<P, C>
+where
+    P: AsRef<Path> + Send + Sync,
+    C: Connection,
+{
+    fn public_key(&self) -> Result<NistP256PublicKey, SigError> {
+        let pubkey_raw = self
+            .get_sealed_signer_for(KeyAlgorithm::NistP256)
+            .and_then(|sealed_signer| self.request_pubkey(sealed_signer))
+            .map_err(SigError::from_source)?;
+        NistP256PublicKey::from_bytes(&pubkey_raw).ok_or_else(SigError::new)
+    }
+}
+
+impl<P, C> Signer<nistp256::FixedSignature> for SgxSigner<P, C>
+where
+    P: AsRef<Path> + Send + Sync,
+    C: Connection,
+{
+    fn try_sign(&self, msg: &[u8]) -> Result<nistp256::FixedSignature, SigError> {
+        let signature_raw = self
+            .get_sealed_signer_for(KeyAlgorithm::NistP256)
+            .and_then(|sealed_signer| self.request_sign(sealed_signer, msg))
+            .map_err(SigError::from_source)?;
+        nistp256::FixedSignature::from_bytes(&signature_raw[..])
+    }
+}
+
+/// Check a key is used with the algorithm it was created for
+pub(crate) fn check_algorithm(actual: KeyAlgorithm, expected: KeyAlgorithm) -> Result<(), Error> {
+    if actual != expected {
+        return Err(Error::new(format!(
+            "the key is a {:?} key, not a {:?} key",
+            actual, expected
+        )));
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
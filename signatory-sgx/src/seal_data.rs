@@ -1,15 +1,17 @@
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
-use sgx_isa::{Attributes, Miscselect};
+use sgx_isa::{Attributes, Keypolicy, Miscselect};
 
-#[cfg(feature = "sgx")]
+#[cfg(any(feature = "sgx", feature = "sim"))]
 use crate::error::Error;
-#[cfg(feature = "sgx")]
+#[cfg(feature = "sim")]
+use crate::sim::report_for_self;
+#[cfg(any(feature = "sgx", feature = "sim"))]
 use rand::random;
-#[cfg(feature = "sgx")]
+#[cfg(any(feature = "sgx", feature = "sim"))]
 use sgx_isa::Report;
-#[cfg(feature = "sgx")]
-use sgx_isa::{Keyname, Keypolicy, Keyrequest};
-#[cfg(feature = "sgx")]
+#[cfg(any(feature = "sgx", feature = "sim"))]
+use sgx_isa::{Keyname, Keyrequest};
+#[cfg(any(feature = "sgx", feature = "sim"))]
 use std::convert::AsRef;
 
 #[derive(Debug, Clone, PartialEq)]
@@ -87,6 +89,10 @@ pub type Nonce = [u8; 12];
 pub type CpuSvn = [u8; 16];
 pub type IsvSvn = u16;
 
+/// Identity the sealing keys are bound to: keys are shared by all enclaves
+/// from the same signer with the same product ID
+pub const SEAL_KEY_POLICY: Keypolicy = Keypolicy::MRSIGNER;
+
 /// Information about how the sealing key was derived. This
 /// should be stored alongside the sealed data, so that the enclave
 /// can rederive the same key later.
@@ -103,8 +109,20 @@ pub struct SealData {
     miscselect: SealMiscselect,
 }
 
+impl SealData {
+    /// ISVSVN of the enclave at seal time
+    pub fn isvsvn(&self) -> IsvSvn {
+        self.isvsvn
+    }
+
+    /// CPUSVN of the platform at seal time
+    pub fn cpusvn(&self) -> CpuSvn {
+        self.cpusvn
+    }
+}
+
 /// Derive a sealing key for the current enclave given `label` and `seal_data`.
-#[cfg(feature = "sgx")]
+#[cfg(any(feature = "sgx", feature = "sim"))]
 fn egetkey(label: Label, seal_data: &SealData) -> Result<EgetKey, Error> {
     // Key ID is combined from fixed label and random data
     let mut keyid = [0; 32];
@@ -114,18 +132,29 @@ fn egetkey(label: Label, seal_data: &SealData) -> Result<EgetKey, Error> {
         rand_dst.copy_from_slice(&seal_data.rand);
     }
 
-    Keyrequest {
+    let request = Keyrequest {
         keyname: Keyname::Seal as _,
-        keypolicy: Keypolicy::MRSIGNER,
+        keypolicy: SEAL_KEY_POLICY,
         isvsvn: seal_data.isvsvn,
         cpusvn: seal_data.cpusvn,
         attributemask: [!0; 2],
         keyid,
         miscmask: !0,
         ..Default::default()
-    }
-    .egetkey()
-    .map_err(|e| Error::new(format!("get egetkey failed with error: {:?}", e)))
+    };
+
+    #[cfg(not(feature = "sim"))]
+    let key = request.egetkey();
+    #[cfg(feature = "sim")]
+    let key = crate::sim::egetkey(&request);
+
+    key.map_err(|e| Error::new(format!("get egetkey failed with error: {:?}", e)))
+}
+
+/// Get the report of the current enclave
+#[cfg(all(feature = "sgx", not(feature = "sim")))]
+fn report_for_self() -> Report {
+    Report::for_self()
 }
 
 /// Get a key for sealing data.
@@ -136,9 +165,9 @@ fn egetkey(label: Label, seal_data: &SealData) -> Result<EgetKey, Error> {
 /// different types of data, make sure to pass a different `label`.
 /// The returned `SealData` should be stored alongside the
 /// ciphertext to make sure the data can be unsealed again later.
-#[cfg(feature = "sgx")]
+#[cfg(any(feature = "sgx", feature = "sim"))]
 pub fn seal_key(label: Label) -> (EgetKey, SealData) {
-    let report = Report::for_self();
+    let report = report_for_self();
     let seal_data = SealData {
         // Generate fresh randomness for each sealing operation.
         rand: random(),
@@ -168,9 +197,9 @@ pub fn seal_key(label: Label) -> (EgetKey, SealData) {
 /// May return an error if the sealing key was not generated by the
 /// same enclave configuration, or if the SGX TCB level has been
 /// downgraded.
-#[cfg(feature = "sgx")]
+#[cfg(any(feature = "sgx", feature = "sim"))]
 pub fn unseal_key(label: Label, seal_data: &SealData) -> Result<EgetKey, Error> {
-    let report = Report::for_self();
+    let report = report_for_self();
     // Make sure the parameters that are not checked for correctness
     // by EGETKEY match the current enclave. Without this check,
     // EGETKEY will proceed to derive a key, which will be an
@@ -184,13 +213,44 @@ pub fn unseal_key(label: Label, seal_data: &SealData) -> Result<EgetKey, Error>
     egetkey(label, &seal_data)
 }
 
-#[cfg(all(test, feature = "sgx"))]
+/// Check that data sealed with `seal_data` may be re-sealed by the current
+/// enclave.
+///
+/// Re-sealing must only ever move data to the same or a newer security
+/// version: sealing it under an older ISVSVN or CPUSVN would make it
+/// available to enclave or microcode versions that may have known
+/// vulnerabilities.
+#[cfg(any(feature = "sgx", feature = "sim"))]
+pub fn check_reseal(seal_data: &SealData) -> Result<(), Error> {
+    check_svn_not_downgraded(seal_data, &report_for_self())
+}
+
+#[cfg(any(feature = "sgx", feature = "sim"))]
+fn check_svn_not_downgraded(seal_data: &SealData, report: &Report) -> Result<(), Error> {
+    if report.isvsvn < seal_data.isvsvn {
+        return Err(Error::new(format!(
+            "refusing to downgrade ISVSVN from {} to {}",
+            seal_data.isvsvn, report.isvsvn
+        )));
+    }
+    if report
+        .cpusvn
+        .iter()
+        .zip(seal_data.cpusvn.iter())
+        .any(|(current, sealed)| current < sealed)
+    {
+        return Err(Error::new("refusing to downgrade CPUSVN"));
+    }
+    Ok(())
+}
+
+#[cfg(all(test, any(feature = "sgx", feature = "sim")))]
 mod tests {
     use super::*;
 
     #[test]
     fn test_seal_attributes_serde() {
-        let report = Report::for_self();
+        let report = report_for_self();
         let seal_attributes = SealAttributes(report.attributes);
         let encoded = bincode::serialize(&seal_attributes).unwrap();
         let decoded = bincode::deserialize(&encoded[..]).unwrap();
@@ -199,7 +259,7 @@ mod tests {
 
     #[test]
     fn test_seal_misc_serde() {
-        let report = Report::for_self();
+        let report = report_for_self();
         let seal_data = SealMiscselect(report.miscselect);
         let encoded = bincode::serialize(&seal_data).unwrap();
         let decoded = bincode::deserialize(&encoded[..]).unwrap();
@@ -214,4 +274,25 @@ mod tests {
         let decoded = bincode::deserialize(&encoded[..]).unwrap();
         assert_eq!(seal_data, decoded);
     }
+
+    #[test]
+    fn test_check_svn_not_downgraded() {
+        let label = Label::from([0; 16]);
+        let (_, seal_data) = seal_key(label);
+        let report = report_for_self();
+        assert!(check_svn_not_downgraded(&seal_data, &report).is_ok());
+
+        let mut upgraded = report.clone();
+        upgraded.isvsvn += 1;
+        upgraded.cpusvn[0] += 1;
+        assert!(check_svn_not_downgraded(&seal_data, &upgraded).is_ok());
+
+        let mut newer_seal_data = seal_data.clone();
+        newer_seal_data.isvsvn += 1;
+        assert!(check_svn_not_downgraded(&newer_seal_data, &report).is_err());
+
+        let mut newer_seal_data = seal_data;
+        newer_seal_data.cpusvn[15] += 1;
+        assert!(check_svn_not_downgraded(&newer_seal_data, &report).is_err());
+    }
 }
@@ -1,44 +1,100 @@
-use crate::protocol::{Decode, Encode};
+use crate::error::Error;
+use crate::framing::MAX_FRAME_SIZE;
+use crate::protocol::{Decode, Encode, KeyAlgorithm};
 use crate::seal_data::{Label, SealData};
 use serde::{Deserialize, Serialize};
 
-#[cfg(feature = "sgx")]
-use crate::error::Error;
-#[cfg(feature = "sgx")]
-use crate::seal_data::{seal_key, unseal_key};
-#[cfg(feature = "sgx")]
+#[cfg(any(feature = "sgx", feature = "sim"))]
+use crate::backup::{KeyBackup, RecoveryPublicKey};
+#[cfg(any(feature = "sgx", feature = "sim"))]
+use crate::seal_data::{check_reseal, seal_key, unseal_key};
+#[cfg(any(feature = "sgx", feature = "sim"))]
 use aead::{generic_array::GenericArray, Aead, NewAead, Payload};
-#[cfg(feature = "sgx")]
+#[cfg(any(feature = "sgx", feature = "sim"))]
 use aes_gcm_siv::Aes128GcmSiv;
-#[cfg(feature = "sgx")]
+#[cfg(any(feature = "sgx", feature = "sim"))]
 use rand::random;
-#[cfg(feature = "sgx")]
+#[cfg(any(feature = "sgx", feature = "sim"))]
+use signatory::ecdsa::curve::{nistp256, secp256k1};
+#[cfg(any(feature = "sgx", feature = "sim"))]
 use signatory::ed25519;
-#[cfg(feature = "sgx")]
+#[cfg(any(feature = "sgx", feature = "sim"))]
+use signatory::encoding::pkcs8::{FromPkcs8, GeneratePkcs8};
+#[cfg(any(feature = "sgx", feature = "sim"))]
 use signatory::public_key::PublicKeyed;
-#[cfg(feature = "sgx")]
+#[cfg(any(feature = "sgx", feature = "sim"))]
 use signatory::signature::Signature;
-#[cfg(feature = "sgx")]
+#[cfg(any(feature = "sgx", feature = "sim"))]
 use signatory::signature::{Signer, Verifier};
-#[cfg(feature = "sgx")]
+#[cfg(any(feature = "sgx", feature = "sim"))]
 use signatory_dalek::{Ed25519Signer, Ed25519Verifier};
+#[cfg(any(feature = "sgx", feature = "sim"))]
+use signatory_ring::ecdsa::p256;
+#[cfg(any(feature = "sgx", feature = "sim"))]
+use signatory_secp256k1::{
+    EcdsaSigner as Secp256k1Signer, EcdsaVerifier as Secp256k1Verifier,
+    PublicKey as Secp256k1PublicKey, SecretKey as Secp256k1SecretKey,
+};
+#[cfg(any(feature = "sgx", feature = "sim"))]
+use zeroize::Zeroizing;
+
+/// Order of the secp256k1 group, big endian
+#[cfg(any(feature = "sgx", feature = "sim"))]
+const SECP256K1_ORDER: [u8; 32] = [
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe,
+    0xba, 0xae, 0xdc, 0xe6, 0xaf, 0x48, 0xa0, 0x3b, 0xbf, 0xd2, 0x5e, 0x8c, 0xd0, 0x36, 0x41, 0x41,
+];
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+/// A secret key sealed to the enclave.
+///
+/// The sealed secret depends on the key algorithm: the 32-byte seed for
+/// Ed25519, the 32-byte secret scalar for secp256k1 and a PKCS#8 document
+/// for NIST P-256.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct SealedSigner {
     sealed_seed: Vec<u8>,
     seal_data: SealData,
     label: Label,
+    algorithm: KeyAlgorithm,
+}
+
+/// `SealedSigner` as serialized before key algorithms were introduced;
+/// always an Ed25519 key
+#[derive(Deserialize)]
+struct LegacySealedSigner {
+    sealed_seed: Vec<u8>,
+    seal_data: SealData,
+    label: Label,
 }
 
-#[cfg(feature = "sgx")]
 impl SealedSigner {
-    fn create(seed: Option<ed25519::Seed>) -> Result<Self, Error> {
-        let seed = seed.unwrap_or(ed25519::Seed::generate());
+    /// Algorithm of the sealed key
+    pub fn algorithm(&self) -> KeyAlgorithm {
+        self.algorithm
+    }
+
+    /// How the sealing key was derived, including the security versions at
+    /// seal time
+    pub fn seal_data(&self) -> &SealData {
+        &self.seal_data
+    }
+
+    /// Additional authenticated data of the sealed secret: the label, and
+    /// the algorithm for anything but (legacy) Ed25519 keys
+    #[cfg(any(feature = "sgx", feature = "sim"))]
+    fn aad(&self) -> Vec<u8> {
+        aad(self.label, self.algorithm)
+    }
+}
+
+#[cfg(any(feature = "sgx", feature = "sim"))]
+impl SealedSigner {
+    fn create(algorithm: KeyAlgorithm, secret: &[u8]) -> Result<Self, Error> {
         let label: Label = random();
-        let raw_seed = seed.as_secret_slice();
+        let aad = aad(label, algorithm);
         let payload = Payload {
-            msg: raw_seed,
-            aad: &label,
+            msg: secret,
+            aad: &aad,
         };
         let (eget_key, seal_data) = seal_key(label);
         let aead = get_algo(&eget_key);
@@ -51,82 +107,289 @@ impl SealedSigner {
             sealed_seed,
             seal_data,
             label,
+            algorithm,
         };
         Ok(s)
     }
 
+    /// Generate a new Ed25519 key
     pub fn new() -> Result<Self, Error> {
-        Self::create(None)
+        Self::generate(KeyAlgorithm::Ed25519)
+    }
+
+    /// Generate a new key for the given algorithm
+    pub fn generate(algorithm: KeyAlgorithm) -> Result<Self, Error> {
+        match algorithm {
+            KeyAlgorithm::Ed25519 => {
+                let seed = ed25519::Seed::generate();
+                Self::create(algorithm, seed.as_secret_slice())
+            }
+            KeyAlgorithm::Secp256k1 => loop {
+                let secret_key = Secp256k1SecretKey::generate();
+                if is_valid_secp256k1_scalar(secret_key.as_secret_slice()) {
+                    break Self::create(algorithm, secret_key.as_secret_slice());
+                }
+            },
+            KeyAlgorithm::NistP256 => {
+                let pkcs8 = p256::Signer::<nistp256::FixedSignature>::generate_pkcs8()
+                    .map_err(|e| Error::new(format!("generate P-256 key failed: {:?}", e)))?;
+                Self::create(algorithm, pkcs8.as_ref())
+            }
+        }
     }
 
+    /// Import an Ed25519 keypair (seed followed by public key)
     pub fn import(key_pair: Vec<u8>) -> Result<Self, Error> {
-        let seed = ed25519::Seed::from_keypair(&key_pair);
-        if seed.is_none() {
-            return Err(Error::new("invalid key pair"));
+        Self::import_key(KeyAlgorithm::Ed25519, &key_pair)
+    }
+
+    /// Import a secret key for the given algorithm: an Ed25519 keypair (seed
+    /// followed by public key) or seed, a raw secp256k1 secret scalar, or a
+    /// NIST P-256 PKCS#8 document
+    pub fn import_key(algorithm: KeyAlgorithm, secret: &[u8]) -> Result<Self, Error> {
+        match algorithm {
+            KeyAlgorithm::Ed25519 => {
+                let seed = if secret.len() == ed25519::SEED_SIZE {
+                    ed25519::Seed::from_bytes(secret)
+                } else {
+                    ed25519::Seed::from_keypair(secret)
+                };
+                let seed = seed.ok_or_else(|| Error::new("invalid key pair"))?;
+                Self::create(algorithm, seed.as_secret_slice())
+            }
+            KeyAlgorithm::Secp256k1 => {
+                if secret.len() != 32 || !is_valid_secp256k1_scalar(secret) {
+                    return Err(Error::new("invalid secp256k1 secret key"));
+                }
+                Self::create(algorithm, secret)
+            }
+            KeyAlgorithm::NistP256 => {
+                p256::Signer::<nistp256::FixedSignature>::from_pkcs8(secret)
+                    .map_err(|_| Error::new("invalid P-256 PKCS#8 key"))?;
+                Self::create(algorithm, secret)
+            }
         }
-        Self::create(Some(seed.unwrap()))
     }
 
-    fn get_signer(&self) -> Result<Ed25519Signer, Error> {
+    /// Decrypt the sealed secret, which is zeroized when dropped
+    fn unseal(&self) -> Result<Zeroizing<Vec<u8>>, Error> {
         let seal_key = unseal_key(self.label, &self.seal_data)?;
         let nonce = GenericArray::from_slice(&self.seal_data.nonce);
         let aead = get_algo(&seal_key);
+        let aad = self.aad();
         let payload = Payload {
             msg: self.sealed_seed.as_ref(),
-            aad: &self.label,
+            aad: &aad,
         };
-        let raw_seed = aead.decrypt(nonce, payload).map_err(|_e| {
-            Error::new("get signer failed, the host changed or the program changed")
-        })?;
-        if let Some(signer) =
-            ed25519::Seed::from_bytes(raw_seed).map(|seed| Ed25519Signer::from(&seed))
-        {
-            Ok(signer)
-        } else {
-            Err(Error::new("get signer failed"))
-        }
+        aead.decrypt(nonce, payload)
+            .map(Zeroizing::new)
+            .map_err(|_e| Error::new("get signer failed, the host changed or the program changed"))
+    }
+
+    /// Unseal the key
+    pub fn get_signer(&self) -> Result<UnsealedSigner, Error> {
+        let raw_secret = self.unseal()?;
+        UnsealedSigner::from_secret(self.algorithm, &raw_secret)
+            .ok_or_else(|| Error::new("get signer failed"))
+    }
+
+    /// Encrypt the secret of the key to a recovery key; only done when the
+    /// key is generated (`Request::KeyGenWithBackup`)
+    pub(crate) fn backup(
+        &self,
+        recovery_key: &RecoveryPublicKey,
+        public_key: &[u8],
+    ) -> Result<KeyBackup, Error> {
+        let raw_secret = self.unseal()?;
+        KeyBackup::encrypt(recovery_key, self.algorithm, public_key, &raw_secret)
+    }
+
+    /// Seal the key again under the current ISVSVN and CPUSVN, e.g. after an
+    /// enclave or microcode upgrade. Fails if that would be a downgrade.
+    pub fn reseal(&self) -> Result<Self, Error> {
+        check_reseal(&self.seal_data)?;
+        let raw_secret = self.unseal()?;
+        Self::create(self.algorithm, &raw_secret)
     }
 
     pub fn try_sign(&self, data: &[u8]) -> Result<Vec<u8>, Error> {
-        let signer = self.get_signer()?;
-        let sig = signer
-            .try_sign(data)
-            .map_err(|e| Error::new(format!("sign data with error: {}", e)))?;
-        Ok(sig.to_bytes().to_vec())
+        self.get_signer()?.try_sign(data)
     }
 
     pub fn verify(&self, msg: &[u8], sig_raw: &[u8]) -> Result<(), Error> {
         let public_key = self.get_public_key()?;
-        let verifier = Ed25519Verifier::from(&public_key);
-        let sig =
-            ed25519::Signature::from_bytes(sig_raw).map_err(|_| Error::new("invalid signature"))?;
-        verifier
-            .verify(msg, &sig)
-            .map_err(|e| Error::new(format!("varify failed with error: {:?}", e)))
+        verify(self.algorithm, &public_key, msg, sig_raw)
     }
 
-    pub fn get_public_key(&self) -> Result<ed25519::PublicKey, Error> {
-        let signer = self.get_signer()?;
-        signer
-            .public_key()
-            .map_err(|e| Error::new(format!("get public key failed with error: {:?}", e)))
+    /// Public key of the sealed key, encoded as for `UnsealedSigner::public_key`
+    pub fn get_public_key(&self) -> Result<Vec<u8>, Error> {
+        self.get_signer()?.public_key()
     }
 }
 
 impl Encode for SealedSigner {}
-impl<'de> Decode<'de> for SealedSigner {}
 
-#[cfg(feature = "sgx")]
-fn get_algo(seal_key: &[u8]) -> Aes128GcmSiv {
+impl<'de> Decode<'de> for SealedSigner {
+    fn decode(encoded: &'de [u8]) -> Result<Self, Error> {
+        bincode::config()
+            .limit(u64::from(MAX_FRAME_SIZE))
+            .deserialize(encoded)
+            .or_else(|e| {
+                bincode::config()
+                    .limit(u64::from(MAX_FRAME_SIZE))
+                    .deserialize::<LegacySealedSigner>(encoded)
+                    .map(|legacy| SealedSigner {
+                        sealed_seed: legacy.sealed_seed,
+                        seal_data: legacy.seal_data,
+                        label: legacy.label,
+                        algorithm: KeyAlgorithm::Ed25519,
+                    })
+                    .map_err(|_| Error::new(format!("deserialize with error: {:?}", e)))
+            })
+    }
+}
+
+/// An unsealed key, only ever present in enclave memory
+#[cfg(any(feature = "sgx", feature = "sim"))]
+pub enum UnsealedSigner {
+    Ed25519(Ed25519Signer),
+    Secp256k1(Secp256k1Signer),
+    NistP256(p256::Signer<nistp256::FixedSignature>),
+}
+
+#[cfg(any(feature = "sgx", feature = "sim"))]
+impl UnsealedSigner {
+    fn from_secret(algorithm: KeyAlgorithm, secret: &[u8]) -> Option<Self> {
+        match algorithm {
+            KeyAlgorithm::Ed25519 => ed25519::Seed::from_bytes(secret)
+                .map(|seed| UnsealedSigner::Ed25519(Ed25519Signer::from(&seed))),
+            KeyAlgorithm::Secp256k1 => {
+                if !is_valid_secp256k1_scalar(secret) {
+                    return None;
+                }
+                Secp256k1SecretKey::from_bytes(secret)
+                    .map(|secret_key| UnsealedSigner::Secp256k1(Secp256k1Signer::from(&secret_key)))
+            }
+            KeyAlgorithm::NistP256 => p256::Signer::from_pkcs8(secret)
+                .ok()
+                .map(UnsealedSigner::NistP256),
+        }
+    }
+
+    pub fn algorithm(&self) -> KeyAlgorithm {
+        match self {
+            UnsealedSigner::Ed25519(_) => KeyAlgorithm::Ed25519,
+            UnsealedSigner::Secp256k1(_) => KeyAlgorithm::Secp256k1,
+            UnsealedSigner::NistP256(_) => KeyAlgorithm::NistP256,
+        }
+    }
+
+    /// Encoded public key: 32 bytes for Ed25519, a compressed SEC1 point for
+    /// secp256k1 and an uncompressed SEC1 point for NIST P-256
+    pub fn public_key(&self) -> Result<Vec<u8>, Error> {
+        let map_err = |e| Error::new(format!("get public key failed with error: {:?}", e));
+        match self {
+            UnsealedSigner::Ed25519(signer) => signer
+                .public_key()
+                .map(|pk| pk.as_bytes().to_vec())
+                .map_err(map_err),
+            UnsealedSigner::Secp256k1(signer) => signer
+                .public_key()
+                .map(|pk| pk.as_bytes().to_vec())
+                .map_err(map_err),
+            UnsealedSigner::NistP256(signer) => signer
+                .public_key()
+                .map(|pk| pk.as_bytes().to_vec())
+                .map_err(map_err),
+        }
+    }
+
+    /// Sign a message: a 64-byte Ed25519 signature, or a fixed-size (`r || s`)
+    /// ECDSA signature of the message's SHA-256 digest
+    pub fn try_sign(&self, msg: &[u8]) -> Result<Vec<u8>, Error> {
+        let sig = match self {
+            UnsealedSigner::Ed25519(signer) => Signer::<ed25519::Signature>::try_sign(signer, msg)
+                .map(|sig| sig.to_bytes().to_vec()),
+            UnsealedSigner::Secp256k1(signer) => {
+                Signer::<secp256k1::FixedSignature>::try_sign(signer, msg)
+                    .map(|sig| sig.as_ref().to_vec())
+            }
+            UnsealedSigner::NistP256(signer) => {
+                Signer::<nistp256::FixedSignature>::try_sign(signer, msg)
+                    .map(|sig| sig.as_ref().to_vec())
+            }
+        };
+        sig.map_err(|e| Error::new(format!("sign data with error: {}", e)))
+    }
+}
+
+/// Verify a signature produced by `UnsealedSigner::try_sign`
+#[cfg(any(feature = "sgx", feature = "sim"))]
+pub fn verify(
+    algorithm: KeyAlgorithm,
+    public_key: &[u8],
+    msg: &[u8],
+    sig_raw: &[u8],
+) -> Result<(), Error> {
+    let invalid_public_key = || Error::new("invalid public key");
+    let invalid_signature = |_| Error::new("invalid signature");
+    let result = match algorithm {
+        KeyAlgorithm::Ed25519 => {
+            let public_key =
+                ed25519::PublicKey::from_bytes(public_key).ok_or_else(invalid_public_key)?;
+            let sig = ed25519::Signature::from_bytes(sig_raw).map_err(invalid_signature)?;
+            Ed25519Verifier::from(&public_key).verify(msg, &sig)
+        }
+        KeyAlgorithm::Secp256k1 => {
+            let public_key =
+                Secp256k1PublicKey::from_bytes(public_key).ok_or_else(invalid_public_key)?;
+            let sig = secp256k1::FixedSignature::from_bytes(sig_raw).map_err(invalid_signature)?;
+            Secp256k1Verifier::from(&public_key).verify(msg, &sig)
+        }
+        KeyAlgorithm::NistP256 => {
+            let public_key =
+                p256::PublicKey::from_bytes(public_key).ok_or_else(invalid_public_key)?;
+            let sig = nistp256::FixedSignature::from_bytes(sig_raw).map_err(invalid_signature)?;
+            p256::Verifier::from(&public_key).verify(msg, &sig)
+        }
+    };
+    result.map_err(|e| Error::new(format!("varify failed with error: {:?}", e)))
+}
+
+#[cfg(any(feature = "sgx", feature = "sim"))]
+fn aad(label: Label, algorithm: KeyAlgorithm) -> Vec<u8> {
+    let mut aad = label.to_vec();
+    if algorithm != KeyAlgorithm::Ed25519 {
+        aad.push(algorithm as u8);
+    }
+    aad
+}
+
+/// Is `scalar` a valid secp256k1 secret key, i.e. in `[1, n)`?
+#[cfg(any(feature = "sgx", feature = "sim"))]
+fn is_valid_secp256k1_scalar(scalar: &[u8]) -> bool {
+    scalar.len() == SECP256K1_ORDER.len()
+        && scalar.iter().any(|&b| b != 0)
+        && scalar < &SECP256K1_ORDER[..]
+}
+
+#[cfg(any(feature = "sgx", feature = "sim"))]
+pub(crate) fn get_algo(seal_key: &[u8]) -> Aes128GcmSiv {
     let key = GenericArray::clone_from_slice(seal_key);
     let aead = Aes128GcmSiv::new(key);
     aead
 }
 
-#[cfg(all(test, feature = "sgx"))]
+#[cfg(all(test, any(feature = "sgx", feature = "sim")))]
 mod tests {
     use super::*;
 
+    const ALGORITHMS: &[KeyAlgorithm] = &[
+        KeyAlgorithm::Ed25519,
+        KeyAlgorithm::Secp256k1,
+        KeyAlgorithm::NistP256,
+    ];
+
     #[test]
     fn test_serde() {
         let sealed_signer = SealedSigner::new().unwrap();
@@ -135,14 +398,29 @@ mod tests {
         assert_eq!(sealed_signer, decoded);
     }
 
+    #[test]
+    fn test_decode_legacy() {
+        let sealed_signer = SealedSigner::new().unwrap();
+        let mut encoded = sealed_signer.encode().unwrap();
+        // legacy sealed signers have no trailing algorithm
+        encoded.truncate(encoded.len() - 4);
+        let decoded = SealedSigner::decode(&encoded).unwrap();
+        assert_eq!(sealed_signer, decoded);
+        assert_eq!(decoded.algorithm(), KeyAlgorithm::Ed25519);
+    }
+
     #[test]
     fn test_sign() {
-        let sealed = SealedSigner::new().unwrap();
-        // sign message
-        let msg = b"hello world";
-        let sig_raw = sealed.try_sign(msg).unwrap();
-        // verify sig
-        assert!(sealed.verify(msg, &sig_raw).is_ok());
+        for &algorithm in ALGORITHMS {
+            let sealed = SealedSigner::generate(algorithm).unwrap();
+            assert_eq!(sealed.algorithm(), algorithm);
+            // sign message
+            let msg = b"hello world";
+            let sig_raw = sealed.try_sign(msg).unwrap();
+            // verify sig
+            assert!(sealed.verify(msg, &sig_raw).is_ok());
+            assert!(sealed.verify(b"goodbye world", &sig_raw).is_err());
+        }
     }
 
     #[test]
@@ -151,4 +429,43 @@ mod tests {
         let pubkey = sealed.get_public_key();
         assert!(pubkey.is_ok());
     }
+
+    #[test]
+    fn test_algorithm_is_authenticated() {
+        let mut sealed = SealedSigner::generate(KeyAlgorithm::Secp256k1).unwrap();
+        sealed.algorithm = KeyAlgorithm::NistP256;
+        assert!(sealed.get_signer().is_err());
+    }
+
+    #[test]
+    fn test_reseal() {
+        for &algorithm in ALGORITHMS {
+            let sealed = SealedSigner::generate(algorithm).unwrap();
+            let resealed = sealed.reseal().unwrap();
+            assert_ne!(resealed.sealed_seed, sealed.sealed_seed);
+            assert_eq!(resealed.algorithm(), algorithm);
+            assert_eq!(
+                resealed.get_public_key().unwrap(),
+                sealed.get_public_key().unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_import_secp256k1() {
+        assert!(SealedSigner::import_key(KeyAlgorithm::Secp256k1, &[0; 32]).is_err());
+        assert!(SealedSigner::import_key(KeyAlgorithm::Secp256k1, &SECP256K1_ORDER).is_err());
+
+        let mut secret = [0u8; 32];
+        secret[31] = 1;
+        let sealed = SealedSigner::import_key(KeyAlgorithm::Secp256k1, &secret).unwrap();
+        // the generator point, compressed
+        let mut expected_pubkey = vec![0x02];
+        expected_pubkey.extend_from_slice(&[
+            0x79, 0xbe, 0x66, 0x7e, 0xf9, 0xdc, 0xbb, 0xac, 0x55, 0xa0, 0x62, 0x95, 0xce, 0x87,
+            0x0b, 0x07, 0x02, 0x9b, 0xfc, 0xdb, 0x2d, 0xce, 0x28, 0xd9, 0x59, 0xf2, 0x81, 0x5b,
+            0x16, 0xf8, 0x17, 0x98,
+        ]);
+        assert_eq!(sealed.get_public_key().unwrap(), expected_pubkey);
+    }
 }
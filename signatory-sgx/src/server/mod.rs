@@ -1,39 +1,130 @@
 pub mod runner;
+#[cfg(feature = "sim")]
+pub mod simulator;
+pub mod stream;
 use crate::error::Error;
+use crate::framing::{read_frame, write_frame, Frame};
+use crate::protocol::{Encode, Response};
 use crossbeam_channel::{unbounded, Receiver, Sender};
 use runner::run_sgx;
 use std::path::PathBuf;
 use std::thread;
+use stream::ChannelStream;
 
 // C2S: short for client2server
 pub type C2S = (Sender<Vec<u8>>, Vec<u8>);
 
 pub fn run_server(client2server_rx: Receiver<C2S>, sgx_app_file: PathBuf) -> Result<(), Error> {
+    log::info!("run sgx enclave");
+    run_with_enclave(client2server_rx, move |server2sgx_rx, sgx2server_tx| {
+        run_sgx(&sgx_app_file, server2sgx_rx, sgx2server_tx)
+    })
+}
+
+/// Same as `run_server`, but runs the enclave code in-process on top of the
+/// simulated SGX platform (see `crate::sim`) instead of loading an enclave
+#[cfg(feature = "sim")]
+pub fn run_sim_server(client2server_rx: Receiver<C2S>) -> Result<(), Error> {
+    log::info!("run simulated sgx enclave");
+    run_with_enclave(client2server_rx, simulator::run_sim)
+}
+
+/// Same as `run_sim_server`, with the simulated enclave enforcing the given
+/// signing policy
+#[cfg(feature = "sim")]
+pub fn run_sim_server_with_policy(
+    client2server_rx: Receiver<C2S>,
+    signing_policy: crate::policy::SigningPolicy,
+) -> Result<(), Error> {
+    log::info!(
+        "run simulated sgx enclave with {:?} signing policy",
+        signing_policy
+    );
+    run_with_enclave(client2server_rx, move |server2sgx_rx, sgx2server_tx| {
+        simulator::run_sim_with_policy(server2sgx_rx, sgx2server_tx, signing_policy)
+    })
+}
+
+/// Same as `run_sim_server`, with the simulated enclave running on the given
+/// platform (see `sim::with_platform`) rather than the global one
+#[cfg(feature = "sim")]
+pub fn run_sim_server_on_platform(
+    client2server_rx: Receiver<C2S>,
+    platform: crate::sim::Platform,
+) -> Result<(), Error> {
+    log::info!("run simulated sgx enclave with isvsvn {}", platform.isvsvn);
+    run_with_enclave(client2server_rx, move |server2sgx_rx, sgx2server_tx| {
+        crate::sim::with_platform(platform, || {
+            simulator::run_sim(server2sgx_rx, sgx2server_tx)
+        })
+    })
+}
+
+/// Same as `run_sim_server`, with the given configuration of the simulated
+/// enclave
+#[cfg(feature = "sim")]
+pub fn run_sim_server_with_config(
+    client2server_rx: Receiver<C2S>,
+    config: crate::backend::EnclaveConfig,
+) -> Result<(), Error> {
+    log::info!("run simulated sgx enclave with {:?}", config);
+    run_with_enclave(client2server_rx, move |server2sgx_rx, sgx2server_tx| {
+        simulator::run_sim_with_config(server2sgx_rx, sgx2server_tx, config)
+    })
+}
+
+fn run_with_enclave<F>(client2server_rx: Receiver<C2S>, enclave: F) -> Result<(), Error>
+where
+    F: FnOnce(Receiver<Vec<u8>>, Sender<Vec<u8>>) -> Result<(), Error> + Send + 'static,
+{
     let (server2sgx_tx, server2sgx_rx) = unbounded();
     let (sgx2server_tx, sgx2server_rx) = unbounded();
-    log::info!("run sgx enclave");
-    let t = thread::spawn(move ||
-        if let Err(e) = run_sgx(&sgx_app_file, server2sgx_rx, sgx2server_tx) {
+    let t = thread::spawn(move || {
+        if let Err(e) = enclave(server2sgx_rx, sgx2server_tx) {
             log::error!("run sgx error: {:?}", e.what);
-        });
+        }
+    });
+    let mut enclave_stream = ChannelStream::new(sgx2server_rx, server2sgx_tx);
     for (tx, data) in client2server_rx {
-        // have to send length info and then the data
-        server2sgx_tx.send(data[0..8].to_vec())?;
-        server2sgx_tx.send(data[8..].to_vec())?;
-
-        // get response from sgx and send to client
-        let data = sgx2server_rx.recv()?;
+        let response = forward_request(&mut enclave_stream, &data).or_else(error_response)?;
         // send to client which is `SgxSigner`
-        tx.send(data)?;
+        tx.send(response)?;
     }
     // drop server2sgx_tx so that we can stop the sgx thread at `rx.recv()`
-    drop(server2sgx_tx);
+    drop(enclave_stream);
     let _ = t
         .join()
         .map_err(|e| format!("join sgx thread error: {:?}", e))?;
     Ok(())
 }
 
+/// Send a request frame to the enclave and wait for the response frame
+/// carrying the same request id
+fn forward_request(enclave_stream: &mut ChannelStream, data: &[u8]) -> Result<Vec<u8>, Error> {
+    // don't let a malformed frame reach the enclave
+    let request = Frame::from_bytes(data)?;
+    write_frame(enclave_stream, &request)?;
+
+    loop {
+        let response =
+            read_frame(enclave_stream)?.ok_or_else(|| Error::new("the enclave service stopped"))?;
+        if response.request_id == request.request_id || response.request_id == 0 {
+            return response.to_bytes();
+        }
+        log::warn!(
+            "discard stale response {} while waiting for {}",
+            response.request_id,
+            request.request_id
+        );
+    }
+}
+
+/// Frame an error to be returned to the client in place of a response
+fn error_response(e: Error) -> Result<Vec<u8>, Error> {
+    log::error!("forward request error: {:?}", e.what);
+    Frame::new(0, Response::Error(e.what).encode()?).to_bytes()
+}
+
 // when pass the tx into this function, please do **not** use `tx.clone`
 #[inline]
 pub fn stop_server(client2server_tx: Sender<C2S>) {
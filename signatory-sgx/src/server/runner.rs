@@ -11,13 +11,17 @@ use enclave_runner::EnclaveBuilder;
 use log;
 use sgxs_loaders::isgx::Device as IsgxDevice;
 use std::cell::RefCell;
-use std::io::Result as IoResult;
+use std::io::{self, Result as IoResult};
 use std::path::Path;
+use std::sync::Mutex;
 
 /// User call extension allow the enclave code to "connect" to an external service via a customized enclave runner.
 /// Here we customize the runner to intercept calls to connect to an address "sgx" which actually connects the enclave application to
 
-struct SgxServer;
+struct SgxServer {
+    // data received from the server but not yet read by the enclave
+    pending: Mutex<Vec<u8>>,
+}
 
 thread_local! {
     pub static SERVER2SGX_RX: RefCell<Option<Receiver<Vec<u8>>>> = RefCell::new(None);
@@ -27,17 +31,22 @@ thread_local! {
 impl SyncStream for SgxServer {
     fn read(&self, buf: &mut [u8]) -> IoResult<usize> {
         log::debug!("read to buffer");
-        SERVER2SGX_RX.with(|rx| {
-            let receiver = rx.borrow();
-            match receiver.as_ref().unwrap().recv() {
-                Ok(data) => {
-                    buf.copy_from_slice(&data);
-                    Ok(data.len())
-                }
+        let mut pending = self.pending.lock().unwrap();
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        while pending.is_empty() {
+            let received = SERVER2SGX_RX.with(|rx| rx.borrow().as_ref().unwrap().recv());
+            match received {
+                Ok(data) => *pending = data,
                 // return Ok(0) to tell sgx that the stream is finished
-                Err(_e) => Ok(0),
+                Err(_e) => return Ok(0),
             }
-        })
+        }
+        let len = buf.len().min(pending.len());
+        buf[..len].copy_from_slice(&pending[..len]);
+        pending.drain(..len);
+        Ok(len)
     }
 
     fn write(&self, buf: &[u8]) -> IoResult<usize> {
@@ -48,7 +57,7 @@ impl SyncStream for SgxServer {
                 .as_ref()
                 .unwrap()
                 .send(buf[..].to_vec())
-                .expect("send error");
+                .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "server stopped"))?;
             Ok(buf.len())
         })
     }
@@ -72,7 +81,9 @@ impl UsercallExtension for ExternalService {
         // an IP address which is the default behavior.
         match &*addr {
             "sgx" => {
-                let stream = SgxServer;
+                let stream = SgxServer {
+                    pending: Mutex::new(vec![]),
+                };
                 Ok(Some(Box::new(stream)))
             }
             _ => Ok(None),
@@ -0,0 +1,318 @@
+use super::stream::ChannelStream;
+use crate::backend::{self, EnclaveConfig};
+use crate::error::Error;
+use crate::policy::SigningPolicy;
+use crossbeam_channel::{Receiver, Sender};
+
+/// Run the enclave request handler in the current thread, talking to the
+/// server over the given channels (the counterpart of `runner::run_sgx`)
+pub fn run_sim(
+    server2sgx_rx: Receiver<Vec<u8>>,
+    sgx2server_tx: Sender<Vec<u8>>,
+) -> Result<(), Error> {
+    run_sim_with_policy(server2sgx_rx, sgx2server_tx, SigningPolicy::Unrestricted)
+}
+
+/// Same as `run_sim`, enforcing the given signing policy
+pub fn run_sim_with_policy(
+    server2sgx_rx: Receiver<Vec<u8>>,
+    sgx2server_tx: Sender<Vec<u8>>,
+    signing_policy: SigningPolicy,
+) -> Result<(), Error> {
+    let config = EnclaveConfig {
+        signing_policy,
+        ..EnclaveConfig::default()
+    };
+    run_sim_with_config(server2sgx_rx, sgx2server_tx, config)
+}
+
+/// Same as `run_sim`, with the given enclave configuration
+pub fn run_sim_with_config(
+    server2sgx_rx: Receiver<Vec<u8>>,
+    sgx2server_tx: Sender<Vec<u8>>,
+    config: EnclaveConfig,
+) -> Result<(), Error> {
+    log::info!("run enclave code in simulation mode");
+    backend::run_with_config(
+        || {
+            Ok(ChannelStream::new(
+                server2sgx_rx.clone(),
+                sgx2server_tx.clone(),
+            ))
+        },
+        config,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::framing::Frame;
+    use crate::protocol::{Decode, Response};
+    use crate::provider::SgxSigner;
+    use crate::server::{run_sim_server, stop_server, C2S};
+    use crossbeam_channel::unbounded;
+    use signatory::ed25519;
+    use signatory::public_key::PublicKeyed;
+    use signatory::signature::{Signer, Verifier};
+    use signatory_dalek::{Ed25519Signer, Ed25519Verifier};
+    use std::path::PathBuf;
+    use std::thread;
+
+    fn temp_key_path(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("signatory-sgx-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_keygen_and_sign() {
+        let (client2server_tx, client2server_rx) = unbounded::<C2S>();
+        let server = thread::spawn(move || run_sim_server(client2server_rx).unwrap());
+
+        let key_path = temp_key_path("keygen");
+        let signer = SgxSigner::new(client2server_tx.clone(), &key_path);
+        signer.ping().unwrap();
+
+        let keypair = signer.keygen().unwrap();
+        signer.store_key(&keypair).unwrap();
+        let public_key: ed25519::PublicKey = signer.public_key().unwrap();
+        assert_eq!(public_key.as_bytes(), &keypair.pubkey[..]);
+
+        let msg = b"hello world";
+        let signature: ed25519::Signature = signer.sign(msg);
+        assert!(Ed25519Verifier::from(&public_key)
+            .verify(msg, &signature)
+            .is_ok());
+
+        drop(signer);
+        stop_server(client2server_tx);
+        server.join().unwrap();
+        std::fs::remove_file(&key_path).unwrap();
+    }
+
+    #[test]
+    fn test_import() {
+        let (client2server_tx, client2server_rx) = unbounded::<C2S>();
+        let server = thread::spawn(move || run_sim_server(client2server_rx).unwrap());
+
+        let seed = ed25519::Seed::generate();
+        let expected_public_key = Ed25519Signer::from(&seed).public_key().unwrap();
+        let mut keypair = seed.as_secret_slice().to_vec();
+        keypair.extend_from_slice(expected_public_key.as_bytes());
+
+        let key_path = temp_key_path("import");
+        let signer = SgxSigner::new(client2server_tx.clone(), &key_path);
+        let imported = signer
+            .import(
+                crate::protocol::KeyType::Base64,
+                String::from_utf8(subtle_encoding::base64::encode(&keypair)).unwrap(),
+            )
+            .unwrap();
+        assert_eq!(imported.pubkey, expected_public_key.as_bytes().to_vec());
+
+        signer.store_key(&imported).unwrap();
+        let public_key: ed25519::PublicKey = signer.public_key().unwrap();
+        assert_eq!(public_key, expected_public_key);
+
+        drop(signer);
+        stop_server(client2server_tx);
+        server.join().unwrap();
+        std::fs::remove_file(&key_path).unwrap();
+    }
+
+    #[test]
+    fn test_malformed_frame_is_rejected() {
+        let (client2server_tx, client2server_rx) = unbounded::<C2S>();
+        let server = thread::spawn(move || run_sim_server(client2server_rx).unwrap());
+
+        let (tx, rx) = unbounded();
+        client2server_tx.send((tx, b"garbage".to_vec())).unwrap();
+        let response = Frame::from_bytes(&rx.recv().unwrap()).unwrap();
+        assert_eq!(response.request_id, 0);
+        match Response::decode(&response.payload).unwrap() {
+            Response::Error(_) => (),
+            other => panic!("unexpected response: {:?}", other),
+        }
+
+        // the enclave stays in sync for the following requests
+        let signer = SgxSigner::new(client2server_tx.clone(), temp_key_path("malformed"));
+        signer.ping().unwrap();
+
+        drop(signer);
+        stop_server(client2server_tx);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_ecdsa_keygen_and_sign() {
+        use crate::protocol::KeyAlgorithm;
+        use crate::provider::{NistP256PublicKey, Secp256k1PublicKey};
+        use signatory::ecdsa::curve::{nistp256, secp256k1};
+        use signatory_ring::ecdsa::p256;
+        use signatory_secp256k1::EcdsaVerifier;
+
+        let (client2server_tx, client2server_rx) = unbounded::<C2S>();
+        let server = thread::spawn(move || run_sim_server(client2server_rx).unwrap());
+        let msg = b"hello world";
+
+        let key_path = temp_key_path("secp256k1");
+        let signer = SgxSigner::new(client2server_tx.clone(), &key_path)
+            .with_algorithm(KeyAlgorithm::Secp256k1);
+        signer.store_key(&signer.keygen().unwrap()).unwrap();
+        let public_key: Secp256k1PublicKey = signer.public_key().unwrap();
+        let signature: secp256k1::FixedSignature = signer.sign(msg);
+        assert!(EcdsaVerifier::from(&public_key)
+            .verify(msg, &signature)
+            .is_ok());
+        // the key is not an Ed25519 key
+        assert!(Signer::<ed25519::Signature>::try_sign(&signer, msg).is_err());
+        drop(signer);
+        std::fs::remove_file(&key_path).unwrap();
+
+        let key_path = temp_key_path("p256");
+        let signer = SgxSigner::new(client2server_tx.clone(), &key_path)
+            .with_algorithm(KeyAlgorithm::NistP256);
+        signer.store_key(&signer.keygen().unwrap()).unwrap();
+        let public_key: NistP256PublicKey = signer.public_key().unwrap();
+        let signature: nistp256::FixedSignature = signer.sign(msg);
+        assert!(p256::Verifier::from(&public_key)
+            .verify(msg, &signature)
+            .is_ok());
+        drop(signer);
+        std::fs::remove_file(&key_path).unwrap();
+
+        stop_server(client2server_tx);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_tendermint_policy() {
+        use crate::policy::SigningPolicy;
+        use crate::server::run_sim_server_with_policy;
+        use signatory::signature::Signature;
+
+        fn start() -> (crossbeam_channel::Sender<C2S>, thread::JoinHandle<()>) {
+            let (client2server_tx, client2server_rx) = unbounded::<C2S>();
+            let server = thread::spawn(move || {
+                run_sim_server_with_policy(client2server_rx, SigningPolicy::Tendermint).unwrap()
+            });
+            (client2server_tx, server)
+        }
+
+        // a prevote at height 1, round 0 on chain "test"
+        let prevote = |block: u8| {
+            let mut msg = vec![0x08, 0x01, 0x11, 0x01, 0, 0, 0, 0, 0, 0, 0];
+            msg.extend_from_slice(&[0x22, 0x04, 0x0a, 0x02, block, block]);
+            msg.extend_from_slice(&[0x32, 0x04]);
+            msg.extend_from_slice(b"test");
+            let mut sign_bytes = vec![msg.len() as u8];
+            sign_bytes.extend_from_slice(&msg);
+            sign_bytes
+        };
+
+        let key_path = temp_key_path("policy-key");
+        let state_path = temp_key_path("policy-state");
+        let (client2server_tx, server) = start();
+        let signer = SgxSigner::new(client2server_tx.clone(), &key_path);
+        signer.store_key(&signer.keygen().unwrap()).unwrap();
+        let public_key: ed25519::PublicKey = signer.public_key().unwrap();
+
+        // arbitrary data can't be signed
+        assert!(Signer::<ed25519::Signature>::try_sign(&signer, b"hello world").is_err());
+
+        // nothing is signed before the policy is initialised
+        assert!(signer.sign_consensus(&state_path, &prevote(1)).is_err());
+        signer.init_consensus(&state_path, "test", 0, 0).unwrap();
+
+        let signature = signer.sign_consensus(&state_path, &prevote(1)).unwrap();
+        let signature = ed25519::Signature::from_bytes(&signature).unwrap();
+        assert!(Ed25519Verifier::from(&public_key)
+            .verify(&prevote(1), &signature)
+            .is_ok());
+        let old_state = std::fs::read(&state_path).unwrap();
+        signer.sign_consensus(&state_path, &prevote(1)).unwrap();
+        assert!(signer.sign_consensus(&state_path, &prevote(2)).is_err());
+
+        drop(signer);
+        stop_server(client2server_tx);
+        server.join().unwrap();
+
+        // the restarted enclave still refuses to double sign, and detects
+        // the rollback of its sealed state
+        let (client2server_tx, server) = start();
+        let signer = SgxSigner::new(client2server_tx.clone(), &key_path);
+        let latest_state = std::fs::read(&state_path).unwrap();
+        std::fs::write(&state_path, &old_state).unwrap();
+        assert!(signer.sign_consensus(&state_path, &prevote(2)).is_err());
+        std::fs::write(&state_path, &latest_state).unwrap();
+        assert!(signer.sign_consensus(&state_path, &prevote(2)).is_err());
+        signer.sign_consensus(&state_path, &prevote(1)).unwrap();
+
+        drop(signer);
+        stop_server(client2server_tx);
+        server.join().unwrap();
+        std::fs::remove_file(&key_path).unwrap();
+        std::fs::remove_file(&state_path).unwrap();
+    }
+
+    #[test]
+    fn test_keygen_with_backup_and_recover() {
+        use crate::backend::EnclaveConfig;
+        use crate::backup::{KeyBackup, RecoverySecretKey};
+        use crate::protocol::Decode;
+        use crate::server::run_sim_server_with_config;
+
+        let recovery_secret_key = RecoverySecretKey::generate();
+        let config = EnclaveConfig {
+            recovery_key: Some(recovery_secret_key.public_key()),
+            ..EnclaveConfig::default()
+        };
+        let (client2server_tx, client2server_rx) = unbounded::<C2S>();
+        let server =
+            thread::spawn(move || run_sim_server_with_config(client2server_rx, config).unwrap());
+
+        let key_path = temp_key_path("backup-key");
+        let signer = SgxSigner::new(client2server_tx.clone(), &key_path);
+        let (keypair, backup) = signer.keygen_with_backup().unwrap();
+        signer.store_key(&keypair).unwrap();
+
+        // the backup is imported again, e.g. into the enclave of another CPU
+        let backup = KeyBackup::decode(&backup).unwrap();
+        assert_eq!(backup.public_key, keypair.pubkey);
+        assert!(signer
+            .client()
+            .recover(&backup, &RecoverySecretKey::generate())
+            .is_err());
+        let recovered_path = temp_key_path("backup-recovered");
+        let recovered = SgxSigner::new(client2server_tx.clone(), &recovered_path);
+        let recovered_keypair = recovered
+            .client()
+            .recover(&backup, &recovery_secret_key)
+            .unwrap();
+        recovered.store_key(&recovered_keypair).unwrap();
+
+        let msg = b"hello world";
+        let public_key: ed25519::PublicKey = signer.public_key().unwrap();
+        let signature: ed25519::Signature = recovered.sign(msg);
+        assert!(Ed25519Verifier::from(&public_key)
+            .verify(msg, &signature)
+            .is_ok());
+
+        drop(signer);
+        drop(recovered);
+        stop_server(client2server_tx);
+        server.join().unwrap();
+        std::fs::remove_file(&key_path).unwrap();
+        std::fs::remove_file(&recovered_path).unwrap();
+
+        // without a recovery key, there are no backups
+        let (client2server_tx, client2server_rx) = unbounded::<C2S>();
+        let server = thread::spawn(move || run_sim_server(client2server_rx).unwrap());
+        let signer = SgxSigner::new(client2server_tx.clone(), temp_key_path("no-backup"));
+        assert!(signer.keygen_with_backup().is_err());
+        drop(signer);
+        stop_server(client2server_tx);
+        server.join().unwrap();
+    }
+}
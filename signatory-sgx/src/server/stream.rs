@@ -0,0 +1,84 @@
+use crossbeam_channel::{Receiver, Sender};
+use std::io::{self, Read, Write};
+
+/// Byte stream over a pair of channels carrying `Vec<u8>` messages.
+///
+/// Reads are buffered, so a message does not need to match the size of the
+/// caller's buffer. Once the sending side of `rx` is dropped, reads return
+/// `Ok(0)` (end of stream).
+pub struct ChannelStream {
+    rx: Receiver<Vec<u8>>,
+    tx: Sender<Vec<u8>>,
+    pending: Vec<u8>,
+}
+
+impl ChannelStream {
+    pub fn new(rx: Receiver<Vec<u8>>, tx: Sender<Vec<u8>>) -> Self {
+        Self {
+            rx,
+            tx,
+            pending: vec![],
+        }
+    }
+}
+
+impl Read for ChannelStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        while self.pending.is_empty() {
+            match self.rx.recv() {
+                Ok(data) => self.pending = data,
+                Err(_) => return Ok(0),
+            }
+        }
+
+        let len = buf.len().min(self.pending.len());
+        buf[..len].copy_from_slice(&self.pending[..len]);
+        self.pending.drain(..len);
+        Ok(len)
+    }
+}
+
+impl Write for ChannelStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.tx
+            .send(buf.to_vec())
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "channel closed"))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossbeam_channel::unbounded;
+
+    #[test]
+    fn test_buffered_read() {
+        let (in_tx, in_rx) = unbounded();
+        let (out_tx, out_rx) = unbounded();
+        let mut stream = ChannelStream::new(in_rx, out_tx);
+
+        in_tx.send(b"hello".to_vec()).unwrap();
+        in_tx.send(b" world".to_vec()).unwrap();
+        drop(in_tx);
+
+        let mut buf = [0u8; 3];
+        assert_eq!(stream.read(&mut buf).unwrap(), 3);
+        assert_eq!(&buf, b"hel");
+
+        let mut rest = vec![];
+        stream.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, b"lo world");
+
+        stream.write_all(b"reply").unwrap();
+        assert_eq!(out_rx.recv().unwrap(), b"reply");
+    }
+}
@@ -0,0 +1,271 @@
+//! Software simulation of the SGX platform.
+//!
+//! With the `sim` feature the enclave side of this crate (sealing, the
+//! request handler in `backend`) runs as ordinary code on the host instead
+//! of inside an enclave. `Report::for_self` and `EGETKEY` are replaced with
+//! a deterministic stand-in: keys are derived by hashing a fixed platform
+//! secret together with the key request, and requests for an SVN newer
+//! than the simulated platform's are refused, as they are by the CPU.
+//!
+//! This provides **no security whatsoever** and is only meant for
+//! development and testing without SGX hardware.
+
+use crate::seal_data::{CpuSvn, EgetKey, IsvSvn};
+use lazy_static::lazy_static;
+use sgx_isa::{ErrorCode, Keypolicy, Keyrequest, Report, Targetinfo};
+use sha2::{Digest, Sha256};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::{Mutex, RwLock};
+
+/// Domain separation string for simulated key derivation
+const EGETKEY_DOMAIN: &[u8] = b"signatory-sgx simulated EGETKEY";
+
+/// Domain separation string for simulated report keys
+const REPORT_KEY_DOMAIN: &[u8] = b"signatory-sgx simulated report key";
+
+/// Size of the report body covered by the report MAC
+const REPORT_BODY_SIZE: usize = 384;
+
+/// MRENCLAVE of the simulated quoting enclave
+pub const QE_MRENCLAVE: [u8; 32] = [0x33; 32];
+
+/// Default platform secret of the simulated CPU
+const DEFAULT_PLATFORM_SECRET: [u8; 32] = [0x5a; 32];
+
+/// Identity and security version of the simulated platform and enclave
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Platform {
+    /// Secret fused into the simulated CPU, the root of all derived keys
+    pub secret: [u8; 32],
+
+    /// Security version of the simulated CPU
+    pub cpusvn: CpuSvn,
+
+    /// Measurement of the simulated enclave
+    pub mrenclave: [u8; 32],
+
+    /// Hash of the simulated enclave signer's public key
+    pub mrsigner: [u8; 32],
+
+    /// Product ID of the simulated enclave
+    pub isvprodid: u16,
+
+    /// Security version of the simulated enclave
+    pub isvsvn: IsvSvn,
+}
+
+impl Default for Platform {
+    fn default() -> Self {
+        Self {
+            secret: DEFAULT_PLATFORM_SECRET,
+            cpusvn: [0; 16],
+            mrenclave: [0x11; 32],
+            mrsigner: [0x22; 32],
+            isvprodid: 0,
+            isvsvn: 0,
+        }
+    }
+}
+
+lazy_static! {
+    static ref PLATFORM: RwLock<Platform> = RwLock::new(Platform::default());
+    static ref COUNTERS: Mutex<HashMap<String, u64>> = Mutex::new(HashMap::new());
+}
+
+thread_local! {
+    /// Platform simulated by the current thread in place of the global one
+    /// (see `with_platform`)
+    static THREAD_PLATFORM: RefCell<Option<Platform>> = RefCell::new(None);
+}
+
+/// Get the configuration of the simulated platform
+pub fn platform() -> Platform {
+    THREAD_PLATFORM
+        .with(|platform| platform.borrow().clone())
+        .unwrap_or_else(|| PLATFORM.read().unwrap().clone())
+}
+
+/// Run `f` with the current thread simulating the given platform instead of
+/// the global one, e.g. to run an upgraded enclave next to the current one
+/// without changing the platform of other enclaves
+pub fn with_platform<R, F: FnOnce() -> R>(platform: Platform, f: F) -> R {
+    /// Restores the thread's previous platform, even if `f` panics
+    struct Restore(Option<Platform>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let previous = self.0.take();
+            THREAD_PLATFORM.with(|platform| *platform.borrow_mut() = previous);
+        }
+    }
+
+    let _restore = Restore(THREAD_PLATFORM.with(|previous| previous.replace(Some(platform))));
+    f()
+}
+
+/// Replace the configuration of the simulated platform of every thread not
+/// running `with_platform`, e.g. to simulate an enclave upgrade by bumping
+/// `isvsvn`. Tests use `with_platform` instead, so as not to change the
+/// platform of concurrent tests.
+pub fn set_platform(platform: Platform) {
+    *PLATFORM.write().unwrap() = platform;
+}
+
+/// Simulated `Report::for_self`
+pub fn report_for_self() -> Report {
+    let platform = platform();
+
+    Report {
+        cpusvn: platform.cpusvn,
+        mrenclave: platform.mrenclave,
+        mrsigner: platform.mrsigner,
+        isvprodid: platform.isvprodid,
+        isvsvn: platform.isvsvn,
+        ..Default::default()
+    }
+}
+
+/// Simulated `Report::for_target`: a report of the simulated enclave for the
+/// enclave described by `targetinfo`, MACed with that enclave's simulated
+/// report key
+pub fn report_for_target(targetinfo: &Targetinfo, reportdata: &[u8; 64]) -> Report {
+    let mut report = report_for_self();
+    report.reportdata = *reportdata;
+    report.mac = report_mac(&report, &targetinfo.measurement);
+    report
+}
+
+/// Check the MAC of a report targeted at the enclave with measurement
+/// `mrenclave`, as the simulated target enclave would with its report key
+pub fn verify_report(report: &Report, mrenclave: &[u8; 32]) -> bool {
+    let expected = report_mac(report, mrenclave);
+    // not constant time, but neither is anything else in simulation mode
+    expected == report.mac
+}
+
+fn report_mac(report: &Report, target_mrenclave: &[u8; 32]) -> [u8; 16] {
+    let platform = platform();
+    let mut hasher = Sha256::new();
+    hasher.input(REPORT_KEY_DOMAIN);
+    hasher.input(&platform.secret);
+    hasher.input(target_mrenclave);
+    hasher.input(&report.as_ref()[..REPORT_BODY_SIZE]);
+    let mut mac = [0u8; 16];
+    mac.copy_from_slice(&hasher.result()[..16]);
+    mac
+}
+
+/// Read a simulated platform monotonic counter; counters that were never
+/// incremented are 0. Like platform counters, they outlive the simulated
+/// enclave, i.e. survive server restarts within the process.
+pub fn read_counter(name: &str) -> u64 {
+    COUNTERS.lock().unwrap().get(name).cloned().unwrap_or(0)
+}
+
+/// Increment a simulated platform monotonic counter, returning its new value
+pub fn increment_counter(name: &str) -> u64 {
+    let mut counters = COUNTERS.lock().unwrap();
+    let counter = counters.entry(name.to_owned()).or_insert(0);
+    *counter += 1;
+    *counter
+}
+
+/// Simulated `EGETKEY` instruction
+pub fn egetkey(request: &Keyrequest) -> Result<EgetKey, ErrorCode> {
+    let platform = platform();
+
+    if request.isvsvn > platform.isvsvn {
+        return Err(ErrorCode::InvalidIsvsvn);
+    }
+
+    if request
+        .cpusvn
+        .iter()
+        .zip(platform.cpusvn.iter())
+        .any(|(requested, current)| requested > current)
+    {
+        return Err(ErrorCode::InvalidCpusvn);
+    }
+
+    let report = report_for_self();
+    let mut hasher = Sha256::new();
+    hasher.input(EGETKEY_DOMAIN);
+    hasher.input(&platform.secret);
+    hasher.input(&request.keyname.to_le_bytes());
+    hasher.input(&request.keypolicy.bits().to_le_bytes());
+
+    if request.keypolicy.contains(Keypolicy::MRENCLAVE) {
+        hasher.input(&report.mrenclave);
+    }
+
+    if request.keypolicy.contains(Keypolicy::MRSIGNER) {
+        hasher.input(&report.mrsigner);
+    }
+
+    hasher.input(&report.isvprodid.to_le_bytes());
+    hasher.input(&request.isvsvn.to_le_bytes());
+    hasher.input(&request.cpusvn);
+
+    let attributes = report.attributes.as_ref();
+    for (i, byte) in attributes.iter().enumerate() {
+        let mask = request.attributemask[i / 8].to_le_bytes()[i % 8];
+        hasher.input(&[byte & mask]);
+    }
+
+    hasher.input(&(report.miscselect.bits() & request.miscmask).to_le_bytes());
+    hasher.input(&request.keyid);
+
+    let mut key = EgetKey::default();
+    key.copy_from_slice(&hasher.result()[..key.len()]);
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sgx_isa::Keyname;
+
+    fn seal_key_request(isvsvn: IsvSvn, keyid: [u8; 32]) -> Keyrequest {
+        Keyrequest {
+            keyname: Keyname::Seal as _,
+            keypolicy: Keypolicy::MRSIGNER,
+            isvsvn,
+            cpusvn: platform().cpusvn,
+            attributemask: [!0; 2],
+            keyid,
+            miscmask: !0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_egetkey_deterministic() {
+        let request = seal_key_request(platform().isvsvn, [1; 32]);
+        assert_eq!(egetkey(&request).unwrap(), egetkey(&request).unwrap());
+
+        let other_request = seal_key_request(platform().isvsvn, [2; 32]);
+        assert_ne!(egetkey(&request).unwrap(), egetkey(&other_request).unwrap());
+    }
+
+    #[test]
+    fn test_report_mac() {
+        let targetinfo = Targetinfo {
+            measurement: QE_MRENCLAVE,
+            ..Default::default()
+        };
+        let mut report = report_for_target(&targetinfo, &[7; 64]);
+        assert_eq!(&report.reportdata[..], &[7; 64][..]);
+        assert!(verify_report(&report, &QE_MRENCLAVE));
+        assert!(!verify_report(&report, &[0; 32]));
+
+        report.isvsvn += 1;
+        assert!(!verify_report(&report, &QE_MRENCLAVE));
+    }
+
+    #[test]
+    fn test_egetkey_rejects_future_isvsvn() {
+        let request = seal_key_request(platform().isvsvn + 1, [1; 32]);
+        assert_eq!(egetkey(&request), Err(ErrorCode::InvalidIsvsvn));
+    }
+}
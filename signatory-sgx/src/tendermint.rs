@@ -0,0 +1,344 @@
+//! Tendermint consensus messages, as far as the signing policy needs them.
+//!
+//! Validators sign the length-prefixed amino encoding of a `CanonicalVote`
+//! or a `CanonicalProposal`:
+//!
+//! | field | `CanonicalVote`         | `CanonicalProposal`     |
+//! |-------|-------------------------|-------------------------|
+//! | 1     | type (varint)           | type (varint)           |
+//! | 2     | height (fixed64)        | height (fixed64)        |
+//! | 3     | round (fixed64)         | round (fixed64)         |
+//! | 4     | block ID (embedded)     | POL round (varint)      |
+//! | 5     | timestamp (embedded)    | block ID (embedded)     |
+//! | 6     | chain ID (string)       | timestamp (embedded)    |
+//! | 7     |                         | chain ID (string)       |
+//!
+//! Fields with their zero value are omitted. Since the enclave signs the
+//! exact bytes it parsed, the parser is strict: fields must appear at most
+//! once, in order, with the expected wire type, so that no two parties can
+//! read the same sign bytes differently.
+
+use crate::error::Error;
+use serde::{Deserialize, Serialize};
+
+const WIRE_VARINT: u64 = 0;
+const WIRE_FIXED64: u64 = 1;
+const WIRE_LENGTH_DELIMITED: u64 = 2;
+
+/// `SignedMsgType` of a prevote
+const PREVOTE_TYPE: u64 = 0x01;
+/// `SignedMsgType` of a precommit
+const PRECOMMIT_TYPE: u64 = 0x02;
+/// `SignedMsgType` of a proposal
+const PROPOSAL_TYPE: u64 = 0x20;
+
+/// Step of a signed message within a round; later steps compare greater
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum Step {
+    Propose,
+    Prevote,
+    Precommit,
+}
+
+/// A parsed vote or proposal
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConsensusMessage {
+    pub step: Step,
+    pub height: i64,
+    pub round: i64,
+    /// Encoded `CanonicalBlockID`, empty for a vote for nil
+    pub block_id: Vec<u8>,
+    pub chain_id: String,
+}
+
+impl ConsensusMessage {
+    /// Parse the sign bytes of a vote or proposal
+    pub fn parse(sign_bytes: &[u8]) -> Result<Self, Error> {
+        let mut reader = Reader(sign_bytes);
+        let len = reader.uvarint()?;
+        if len != reader.0.len() as u64 {
+            return Err(Error::new("invalid sign bytes length prefix"));
+        }
+
+        let mut fields = Fields {
+            reader,
+            last_field: 0,
+        };
+        let msg_type = fields.varint(1)?.unwrap_or(0);
+        let height = fields.fixed64(2)?.unwrap_or(0) as i64;
+        let round = fields.fixed64(3)?.unwrap_or(0) as i64;
+        let (step, block_id, chain_id) = match msg_type {
+            PREVOTE_TYPE | PRECOMMIT_TYPE => {
+                let block_id = fields.bytes(4)?.unwrap_or_default();
+                fields.bytes(5)?; // timestamp
+                let chain_id = fields.bytes(6)?.unwrap_or_default();
+                let step = if msg_type == PREVOTE_TYPE {
+                    Step::Prevote
+                } else {
+                    Step::Precommit
+                };
+                (step, block_id, chain_id)
+            }
+            PROPOSAL_TYPE => {
+                fields.varint(4)?; // POL round
+                let block_id = fields.bytes(5)?.unwrap_or_default();
+                fields.bytes(6)?; // timestamp
+                let chain_id = fields.bytes(7)?.unwrap_or_default();
+                (Step::Propose, block_id, chain_id)
+            }
+            _ => return Err(Error::new(format!("unknown message type {}", msg_type))),
+        };
+        if !fields.reader.0.is_empty() {
+            return Err(Error::new("unexpected field in sign bytes"));
+        }
+
+        let chain_id =
+            String::from_utf8(chain_id).map_err(|_| Error::new("chain ID is not UTF-8"))?;
+        Ok(Self {
+            step,
+            height,
+            round,
+            block_id,
+            chain_id,
+        })
+    }
+}
+
+/// Latest height, round and step signed for a chain
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct HighWaterMark {
+    pub height: i64,
+    pub round: i64,
+    pub step: Step,
+    pub block_id: Vec<u8>,
+}
+
+impl HighWaterMark {
+    pub fn new(message: &ConsensusMessage) -> Self {
+        Self {
+            height: message.height,
+            round: message.round,
+            step: message.step,
+            block_id: message.block_id.clone(),
+        }
+    }
+
+    /// Check that signing `message` after the message this mark was taken
+    /// from can't be a double sign: it must be for a later height, round or
+    /// step, or for the same block at the same height, round and step.
+    pub fn check(&self, message: &ConsensusMessage) -> Result<(), Error> {
+        let last = (self.height, self.round, self.step);
+        let next = (message.height, message.round, message.step);
+        if next < last {
+            return Err(Error::new(format!(
+                "refusing to sign {:?} at height {} round {}: already signed {:?} at height {} round {}",
+                message.step, message.height, message.round, self.step, self.height, self.round
+            )));
+        }
+        if next == last && message.block_id != self.block_id {
+            return Err(Error::new(format!(
+                "refusing to double sign {:?} at height {} round {} for another block",
+                message.step, message.height, message.round
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Fields of a message, read in order
+struct Fields<'a> {
+    reader: Reader<'a>,
+    last_field: u64,
+}
+
+impl<'a> Fields<'a> {
+    /// Read the key of field `field` if it is next, checking its wire type
+    fn next(&mut self, field: u64, wire_type: u64) -> Result<bool, Error> {
+        let mut peek = Reader(self.reader.0);
+        if peek.0.is_empty() {
+            return Ok(false);
+        }
+        let key = peek.uvarint()?;
+        let (number, actual_wire_type) = (key >> 3, key & 7);
+        if number <= self.last_field {
+            return Err(Error::new("duplicate or unordered field in sign bytes"));
+        }
+        if number != field {
+            return Ok(false);
+        }
+        if actual_wire_type != wire_type {
+            return Err(Error::new(format!("invalid wire type of field {}", field)));
+        }
+        self.reader = peek;
+        self.last_field = field;
+        Ok(true)
+    }
+
+    fn varint(&mut self, field: u64) -> Result<Option<u64>, Error> {
+        if !self.next(field, WIRE_VARINT)? {
+            return Ok(None);
+        }
+        self.reader.uvarint().map(Some)
+    }
+
+    fn fixed64(&mut self, field: u64) -> Result<Option<u64>, Error> {
+        if !self.next(field, WIRE_FIXED64)? {
+            return Ok(None);
+        }
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(self.reader.take(8)?);
+        Ok(Some(u64::from_le_bytes(bytes)))
+    }
+
+    fn bytes(&mut self, field: u64) -> Result<Option<Vec<u8>>, Error> {
+        if !self.next(field, WIRE_LENGTH_DELIMITED)? {
+            return Ok(None);
+        }
+        let len = self.reader.uvarint()?;
+        if len > self.reader.0.len() as u64 {
+            return Err(Error::new("truncated sign bytes"));
+        }
+        self.reader
+            .take(len as usize)
+            .map(|bytes| Some(bytes.to_vec()))
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if self.0.len() < len {
+            return Err(Error::new("truncated sign bytes"));
+        }
+        let (head, tail) = self.0.split_at(len);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn uvarint(&mut self) -> Result<u64, Error> {
+        let mut value = 0u64;
+        for i in 0..10 {
+            let byte = self.take(1)?[0];
+            value |= u64::from(byte & 0x7f) << (7 * i);
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(Error::new("varint overflow in sign bytes"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // sign bytes of votes from Tendermint's `TestVoteSignBytesTestVectors`
+    const PRECOMMIT_1_1: &[u8] = &[
+        0x21, 0x8, 0x2, 0x11, 0x1, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x19, 0x1, 0x0, 0x0, 0x0,
+        0x0, 0x0, 0x0, 0x0, 0x2a, 0xb, 0x8, 0x80, 0x92, 0xb8, 0xc3, 0x98, 0xfe, 0xff, 0xff, 0xff,
+        0x1,
+    ];
+    const VOTE_WITH_CHAIN_ID: &[u8] = &[
+        0x2e, 0x11, 0x1, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x19, 0x1, 0x0, 0x0, 0x0, 0x0, 0x0,
+        0x0, 0x0, 0x2a, 0xb, 0x8, 0x80, 0x92, 0xb8, 0xc3, 0x98, 0xfe, 0xff, 0xff, 0xff, 0x1, 0x32,
+        0xd, 0x74, 0x65, 0x73, 0x74, 0x5f, 0x63, 0x68, 0x61, 0x69, 0x6e, 0x5f, 0x69, 0x64,
+    ];
+
+    fn message(step: Step, height: i64, round: i64, block_id: &[u8]) -> ConsensusMessage {
+        ConsensusMessage {
+            step,
+            height,
+            round,
+            block_id: block_id.to_vec(),
+            chain_id: "test_chain_id".to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_parse_vote() {
+        assert_eq!(
+            ConsensusMessage::parse(PRECOMMIT_1_1).unwrap(),
+            ConsensusMessage {
+                step: Step::Precommit,
+                height: 1,
+                round: 1,
+                block_id: vec![],
+                chain_id: String::new(),
+            }
+        );
+        // votes without a type are refused
+        assert!(ConsensusMessage::parse(VOTE_WITH_CHAIN_ID).is_err());
+    }
+
+    #[test]
+    fn test_parse_proposal() {
+        let block_id = [0x0a, 0x02, 0xab, 0xcd];
+        let mut msg = vec![0x08, 0x20, 0x11, 0x05, 0, 0, 0, 0, 0, 0, 0, 0x19, 0x02];
+        msg.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0]);
+        // POL round -1
+        msg.extend_from_slice(&[
+            0x20, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01,
+        ]);
+        msg.extend_from_slice(&[0x2a, block_id.len() as u8]);
+        msg.extend_from_slice(&block_id);
+        msg.extend_from_slice(&[0x3a, 0x04]);
+        msg.extend_from_slice(b"test");
+        let mut sign_bytes = vec![msg.len() as u8];
+        sign_bytes.extend_from_slice(&msg);
+
+        let proposal = ConsensusMessage::parse(&sign_bytes).unwrap();
+        assert_eq!(proposal.step, Step::Propose);
+        assert_eq!((proposal.height, proposal.round), (5, 2));
+        assert_eq!(proposal.block_id, block_id.to_vec());
+        assert_eq!(proposal.chain_id, "test");
+    }
+
+    #[test]
+    fn test_parse_strict() {
+        // truncated, or with a wrong length prefix
+        for len in 0..PRECOMMIT_1_1.len() {
+            assert!(ConsensusMessage::parse(&PRECOMMIT_1_1[..len]).is_err());
+        }
+        let mut trailing = PRECOMMIT_1_1.to_vec();
+        trailing.push(0);
+        assert!(ConsensusMessage::parse(&trailing).is_err());
+
+        // height given twice
+        let mut duplicate = PRECOMMIT_1_1[1..12].to_vec();
+        duplicate.extend_from_slice(&PRECOMMIT_1_1[3..12]);
+        duplicate.insert(0, duplicate.len() as u8);
+        assert!(ConsensusMessage::parse(&duplicate).is_err());
+
+        // height as a varint
+        let wrong_wire_type = [0x04, 0x08, 0x02, 0x10, 0x01];
+        assert!(ConsensusMessage::parse(&wrong_wire_type).is_err());
+    }
+
+    #[test]
+    fn test_high_water_mark() {
+        let mark = HighWaterMark::new(&message(Step::Prevote, 10, 1, b"block"));
+
+        assert!(mark
+            .check(&message(Step::Precommit, 10, 1, b"block"))
+            .is_ok());
+        assert!(mark.check(&message(Step::Propose, 10, 2, b"other")).is_ok());
+        assert!(mark.check(&message(Step::Propose, 11, 0, b"other")).is_ok());
+        // signing the same block again is harmless
+        assert!(mark.check(&message(Step::Prevote, 10, 1, b"block")).is_ok());
+
+        assert!(mark
+            .check(&message(Step::Prevote, 10, 1, b"other"))
+            .is_err());
+        assert!(mark.check(&message(Step::Prevote, 10, 1, b"")).is_err());
+        assert!(mark
+            .check(&message(Step::Propose, 10, 1, b"block"))
+            .is_err());
+        assert!(mark
+            .check(&message(Step::Precommit, 10, 0, b"block"))
+            .is_err());
+        assert!(mark
+            .check(&message(Step::Precommit, 9, 5, b"block"))
+            .is_err());
+    }
+}
@@ -1,4 +1,4 @@
-//! Signatory Ed25519 provider for *sodiumoxide*
+//! Signatory Ed25519 and X25519 provider for *sodiumoxide*
 
 #![no_std]
 #![forbid(unsafe_code)]
@@ -12,9 +12,14 @@
 #[macro_use]
 extern crate signatory;
 
+mod x25519;
+
+pub use self::x25519::X25519Secret;
+
 use signatory::{
     ed25519,
     public_key::PublicKeyed,
+    sha2::Sha512,
     signature::{Error, Signature, Signer, Verifier},
 };
 use sodiumoxide::crypto::sign::ed25519::{self as sodiumoxide_ed25519, SecretKey};
@@ -54,16 +59,47 @@ impl Signer<ed25519::Signature> for Ed25519Signer {
 
 /// Ed25519 verifier for sodiumoxide
 #[derive(Clone, Debug, Eq, PartialEq)]
-pub struct Ed25519Verifier(sodiumoxide_ed25519::PublicKey);
+pub struct Ed25519Verifier(
+    sodiumoxide_ed25519::PublicKey,
+    Option<ed25519::VerificationMode>,
+);
+
+impl Ed25519Verifier {
+    /// Create a verifier which verifies signatures in the given mode
+    pub fn with_mode(public_key: &ed25519::PublicKey, mode: ed25519::VerificationMode) -> Self {
+        Ed25519Verifier(
+            sodiumoxide_ed25519::PublicKey::from_slice(public_key.as_bytes()).unwrap(),
+            Some(mode),
+        )
+    }
+
+    /// Mode of Ed25519ctx and Ed25519ph verification, which libsodium's own
+    /// rules don't cover
+    fn context_mode(&self) -> ed25519::VerificationMode {
+        self.1.unwrap_or(ed25519::VerificationMode::Rfc8032)
+    }
+}
 
 impl<'a> From<&'a ed25519::PublicKey> for Ed25519Verifier {
+    /// Create a verifier with libsodium's own rules
     fn from(public_key: &'a ed25519::PublicKey) -> Self {
-        Ed25519Verifier(sodiumoxide_ed25519::PublicKey::from_slice(public_key.as_bytes()).unwrap())
+        Ed25519Verifier(
+            sodiumoxide_ed25519::PublicKey::from_slice(public_key.as_bytes()).unwrap(),
+            None,
+        )
     }
 }
 
 impl Verifier<ed25519::Signature> for Ed25519Verifier {
     fn verify(&self, msg: &[u8], signature: &ed25519::Signature) -> Result<(), Error> {
+        if let Some(mode) = self.1 {
+            let public_key = ed25519::PublicKey::new((self.0).0);
+            mode.check_encodings(&public_key, signature)?;
+            if mode.is_cofactored() {
+                return ed25519::verify_cofactored(&public_key, msg, signature);
+            }
+        }
+
         let sig = sodiumoxide_ed25519::Signature::from_slice(signature.as_ref()).unwrap();
         if sodiumoxide_ed25519::verify_detached(&sig, msg, &self.0) {
             Ok(())
@@ -73,8 +109,53 @@ impl Verifier<ed25519::Signature> for Ed25519Verifier {
     }
 }
 
+/// Ed25519ctx and Ed25519ph, which sodiumoxide doesn't implement, are
+/// verified by Signatory itself, in the verifier's mode (RFC 8032 for
+/// verifiers created `From` a public key)
+impl ed25519::ContextVerifier for Ed25519Verifier {
+    fn verify_ctx(
+        &self,
+        context: ed25519::Context<'_>,
+        msg: &[u8],
+        signature: &ed25519::Signature,
+    ) -> Result<(), Error> {
+        let public_key = ed25519::PublicKey::new((self.0).0);
+        self.context_mode()
+            .verify_ctx(&public_key, context, msg, signature)
+    }
+
+    fn verify_ph(
+        &self,
+        context: ed25519::Context<'_>,
+        digest: Sha512,
+        signature: &ed25519::Signature,
+    ) -> Result<(), Error> {
+        let public_key = ed25519::PublicKey::new((self.0).0);
+        self.context_mode()
+            .verify_ph(&public_key, context, digest, signature)
+    }
+}
+
+/// Sequential fallback: libsodium has no batch verification
+impl ed25519::BatchVerifier for Ed25519Verifier {
+    fn verify_batch(items: &[ed25519::BatchItem<'_>]) -> Result<(), ed25519::BatchError> {
+        ed25519::verify_batch_sequentially::<Self>(items)
+    }
+
+    fn verify_batch_with_mode(
+        mode: ed25519::VerificationMode,
+        items: &[ed25519::BatchItem<'_>],
+    ) -> Result<(), ed25519::BatchError> {
+        ed25519::verify_batch_with(items, |public_key| Self::with_mode(public_key, mode))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::{Ed25519Signer, Ed25519Verifier};
     ed25519_tests!(Ed25519Signer, Ed25519Verifier);
+    ed25519_context_verifier_tests!(Ed25519Verifier);
+    ed25519_slip10_tests!(Ed25519Signer);
+    ed25519_batch_tests!(Ed25519Verifier);
+    ed25519_verification_mode_tests!(Ed25519Verifier);
 }
@@ -0,0 +1,67 @@
+//! X25519 provider for sodiumoxide
+
+use signatory::{ed25519, public_key::PublicKeyed, signature::Error, x25519};
+use sodiumoxide::crypto::{
+    scalarmult::curve25519::{self, GroupElement, Scalar},
+    sign::ed25519 as sodiumoxide_ed25519,
+};
+
+/// X25519 secret key provider for sodiumoxide
+pub struct X25519Secret(Scalar);
+
+impl<'a> From<&'a x25519::SecretKey> for X25519Secret {
+    /// Create a new X25519Secret from a secret key
+    fn from(secret_key: &'a x25519::SecretKey) -> Self {
+        X25519Secret(Scalar::from_slice(secret_key.as_secret_slice()).unwrap())
+    }
+}
+
+impl<'a> From<&'a ed25519::Seed> for X25519Secret {
+    /// Create a new X25519Secret from an Ed25519 seed, with libsodium's
+    /// `crypto_sign_ed25519_sk_to_curve25519`
+    fn from(seed: &'a ed25519::Seed) -> Self {
+        let sodiumoxide_seed =
+            sodiumoxide_ed25519::Seed::from_slice(seed.as_secret_slice()).unwrap();
+        let (_, secret_key) = sodiumoxide_ed25519::keypair_from_seed(&sodiumoxide_seed);
+        X25519Secret(sodiumoxide_ed25519::to_curve25519_sk(&secret_key).unwrap())
+    }
+}
+
+impl PublicKeyed<x25519::PublicKey> for X25519Secret {
+    fn public_key(&self) -> Result<x25519::PublicKey, Error> {
+        Ok(x25519::PublicKey::new(
+            curve25519::scalarmult_base(&self.0).0,
+        ))
+    }
+}
+
+impl x25519::DiffieHellman for X25519Secret {
+    fn diffie_hellman(
+        &self,
+        public_key: &x25519::PublicKey,
+    ) -> Result<x25519::SharedSecret, Error> {
+        let shared_point = curve25519::scalarmult(&self.0, &GroupElement(*public_key.as_bytes()))
+            .map_err(|_| Error::new())?;
+
+        Ok(x25519::SharedSecret::new(shared_point.0))
+    }
+}
+
+impl x25519::Ed25519PublicKeyConverter for X25519Secret {
+    /// Convert an Ed25519 public key with libsodium's
+    /// `crypto_sign_ed25519_pk_to_curve25519`
+    fn convert_public_key(public_key: &ed25519::PublicKey) -> Result<x25519::PublicKey, Error> {
+        let sodiumoxide_public_key =
+            sodiumoxide_ed25519::PublicKey::from_slice(public_key.as_bytes()).unwrap();
+        let point = sodiumoxide_ed25519::to_curve25519_pk(&sodiumoxide_public_key)
+            .map_err(|_| Error::new())?;
+
+        Ok(x25519::PublicKey::new(point.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::X25519Secret;
+    x25519_tests!(X25519Secret);
+}
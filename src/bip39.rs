@@ -0,0 +1,403 @@
+//! BIP39 mnemonic phrases: backups of master secrets as lists of words
+//!
+//! Described in BIP39: <https://github.com/bitcoin/bips/blob/master/bip-0039.mediawiki>
+//!
+//! A mnemonic encodes 128 to 256 bits of entropy, plus a checksum, as 12 to
+//! 24 words. Its 64-byte seed (derived with PBKDF2 and an optional
+//! passphrase) is the master secret of SLIP-0010 Ed25519 seeds (see
+//! `Mnemonic::to_ed25519_seed`) and of BIP32 secp256k1 keys (see
+//! `signatory_secp256k1::bip32`).
+//!
+//! The English wordlist is always available; the other BIP39 wordlists are
+//! enabled by the `bip39-<language>` features.
+
+use alloc::{string::String, vec::Vec};
+use core::mem;
+use hmac::Hmac;
+use sha2::{Digest, Sha256, Sha512};
+use signature::Error;
+use unicode_normalization::UnicodeNormalization;
+use zeroize::Zeroize;
+
+/// Number of PBKDF2 rounds of seed derivation
+const PBKDF2_ROUNDS: usize = 2048;
+
+/// Size of the seed of a mnemonic
+pub const SEED_SIZE: usize = 64;
+
+/// Language of the wordlist of a mnemonic
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Language {
+    /// English
+    English,
+
+    /// Simplified Chinese
+    #[cfg(feature = "bip39-chinese-simplified")]
+    ChineseSimplified,
+
+    /// Traditional Chinese
+    #[cfg(feature = "bip39-chinese-traditional")]
+    ChineseTraditional,
+
+    /// French
+    #[cfg(feature = "bip39-french")]
+    French,
+
+    /// Italian
+    #[cfg(feature = "bip39-italian")]
+    Italian,
+
+    /// Japanese
+    #[cfg(feature = "bip39-japanese")]
+    Japanese,
+
+    /// Korean
+    #[cfg(feature = "bip39-korean")]
+    Korean,
+
+    /// Spanish
+    #[cfg(feature = "bip39-spanish")]
+    Spanish,
+}
+
+impl Default for Language {
+    fn default() -> Self {
+        Language::English
+    }
+}
+
+impl Language {
+    /// Word with the given index (0 to 2047) in the wordlist
+    pub fn word(self, index: u16) -> Option<&'static str> {
+        self.wordlist().lines().nth(usize::from(index))
+    }
+
+    /// Index of a word in the wordlist, comparing their NFKD forms
+    pub fn index_of(self, word: &str) -> Option<u16> {
+        self.wordlist()
+            .lines()
+            .position(|candidate| candidate.nfkd().eq(word.nfkd()))
+            .map(|index| index as u16)
+    }
+
+    /// Separator of the words of mnemonics
+    fn separator(self) -> &'static str {
+        match self {
+            #[cfg(feature = "bip39-japanese")]
+            Language::Japanese => "\u{3000}",
+            _ => " ",
+        }
+    }
+
+    /// The 2048 words of the language, one per line
+    fn wordlist(self) -> &'static str {
+        match self {
+            Language::English => include_str!("bip39/wordlists/english.txt"),
+            #[cfg(feature = "bip39-chinese-simplified")]
+            Language::ChineseSimplified => include_str!("bip39/wordlists/chinese_simplified.txt"),
+            #[cfg(feature = "bip39-chinese-traditional")]
+            Language::ChineseTraditional => {
+                include_str!("bip39/wordlists/chinese_traditional.txt")
+            }
+            #[cfg(feature = "bip39-french")]
+            Language::French => include_str!("bip39/wordlists/french.txt"),
+            #[cfg(feature = "bip39-italian")]
+            Language::Italian => include_str!("bip39/wordlists/italian.txt"),
+            #[cfg(feature = "bip39-japanese")]
+            Language::Japanese => include_str!("bip39/wordlists/japanese.txt"),
+            #[cfg(feature = "bip39-korean")]
+            Language::Korean => include_str!("bip39/wordlists/korean.txt"),
+            #[cfg(feature = "bip39-spanish")]
+            Language::Spanish => include_str!("bip39/wordlists/spanish.txt"),
+        }
+    }
+}
+
+/// BIP39 mnemonic phrase with a valid checksum
+pub struct Mnemonic {
+    phrase: String,
+    entropy: Vec<u8>,
+    language: Language,
+}
+
+impl Mnemonic {
+    /// Generate a mnemonic of 12, 15, 18, 21 or 24 words using the
+    /// operating system's cryptographically secure random number generator
+    #[cfg(feature = "getrandom")]
+    pub fn generate(language: Language, word_count: usize) -> Result<Self, Error> {
+        if word_count < 12 || word_count > 24 || word_count % 3 != 0 {
+            return Err(Error::new());
+        }
+        let mut entropy = [0u8; 32];
+        let entropy = &mut entropy[..word_count * 4 / 3];
+        getrandom::getrandom(entropy).expect("RNG failure!");
+        let mnemonic = Self::from_entropy(language, entropy);
+        entropy.zeroize();
+        mnemonic
+    }
+
+    /// Mnemonic of 16, 20, 24, 28 or 32 bytes of entropy
+    pub fn from_entropy(language: Language, entropy: &[u8]) -> Result<Self, Error> {
+        if entropy.len() < 16 || entropy.len() > 32 || entropy.len() % 4 != 0 {
+            return Err(Error::new());
+        }
+        let checksum = Sha256::digest(entropy)[0];
+        let word_count = entropy.len() * 3 / 4;
+
+        let mut phrase = String::new();
+        for i in 0..word_count {
+            // the 11 bits of the word, from the entropy followed by the
+            // checksum
+            let mut index = 0u16;
+            for bit in i * 11..(i + 1) * 11 {
+                let byte = entropy.get(bit / 8).cloned().unwrap_or(checksum);
+                index = index << 1 | u16::from(byte >> (7 - bit % 8) & 1);
+            }
+            if i > 0 {
+                phrase.push_str(language.separator());
+            }
+            phrase.push_str(language.word(index).ok_or_else(Error::new)?);
+        }
+
+        Ok(Self {
+            phrase,
+            entropy: entropy.to_vec(),
+            language,
+        })
+    }
+
+    /// Parse a mnemonic phrase, checking its words and checksum
+    pub fn from_phrase(language: Language, phrase: &str) -> Result<Self, Error> {
+        let mut indexes = Vec::new();
+        for word in phrase.split_whitespace() {
+            indexes.push(language.index_of(word).ok_or_else(Error::new)?);
+        }
+        let word_count = indexes.len();
+        if word_count < 12 || word_count > 24 || word_count % 3 != 0 {
+            return Err(Error::new());
+        }
+
+        // 11 bits per word: the entropy followed by a checksum of 1 bit per
+        // 32 bits of entropy
+        let mut bytes = alloc::vec![0u8; (word_count * 11 + 7) / 8];
+        for (i, index) in indexes.iter().enumerate() {
+            for bit in 0..11 {
+                if index >> (10 - bit) & 1 == 1 {
+                    let position = i * 11 + bit;
+                    bytes[position / 8] |= 0x80 >> (position % 8);
+                }
+            }
+        }
+        let entropy_size = word_count * 4 / 3;
+        let checksum_bits = word_count / 3;
+        let checksum = bytes[entropy_size] >> (8 - checksum_bits);
+        bytes.truncate(entropy_size);
+
+        let expected_checksum = Sha256::digest(&bytes)[0] >> (8 - checksum_bits);
+        if checksum != expected_checksum {
+            bytes.as_mut_slice().zeroize();
+            return Err(Error::new());
+        }
+        let mnemonic = Self::from_entropy(language, &bytes);
+        bytes.as_mut_slice().zeroize();
+        mnemonic
+    }
+
+    /// The words of the mnemonic, separated by single spaces (ideographic
+    /// spaces in Japanese)
+    pub fn phrase(&self) -> &str {
+        &self.phrase
+    }
+
+    /// Entropy encoded by the mnemonic
+    pub fn entropy(&self) -> &[u8] {
+        &self.entropy
+    }
+
+    /// Language of the words of the mnemonic
+    pub fn language(&self) -> Language {
+        self.language
+    }
+
+    /// 64-byte seed of the mnemonic with a passphrase (empty if none), which
+    /// is the master secret of BIP32 and SLIP-0010 derivation
+    pub fn to_seed(&self, passphrase: &str) -> MnemonicSeed {
+        let mut password: String = self.phrase.nfkd().collect();
+        let mut salt = String::from("mnemonic");
+        salt.extend(passphrase.nfkd());
+
+        let mut seed = [0u8; SEED_SIZE];
+        pbkdf2::pbkdf2::<Hmac<Sha512>>(
+            password.as_bytes(),
+            salt.as_bytes(),
+            PBKDF2_ROUNDS,
+            &mut seed,
+        );
+        zeroize_string(&mut password);
+        zeroize_string(&mut salt);
+        MnemonicSeed(seed)
+    }
+
+    /// Ed25519 seed derived with SLIP-0010 along a (hardened) path, e.g.
+    /// `m/44'/118'/0'/0'/0'`, from the seed of the mnemonic
+    #[cfg(feature = "slip10")]
+    pub fn to_ed25519_seed(
+        &self,
+        passphrase: &str,
+        path: &crate::ed25519::slip10::DerivationPath,
+    ) -> Result<crate::ed25519::Seed, Error> {
+        crate::ed25519::slip10::derive_seed(self.to_seed(passphrase).as_bytes(), path)
+    }
+}
+
+impl Drop for Mnemonic {
+    fn drop(&mut self) {
+        zeroize_string(&mut self.phrase);
+        self.entropy.as_mut_slice().zeroize();
+    }
+}
+
+/// 64-byte seed of a mnemonic: the master secret of key derivation
+pub struct MnemonicSeed([u8; SEED_SIZE]);
+
+impl MnemonicSeed {
+    /// Expose the bytes of the seed
+    pub fn as_bytes(&self) -> &[u8; SEED_SIZE] {
+        &self.0
+    }
+}
+
+impl Drop for MnemonicSeed {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+/// Zeroize and empty a string
+fn zeroize_string(string: &mut String) {
+    let mut bytes = mem::replace(string, String::new()).into_bytes();
+    bytes.as_mut_slice().zeroize();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn from_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len() / 2)
+            .map(|i| u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap())
+            .collect()
+    }
+
+    /// Entropy, mnemonic and seed with the passphrase `TREZOR`
+    const TEST_VECTORS: &[(&str, &str, &str)] = &[
+        (
+            "00000000000000000000000000000000",
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+            "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04",
+        ),
+        (
+            "7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f",
+            "legal winner thank year wave sausage worth useful legal winner thank yellow",
+            "2e8905819b8723fe2c1d161860e5ee1830318dbf49a83bd451cfb8440c28bd6fa457fe1296106559a3c80937a1c1069be3a3a5bd381ee6260e8d9739fce1f607",
+        ),
+        (
+            "80808080808080808080808080808080",
+            "letter advice cage absurd amount doctor acoustic avoid letter advice cage above",
+            "d71de856f81a8acc65e6fc851a38d4d7ec216fd0796d0a6827a3ad6ed5511a30fa280f12eb2e47ed2ac03b5c462a0358d18d69fe4f985ec81778c1b370b652a8",
+        ),
+        (
+            "ffffffffffffffffffffffffffffffff",
+            "zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo wrong",
+            "ac27495480225222079d7be181583751e86f571027b0497b5b5d11218e0a8a13332572917f0f8e5a589620c6f15b11c61dee327651a14c34e18231052e48c069",
+        ),
+        (
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon art",
+            "bda85446c68413707090a52022edd26a1c9462295029f2e60cd7c4f2bbd3097170af7a4d73245cafa9c3cca8d561a7c3de6f5d4a10be8ed2a5e608d68f92fcc8",
+        ),
+        (
+            "9e885d952ad362caeb4efe34a8e91bd2",
+            "ozone drill grab fiber curtain grace pudding thank cruise elder eight picnic",
+            "274ddc525802f7c828d8ef7ddbcdc5304e87ac3535913611fbbfa986d0c9e5476c91689f9c8a54fd55bd38606aa6a8595ad213d4c9c9f9aca3fb217069a41028",
+        ),
+        (
+            "68a79eaca2324873eacc50cb9c6eca8cc68ea5d936f98787c60c7ebc74e6ce7c",
+            "hamster diagram private dutch cause delay private meat slide toddler razor book happy fancy gospel tennis maple dilemma loan word shrug inflict delay length",
+            "64c87cde7e12ecf6704ab95bb1408bef047c22db4cc7491c4271d170a1b213d20b385bc1588d9c7b38f1b39d415665b8a9030c9ec653d75e65f847d8fc1fc440",
+        ),
+        (
+            "f585c11aec520db57dd353c69554b21a89b20fb0650966fa0a9d6f74fd989d8f",
+            "void come effort suffer camp survey warrior heavy shoot primary clutch crush open amazing screen patrol group space point ten exist slush involve unfold",
+            "01f5bced59dec48e362f2c45b5de68b9fd6c92c6634f44d6d40aab69056506f0e35524a518034ddc1192e1dacd32c1ed3eaa3c3b131c88ed8e7e54c49a5d0998",
+        ),
+    ];
+
+    #[test]
+    fn bip39_test_vectors() {
+        for (entropy, phrase, seed) in TEST_VECTORS {
+            let mnemonic = Mnemonic::from_entropy(Language::English, &from_hex(entropy)).unwrap();
+            assert_eq!(mnemonic.phrase(), *phrase);
+
+            let parsed = Mnemonic::from_phrase(Language::English, phrase).unwrap();
+            assert_eq!(parsed.entropy(), &from_hex(entropy)[..]);
+            assert_eq!(
+                &parsed.to_seed("TREZOR").as_bytes()[..],
+                &from_hex(seed)[..]
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_phrases() {
+        for phrase in &[
+            // bad checksum
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon",
+            // not a word
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abou",
+            // 11 words
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+        ] {
+            assert!(Mnemonic::from_phrase(Language::English, phrase).is_err());
+        }
+        assert!(Mnemonic::from_entropy(Language::English, &[0; 15]).is_err());
+    }
+
+    #[test]
+    fn extra_whitespace() {
+        let mnemonic = Mnemonic::from_phrase(
+            Language::English,
+            "  legal winner thank year wave sausage\nworth useful legal  winner thank yellow ",
+        )
+        .unwrap();
+        assert_eq!(mnemonic.phrase(), TEST_VECTORS[1].1);
+    }
+
+    #[cfg(feature = "slip10")]
+    #[test]
+    fn ed25519_seed() {
+        use crate::ed25519::slip10;
+        let mnemonic = Mnemonic::from_phrase(Language::English, TEST_VECTORS[0].1).unwrap();
+        let path = "m/44'/118'/0'/0'/0'".parse().unwrap();
+        assert_eq!(
+            mnemonic
+                .to_ed25519_seed("TREZOR", &path)
+                .unwrap()
+                .as_secret_slice(),
+            slip10::derive_seed(&from_hex(TEST_VECTORS[0].2), &path)
+                .unwrap()
+                .as_secret_slice()
+        );
+    }
+
+    #[cfg(feature = "getrandom")]
+    #[test]
+    fn generate() {
+        let mnemonic = Mnemonic::generate(Language::English, 24).unwrap();
+        assert_eq!(mnemonic.entropy().len(), 32);
+        let parsed = Mnemonic::from_phrase(Language::English, mnemonic.phrase()).unwrap();
+        assert_eq!(parsed.entropy(), mnemonic.entropy());
+        assert!(Mnemonic::generate(Language::English, 13).is_err());
+        assert!(Mnemonic::generate(Language::English, 27).is_err());
+    }
+}
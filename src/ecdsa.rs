@@ -1,10 +1,25 @@
 //! The Elliptic Curve Digital Signature Algorithm (ECDSA) as specified in
 //! FIPS 186-4 (Digital Signature Standard)
+//!
+//! Providers can also use ECDSA keys for Elliptic Curve Diffie-Hellman
+//! (ECDH) key agreement, with `KeyAgreement`.
+//!
+//! Signatures can be normalized to their low-S form (see `LowS`), and
+//! verifiers can reject high-S signatures with `VerificationMode::Strict`.
 
+mod key_agreement;
+mod low_s;
 mod public_key;
+mod recoverable;
 mod secret_key;
 
-pub use self::{public_key::PublicKey, secret_key::SecretKey};
+pub use self::{
+    key_agreement::{KeyAgreement, SharedSecret},
+    low_s::{LowS, VerificationMode},
+    public_key::PublicKey,
+    recoverable::{RecoverableSignature, RECOVERABLE_SIGNATURE_SIZE},
+    secret_key::SecretKey,
+};
 
 // Use signature and curve types from the `ecdsa` crate
 pub use ::ecdsa::{curve, generic_array, Asn1Signature, Curve, FixedSignature};
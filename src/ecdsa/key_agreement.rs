@@ -0,0 +1,67 @@
+//! Elliptic Curve Diffie-Hellman (ECDH) key agreement with ECDSA keys
+
+use super::PublicKey;
+use ::ecdsa::{
+    curve::point::{CompressedPointSize, UncompressedPointSize},
+    generic_array::{
+        typenum::{Unsigned, U1},
+        ArrayLength, GenericArray,
+    },
+    Curve,
+};
+use core::ops::Add;
+use signature::Error;
+use zeroize::Zeroize;
+
+/// Secret shared by the two parties of an ECDH key agreement, which should
+/// be passed through a KDF before being used as a key. Unless a provider
+/// documents otherwise, it's the x-coordinate of the shared point.
+pub struct SharedSecret<C: Curve> {
+    /// Byte serialization of the shared secret
+    bytes: GenericArray<u8, C::ScalarSize>,
+}
+
+impl<C: Curve> SharedSecret<C> {
+    /// Create a shared secret
+    pub fn new(into_bytes: impl Into<GenericArray<u8, C::ScalarSize>>) -> Self {
+        Self {
+            bytes: into_bytes.into(),
+        }
+    }
+
+    /// Create a shared secret from the given byte slice
+    pub fn from_bytes(bytes: impl AsRef<[u8]>) -> Option<Self> {
+        let slice = bytes.as_ref();
+
+        if slice.len() == C::ScalarSize::to_usize() {
+            Some(Self::new(GenericArray::clone_from_slice(slice)))
+        } else {
+            None
+        }
+    }
+
+    /// Expose this `SharedSecret` as a byte slice
+    pub fn as_secret_slice(&self) -> &[u8] {
+        self.bytes.as_ref()
+    }
+}
+
+impl<C: Curve> Drop for SharedSecret<C> {
+    fn drop(&mut self) {
+        self.bytes.as_mut().zeroize();
+    }
+}
+
+/// ECDH key agreement (to be implemented by Signatory providers).
+///
+/// Ephemeral secrets implement it by value, so they can only be used once,
+/// and static secrets by reference.
+pub trait KeyAgreement<C: Curve>
+where
+    <C::ScalarSize as Add>::Output: Add<U1>,
+    CompressedPointSize<C::ScalarSize>: ArrayLength<u8>,
+    UncompressedPointSize<C::ScalarSize>: ArrayLength<u8>,
+{
+    /// Compute the secret shared with the owner of the given public key
+    fn agree(self, public_key: &PublicKey<C>) -> Result<SharedSecret<C>, Error>;
+}
@@ -0,0 +1,262 @@
+//! Low-S normalization of ECDSA signatures.
+//!
+//! ECDSA signatures are malleable: if `(r, s)` is a valid signature, then so
+//! is `(r, n - s)`, where `n` is the order of the curve. Bitcoin (BIP 146)
+//! and Tendermint/Cosmos remove this malleability by only accepting "low-S"
+//! signatures, whose `s` is at most `n / 2`.
+
+use core::{cmp::Ordering, iter};
+use ecdsa::curve::{nistp256, nistp384, secp256k1};
+use signature::{Error, Signature};
+
+/// Order of the NIST P-256 group (big-endian)
+const NISTP256_ORDER: &[u8] = b"\xFF\xFF\xFF\xFF\x00\x00\x00\x00\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\
+                                \xBC\xE6\xFA\xAD\xA7\x17\x9E\x84\xF3\xB9\xCA\xC2\xFC\x63\x25\x51";
+
+/// Order of the NIST P-384 group (big-endian)
+const NISTP384_ORDER: &[u8] = b"\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\
+                                \xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xC7\x63\x4D\x81\xF4\x37\x2D\xDF\
+                                \x58\x1A\x0D\xB2\x48\xB0\xA7\x7A\xEC\xEC\x19\x6A\xCC\xC5\x29\x73";
+
+/// Order of the secp256k1 group (big-endian)
+const SECP256K1_ORDER: &[u8] = b"\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFE\
+                                 \xBA\xAE\xDC\xE6\xAF\x48\xA0\x3B\xBF\xD2\x5E\x8C\xD0\x36\x41\x41";
+
+/// Size of the largest fixed-sized signature (P-384)
+const MAX_FIXED_SIGNATURE_SIZE: usize = 96;
+
+/// Low-S checks and normalization of ECDSA signatures
+pub trait LowS: Sized {
+    /// Is `s` at most half the order of the curve?
+    fn is_low_s(&self) -> bool;
+
+    /// The low-S form of this signature (i.e. with `s` replaced by `n - s`
+    /// if it's high), which verifies under the same public key
+    fn normalize_s(&self) -> Self;
+}
+
+/// Rules of ECDSA signature verification regarding malleability
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum VerificationMode {
+    /// FIPS 186-4: accept any `s`, so both `(r, s)` and `(r, n - s)` verify
+    Fips186,
+
+    /// Reject high-S signatures, as Bitcoin and Tendermint/Cosmos do
+    Strict,
+}
+
+impl VerificationMode {
+    /// Check a signature against this mode, before verifying it
+    pub fn check<S: LowS>(self, signature: &S) -> Result<(), Error> {
+        match self {
+            VerificationMode::Fips186 => Ok(()),
+            VerificationMode::Strict if signature.is_low_s() => Ok(()),
+            VerificationMode::Strict => Err(Error::new()),
+        }
+    }
+}
+
+macro_rules! impl_low_s {
+    ($curve:ident, $order:expr) => {
+        impl LowS for $curve::FixedSignature {
+            fn is_low_s(&self) -> bool {
+                is_low(&self.as_ref()[$order.len()..], $order)
+            }
+
+            fn normalize_s(&self) -> Self {
+                let mut bytes = [0u8; MAX_FIXED_SIGNATURE_SIZE];
+                let bytes = &mut bytes[..$order.len() * 2];
+                bytes.copy_from_slice(self.as_ref());
+                normalize(bytes, $order);
+                Self::from_bytes(&bytes[..]).unwrap()
+            }
+        }
+
+        impl LowS for $curve::Asn1Signature {
+            /// Malformed signatures are never low-S
+            fn is_low_s(&self) -> bool {
+                der_integers(self.as_ref(), $order.len())
+                    .map(|(_, s)| is_low(s, $order))
+                    .unwrap_or(false)
+            }
+
+            /// Malformed signatures are returned unchanged
+            fn normalize_s(&self) -> Self {
+                let (r, s) = match der_integers(self.as_ref(), $order.len()) {
+                    Ok(integers) => integers,
+                    Err(_) => return Self::from_bytes(self.as_ref()).unwrap(),
+                };
+
+                let mut bytes = [0u8; MAX_FIXED_SIGNATURE_SIZE];
+                let bytes = &mut bytes[..$order.len() * 2];
+                bytes[$order.len() - r.len()..$order.len()].copy_from_slice(r);
+                bytes[$order.len() * 2 - s.len()..].copy_from_slice(s);
+
+                let fixed_signature = $curve::FixedSignature::from_bytes(&bytes[..])
+                    .unwrap()
+                    .normalize_s();
+                Self::from(&fixed_signature)
+            }
+        }
+    };
+}
+
+impl_low_s!(nistp256, NISTP256_ORDER);
+impl_low_s!(nistp384, NISTP384_ORDER);
+impl_low_s!(secp256k1, SECP256K1_ORDER);
+
+/// Is the big-endian scalar `s` at most `order / 2`?
+fn is_low(s: &[u8], order: &[u8]) -> bool {
+    if s.len() > order.len() {
+        return false;
+    }
+
+    // `order` is odd, so `order >> 1` is `(order - 1) / 2`
+    let half_order = order
+        .iter()
+        .enumerate()
+        .map(|(i, byte)| (byte >> 1) | if i > 0 { order[i - 1] << 7 } else { 0 });
+
+    iter::repeat(0)
+        .take(order.len() - s.len())
+        .chain(s.iter().cloned())
+        .cmp(half_order)
+        != Ordering::Greater
+}
+
+/// Replace `s` in the fixed-sized signature `r || s` with `order - s` if it's
+/// high
+fn normalize(signature: &mut [u8], order: &[u8]) {
+    let s = &mut signature[order.len()..];
+
+    if is_low(s, order) {
+        return;
+    }
+
+    let mut borrow = 0i16;
+    for i in (0..order.len()).rev() {
+        let mut diff = i16::from(order[i]) - i16::from(s[i]) - borrow;
+        borrow = if diff < 0 { 1 } else { 0 };
+        diff += borrow * 256;
+        s[i] = diff as u8;
+    }
+}
+
+/// Parse `r` and `s` from an ASN.1 DER-encoded signature (i.e.
+/// `SEQUENCE { INTEGER r, INTEGER s }`), with leading zeroes stripped
+fn der_integers(der: &[u8], scalar_size: usize) -> Result<(&[u8], &[u8]), Error> {
+    if der.len() < 8 || der[0] != 0x30 || usize::from(der[1]) != der.len() - 2 {
+        return Err(Error::new());
+    }
+
+    let (r, rest) = der_integer(&der[2..], scalar_size)?;
+    let (s, rest) = der_integer(rest, scalar_size)?;
+
+    if !rest.is_empty() {
+        return Err(Error::new());
+    }
+
+    Ok((r, s))
+}
+
+/// Parse a DER INTEGER of at most `scalar_size` bytes (once leading zeroes
+/// are stripped), returning it along with the remaining input
+fn der_integer(input: &[u8], scalar_size: usize) -> Result<(&[u8], &[u8]), Error> {
+    if input.len() < 2 || input[0] != 0x02 {
+        return Err(Error::new());
+    }
+
+    let len = usize::from(input[1]);
+
+    if len == 0 || input.len() < 2 + len {
+        return Err(Error::new());
+    }
+
+    let (mut value, rest) = input[2..].split_at(len);
+
+    while value.len() > 1 && value[0] == 0 {
+        value = &value[1..];
+    }
+
+    if value.len() > scalar_size {
+        return Err(Error::new());
+    }
+
+    Ok((value, rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `(n - 1) / 2` for secp256k1, the highest low `s`
+    const SECP256K1_HALF_ORDER: &[u8] = b"\x7F\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\
+                                          \x5D\x57\x6E\x73\x57\xA4\x50\x1D\xDF\xE9\x2F\x46\x68\x1B\x20\xA0";
+
+    /// secp256k1 signature with `r = 1` and the given `s`
+    fn secp256k1_signature(s: &[u8]) -> secp256k1::FixedSignature {
+        let mut bytes = [0u8; 64];
+        bytes[31] = 1;
+        bytes[32..].copy_from_slice(s);
+        secp256k1::FixedSignature::from_bytes(&bytes[..]).unwrap()
+    }
+
+    #[test]
+    fn half_order_is_low_s() {
+        let signature = secp256k1_signature(SECP256K1_HALF_ORDER);
+        assert!(signature.is_low_s());
+        assert_eq!(signature.normalize_s().as_ref(), signature.as_ref());
+    }
+
+    #[test]
+    fn normalizes_high_s() {
+        // `(n + 1) / 2` is the lowest high `s`, and `n - (n + 1) / 2` is
+        // `(n - 1) / 2`
+        let mut s = [0u8; 32];
+        s.copy_from_slice(SECP256K1_HALF_ORDER);
+        s[31] += 1;
+
+        let signature = secp256k1_signature(&s);
+        assert!(!signature.is_low_s());
+
+        let normalized = signature.normalize_s();
+        assert!(normalized.is_low_s());
+        assert_eq!(
+            normalized.as_ref(),
+            secp256k1_signature(SECP256K1_HALF_ORDER).as_ref()
+        );
+    }
+
+    #[test]
+    fn normalizes_asn1_signatures() {
+        // `r = 1`, `s = n - 1`
+        let signature = secp256k1::Asn1Signature::from_bytes(
+            &b"\x30\x26\x02\x01\x01\x02\x21\x00\
+               \xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFE\
+               \xBA\xAE\xDC\xE6\xAF\x48\xA0\x3B\xBF\xD2\x5E\x8C\xD0\x36\x41\x40"[..],
+        )
+        .unwrap();
+        assert!(!signature.is_low_s());
+
+        // `r = 1`, `s = 1`
+        let normalized = signature.normalize_s();
+        assert!(normalized.is_low_s());
+        assert_eq!(
+            normalized.as_ref(),
+            &b"\x30\x06\x02\x01\x01\x02\x01\x01"[..]
+        );
+    }
+
+    #[test]
+    fn strict_mode_rejects_high_s() {
+        let signature = secp256k1_signature(SECP256K1_HALF_ORDER);
+        assert!(VerificationMode::Strict.check(&signature).is_ok());
+
+        let mut s = [0u8; 32];
+        s.copy_from_slice(SECP256K1_ORDER);
+        s[31] -= 1;
+        let signature = secp256k1_signature(&s);
+        assert!(VerificationMode::Fips186.check(&signature).is_ok());
+        assert!(VerificationMode::Strict.check(&signature).is_err());
+    }
+}
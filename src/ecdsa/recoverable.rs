@@ -0,0 +1,95 @@
+//! Recoverable ECDSA signatures over secp256k1: signatures carrying the
+//! extra information needed to recover the signer's public key from them
+//! (a.k.a. `ecrecover`), as used by Ethereum.
+
+use core::fmt::{self, Debug};
+use ecdsa::{
+    curve::secp256k1::FixedSignature,
+    generic_array::{typenum::U65, GenericArray},
+};
+use signature::{Error, Signature};
+
+/// Size of a recoverable signature: `r` and `s` (32 bytes each) followed by
+/// the recovery ID `v`
+pub const RECOVERABLE_SIGNATURE_SIZE: usize = 65;
+
+/// Recoverable ECDSA signature over secp256k1: `r || s || v`, where `v` is
+/// the recovery ID (0 to 3).
+///
+/// Ethereum transactions and `ecrecover` encode the recovery ID with an
+/// offset (e.g. `v = 27 + recovery ID`), which has to be removed before
+/// parsing such signatures.
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub struct RecoverableSignature {
+    bytes: GenericArray<u8, U65>,
+}
+
+impl RecoverableSignature {
+    /// Create a recoverable signature from a fixed-sized signature and the
+    /// recovery ID of the signer's public key
+    pub fn new(signature: &FixedSignature, recovery_id: u8) -> Result<Self, Error> {
+        let mut bytes = GenericArray::default();
+        bytes[..64].copy_from_slice(signature.as_ref());
+        bytes[64] = recovery_id;
+        Self::from_bytes(bytes.as_slice())
+    }
+
+    /// The recovery ID of the signer's public key (0 to 3)
+    pub fn recovery_id(&self) -> u8 {
+        self.bytes[64]
+    }
+
+    /// The `r` component of the signature
+    pub fn r(&self) -> &[u8] {
+        &self.bytes[..32]
+    }
+
+    /// The `s` component of the signature
+    pub fn s(&self) -> &[u8] {
+        &self.bytes[32..64]
+    }
+
+    /// The fixed-sized signature (`r || s`) without the recovery ID, which
+    /// verifies like any other signature of the signer
+    pub fn to_fixed_signature(&self) -> FixedSignature {
+        FixedSignature::from_bytes(&self.bytes[..64]).unwrap()
+    }
+}
+
+impl Signature for RecoverableSignature {
+    /// Parse `r || s || v`, where `v` is the recovery ID
+    fn from_bytes<B: AsRef<[u8]>>(bytes: B) -> Result<Self, Error> {
+        let bytes = bytes.as_ref();
+
+        if bytes.len() != RECOVERABLE_SIGNATURE_SIZE || bytes[64] > 3 {
+            return Err(Error::new());
+        }
+
+        Ok(Self {
+            bytes: GenericArray::clone_from_slice(bytes),
+        })
+    }
+}
+
+impl AsRef<[u8]> for RecoverableSignature {
+    fn as_ref(&self) -> &[u8] {
+        self.bytes.as_slice()
+    }
+}
+
+impl Debug for RecoverableSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "RecoverableSignature(")?;
+        for byte in self.bytes.iter() {
+            write!(f, "{:02x}", byte)?;
+        }
+        write!(f, ")")
+    }
+}
+
+#[cfg(all(feature = "digest", feature = "sha2"))]
+impl signature::DigestSignature for RecoverableSignature {
+    /// Signatures of messages sign their SHA-256 digest, like the other
+    /// secp256k1 signatures
+    type Digest = sha2::Sha256;
+}
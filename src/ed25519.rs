@@ -5,6 +5,16 @@
 //! This module contains two convenience methods for signing and verifying
 //! Ed25519 signatures which work with any signer or verifier.
 //!
+//! With the `alloc` feature, providers implement `BatchVerifier` to verify
+//! many signatures at once (e.g. all the signatures of a block).
+//!
+//! Provider verifiers can be created `with_mode` a `VerificationMode`, which
+//! pins down which signatures are valid in edge cases (see the
+//! `verification_mode` module). With the `ed25519-cofactored` feature,
+//! providers also verify Ed25519ctx and Ed25519ph signatures, which have a
+//! `Context` string, with `ContextVerifier`; signatory-dalek signs them with
+//! `ContextSigner`.
+//!
 //! # Example (with ed25519-dalek)
 //!
 //! ```nobuild
@@ -28,19 +38,37 @@
 //! assert!(ed25519::verify(&verifier, msg.as_bytes(), &sig).is_ok());
 //! ```
 
+#[cfg(feature = "alloc")]
+mod batch;
+#[cfg(feature = "ed25519-cofactored")]
+mod cofactored;
+#[cfg(feature = "sha2")]
+mod context;
 mod public_key;
 mod seed;
+#[cfg(feature = "slip10")]
+pub mod slip10;
+pub mod verification_mode;
 
 #[cfg(feature = "test-vectors")]
 #[macro_use]
 mod test_macros;
 
-/// RFC 8032 Ed25519 test vectors
+/// RFC 8032 Ed25519, Ed25519ctx and Ed25519ph test vectors
 #[cfg(feature = "test-vectors")]
 mod test_vectors;
 
+#[cfg(feature = "alloc")]
+pub use self::batch::{
+    verify_batch_sequentially, verify_batch_with, BatchError, BatchItem, BatchVerifier,
+};
+#[cfg(feature = "ed25519-cofactored")]
+pub use self::cofactored::verify_cofactored;
+#[cfg(feature = "sha2")]
+pub use self::context::{Context, ContextSigner, ContextVerifier, CONTEXT_MAX_SIZE};
 #[cfg(feature = "test-vectors")]
 pub use self::test_vectors::TEST_VECTORS;
+pub use self::verification_mode::VerificationMode;
 pub use self::{
     public_key::{PublicKey, PUBLIC_KEY_SIZE},
     seed::{Seed, SEED_SIZE},
@@ -0,0 +1,95 @@
+//! Batch verification of Ed25519 signatures
+
+use super::{PublicKey, Signature, VerificationMode};
+use alloc::vec::Vec;
+use core::fmt::{self, Display};
+use signature::Verifier;
+
+/// Public key, message and signature of a batch to verify
+pub type BatchItem<'a> = (&'a PublicKey, &'a [u8], &'a Signature);
+
+/// Verify many Ed25519 signatures at once.
+///
+/// A batch accepts exactly the signatures which the provider's verifier
+/// accepts one at a time, with the same rules.
+pub trait BatchVerifier {
+    /// Verify a batch of signatures, each with its public key and message,
+    /// with the provider's own rules (as verifiers created `From` a public
+    /// key), reporting the indices of the invalid ones
+    fn verify_batch(items: &[BatchItem<'_>]) -> Result<(), BatchError>;
+
+    /// Verify a batch of signatures in the given `VerificationMode`
+    fn verify_batch_with_mode(
+        mode: VerificationMode,
+        items: &[BatchItem<'_>],
+    ) -> Result<(), BatchError>;
+}
+
+/// Verify a batch of signatures one at a time with the given verifier,
+/// for providers without batch verification
+pub fn verify_batch_sequentially<V>(items: &[BatchItem<'_>]) -> Result<(), BatchError>
+where
+    V: for<'a> From<&'a PublicKey> + Verifier<Signature>,
+{
+    verify_batch_with(items, |public_key| V::from(public_key))
+}
+
+/// Verify a batch of signatures one at a time, each with the verifier
+/// created for its public key by `verifier` (e.g. `with_mode`)
+pub fn verify_batch_with<V, F>(items: &[BatchItem<'_>], verifier: F) -> Result<(), BatchError>
+where
+    V: Verifier<Signature>,
+    F: Fn(&PublicKey) -> V,
+{
+    let invalid_indices: Vec<usize> = items
+        .iter()
+        .enumerate()
+        .filter(|(_, (public_key, msg, signature))| {
+            verifier(public_key).verify(msg, signature).is_err()
+        })
+        .map(|(index, _)| index)
+        .collect();
+
+    if invalid_indices.is_empty() {
+        Ok(())
+    } else {
+        Err(BatchError::new(invalid_indices))
+    }
+}
+
+/// Failed batch verification
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BatchError {
+    invalid_indices: Vec<usize>,
+}
+
+impl BatchError {
+    /// Create an error with the indices of the invalid items of a batch
+    pub fn new(invalid_indices: Vec<usize>) -> Self {
+        Self { invalid_indices }
+    }
+
+    /// Indices of the invalid items of the batch, in increasing order
+    pub fn invalid_indices(&self) -> &[usize] {
+        &self.invalid_indices
+    }
+}
+
+impl Display for BatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid Ed25519 signatures at indices {:?}",
+            self.invalid_indices
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BatchError {}
+
+impl From<BatchError> for signature::Error {
+    fn from(_: BatchError) -> signature::Error {
+        signature::Error::new()
+    }
+}
@@ -0,0 +1,71 @@
+//! Ed25519 verification equations which providers don't implement, on top
+//! of curve25519-dalek: the cofactored equation `[8][S]B = [8]R + [8][k]A`
+//! of RFC 8032 and ZIP-215, and Ed25519ctx and Ed25519ph verification.
+//!
+//! curve25519-dalek needs one of its backend features enabled (e.g.
+//! `curve25519-dalek/u64_backend`), which is left to the crate enabling the
+//! `ed25519-cofactored` feature.
+
+use super::{PublicKey, Signature};
+use curve25519_dalek::{
+    edwards::{CompressedEdwardsY, EdwardsPoint},
+    scalar::Scalar,
+    traits::IsIdentity,
+};
+use sha2::{Digest, Sha512};
+use signature::Error;
+
+/// Verify a signature with the cofactored equation `[8][S]B = [8]R + [8][k]A`,
+/// decoding points as ZIP-215 does (non-canonical encodings are accepted:
+/// reject them first with `VerificationMode::check_encodings`)
+pub fn verify_cofactored(
+    public_key: &PublicKey,
+    msg: &[u8],
+    signature: &Signature,
+) -> Result<(), Error> {
+    verify_equation(public_key, Sha512::new(), msg, signature, true)
+}
+
+/// Verify a signature with the challenge `k` computed by the given hasher,
+/// which may have already hashed a `dom2` prefix
+pub(super) fn verify_equation(
+    public_key: &PublicKey,
+    hasher: Sha512,
+    msg: &[u8],
+    signature: &Signature,
+    cofactored: bool,
+) -> Result<(), Error> {
+    let mut r_bytes = [0u8; 32];
+    let mut s_bytes = [0u8; 32];
+    r_bytes.copy_from_slice(&signature.as_ref()[..32]);
+    s_bytes.copy_from_slice(&signature.as_ref()[32..]);
+
+    let s = Scalar::from_canonical_bytes(s_bytes).ok_or_else(Error::new)?;
+    let a = CompressedEdwardsY(*public_key.as_bytes())
+        .decompress()
+        .ok_or_else(Error::new)?;
+    let r = CompressedEdwardsY(r_bytes)
+        .decompress()
+        .ok_or_else(Error::new)?;
+    let k = Scalar::from_hash(
+        hasher
+            .chain(&r_bytes[..])
+            .chain(&public_key.as_bytes()[..])
+            .chain(msg),
+    );
+
+    // [S]B - [k]A
+    let expected_r = EdwardsPoint::vartime_double_scalar_mul_basepoint(&k, &-a, &s);
+
+    let valid = if cofactored {
+        (expected_r - r).mul_by_cofactor().is_identity()
+    } else {
+        expected_r.compress() == CompressedEdwardsY(r_bytes)
+    };
+
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::new())
+    }
+}
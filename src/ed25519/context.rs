@@ -0,0 +1,102 @@
+//! Ed25519ctx and Ed25519ph: Ed25519 with context strings
+//!
+//! Both variants (RFC 8032 section 5.1) hash `dom2(phflag, context)` before
+//! anything else, so a signature made for one context (or variant) never
+//! verifies in another. Ed25519ph signs the SHA-512 digest of a message
+//! rather than the message itself.
+
+use super::Signature;
+use sha2::{Digest, Sha512};
+use signature::Error;
+
+/// Maximum size of a context string
+pub const CONTEXT_MAX_SIZE: usize = 255;
+
+/// Prefix of `dom2(phflag, context)`
+const DOM2_PREFIX: &[u8] = b"SigEd25519 no Ed25519 collisions";
+
+/// Context string of an Ed25519ctx or Ed25519ph signature, which separates
+/// the signatures made for different purposes
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Context<'a>(&'a [u8]);
+
+impl<'a> Context<'a> {
+    /// Create a context string, of at most `CONTEXT_MAX_SIZE` bytes
+    pub fn new(bytes: &'a [u8]) -> Result<Self, Error> {
+        if bytes.len() > CONTEXT_MAX_SIZE {
+            return Err(Error::new());
+        }
+
+        Ok(Context(bytes))
+    }
+
+    /// Borrow the bytes of this context string
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.0
+    }
+
+    /// SHA-512 hasher which has already hashed `dom2(phflag, context)`, for
+    /// Ed25519ctx (`prehashed = false`) or Ed25519ph (`prehashed = true`)
+    pub fn hasher(&self, prehashed: bool) -> Sha512 {
+        Sha512::new()
+            .chain(DOM2_PREFIX)
+            .chain([u8::from(prehashed), self.0.len() as u8])
+            .chain(self.0)
+    }
+}
+
+impl<'a> AsRef<[u8]> for Context<'a> {
+    fn as_ref(&self) -> &[u8] {
+        self.0
+    }
+}
+
+/// Sign messages with Ed25519ctx and Ed25519ph
+pub trait ContextSigner {
+    /// Sign a message with Ed25519ctx
+    fn try_sign_ctx(&self, context: Context<'_>, msg: &[u8]) -> Result<Signature, Error>;
+
+    /// Sign the SHA-512 digest of a message with Ed25519ph
+    fn try_sign_ph(&self, context: Context<'_>, digest: Sha512) -> Result<Signature, Error>;
+}
+
+/// Verify Ed25519ctx and Ed25519ph signatures
+pub trait ContextVerifier {
+    /// Verify an Ed25519ctx signature of a message
+    fn verify_ctx(
+        &self,
+        context: Context<'_>,
+        msg: &[u8],
+        signature: &Signature,
+    ) -> Result<(), Error>;
+
+    /// Verify an Ed25519ph signature of the SHA-512 digest of a message
+    fn verify_ph(
+        &self,
+        context: Context<'_>,
+        digest: Sha512,
+        signature: &Signature,
+    ) -> Result<(), Error>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn context_max_size() {
+        assert!(Context::new(&[0u8; CONTEXT_MAX_SIZE]).is_ok());
+        assert!(Context::new(&[0u8; CONTEXT_MAX_SIZE + 1]).is_err());
+    }
+
+    #[test]
+    fn dom2() {
+        let context = Context::new(b"foo").unwrap();
+        assert_eq!(
+            context.hasher(true).chain(b"msg").result(),
+            Sha512::new()
+                .chain(&b"SigEd25519 no Ed25519 collisions\x01\x03foomsg"[..])
+                .result()
+        );
+    }
+}
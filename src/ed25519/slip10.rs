@@ -0,0 +1,263 @@
+//! SLIP-0010 hierarchical deterministic derivation of Ed25519 seeds
+//!
+//! Described in SLIP-0010: <https://github.com/satoshilabs/slips/blob/master/slip-0010.md>
+//!
+//! Ed25519 only supports hardened derivation: every child index of a path
+//! is hardened (e.g. `m/44'/118'/0'`). Derived seeds work with any Ed25519
+//! provider.
+//!
+//! # Example
+//!
+//! ```nobuild
+//! use signatory::ed25519::slip10;
+//! use signatory_dalek::Ed25519Signer;
+//!
+//! let path = "m/44'/118'/0'/0'/0'".parse().unwrap();
+//! let seed = slip10::derive_seed(master_secret, &path).unwrap();
+//! let signer = Ed25519Signer::from(&seed);
+//! ```
+
+use super::{Seed, SEED_SIZE};
+use alloc::vec::Vec;
+use core::fmt::{self, Display};
+use core::str::FromStr;
+use hmac::{Hmac, Mac};
+use sha2::Sha512;
+use signature::Error;
+use zeroize::Zeroize;
+
+#[cfg(feature = "test-vectors")]
+mod test_vectors;
+
+#[cfg(feature = "test-vectors")]
+pub use self::test_vectors::{TestVector, TEST_VECTORS};
+
+/// Flag of hardened child indexes
+pub const HARDENED_FLAG: u32 = 1 << 31;
+
+/// Derivation path of hardened child indexes, e.g. `m/44'/118'/0'`
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct DerivationPath(Vec<u32>);
+
+impl DerivationPath {
+    /// Path of the (hardened) child with the given index of this path
+    pub fn child(&self, index: u32) -> Self {
+        let mut indexes = self.0.clone();
+        indexes.push(index | HARDENED_FLAG);
+        DerivationPath(indexes)
+    }
+
+    /// Child indexes of the path, with the hardened flag
+    pub fn indexes(&self) -> &[u32] {
+        &self.0
+    }
+}
+
+impl Display for DerivationPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "m")?;
+        for index in &self.0 {
+            write!(f, "/{}'", index & !HARDENED_FLAG)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse a path like `m/44'/118'/0'`. Every index of the path must be
+/// hardened (with `'`, `h` or `H`).
+impl FromStr for DerivationPath {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let mut components = s.split('/');
+        if components.next() != Some("m") {
+            return Err(Error::new());
+        }
+        components
+            .map(|component| parse_hardened_index(component).map(|index| index | HARDENED_FLAG))
+            .collect::<Result<Vec<_>, _>>()
+            .map(DerivationPath)
+    }
+}
+
+/// Ed25519 seed along with the chain code needed to derive its children
+#[derive(Clone)]
+pub struct ExtendedSeed {
+    seed: Seed,
+    chain_code: [u8; 32],
+    depth: u8,
+    child_index: u32,
+}
+
+impl ExtendedSeed {
+    /// Master seed of a secret (e.g. a BIP39 seed), of 16 to 64 bytes
+    pub fn new_master(secret: &[u8]) -> Result<Self, Error> {
+        if secret.len() < 16 || secret.len() > 64 {
+            return Err(Error::new());
+        }
+        let (seed, chain_code) = hmac_sha512(b"ed25519 seed", &[secret]);
+        Ok(Self {
+            seed: Seed::new(seed),
+            chain_code,
+            depth: 0,
+            child_index: 0,
+        })
+    }
+
+    /// Hardened child seed with the given index, with or without the
+    /// hardened flag (i.e. `0` and `0x80000000` both derive `0'`)
+    pub fn derive_child(&self, index: u32) -> Result<Self, Error> {
+        let child_index = index | HARDENED_FLAG;
+        let mut key = [0u8; 1 + SEED_SIZE];
+        key[1..].copy_from_slice(self.seed.as_secret_slice());
+        let (seed, chain_code) = hmac_sha512(
+            &self.chain_code,
+            &[&key[..], &child_index.to_be_bytes()[..]],
+        );
+        key.zeroize();
+
+        Ok(Self {
+            seed: Seed::new(seed),
+            chain_code,
+            depth: self.depth.checked_add(1).ok_or_else(Error::new)?,
+            child_index,
+        })
+    }
+
+    /// Seed at the given path from this seed
+    pub fn derive_path(&self, path: &DerivationPath) -> Result<Self, Error> {
+        path.indexes()
+            .iter()
+            .try_fold(self.clone(), |extended_seed, &index| {
+                extended_seed.derive_child(index)
+            })
+    }
+
+    /// Ed25519 seed, for use with any Ed25519 provider
+    pub fn seed(&self) -> &Seed {
+        &self.seed
+    }
+
+    /// Chain code of the seed
+    pub fn chain_code(&self) -> &[u8; 32] {
+        &self.chain_code
+    }
+
+    /// Depth of the seed: 0 for master seeds
+    pub fn depth(&self) -> u8 {
+        self.depth
+    }
+
+    /// Index of the seed in its parent's children, with the hardened flag
+    /// (0 for master seeds)
+    pub fn child_index(&self) -> u32 {
+        self.child_index
+    }
+}
+
+impl Drop for ExtendedSeed {
+    fn drop(&mut self) {
+        self.chain_code.zeroize();
+    }
+}
+
+/// Derive the Ed25519 seed at a path (e.g. `m/44'/118'/0'`) from a master
+/// secret
+pub fn derive_seed(secret: &[u8], path: &DerivationPath) -> Result<Seed, Error> {
+    Ok(ExtendedSeed::new_master(secret)?
+        .derive_path(path)?
+        .seed()
+        .clone())
+}
+
+/// Parse a hardened index: digits followed by `'`, `h` or `H`
+fn parse_hardened_index(component: &str) -> Result<u32, Error> {
+    let index = match component.chars().last() {
+        Some('\'') | Some('h') | Some('H') => &component[..component.len() - 1],
+        _ => return Err(Error::new()),
+    };
+    // reject signs, which `u32::from_str` accepts
+    if index.is_empty() || !index.bytes().all(|byte| byte.is_ascii_digit()) {
+        return Err(Error::new());
+    }
+    match index.parse::<u32>() {
+        Ok(index) if index & HARDENED_FLAG == 0 => Ok(index),
+        _ => Err(Error::new()),
+    }
+}
+
+/// Split `HMAC-SHA512(key, data)` into a seed and a chain code
+fn hmac_sha512(key: &[u8], data: &[&[u8]]) -> ([u8; SEED_SIZE], [u8; 32]) {
+    let mut mac = Hmac::<Sha512>::new_varkey(key).unwrap();
+    for bytes in data {
+        mac.input(*bytes);
+    }
+    let mut output = mac.result().code();
+    let mut seed = [0u8; SEED_SIZE];
+    let mut chain_code = [0u8; 32];
+    seed.copy_from_slice(&output[..SEED_SIZE]);
+    chain_code.copy_from_slice(&output[SEED_SIZE..]);
+    output.as_mut_slice().zeroize();
+    (seed, chain_code)
+}
+
+#[cfg(all(test, feature = "test-vectors"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slip10_test_vectors() {
+        for vector in TEST_VECTORS {
+            let path = vector.path.parse().unwrap();
+            let extended_seed = ExtendedSeed::new_master(vector.secret)
+                .unwrap()
+                .derive_path(&path)
+                .unwrap();
+            assert_eq!(extended_seed.chain_code(), vector.chain_code);
+            assert_eq!(extended_seed.seed().as_secret_slice(), vector.seed);
+            assert_eq!(
+                derive_seed(vector.secret, &path).unwrap().as_secret_slice(),
+                vector.seed
+            );
+        }
+    }
+
+    #[test]
+    fn hardened_only_paths() {
+        let master = ExtendedSeed::new_master(&[0x42; 32]).unwrap();
+        let path = "m/44h/118H/0'".parse::<DerivationPath>().unwrap();
+        assert_eq!(path.to_string(), "m/44'/118'/0'");
+        assert_eq!(
+            path,
+            DerivationPath::default().child(44).child(118).child(0)
+        );
+        let child = master.derive_path(&path).unwrap();
+        assert_eq!(child.depth(), 3);
+        assert_eq!(child.child_index(), HARDENED_FLAG);
+        assert_eq!(
+            child.seed().as_secret_slice(),
+            master
+                .derive_child(44)
+                .unwrap()
+                .derive_child(118 | HARDENED_FLAG)
+                .unwrap()
+                .derive_child(0)
+                .unwrap()
+                .seed()
+                .as_secret_slice()
+        );
+
+        for invalid in &[
+            "",
+            "44'",
+            "m/0",
+            "m/0'/1",
+            "m/-1'",
+            "m/2147483648'",
+            "m//0'",
+        ] {
+            assert!(invalid.parse::<DerivationPath>().is_err(), "{}", invalid);
+        }
+        assert!(ExtendedSeed::new_master(&[0x42; 15]).is_err());
+    }
+}
@@ -0,0 +1,108 @@
+//! SLIP-0010 test vectors for Ed25519 (test vectors 1 and 2)
+
+/// SLIP-0010 test vector: a key derived from a master secret along a path
+pub struct TestVector {
+    /// Master secret
+    pub secret: &'static [u8],
+
+    /// Derivation path
+    pub path: &'static str,
+
+    /// Chain code of the derived key
+    pub chain_code: &'static [u8],
+
+    /// Derived Ed25519 seed (SLIP-0010 "private key")
+    pub seed: &'static [u8],
+
+    /// Ed25519 public key of the derived seed
+    pub public_key: &'static [u8],
+}
+
+/// SLIP-0010 Ed25519 test vectors (converted to Rust bytestring literals)
+#[rustfmt::skip]
+pub const TEST_VECTORS: &[TestVector] = &[
+    TestVector {
+        secret: b"\x00\x01\x02\x03\x04\x05\x06\x07\x08\x09\x0A\x0B\x0C\x0D\x0E\x0F",
+        path: "m",
+        chain_code: b"\x90\x04\x6A\x93\xDE\x53\x80\xA7\x2B\x5E\x45\x01\x07\x48\x56\x7D\x5E\xA0\x2B\xBF\x65\x22\xF9\x79\xE0\x5C\x0D\x8D\x8C\xA9\xFF\xFB",
+        seed: b"\x2B\x4B\xE7\xF1\x9E\xE2\x7B\xBF\x30\xC6\x67\xB6\x42\xD5\xF4\xAA\x69\xFD\x16\x98\x72\xF8\xFC\x30\x59\xC0\x8E\xBA\xE2\xEB\x19\xE7",
+        public_key: b"\xA4\xB2\x85\x6B\xFE\xC5\x10\xAB\xAB\x89\x75\x3F\xAC\x1A\xC0\xE1\x11\x23\x64\xE7\xD2\x50\x54\x59\x63\xF1\x35\xF2\xA3\x31\x88\xED",
+    },
+    TestVector {
+        secret: b"\x00\x01\x02\x03\x04\x05\x06\x07\x08\x09\x0A\x0B\x0C\x0D\x0E\x0F",
+        path: "m/0'",
+        chain_code: b"\x8B\x59\xAA\x11\x38\x0B\x62\x4E\x81\x50\x7A\x27\xFE\xDD\xA5\x9F\xEA\x6D\x0B\x77\x9A\x77\x89\x18\xA2\xFD\x35\x90\xE1\x6E\x9C\x69",
+        seed: b"\x68\xE0\xFE\x46\xDF\xB6\x7E\x36\x8C\x75\x37\x9A\xCE\xC5\x91\xDA\xD1\x9D\xF3\xCD\xE2\x6E\x63\xB9\x3A\x8E\x70\x4F\x1D\xAD\xE7\xA3",
+        public_key: b"\x8C\x8A\x13\xDF\x77\xA2\x8F\x34\x45\x21\x3A\x0F\x43\x2F\xDE\x64\x4A\xCA\xA2\x15\xFC\x72\xDC\xDF\x30\x0D\x5E\xFA\xA8\x5D\x35\x0C",
+    },
+    TestVector {
+        secret: b"\x00\x01\x02\x03\x04\x05\x06\x07\x08\x09\x0A\x0B\x0C\x0D\x0E\x0F",
+        path: "m/0'/1'",
+        chain_code: b"\xA3\x20\x42\x5F\x77\xD1\xB5\xC2\x50\x5A\x6B\x1B\x27\x38\x2B\x37\x36\x8E\xE6\x40\xE3\x55\x7C\x31\x54\x16\x80\x12\x43\x55\x2F\x14",
+        seed: b"\xB1\xD0\xBA\xD4\x04\xBF\x35\xDA\x78\x5A\x64\xCA\x1A\xC5\x4B\x26\x17\x21\x1D\x27\x77\x69\x6F\xBF\xFA\xF2\x08\xF7\x46\xAE\x84\xF2",
+        public_key: b"\x19\x32\xA5\x27\x0F\x33\x5B\xED\x61\x7D\x5B\x93\x5C\x80\xAE\xDB\x1A\x35\xBD\x9F\xC1\xE3\x1A\xCA\xFD\x53\x72\xC3\x0F\x5C\x11\x87",
+    },
+    TestVector {
+        secret: b"\x00\x01\x02\x03\x04\x05\x06\x07\x08\x09\x0A\x0B\x0C\x0D\x0E\x0F",
+        path: "m/0'/1'/2'",
+        chain_code: b"\x2E\x69\x92\x9E\x00\xB5\xAB\x25\x0F\x49\xC3\xFB\x1C\x12\xF2\x52\xDE\x4F\xED\x2C\x1D\xB8\x83\x87\x09\x4A\x0F\x8C\x4C\x9C\xCD\x6C",
+        seed: b"\x92\xA5\xB2\x3C\x0B\x8A\x99\xE3\x7D\x07\xDF\x3F\xB9\x96\x69\x17\xF5\xD0\x6E\x02\xDD\xBD\x90\x9C\x7E\x18\x43\x71\x46\x3E\x9F\xC9",
+        public_key: b"\xAE\x98\x73\x65\x66\xD3\x0E\xD0\xE9\xD2\xF4\x48\x6A\x64\xBC\x95\x74\x0D\x89\xC7\xDB\x33\xF5\x21\x21\xF8\xEA\x8F\x76\xFF\x0F\xC1",
+    },
+    TestVector {
+        secret: b"\x00\x01\x02\x03\x04\x05\x06\x07\x08\x09\x0A\x0B\x0C\x0D\x0E\x0F",
+        path: "m/0'/1'/2'/2'",
+        chain_code: b"\x8F\x6D\x87\xF9\x3D\x75\x0E\x0E\xFC\xCD\xA0\x17\xD6\x62\xA1\xB3\x1A\x26\x6E\x4A\x6F\x59\x93\xB1\x5F\x5C\x1F\x07\xF7\x4D\xD5\xCC",
+        seed: b"\x30\xD1\xDC\x7E\x5F\xC0\x4C\x31\x21\x9A\xB2\x5A\x27\xAE\x00\xB5\x0F\x6F\xD6\x66\x22\xF6\xE9\xC9\x13\x25\x3D\x65\x11\xD1\xE6\x62",
+        public_key: b"\x8A\xBA\xE2\xD6\x63\x61\xC8\x79\xB9\x00\xD2\x04\xAD\x2C\xC4\x98\x4F\xA2\xAA\x34\x4D\xD7\xDD\xC4\x60\x07\x32\x9A\xC7\x6C\x42\x9C",
+    },
+    TestVector {
+        secret: b"\x00\x01\x02\x03\x04\x05\x06\x07\x08\x09\x0A\x0B\x0C\x0D\x0E\x0F",
+        path: "m/0'/1'/2'/2'/1000000000'",
+        chain_code: b"\x68\x78\x99\x23\xA0\xCA\xC2\xCD\x5A\x29\x17\x2A\x47\x5F\xE9\xE0\xFB\x14\xCD\x6A\xDB\x5A\xD9\x8A\x3F\xA7\x03\x33\xE7\xAF\xA2\x30",
+        seed: b"\x8F\x94\xD3\x94\xA8\xE8\xFD\x6B\x1B\xC2\xF3\xF4\x9F\x5C\x47\xE3\x85\x28\x1D\x5C\x17\xE6\x53\x24\xB0\xF6\x24\x83\xE3\x7E\x87\x93",
+        public_key: b"\x3C\x24\xDA\x04\x94\x51\x55\x5D\x51\xA7\x01\x4A\x37\x33\x7A\xA4\xE1\x2D\x41\xE4\x85\xAB\xCC\xFA\x46\xB4\x7D\xFB\x2A\xF5\x4B\x7A",
+    },
+    TestVector {
+        secret: b"\xFF\xFC\xF9\xF6\xF3\xF0\xED\xEA\xE7\xE4\xE1\xDE\xDB\xD8\xD5\xD2\xCF\xCC\xC9\xC6\xC3\xC0\xBD\xBA\xB7\xB4\xB1\xAE\xAB\xA8\xA5\xA2\x9F\x9C\x99\x96\x93\x90\x8D\x8A\x87\x84\x81\x7E\x7B\x78\x75\x72\x6F\x6C\x69\x66\x63\x60\x5D\x5A\x57\x54\x51\x4E\x4B\x48\x45\x42",
+        path: "m",
+        chain_code: b"\xEF\x70\xA7\x4D\xB9\xC3\xA5\xAF\x93\x1B\x5F\xE7\x3E\xD8\xE1\xA5\x34\x64\x13\x36\x54\xFD\x55\xE7\xA6\x6F\x85\x70\xB8\xE3\x3C\x3B",
+        seed: b"\x17\x1C\xB8\x8B\x1B\x3C\x1D\xB2\x5A\xDD\x59\x97\x12\xE3\x62\x45\xD7\x5B\xC6\x5A\x1A\x5C\x9E\x18\xD7\x6F\x9F\x2B\x1E\xAB\x40\x12",
+        public_key: b"\x8F\xE9\x69\x3F\x8F\xA6\x2A\x43\x05\xA1\x40\xB9\x76\x4C\x5E\xE0\x1E\x45\x59\x63\x74\x4F\xE1\x82\x04\xB4\xFB\x94\x82\x49\x30\x8A",
+    },
+    TestVector {
+        secret: b"\xFF\xFC\xF9\xF6\xF3\xF0\xED\xEA\xE7\xE4\xE1\xDE\xDB\xD8\xD5\xD2\xCF\xCC\xC9\xC6\xC3\xC0\xBD\xBA\xB7\xB4\xB1\xAE\xAB\xA8\xA5\xA2\x9F\x9C\x99\x96\x93\x90\x8D\x8A\x87\x84\x81\x7E\x7B\x78\x75\x72\x6F\x6C\x69\x66\x63\x60\x5D\x5A\x57\x54\x51\x4E\x4B\x48\x45\x42",
+        path: "m/0'",
+        chain_code: b"\x0B\x78\xA3\x22\x6F\x91\x5C\x08\x2B\xF1\x18\xF8\x36\x18\xA6\x18\xAB\x6D\xEC\x79\x37\x52\x62\x4C\xBE\xB6\x22\xAC\xB5\x62\x86\x2D",
+        seed: b"\x15\x59\xEB\x2B\xBE\xC5\x79\x0B\x0C\x65\xD8\x69\x3E\x4D\x08\x75\xB1\x74\x7F\x49\x70\xAE\x8B\x65\x04\x86\xED\x74\x70\x84\x56\x35",
+        public_key: b"\x86\xFA\xB6\x8D\xCB\x57\xAA\x19\x6C\x77\xC5\xF2\x64\xF2\x15\xA1\x12\xC2\x2A\x91\x2C\x10\xD1\x23\xB0\xD0\x3C\x3C\x28\xEF\x10\x37",
+    },
+    TestVector {
+        secret: b"\xFF\xFC\xF9\xF6\xF3\xF0\xED\xEA\xE7\xE4\xE1\xDE\xDB\xD8\xD5\xD2\xCF\xCC\xC9\xC6\xC3\xC0\xBD\xBA\xB7\xB4\xB1\xAE\xAB\xA8\xA5\xA2\x9F\x9C\x99\x96\x93\x90\x8D\x8A\x87\x84\x81\x7E\x7B\x78\x75\x72\x6F\x6C\x69\x66\x63\x60\x5D\x5A\x57\x54\x51\x4E\x4B\x48\x45\x42",
+        path: "m/0'/2147483647'",
+        chain_code: b"\x13\x8F\x0B\x25\x51\xBC\xAF\xEC\xA6\xFF\x2A\xA8\x8B\xA8\xED\x0E\xD8\xDE\x07\x08\x41\xF0\xC4\xEF\x01\x65\xDF\x81\x81\xEA\xAD\x7F",
+        seed: b"\xEA\x4F\x5B\xFE\x86\x94\xD8\xBB\x74\xB7\xB5\x94\x04\x63\x2F\xD5\x96\x8B\x77\x4E\xD5\x45\xE8\x10\xDE\x9C\x32\xA4\xFB\x41\x92\xF4",
+        public_key: b"\x5B\xA3\xB9\xAC\x6E\x90\xE8\x3E\xFF\xCD\x25\xAC\x4E\x58\xA1\x36\x5A\x9E\x35\xA3\xD3\xAE\x5E\xB0\x7B\x9E\x4D\x90\xBC\xF7\x50\x6D",
+    },
+    TestVector {
+        secret: b"\xFF\xFC\xF9\xF6\xF3\xF0\xED\xEA\xE7\xE4\xE1\xDE\xDB\xD8\xD5\xD2\xCF\xCC\xC9\xC6\xC3\xC0\xBD\xBA\xB7\xB4\xB1\xAE\xAB\xA8\xA5\xA2\x9F\x9C\x99\x96\x93\x90\x8D\x8A\x87\x84\x81\x7E\x7B\x78\x75\x72\x6F\x6C\x69\x66\x63\x60\x5D\x5A\x57\x54\x51\x4E\x4B\x48\x45\x42",
+        path: "m/0'/2147483647'/1'",
+        chain_code: b"\x73\xBD\x9F\xFF\x1C\xFB\xDE\x33\xA1\xB8\x46\xC2\x70\x85\xF7\x11\xC0\xFE\x2D\x66\xFD\x32\xE1\x39\xD3\xEB\xC2\x8E\x5A\x4A\x6B\x90",
+        seed: b"\x37\x57\xC7\x57\x71\x70\x17\x9C\x78\x68\x35\x3A\xDA\x79\x6C\x83\x91\x35\xB3\xD3\x05\x54\xBB\xB7\x4A\x4B\x1E\x4A\x5A\x58\x50\x5C",
+        public_key: b"\x2E\x66\xAA\x57\x06\x9C\x86\xCC\x18\x24\x9A\xEC\xF5\xCB\x5A\x9C\xEB\xBF\xD6\xFA\xDE\xAB\x05\x62\x54\x76\x38\x74\xA9\x35\x2B\x45",
+    },
+    TestVector {
+        secret: b"\xFF\xFC\xF9\xF6\xF3\xF0\xED\xEA\xE7\xE4\xE1\xDE\xDB\xD8\xD5\xD2\xCF\xCC\xC9\xC6\xC3\xC0\xBD\xBA\xB7\xB4\xB1\xAE\xAB\xA8\xA5\xA2\x9F\x9C\x99\x96\x93\x90\x8D\x8A\x87\x84\x81\x7E\x7B\x78\x75\x72\x6F\x6C\x69\x66\x63\x60\x5D\x5A\x57\x54\x51\x4E\x4B\x48\x45\x42",
+        path: "m/0'/2147483647'/1'/2147483646'",
+        chain_code: b"\x09\x02\xFE\x8A\x29\xF9\x14\x04\x80\xA0\x0E\xF2\x44\xBD\x18\x3E\x8A\x13\x28\x8E\x44\x12\xD8\x38\x9D\x14\x0A\xAC\x17\x94\x82\x5A",
+        seed: b"\x58\x37\x73\x6C\x89\x57\x0D\xE8\x61\xEB\xC1\x73\xB1\x08\x6D\xA4\xF5\x05\xD4\xAD\xB3\x87\xC6\xA1\xB1\x34\x2D\x5E\x4A\xC9\xEC\x72",
+        public_key: b"\xE3\x3C\x0F\x7D\x81\xD8\x43\xC5\x72\x27\x5F\x28\x74\x98\xE8\xD4\x08\x65\x4F\xDF\x0D\x1E\x06\x5B\x84\xE2\xE6\xF1\x57\xAA\xB0\x9B",
+    },
+    TestVector {
+        secret: b"\xFF\xFC\xF9\xF6\xF3\xF0\xED\xEA\xE7\xE4\xE1\xDE\xDB\xD8\xD5\xD2\xCF\xCC\xC9\xC6\xC3\xC0\xBD\xBA\xB7\xB4\xB1\xAE\xAB\xA8\xA5\xA2\x9F\x9C\x99\x96\x93\x90\x8D\x8A\x87\x84\x81\x7E\x7B\x78\x75\x72\x6F\x6C\x69\x66\x63\x60\x5D\x5A\x57\x54\x51\x4E\x4B\x48\x45\x42",
+        path: "m/0'/2147483647'/1'/2147483646'/2'",
+        chain_code: b"\x5D\x70\xAF\x78\x1F\x3A\x37\xB8\x29\xF0\xD0\x60\x92\x4D\x5E\x96\x0B\xDC\x02\xE8\x54\x23\x49\x4A\xFC\x0B\x1A\x41\xBB\xE1\x96\xD4",
+        seed: b"\x55\x1D\x33\x31\x77\xDF\x54\x1A\xD8\x76\xA6\x0E\xA7\x1F\x00\x44\x79\x31\xC0\xA9\xDA\x16\xF2\x27\xC1\x1E\xA0\x80\xD7\x39\x1B\x8D",
+        public_key: b"\x47\x15\x0C\x75\xDB\x26\x35\x59\xA7\x0D\x57\x78\xBF\x36\xAB\xBA\xB3\x0F\xB0\x61\xAD\x69\xF6\x9E\xCE\x61\xA7\x2B\x0C\xFA\x4F\xC0",
+    },
+];
@@ -6,10 +6,18 @@ macro_rules! ed25519_tests {
     ($signer:ident, $verifier:ident) => {
         use $crate::ed25519::{self, SIGNATURE_SIZE, TEST_VECTORS};
         use $crate::signature::{Signer as _, Verifier as _};
+        use $crate::test_vector::{TestVector, TestVectorAlgorithm};
+
+        /// Plain Ed25519 test vectors (i.e. without a context string)
+        fn ed25519_test_vectors() -> impl Iterator<Item = &'static TestVector> {
+            TEST_VECTORS
+                .iter()
+                .filter(|vector| vector.alg == TestVectorAlgorithm::Ed25519)
+        }
 
         #[test]
         fn sign_rfc8032_test_vectors() {
-            for vector in TEST_VECTORS {
+            for vector in ed25519_test_vectors() {
                 let seed = ed25519::Seed::from_bytes(vector.sk).unwrap();
                 let signer = $signer::from(&seed);
                 assert_eq!(signer.sign(vector.msg).as_ref(), vector.sig);
@@ -19,7 +27,7 @@ macro_rules! ed25519_tests {
         #[test]
         fn verify_rfc8032_test_vectors() {
             use $crate::signature::Signature;
-            for vector in TEST_VECTORS {
+            for vector in ed25519_test_vectors() {
                 let pk = ed25519::PublicKey::from_bytes(vector.pk).unwrap();
                 let verifier = $verifier::from(&pk);
                 let sig = ed25519::Signature::from_bytes(vector.sig).unwrap();
@@ -33,7 +41,7 @@ macro_rules! ed25519_tests {
         #[test]
         fn rejects_tweaked_rfc8032_test_vectors() {
             use $crate::signature::Signature;
-            for vector in TEST_VECTORS {
+            for vector in ed25519_test_vectors() {
                 let pk = ed25519::PublicKey::from_bytes(vector.pk).unwrap();
                 let verifier = $verifier::from(&pk);
 
@@ -54,3 +62,276 @@ macro_rules! ed25519_tests {
         }
     };
 }
+
+/// Generate tests for verifying Ed25519ctx and Ed25519ph signatures
+#[macro_export]
+macro_rules! ed25519_context_verifier_tests {
+    ($verifier:ident) => {
+        /// Verify an Ed25519ctx or Ed25519ph test vector's signature with the
+        /// given algorithm and context string
+        fn verify_with_context(
+            verifier: &$verifier,
+            vector: &$crate::test_vector::TestVector,
+            alg: $crate::test_vector::TestVectorAlgorithm,
+            context: &[u8],
+        ) -> Result<(), $crate::signature::Error> {
+            use $crate::ed25519::{self, Context, ContextVerifier as _};
+            use $crate::sha2::{Digest as _, Sha512};
+            use $crate::signature::Signature;
+            use $crate::test_vector::TestVectorAlgorithm;
+
+            let context = Context::new(context).unwrap();
+            let sig = ed25519::Signature::from_bytes(vector.sig).unwrap();
+            match alg {
+                TestVectorAlgorithm::Ed25519ctx => verifier.verify_ctx(context, vector.msg, &sig),
+                TestVectorAlgorithm::Ed25519ph => {
+                    verifier.verify_ph(context, Sha512::new().chain(vector.msg), &sig)
+                }
+                other => panic!("not a context string algorithm: {:?}", other),
+            }
+        }
+
+        #[test]
+        fn verify_rfc8032_ctx_ph_test_vectors() {
+            use $crate::ed25519::{self, TEST_VECTORS};
+
+            for vector in TEST_VECTORS
+                .iter()
+                .filter(|vector| vector.context.is_some())
+            {
+                let pk = ed25519::PublicKey::from_bytes(vector.pk).unwrap();
+                let verifier = $verifier::from(&pk);
+                assert!(
+                    verify_with_context(&verifier, vector, vector.alg, vector.context.unwrap())
+                        .is_ok(),
+                    "expected signature to verify"
+                );
+            }
+        }
+
+        #[test]
+        fn rejects_rfc8032_ctx_ph_test_vectors_in_other_domains() {
+            use $crate::ed25519::{self, TEST_VECTORS};
+            use $crate::signature::{Signature, Verifier as _};
+            use $crate::test_vector::TestVectorAlgorithm;
+
+            for vector in TEST_VECTORS
+                .iter()
+                .filter(|vector| vector.context.is_some())
+            {
+                let pk = ed25519::PublicKey::from_bytes(vector.pk).unwrap();
+                let verifier = $verifier::from(&pk);
+                let other_alg = match vector.alg {
+                    TestVectorAlgorithm::Ed25519ctx => TestVectorAlgorithm::Ed25519ph,
+                    _ => TestVectorAlgorithm::Ed25519ctx,
+                };
+
+                for (alg, context) in &[
+                    (vector.alg, &b"baz"[..]),
+                    (other_alg, vector.context.unwrap()),
+                ] {
+                    assert!(
+                        verify_with_context(&verifier, vector, *alg, context).is_err(),
+                        "expected signature verification failure but it succeeded"
+                    );
+                }
+
+                let sig = ed25519::Signature::from_bytes(vector.sig).unwrap();
+                assert!(
+                    verifier.verify(vector.msg, &sig).is_err(),
+                    "expected signature verification failure but it succeeded"
+                );
+            }
+        }
+    };
+}
+
+/// Generate tests for signers of seeds derived with SLIP-10
+#[macro_export]
+macro_rules! ed25519_slip10_tests {
+    ($signer:ident) => {
+        #[test]
+        fn slip10_derived_seeds() {
+            use $crate::ed25519::slip10;
+            use $crate::public_key::PublicKeyed;
+
+            for vector in slip10::TEST_VECTORS {
+                let path = vector.path.parse().unwrap();
+                let seed = slip10::derive_seed(vector.secret, &path).unwrap();
+                let signer = $signer::from(&seed);
+                assert_eq!(signer.public_key().unwrap().as_ref(), vector.public_key);
+            }
+        }
+    };
+}
+
+/// Generate tests for signing with Ed25519ctx and Ed25519ph
+#[macro_export]
+macro_rules! ed25519_context_signer_tests {
+    ($signer:ident) => {
+        #[test]
+        fn sign_rfc8032_ctx_ph_test_vectors() {
+            use $crate::ed25519::{self, Context, ContextSigner as _, TEST_VECTORS};
+            use $crate::sha2::{Digest as _, Sha512};
+            use $crate::test_vector::TestVectorAlgorithm;
+
+            for vector in TEST_VECTORS
+                .iter()
+                .filter(|vector| vector.context.is_some())
+            {
+                let seed = ed25519::Seed::from_bytes(vector.sk).unwrap();
+                let signer = $signer::from(&seed);
+                let context = Context::new(vector.context.unwrap()).unwrap();
+                let sig = match vector.alg {
+                    TestVectorAlgorithm::Ed25519ctx => signer.try_sign_ctx(context, vector.msg),
+                    TestVectorAlgorithm::Ed25519ph => {
+                        signer.try_sign_ph(context, Sha512::new().chain(vector.msg))
+                    }
+                    other => panic!("not a context string algorithm: {:?}", other),
+                };
+                assert_eq!(sig.unwrap().as_ref(), vector.sig);
+            }
+        }
+    };
+}
+
+/// Generate tests for Ed25519 batch verification
+#[macro_export]
+macro_rules! ed25519_batch_tests {
+    ($verifier:ident) => {
+        #[test]
+        fn verify_batch_rfc8032_test_vectors() {
+            use $crate::ed25519::{self, BatchVerifier as _, SIGNATURE_SIZE, TEST_VECTORS};
+            use $crate::signature::Signature as _;
+
+            let public_key = |i: usize| ed25519::PublicKey::from_bytes(TEST_VECTORS[i].pk).unwrap();
+            let public_keys = [public_key(0), public_key(1), public_key(2)];
+            let signature = |i: usize| ed25519::Signature::from_bytes(TEST_VECTORS[i].sig).unwrap();
+            let mut signatures = [signature(0), signature(1), signature(2)];
+            let items = [
+                (&public_keys[0], TEST_VECTORS[0].msg, &signatures[0]),
+                (&public_keys[1], TEST_VECTORS[1].msg, &signatures[1]),
+                (&public_keys[2], TEST_VECTORS[2].msg, &signatures[2]),
+            ];
+            assert!($verifier::verify_batch(&items).is_ok());
+            assert!($verifier::verify_batch(&[]).is_ok());
+
+            let mut tweaked_sig = [0u8; SIGNATURE_SIZE];
+            tweaked_sig.copy_from_slice(TEST_VECTORS[1].sig);
+            tweaked_sig[0] ^= 0x42;
+            signatures[1] = ed25519::Signature::from_bytes(&tweaked_sig[..]).unwrap();
+            let items = [
+                (&public_keys[0], TEST_VECTORS[0].msg, &signatures[0]),
+                (&public_keys[1], TEST_VECTORS[1].msg, &signatures[1]),
+                (&public_keys[2], TEST_VECTORS[0].msg, &signatures[2]),
+            ];
+            let error = $verifier::verify_batch(&items).unwrap_err();
+            assert_eq!(error.invalid_indices(), &[1, 2][..]);
+        }
+
+        #[test]
+        fn verify_batch_verification_mode_test_vectors() {
+            use $crate::ed25519::{
+                self,
+                verification_mode::{VerificationMode, TEST_VECTORS},
+                BatchVerifier as _,
+            };
+            use $crate::signature::{Signature as _, Verifier as _};
+
+            for vector in TEST_VECTORS {
+                let pk = ed25519::PublicKey::from_bytes(vector.pk).unwrap();
+                let sig = ed25519::Signature::from_bytes(vector.sig).unwrap();
+                // more than one item, so that batch verification is used
+                let items = [(&pk, vector.msg, &sig), (&pk, vector.msg, &sig)];
+
+                // a batch accepts exactly what individual verification does
+                assert_eq!(
+                    $verifier::verify_batch(&items).is_ok(),
+                    $verifier::from(&pk).verify(vector.msg, &sig).is_ok(),
+                    "{}",
+                    vector.description
+                );
+
+                for (mode, expected) in &[
+                    (VerificationMode::Rfc8032, vector.rfc8032),
+                    (VerificationMode::Strict, vector.strict),
+                    (VerificationMode::Zip215, vector.zip215),
+                ] {
+                    assert_eq!(
+                        $verifier::verify_batch_with_mode(*mode, &items).is_ok(),
+                        *expected,
+                        "{:?}: {}",
+                        mode,
+                        vector.description
+                    );
+                }
+            }
+        }
+
+        #[test]
+        fn verify_batch_rejects_torsion_rejected_by_verify() {
+            use $crate::ed25519::{
+                self,
+                verification_mode::{VerificationMode, TEST_VECTORS},
+                BatchVerifier as _,
+            };
+            use $crate::signature::{Signature as _, Verifier as _};
+
+            // only valid with the cofactored equation
+            let vector = TEST_VECTORS
+                .iter()
+                .find(|vector| vector.description == "mixed-order R")
+                .unwrap();
+            let pk = ed25519::PublicKey::from_bytes(vector.pk).unwrap();
+            let sig = ed25519::Signature::from_bytes(vector.sig).unwrap();
+            let items = [(&pk, vector.msg, &sig), (&pk, vector.msg, &sig)];
+
+            let verifier = $verifier::with_mode(&pk, VerificationMode::Strict);
+            assert!(verifier.verify(vector.msg, &sig).is_err());
+            let error =
+                $verifier::verify_batch_with_mode(VerificationMode::Strict, &items).unwrap_err();
+            assert_eq!(error.invalid_indices(), &[0, 1][..]);
+
+            if $verifier::from(&pk).verify(vector.msg, &sig).is_err() {
+                let error = $verifier::verify_batch(&items).unwrap_err();
+                assert_eq!(error.invalid_indices(), &[0, 1][..]);
+            }
+        }
+    };
+}
+
+/// Generate tests for Ed25519 verification modes, which every provider must
+/// pass with the same results
+#[macro_export]
+macro_rules! ed25519_verification_mode_tests {
+    ($verifier:ident) => {
+        #[test]
+        fn verification_mode_test_vectors() {
+            use $crate::ed25519::{
+                self,
+                verification_mode::{VerificationMode, TEST_VECTORS},
+            };
+            use $crate::signature::{Signature as _, Verifier as _};
+
+            for vector in TEST_VECTORS {
+                let pk = ed25519::PublicKey::from_bytes(vector.pk).unwrap();
+                let sig = ed25519::Signature::from_bytes(vector.sig).unwrap();
+
+                for (mode, expected) in &[
+                    (VerificationMode::Rfc8032, vector.rfc8032),
+                    (VerificationMode::Strict, vector.strict),
+                    (VerificationMode::Zip215, vector.zip215),
+                ] {
+                    let verifier = $verifier::with_mode(&pk, *mode);
+                    assert_eq!(
+                        verifier.verify(vector.msg, &sig).is_ok(),
+                        *expected,
+                        "{:?}: {}",
+                        mode,
+                        vector.description
+                    );
+                }
+            }
+        }
+    };
+}
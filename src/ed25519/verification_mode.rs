@@ -0,0 +1,282 @@
+//! Ed25519 verification modes: which signatures are valid in edge cases
+//!
+//! Implementations disagree on non-canonical encodings, small-order points
+//! and whether to check the cofactored equation, which is a consensus
+//! hazard. Each mode pins these down, and every provider verifier gives the
+//! same answers in the same mode:
+//!
+//! | Mode      | Non-canonical `S` | Non-canonical `A`, `R` | Small-order `A`, `R` | Equation     |
+//! |-----------|-------------------|------------------------|----------------------|--------------|
+//! | `Rfc8032` | rejected          | rejected               | accepted             | cofactored   |
+//! | `Strict`  | rejected          | rejected               | rejected             | cofactorless |
+//! | `Zip215`  | rejected          | accepted               | accepted             | cofactored   |
+//!
+//! `Strict` is ed25519-dalek's `verify_strict`, and uses the providers'
+//! own (cofactorless) verification. Providers don't implement the
+//! cofactored equation, which is checked by `verify_cofactored` instead
+//! (with the `ed25519-cofactored` feature). Ed25519ctx and Ed25519ph
+//! signatures are checked by Signatory in every mode, with `verify_ctx` and
+//! `verify_ph`.
+//!
+//! Verifiers created `From` a public key keep their provider's own rules,
+//! which differ between providers: use `with_mode` where it matters.
+
+#[cfg(feature = "test-vectors")]
+mod test_vectors;
+
+#[cfg(feature = "test-vectors")]
+pub use self::test_vectors::{TestVector, TEST_VECTORS};
+
+#[cfg(feature = "ed25519-cofactored")]
+use super::{cofactored::verify_equation, Context};
+use super::{PublicKey, Signature};
+#[cfg(feature = "ed25519-cofactored")]
+use sha2::{Digest, Sha512};
+use signature::Error;
+
+/// Order of the prime-order subgroup, little endian:
+/// 2^252 + 27742317777372353535851937790883648493
+const ORDER: [u8; 32] = [
+    0xed, 0xd3, 0xf5, 0x5c, 0x1a, 0x63, 0x12, 0x58, 0xd6, 0x9c, 0xf7, 0xa2, 0xde, 0xf9, 0xde, 0x14,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10,
+];
+
+/// Canonical encodings of the points of small order (i.e. dividing 8)
+const SMALL_ORDER_POINTS: [[u8; 32]; 8] = [
+    // order 1
+    [
+        0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00,
+    ],
+    // order 2
+    [
+        0xec, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0xff, 0x7f,
+    ],
+    // order 4
+    [
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00,
+    ],
+    [
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x80,
+    ],
+    // order 8
+    [
+        0x26, 0xe8, 0x95, 0x8f, 0xc2, 0xb2, 0x27, 0xb0, 0x45, 0xc3, 0xf4, 0x89, 0xf2, 0xef, 0x98,
+        0xf0, 0xd5, 0xdf, 0xac, 0x05, 0xd3, 0xc6, 0x33, 0x39, 0xb1, 0x38, 0x02, 0x88, 0x6d, 0x53,
+        0xfc, 0x05,
+    ],
+    [
+        0x26, 0xe8, 0x95, 0x8f, 0xc2, 0xb2, 0x27, 0xb0, 0x45, 0xc3, 0xf4, 0x89, 0xf2, 0xef, 0x98,
+        0xf0, 0xd5, 0xdf, 0xac, 0x05, 0xd3, 0xc6, 0x33, 0x39, 0xb1, 0x38, 0x02, 0x88, 0x6d, 0x53,
+        0xfc, 0x85,
+    ],
+    [
+        0xc7, 0x17, 0x6a, 0x70, 0x3d, 0x4d, 0xd8, 0x4f, 0xba, 0x3c, 0x0b, 0x76, 0x0d, 0x10, 0x67,
+        0x0f, 0x2a, 0x20, 0x53, 0xfa, 0x2c, 0x39, 0xcc, 0xc6, 0x4e, 0xc7, 0xfd, 0x77, 0x92, 0xac,
+        0x03, 0x7a,
+    ],
+    [
+        0xc7, 0x17, 0x6a, 0x70, 0x3d, 0x4d, 0xd8, 0x4f, 0xba, 0x3c, 0x0b, 0x76, 0x0d, 0x10, 0x67,
+        0x0f, 0x2a, 0x20, 0x53, 0xfa, 0x2c, 0x39, 0xcc, 0xc6, 0x4e, 0xc7, 0xfd, 0x77, 0x92, 0xac,
+        0x03, 0xfa,
+    ],
+];
+
+/// Rules of Ed25519 signature verification in edge cases
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum VerificationMode {
+    /// RFC 8032: canonical encodings and the cofactored equation
+    Rfc8032,
+
+    /// ed25519-dalek's `verify_strict`: canonical encodings, no small-order
+    /// points and the cofactorless equation
+    Strict,
+
+    /// ZIP-215 (Zcash): any encoding of a point and the cofactored equation
+    Zip215,
+}
+
+impl VerificationMode {
+    /// Check the encodings of a public key and signature against this mode,
+    /// before any curve arithmetic
+    pub fn check_encodings(
+        self,
+        public_key: &PublicKey,
+        signature: &Signature,
+    ) -> Result<(), Error> {
+        let (r, s) = split_signature(signature);
+        if !is_canonical_scalar(&s) {
+            return Err(Error::new());
+        }
+
+        match self {
+            VerificationMode::Rfc8032 => {
+                if !is_canonical_point(public_key.as_bytes()) || !is_canonical_point(&r) {
+                    return Err(Error::new());
+                }
+            }
+            VerificationMode::Strict => {
+                for point in &[public_key.as_bytes(), &r] {
+                    if !is_canonical_point(point) || SMALL_ORDER_POINTS.contains(point) {
+                        return Err(Error::new());
+                    }
+                }
+            }
+            VerificationMode::Zip215 => (),
+        }
+
+        Ok(())
+    }
+
+    /// Whether this mode checks the cofactored equation (with
+    /// `verify_cofactored`) rather than providers' cofactorless one
+    pub fn is_cofactored(self) -> bool {
+        self != VerificationMode::Strict
+    }
+
+    /// Verify an Ed25519ctx signature in this mode
+    #[cfg(feature = "ed25519-cofactored")]
+    pub fn verify_ctx(
+        self,
+        public_key: &PublicKey,
+        context: Context<'_>,
+        msg: &[u8],
+        signature: &Signature,
+    ) -> Result<(), Error> {
+        self.check_encodings(public_key, signature)?;
+        verify_equation(
+            public_key,
+            context.hasher(false),
+            msg,
+            signature,
+            self.is_cofactored(),
+        )
+    }
+
+    /// Verify an Ed25519ph signature in this mode
+    #[cfg(feature = "ed25519-cofactored")]
+    pub fn verify_ph(
+        self,
+        public_key: &PublicKey,
+        context: Context<'_>,
+        digest: Sha512,
+        signature: &Signature,
+    ) -> Result<(), Error> {
+        self.check_encodings(public_key, signature)?;
+        verify_equation(
+            public_key,
+            context.hasher(true),
+            &digest.result(),
+            signature,
+            self.is_cofactored(),
+        )
+    }
+}
+
+/// Split a signature into the encoding of `R` and the scalar `S`
+fn split_signature(signature: &Signature) -> ([u8; 32], [u8; 32]) {
+    let mut r = [0u8; 32];
+    let mut s = [0u8; 32];
+    r.copy_from_slice(&signature.as_ref()[..32]);
+    s.copy_from_slice(&signature.as_ref()[32..]);
+    (r, s)
+}
+
+/// Whether a little-endian scalar is below the order of the base point
+fn is_canonical_scalar(scalar: &[u8; 32]) -> bool {
+    for (byte, order_byte) in scalar.iter().zip(ORDER.iter()).rev() {
+        if byte != order_byte {
+            return byte < order_byte;
+        }
+    }
+    false
+}
+
+/// Whether a point encoding is canonical: its Y coordinate is below
+/// `p = 2^255 - 19`, and its sign bit is clear if its X coordinate is zero
+/// (i.e. if `y = 1` or `y = -1`)
+fn is_canonical_point(bytes: &[u8; 32]) -> bool {
+    let mut y = *bytes;
+    y[31] &= 0x7f;
+    let sign = bytes[31] >> 7 == 1;
+
+    // y >= p if and only if its encoding is 0xed..=0xff, 0xff * 30, 0x7f
+    if y[31] == 0x7f && y[1..31].iter().all(|byte| *byte == 0xff) && y[0] >= 0xed {
+        return false;
+    }
+
+    !(sign && (y == SMALL_ORDER_POINTS[0] || y == SMALL_ORDER_POINTS[1]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(all(feature = "ed25519-cofactored", feature = "test-vectors"))]
+    #[test]
+    fn cofactored_modes_test_vectors() {
+        use crate::ed25519::verify_cofactored;
+        use signature::Signature as _;
+
+        for vector in TEST_VECTORS {
+            let public_key = PublicKey::from_bytes(vector.pk).unwrap();
+            let signature = Signature::from_bytes(vector.sig).unwrap();
+
+            for (mode, expected) in &[
+                (VerificationMode::Rfc8032, vector.rfc8032),
+                (VerificationMode::Zip215, vector.zip215),
+            ] {
+                let result = mode
+                    .check_encodings(&public_key, &signature)
+                    .and_then(|()| verify_cofactored(&public_key, vector.msg, &signature));
+                assert_eq!(
+                    result.is_ok(),
+                    *expected,
+                    "{:?}: {}",
+                    mode,
+                    vector.description
+                );
+            }
+
+            // strict mode rejects every signature its checks reject
+            if VerificationMode::Strict
+                .check_encodings(&public_key, &signature)
+                .is_err()
+            {
+                assert!(!vector.strict, "{}", vector.description);
+            }
+        }
+    }
+
+    #[test]
+    fn canonical_scalars() {
+        let mut scalar = ORDER;
+        assert!(!is_canonical_scalar(&scalar));
+        scalar[0] -= 1;
+        assert!(is_canonical_scalar(&scalar));
+        assert!(!is_canonical_scalar(&[0xff; 32]));
+    }
+
+    #[test]
+    fn canonical_points() {
+        assert!(is_canonical_point(&SMALL_ORDER_POINTS[0]));
+        assert!(is_canonical_point(&SMALL_ORDER_POINTS[3]));
+
+        // y = p - 1 with the sign bit set, y = p and y = 2^255 - 1
+        let mut bytes = SMALL_ORDER_POINTS[1];
+        bytes[31] |= 0x80;
+        assert!(!is_canonical_point(&bytes));
+        bytes[0] = 0xed;
+        bytes[31] = 0x7f;
+        assert!(!is_canonical_point(&bytes));
+        bytes[0] = 0xff;
+        assert!(!is_canonical_point(&bytes));
+    }
+}
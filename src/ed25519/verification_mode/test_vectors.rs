@@ -0,0 +1,148 @@
+//! Ed25519 edge cases and whether they verify in each `VerificationMode`:
+//! non-canonical encodings, small-order and mixed-order points
+
+/// Signature which may or may not verify depending on the mode
+pub struct TestVector {
+    /// What the vector exercises
+    pub description: &'static str,
+
+    /// Public key
+    pub pk: &'static [u8],
+
+    /// Message
+    pub msg: &'static [u8],
+
+    /// Signature
+    pub sig: &'static [u8],
+
+    /// Whether the signature verifies with `VerificationMode::Rfc8032`
+    pub rfc8032: bool,
+
+    /// Whether the signature verifies with `VerificationMode::Strict`
+    pub strict: bool,
+
+    /// Whether the signature verifies with `VerificationMode::Zip215`
+    pub zip215: bool,
+}
+
+/// Ed25519 verification mode test vectors (converted to Rust bytestring literals)
+#[rustfmt::skip]
+pub const TEST_VECTORS: &[TestVector] = &[
+    TestVector {
+        description: "RFC 8032 test 1",
+        pk: b"\xD7\x5A\x98\x01\x82\xB1\x0A\xB7\xD5\x4B\xFE\xD3\xC9\x64\x07\x3A\x0E\xE1\x72\xF3\xDA\xA6\x23\x25\xAF\x02\x1A\x68\xF7\x07\x51\x1A",
+        msg: b"",
+        sig: b"\xE5\x56\x43\x00\xC3\x60\xAC\x72\x90\x86\xE2\xCC\x80\x6E\x82\x8A\x84\x87\x7F\x1E\xB8\xE5\xD9\x74\xD8\x73\xE0\x65\x22\x49\x01\x55\x5F\xB8\x82\x15\x90\xA3\x3B\xAC\xC6\x1E\x39\x70\x1C\xF9\xB4\x6B\xD2\x5B\xF5\xF0\x59\x5B\xBE\x24\x65\x51\x41\x43\x8E\x7A\x10\x0B",
+        rfc8032: true,
+        strict: true,
+        zip215: true,
+    },
+    TestVector {
+        description: "valid signature",
+        pk: b"\x88\xAA\x55\xE6\xAF\xEE\xD8\x96\x0A\x31\x91\xDE\x78\x60\x03\x47\xDB\x8A\xCD\x0A\x37\x95\x41\x2F\x27\x5F\x04\x9B\x86\x26\x04\x88",
+        msg: b"valid",
+        sig: b"\x8F\xF6\xBD\x69\x25\xE5\xBF\xA3\x13\x7C\xC2\x1E\x33\x33\x95\x5C\x0C\xB6\x2C\xEA\xF8\xA8\x90\x44\xA8\x10\xF9\xB5\x0D\xAB\xEA\x43\x0C\x21\x67\x0E\xA9\x68\xFB\x4F\x0E\x8F\xE4\xEC\x29\xB7\xE0\xC0\x57\x6D\x7E\x59\x38\xD6\x09\xD3\x14\x15\xEA\x9B\x6A\xB9\x49\x0F",
+        rfc8032: true,
+        strict: true,
+        zip215: true,
+    },
+    TestVector {
+        description: "wrong message",
+        pk: b"\x88\xAA\x55\xE6\xAF\xEE\xD8\x96\x0A\x31\x91\xDE\x78\x60\x03\x47\xDB\x8A\xCD\x0A\x37\x95\x41\x2F\x27\x5F\x04\x9B\x86\x26\x04\x88",
+        msg: b"invalid",
+        sig: b"\x8F\xF6\xBD\x69\x25\xE5\xBF\xA3\x13\x7C\xC2\x1E\x33\x33\x95\x5C\x0C\xB6\x2C\xEA\xF8\xA8\x90\x44\xA8\x10\xF9\xB5\x0D\xAB\xEA\x43\x0C\x21\x67\x0E\xA9\x68\xFB\x4F\x0E\x8F\xE4\xEC\x29\xB7\xE0\xC0\x57\x6D\x7E\x59\x38\xD6\x09\xD3\x14\x15\xEA\x9B\x6A\xB9\x49\x0F",
+        rfc8032: false,
+        strict: false,
+        zip215: false,
+    },
+    TestVector {
+        description: "non-canonical S (S + L)",
+        pk: b"\x88\xAA\x55\xE6\xAF\xEE\xD8\x96\x0A\x31\x91\xDE\x78\x60\x03\x47\xDB\x8A\xCD\x0A\x37\x95\x41\x2F\x27\x5F\x04\x9B\x86\x26\x04\x88",
+        msg: b"valid",
+        sig: b"\x8F\xF6\xBD\x69\x25\xE5\xBF\xA3\x13\x7C\xC2\x1E\x33\x33\x95\x5C\x0C\xB6\x2C\xEA\xF8\xA8\x90\x44\xA8\x10\xF9\xB5\x0D\xAB\xEA\x43\xF9\xF4\x5C\x6B\xC3\xCB\x0D\xA8\xE4\x2B\xDC\x8F\x08\xB1\xBF\xD5\x57\x6D\x7E\x59\x38\xD6\x09\xD3\x14\x15\xEA\x9B\x6A\xB9\x49\x1F",
+        rfc8032: false,
+        strict: false,
+        zip215: false,
+    },
+    TestVector {
+        description: "small-order A (identity)",
+        pk: b"\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00",
+        msg: b"small A",
+        sig: b"\x5C\x0C\x9C\x44\xA8\x68\xC1\xFD\x97\x71\x93\xE6\x66\x88\x01\x24\x5D\x54\x2B\x1F\x38\xF4\xCA\xD5\x47\x33\x92\xAF\xA0\xF7\x83\x2C\x33\x18\xBD\x9D\xA7\xCF\x6D\xB9\x49\x62\x00\x71\xA3\xE6\xB2\x04\x58\x82\x67\x39\xC0\xBF\x32\xCA\x4A\x1A\x05\x98\x9F\x9F\x44\x01",
+        rfc8032: true,
+        strict: false,
+        zip215: true,
+    },
+    TestVector {
+        description: "small-order A (order 8)",
+        pk: b"\xC7\x17\x6A\x70\x3D\x4D\xD8\x4F\xBA\x3C\x0B\x76\x0D\x10\x67\x0F\x2A\x20\x53\xFA\x2C\x39\xCC\xC6\x4E\xC7\xFD\x77\x92\xAC\x03\x7A",
+        msg: b"order 8 A 0",
+        sig: b"\x76\x30\xAB\xF5\x3A\x26\x26\x39\xCE\xDF\x04\xEA\x4A\xC6\xCA\xE4\x14\x20\x05\x40\x78\x17\x73\xC2\x3B\x34\x7E\x5E\x0E\x8D\xA1\x2A\x9F\xFD\xD2\x48\x10\x3F\xB2\xCD\x1D\x65\xF0\x37\x6F\xA5\xDC\xAC\x04\xE8\x13\xC5\x6A\x9B\x44\x22\x55\xDF\xE9\xFC\x40\x55\x44\x0E",
+        rfc8032: true,
+        strict: false,
+        zip215: true,
+    },
+    TestVector {
+        description: "mixed-order R",
+        pk: b"\x88\xAA\x55\xE6\xAF\xEE\xD8\x96\x0A\x31\x91\xDE\x78\x60\x03\x47\xDB\x8A\xCD\x0A\x37\x95\x41\x2F\x27\x5F\x04\x9B\x86\x26\x04\x88",
+        msg: b"mixed R",
+        sig: b"\x72\xFF\xB6\x00\x9B\xD7\xC3\x18\x47\xE9\x78\x02\x0D\xEC\x3B\xB2\x36\x87\xD3\xA7\x6B\x08\x8B\x4A\x55\x56\xD1\xB0\xB1\x95\x25\x2C\x5E\x79\xB9\x99\x53\x71\xB5\xB2\x72\x49\x5A\x60\x29\x5E\x2E\xFC\xC0\xC7\xD1\x6A\x41\xC8\xC1\x30\xFA\xD7\x2E\xF3\x9D\xA8\x03\x00",
+        rfc8032: true,
+        strict: false,
+        zip215: true,
+    },
+    TestVector {
+        description: "mixed-order A",
+        pk: b"\x99\x55\x5E\xFE\x5D\xBF\x5B\x50\xB6\xFB\x4D\x8C\x38\xEE\x05\xC4\xEA\x8A\x97\x01\x1C\x08\xE1\x13\x66\xE8\xC8\xC0\x0E\x8C\xE5\xD5",
+        msg: b"mixed A 0",
+        sig: b"\xA7\x0D\xA2\x7E\xDE\xC3\x49\x9B\x6A\x73\xC2\x1E\xA5\x44\x4F\x8A\xF2\x5B\x69\x1E\x22\xE7\x64\x33\xD5\x13\xD8\xA5\x84\x16\x7F\xAC\x54\xC7\x3D\x53\x24\x90\xC8\x7E\x84\x67\xE2\x30\xB0\x08\xF5\xB7\x29\x7F\xD6\xFB\x6E\xFE\xB3\xE1\x72\xDF\x1F\x3F\x44\xB0\x19\x0D",
+        rfc8032: true,
+        strict: false,
+        zip215: true,
+    },
+    TestVector {
+        description: "mixed-order A, k multiple of 8",
+        pk: b"\x99\x55\x5E\xFE\x5D\xBF\x5B\x50\xB6\xFB\x4D\x8C\x38\xEE\x05\xC4\xEA\x8A\x97\x01\x1C\x08\xE1\x13\x66\xE8\xC8\xC0\x0E\x8C\xE5\xD5",
+        msg: b"mixed A 10",
+        sig: b"\x1C\xCB\xB8\x36\x6A\x5A\x5A\xE3\x4C\xCA\x84\x06\x7E\x22\x12\xD1\xAF\x03\xD9\x55\x16\xAC\x40\x10\xE8\xCF\xD9\x90\x76\xC0\x4B\x55\xE5\x31\x81\x23\x73\x6F\xB9\x02\x90\x50\xBA\xDC\xC5\x1E\x55\xBE\xB1\x49\x26\x38\x57\x86\x7C\x87\x6A\x8B\xE6\x2F\x1C\x98\x63\x00",
+        rfc8032: true,
+        strict: true,
+        zip215: true,
+    },
+    TestVector {
+        description: "non-canonical A (y = p + 1)",
+        pk: b"\xEE\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\x7F",
+        msg: b"non-canonical A",
+        sig: b"\x2B\xC9\x84\x6D\x67\xC9\x8A\xB5\x3C\xAE\x3F\xB8\xE8\x79\xD0\x71\xDC\xBF\x4D\x8C\xC6\x72\x5C\x0D\xF1\x71\x44\xA1\xFD\xBB\xE2\x88\x83\xF7\xCE\x41\x7A\xDC\x02\xF7\x79\xAD\xAD\x32\x13\xF6\x02\xFD\x80\xCF\x4D\x80\x60\x75\x4B\x4C\xD9\x70\xF6\x25\x0B\x9D\xF8\x01",
+        rfc8032: false,
+        strict: false,
+        zip215: true,
+    },
+    TestVector {
+        description: "non-canonical A (x = 0 with sign bit)",
+        pk: b"\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x80",
+        msg: b"negative zero A",
+        sig: b"\x2B\xC9\x84\x6D\x67\xC9\x8A\xB5\x3C\xAE\x3F\xB8\xE8\x79\xD0\x71\xDC\xBF\x4D\x8C\xC6\x72\x5C\x0D\xF1\x71\x44\xA1\xFD\xBB\xE2\x88\x83\xF7\xCE\x41\x7A\xDC\x02\xF7\x79\xAD\xAD\x32\x13\xF6\x02\xFD\x80\xCF\x4D\x80\x60\x75\x4B\x4C\xD9\x70\xF6\x25\x0B\x9D\xF8\x01",
+        rfc8032: false,
+        strict: false,
+        zip215: true,
+    },
+    TestVector {
+        description: "non-canonical R (y = p + 1)",
+        pk: b"\x88\xAA\x55\xE6\xAF\xEE\xD8\x96\x0A\x31\x91\xDE\x78\x60\x03\x47\xDB\x8A\xCD\x0A\x37\x95\x41\x2F\x27\x5F\x04\x9B\x86\x26\x04\x88",
+        msg: b"non-canonical R",
+        sig: b"\xEE\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF\x7F\xEC\xAB\xE3\x43\xC9\xBD\x5B\xE3\xE3\xAB\xBE\x9F\x06\xBF\xBE\xC4\xCB\xAB\x33\xD5\xEA\x31\x93\xD2\xD5\x88\x40\x8A\x70\x6E\x3A\x07",
+        rfc8032: false,
+        strict: false,
+        zip215: true,
+    },
+    TestVector {
+        description: "small-order R (identity)",
+        pk: b"\x88\xAA\x55\xE6\xAF\xEE\xD8\x96\x0A\x31\x91\xDE\x78\x60\x03\x47\xDB\x8A\xCD\x0A\x37\x95\x41\x2F\x27\x5F\x04\x9B\x86\x26\x04\x88",
+        msg: b"small-order R",
+        sig: b"\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x83\x3A\xE3\xCA\xF2\x7B\x21\x30\xD7\x82\xA1\x7A\x5A\x9A\x50\x22\x9D\x65\xCA\x2C\x60\xAF\x47\xC5\xA6\xE6\x8A\xC7\xBE\x4E\xD1\x01",
+        rfc8032: true,
+        strict: false,
+        zip215: true,
+    },
+];
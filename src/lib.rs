@@ -9,20 +9,28 @@
 //! - [ecdsa]: Elliptic Curve Digital Signature Algorithm ([FIPS 186-4])
 //! - [ed25519]: Edwards Digital Signature Algorithm (EdDSA) instantiated using
 //!   the twisted Edwards form of Curve25519 ([RFC 8032]).
+//! - [schnorr]: Schnorr signatures over secp256k1 with x-only public keys
+//!   ([BIP340]).
+//!
+//! Master secrets of key derivation can be backed up as [bip39] mnemonic
+//! phrases, and Ed25519 seeds and ECDSA secret keys can be split into
+//! [shamir] secret shares. Ed25519 keys can be converted to [x25519] keys
+//! for Diffie-Hellman key exchange ([RFC 7748]), and ECDSA keys can be used
+//! for ECDH key agreement.
 //!
 //! ## Providers
 //!
 //! There are several backend providers available, which are each available
 //! in their own crates:
 //!
-//! - [signatory-dalek]: Ed25519 signing/verification using the pure-Rust
-//!   [ed25519-dalek] crate.
-//! - [signatory-ring]: ECDSA and Ed25519 signing/verification provider
-//!   for the [*ring*] cryptography library.
-//! - [signatory-secp256k1]: ECDSA signing/verification for the secp256k1
-//!   elliptic curve (commonly used by Bitcoin and other cryptocurrrencies)
-//!   which wraps the [libsecp256k1] library from Bitcoin Core.
-//! - [signatory-sodiumoxide]: Ed25519 signing/verification with the
+//! - [signatory-dalek]: Ed25519 signing/verification and X25519 using the
+//!   pure-Rust [ed25519-dalek] crate.
+//! - [signatory-ring]: ECDSA and Ed25519 signing/verification and ECDH
+//!   provider for the [*ring*] cryptography library.
+//! - [signatory-secp256k1]: ECDSA signing/verification and ECDH for the
+//!   secp256k1 elliptic curve (commonly used by Bitcoin and other
+//!   cryptocurrrencies) which wraps the [libsecp256k1] library from Bitcoin Core.
+//! - [signatory-sodiumoxide]: Ed25519 signing/verification and X25519 with the
 //!   [sodiumoxide] crate, a Rust wrapper for libsodium (NOTE: requires
 //!   libsodium to be installed on the system)
 //! - [yubihsm-rs]: ECDSA and Ed25519 signing provider support for
@@ -42,8 +50,14 @@
 //!
 //! [FIPS 186-4]: https://csrc.nist.gov/publications/detail/fips/186/4/final
 //! [RFC 8032]: https://tools.ietf.org/html/rfc8032
+//! [RFC 7748]: https://tools.ietf.org/html/rfc7748
+//! [BIP340]: https://github.com/bitcoin/bips/blob/master/bip-0340.mediawiki
+//! [bip39]: https://docs.rs/signatory/latest/signatory/bip39/index.html
 //! [ecdsa]: https://docs.rs/signatory/latest/signatory/ecdsa/index.html
 //! [ed25519]: https://docs.rs/signatory/latest/signatory/ed25519/index.html
+//! [schnorr]: https://docs.rs/signatory/latest/signatory/schnorr/index.html
+//! [shamir]: https://docs.rs/signatory/latest/signatory/shamir/index.html
+//! [x25519]: https://docs.rs/signatory/latest/signatory/x25519/index.html
 //! [signatory-dalek]: https://docs.rs/crate/signatory-dalek/
 //! [ed25519-dalek]: https://docs.rs/crate/ed25519-dalek/
 //! [signatory-ring]: https://docs.rs/crate/signatory-ring/
@@ -75,6 +89,8 @@ extern crate alloc;
 #[macro_use]
 extern crate std;
 
+#[cfg(feature = "bip39")]
+pub mod bip39;
 #[cfg(feature = "ecdsa")]
 pub mod ecdsa;
 #[cfg(feature = "ed25519")]
@@ -83,8 +99,15 @@ pub mod ed25519;
 #[cfg(feature = "encoding")]
 pub mod encoding;
 pub mod public_key;
+#[cfg(feature = "schnorr")]
+pub mod schnorr;
+#[cfg(feature = "shamir")]
+pub mod shamir;
 #[cfg(feature = "test-vectors")]
 pub mod test_vector;
+#[cfg(feature = "x25519")]
+#[macro_use]
+pub mod x25519;
 #[cfg(feature = "generic-array")]
 pub use generic_array;
 #[cfg(feature = "sha2")]
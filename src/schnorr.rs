@@ -0,0 +1,170 @@
+//! Schnorr signatures over secp256k1 as specified in BIP340, used by
+//! Bitcoin Taproot.
+//!
+//! Described in BIP340: <https://github.com/bitcoin/bips/blob/master/bip-0340.mediawiki>
+//!
+//! BIP340 signatures are verified with x-only public keys: the X coordinate
+//! of the point whose Y coordinate is even. Unlike ECDSA, messages are
+//! signed as they are (usually already 32-byte hashes), not digests of them.
+
+use core::fmt::{self, Debug};
+use signature::Error;
+
+/// Size of a BIP340 signature: the X coordinate of `R` and the scalar `s`
+pub const SIGNATURE_SIZE: usize = 64;
+
+/// Size of an x-only public key
+pub const PUBLIC_KEY_SIZE: usize = 32;
+
+/// BIP340 Schnorr signature
+#[derive(Copy, Clone)]
+pub struct Signature([u8; SIGNATURE_SIZE]);
+
+impl Signature {
+    /// Create a signature from a 64-byte array
+    pub fn new(bytes: [u8; SIGNATURE_SIZE]) -> Self {
+        Signature(bytes)
+    }
+
+    /// The X coordinate of the nonce point `R`
+    pub fn r(&self) -> &[u8] {
+        &self.0[..32]
+    }
+
+    /// The scalar `s`
+    pub fn s(&self) -> &[u8] {
+        &self.0[32..]
+    }
+
+    /// Obtain the signature as a byte array reference
+    pub fn as_bytes(&self) -> &[u8; SIGNATURE_SIZE] {
+        &self.0
+    }
+}
+
+impl signature::Signature for Signature {
+    fn from_bytes<B: AsRef<[u8]>>(bytes: B) -> Result<Self, Error> {
+        let bytes = bytes.as_ref();
+
+        if bytes.len() != SIGNATURE_SIZE {
+            return Err(Error::new());
+        }
+
+        let mut signature = [0u8; SIGNATURE_SIZE];
+        signature.copy_from_slice(bytes);
+        Ok(Signature(signature))
+    }
+}
+
+impl AsRef<[u8]> for Signature {
+    fn as_ref(&self) -> &[u8] {
+        &self.0[..]
+    }
+}
+
+impl Debug for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "schnorr::Signature(")?;
+        for byte in self.0.iter() {
+            write!(f, "{:02x}", byte)?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl PartialEq for Signature {
+    fn eq(&self, other: &Self) -> bool {
+        self.0[..] == other.0[..]
+    }
+}
+
+impl Eq for Signature {}
+
+/// x-only public key: the X coordinate of a secp256k1 point, standing for
+/// the point with an even Y coordinate
+#[derive(Copy, Clone, Eq, Hash, PartialEq, PartialOrd, Ord)]
+pub struct XOnlyPublicKey(pub [u8; PUBLIC_KEY_SIZE]);
+
+impl XOnlyPublicKey {
+    /// Create an x-only public key from a 32-byte array
+    pub fn new(bytes: [u8; PUBLIC_KEY_SIZE]) -> Self {
+        XOnlyPublicKey(bytes)
+    }
+
+    /// Create an x-only public key from its 32-byte serialization. Whether
+    /// it is a point on the curve is checked by verifiers.
+    pub fn from_bytes<B>(bytes: B) -> Option<Self>
+    where
+        B: AsRef<[u8]>,
+    {
+        if bytes.as_ref().len() == PUBLIC_KEY_SIZE {
+            let mut public_key = [0u8; PUBLIC_KEY_SIZE];
+            public_key.copy_from_slice(bytes.as_ref());
+            Some(XOnlyPublicKey(public_key))
+        } else {
+            None
+        }
+    }
+
+    /// Obtain public key as a byte array reference
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8; PUBLIC_KEY_SIZE] {
+        &self.0
+    }
+
+    /// Convert public key into owned byte array
+    #[inline]
+    pub fn into_bytes(self) -> [u8; PUBLIC_KEY_SIZE] {
+        self.0
+    }
+}
+
+/// The x-only public key of a secp256k1 ECDSA public key (compressed or
+/// uncompressed), i.e. of the same secret key
+#[cfg(feature = "ecdsa")]
+impl<'a> From<&'a crate::ecdsa::PublicKey<crate::ecdsa::curve::Secp256k1>> for XOnlyPublicKey {
+    fn from(public_key: &'a crate::ecdsa::PublicKey<crate::ecdsa::curve::Secp256k1>) -> Self {
+        let mut bytes = [0u8; PUBLIC_KEY_SIZE];
+        bytes.copy_from_slice(&public_key.as_bytes()[1..1 + PUBLIC_KEY_SIZE]);
+        XOnlyPublicKey(bytes)
+    }
+}
+
+impl AsRef<[u8]> for XOnlyPublicKey {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        self.0.as_ref()
+    }
+}
+
+impl Debug for XOnlyPublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "schnorr::XOnlyPublicKey({:?})", self.as_ref())
+    }
+}
+
+impl crate::public_key::PublicKey for XOnlyPublicKey {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use signature::Signature as _;
+
+    #[test]
+    fn signature_from_bytes() {
+        let mut bytes = [0u8; SIGNATURE_SIZE];
+        bytes[0] = 1;
+        bytes[32] = 2;
+        let signature = Signature::from_bytes(&bytes[..]).unwrap();
+        assert_eq!(signature.r()[0], 1);
+        assert_eq!(signature.s()[0], 2);
+        assert_eq!(signature.as_ref(), &bytes[..]);
+        assert!(Signature::from_bytes(&bytes[1..]).is_err());
+    }
+
+    #[test]
+    fn x_only_public_key_from_bytes() {
+        assert!(XOnlyPublicKey::from_bytes([7u8; 32]).is_some());
+        assert!(XOnlyPublicKey::from_bytes([7u8; 33]).is_none());
+    }
+}
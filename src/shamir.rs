@@ -0,0 +1,387 @@
+//! Shamir secret sharing of Ed25519 seeds and ECDSA secret keys
+//!
+//! A secret is split into `n` shares, any `k` (the threshold) of which
+//! recombine into the secret, while fewer reveal nothing about it. Each byte
+//! of the secret is the constant term of a random polynomial of degree
+//! `k - 1` over GF(256), and each share holds the values of these
+//! polynomials at its (non-zero) index.
+//!
+//! Shares are zeroized on drop. Their binary serialization carries:
+//!
+//! - a random 2-byte identifier common to the shares of a secret
+//! - the threshold
+//! - the index of the share
+//! - the share value (as long as the secret)
+//! - a 4-byte checksum: the first bytes of the SHA-256 of all the above
+//!
+//! Shares can be encoded as hex or Base64 with the `encoding` feature.
+
+#[cfg(feature = "encoding")]
+use crate::encoding::Decode;
+#[cfg(feature = "encoding")]
+use crate::encoding::Encode;
+use alloc::vec::Vec;
+use getrandom::getrandom;
+use sha2::{Digest, Sha256};
+use signature::Error;
+#[cfg(feature = "encoding")]
+use subtle_encoding::Encoding;
+use zeroize::Zeroize;
+
+/// Size of the checksum of serialized shares
+const CHECKSUM_SIZE: usize = 4;
+
+/// Size of the identifier, threshold and index of serialized shares
+const HEADER_SIZE: usize = 4;
+
+/// Maximum size of shared secrets
+const MAX_SECRET_SIZE: usize = 64;
+
+/// Share of a secret
+#[derive(Clone)]
+pub struct Share {
+    id: u16,
+    threshold: u8,
+    index: u8,
+    value: Vec<u8>,
+}
+
+impl Share {
+    /// Decode a share from its binary serialization, verifying its checksum
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() <= HEADER_SIZE + CHECKSUM_SIZE
+            || bytes.len() > HEADER_SIZE + MAX_SECRET_SIZE + CHECKSUM_SIZE
+        {
+            return Err(Error::new());
+        }
+        let (body, checksum) = bytes.split_at(bytes.len() - CHECKSUM_SIZE);
+        if Sha256::digest(body)[..CHECKSUM_SIZE] != *checksum {
+            return Err(Error::new());
+        }
+
+        let share = Self {
+            id: u16::from_be_bytes([body[0], body[1]]),
+            threshold: body[2],
+            index: body[3],
+            value: body[HEADER_SIZE..].to_vec(),
+        };
+        if share.threshold == 0 || share.index == 0 {
+            return Err(Error::new());
+        }
+        Ok(share)
+    }
+
+    /// Serialize this share, with a checksum
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_SIZE + self.value.len() + CHECKSUM_SIZE);
+        bytes.extend_from_slice(&self.id.to_be_bytes());
+        bytes.push(self.threshold);
+        bytes.push(self.index);
+        bytes.extend_from_slice(&self.value);
+        let checksum = Sha256::digest(&bytes);
+        bytes.extend_from_slice(&checksum[..CHECKSUM_SIZE]);
+        bytes
+    }
+
+    /// Identifier common to the shares of a secret
+    pub fn id(&self) -> u16 {
+        self.id
+    }
+
+    /// Number of shares needed to recombine the secret
+    pub fn threshold(&self) -> u8 {
+        self.threshold
+    }
+
+    /// Index of this share (from 1)
+    pub fn index(&self) -> u8 {
+        self.index
+    }
+}
+
+#[cfg(feature = "encoding")]
+impl Decode for Share {
+    /// Decode a share from a byte slice with the given encoding (e.g. hex,
+    /// Base64), verifying its checksum
+    fn decode<E: Encoding>(
+        encoded_share: &[u8],
+        encoding: &E,
+    ) -> Result<Self, crate::encoding::Error> {
+        let mut decoded_share = [0u8; HEADER_SIZE + MAX_SECRET_SIZE + CHECKSUM_SIZE];
+        let decoded_len = encoding.decode_to_slice(encoded_share, &mut decoded_share)?;
+        let share = Self::from_bytes(&decoded_share[..decoded_len]);
+        decoded_share.zeroize();
+        share.map_err(|_| crate::encoding::error::ErrorKind::Decode.into())
+    }
+}
+
+#[cfg(feature = "encoding")]
+impl Encode for Share {
+    /// Encode a share with the given encoding (e.g. hex, Base64)
+    fn encode<E: Encoding>(&self, encoding: &E) -> Vec<u8> {
+        let mut bytes = self.to_bytes();
+        let encoded = encoding.encode(&bytes);
+        bytes.as_mut_slice().zeroize();
+        encoded
+    }
+}
+
+impl Drop for Share {
+    fn drop(&mut self) {
+        self.value.as_mut_slice().zeroize();
+    }
+}
+
+/// Split an Ed25519 seed into `share_count` shares, `threshold` of which
+/// recombine it
+#[cfg(feature = "ed25519")]
+pub fn split_seed(
+    seed: &crate::ed25519::Seed,
+    threshold: u8,
+    share_count: u8,
+) -> Result<Vec<Share>, Error> {
+    split(seed.as_secret_slice(), threshold, share_count)
+}
+
+/// Recombine an Ed25519 seed from at least as many shares as its threshold
+#[cfg(feature = "ed25519")]
+pub fn combine_seed(shares: &[Share]) -> Result<crate::ed25519::Seed, Error> {
+    let mut bytes = combine(shares)?;
+    let seed = crate::ed25519::Seed::from_bytes(&bytes).ok_or_else(Error::new);
+    bytes.as_mut_slice().zeroize();
+    seed
+}
+
+/// Split an ECDSA secret key into `share_count` shares, `threshold` of which
+/// recombine it
+#[cfg(feature = "ecdsa")]
+pub fn split_secret_key<C: ::ecdsa::Curve>(
+    secret_key: &crate::ecdsa::SecretKey<C>,
+    threshold: u8,
+    share_count: u8,
+) -> Result<Vec<Share>, Error> {
+    split(secret_key.as_secret_slice(), threshold, share_count)
+}
+
+/// Recombine an ECDSA secret key from at least as many shares as its
+/// threshold
+#[cfg(feature = "ecdsa")]
+pub fn combine_secret_key<C: ::ecdsa::Curve>(
+    shares: &[Share],
+) -> Result<crate::ecdsa::SecretKey<C>, Error> {
+    let mut bytes = combine(shares)?;
+    let secret_key = crate::ecdsa::SecretKey::from_bytes(&bytes).ok_or_else(Error::new);
+    bytes.as_mut_slice().zeroize();
+    secret_key
+}
+
+/// Split a secret into shares with random polynomials
+fn split(secret: &[u8], threshold: u8, share_count: u8) -> Result<Vec<Share>, Error> {
+    if secret.is_empty()
+        || secret.len() > MAX_SECRET_SIZE
+        || threshold == 0
+        || threshold > share_count
+    {
+        return Err(Error::new());
+    }
+
+    let mut id = [0u8; 2];
+    getrandom(&mut id).expect("RNG failure!");
+
+    // coefficients of degree 1 to `threshold - 1` of the polynomial of each
+    // byte of the secret
+    let mut coefficients = alloc::vec![0u8; secret.len() * (usize::from(threshold) - 1)];
+    getrandom(&mut coefficients).expect("RNG failure!");
+
+    let shares = (1..=share_count)
+        .map(|index| Share {
+            id: u16::from_be_bytes(id),
+            threshold,
+            index,
+            value: evaluate(secret, &coefficients, index),
+        })
+        .collect();
+    coefficients.as_mut_slice().zeroize();
+    Ok(shares)
+}
+
+/// Values at `x` of the polynomials whose constant terms are the bytes of
+/// the secret
+fn evaluate(secret: &[u8], coefficients: &[u8], x: u8) -> Vec<u8> {
+    let degree = coefficients.len() / secret.len();
+    secret
+        .iter()
+        .enumerate()
+        .map(|(i, byte)| {
+            // Horner's method, from the coefficient of highest degree down
+            // to the constant term
+            let value = coefficients[i * degree..(i + 1) * degree]
+                .iter()
+                .rev()
+                .fold(0, |value, coefficient| gf256_mul(value, x) ^ coefficient);
+            gf256_mul(value, x) ^ byte
+        })
+        .collect()
+}
+
+/// Recombine a secret with Lagrange interpolation at 0
+fn combine(shares: &[Share]) -> Result<Vec<u8>, Error> {
+    let first = shares.first().ok_or_else(Error::new)?;
+    let threshold = usize::from(first.threshold);
+    if shares.len() < threshold {
+        return Err(Error::new());
+    }
+    let shares = &shares[..threshold];
+
+    for (i, share) in shares.iter().enumerate() {
+        if share.id != first.id
+            || share.threshold != first.threshold
+            || share.value.len() != first.value.len()
+            || shares[..i].iter().any(|other| other.index == share.index)
+        {
+            return Err(Error::new());
+        }
+    }
+
+    let mut secret = alloc::vec![0u8; first.value.len()];
+    for share in shares {
+        // Lagrange basis polynomial of the share at 0: the product of
+        // `x_j / (x_j - x_i)` for the other shares (subtraction is XOR)
+        let basis = shares
+            .iter()
+            .filter(|other| other.index != share.index)
+            .fold(1, |basis, other| {
+                gf256_mul(
+                    basis,
+                    gf256_mul(other.index, gf256_inv(other.index ^ share.index)),
+                )
+            });
+        for (byte, value) in secret.iter_mut().zip(&share.value) {
+            *byte ^= gf256_mul(basis, *value);
+        }
+    }
+    Ok(secret)
+}
+
+/// Multiplication in GF(256) modulo `x^8 + x^4 + x^3 + x + 1` (as in AES),
+/// without branching on its operands
+fn gf256_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0u8;
+    for _ in 0..8 {
+        product ^= a & (b & 1).wrapping_neg();
+        a = (a << 1) ^ (0x1b & (a >> 7).wrapping_neg());
+        b >>= 1;
+    }
+    product
+}
+
+/// Multiplicative inverse in GF(256): `a^254` (0 for 0)
+fn gf256_inv(a: u8) -> u8 {
+    // a^254 = a^(2 + 4 + 8 + 16 + 32 + 64 + 128)
+    let mut square = a;
+    let mut inverse = 1;
+    for _ in 1..8 {
+        square = gf256_mul(square, square);
+        inverse = gf256_mul(inverse, square);
+    }
+    inverse
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gf256_arithmetic() {
+        // FIPS 197, section 4.2 and 4.2.1
+        assert_eq!(gf256_mul(0x57, 0x83), 0xc1);
+        assert_eq!(gf256_mul(0x57, 0x13), 0xfe);
+        for a in 1..=255 {
+            assert_eq!(gf256_mul(a, gf256_inv(a)), 1);
+        }
+        assert_eq!(gf256_inv(0), 0);
+    }
+
+    #[test]
+    fn split_and_combine() {
+        let secret = [0x42u8; 32];
+        let shares = split(&secret, 3, 5).unwrap();
+        assert_eq!(shares.len(), 5);
+
+        for i in 0..5 {
+            for j in 0..5 {
+                for k in 0..5 {
+                    if i == j || j == k || i == k {
+                        continue;
+                    }
+                    let subset = [shares[i].clone(), shares[j].clone(), shares[k].clone()];
+                    assert_eq!(combine(&subset).unwrap(), &secret[..]);
+                }
+            }
+        }
+
+        // too few shares
+        assert!(combine(&shares[..2]).is_err());
+        // the same share twice
+        assert!(combine(&[shares[0].clone(), shares[1].clone(), shares[0].clone()]).is_err());
+        // shares of another secret
+        let other_shares = split(&secret, 3, 5).unwrap();
+        if other_shares[0].id != shares[0].id {
+            assert!(combine(&[
+                shares[0].clone(),
+                shares[1].clone(),
+                other_shares[2].clone()
+            ])
+            .is_err());
+        }
+    }
+
+    #[test]
+    fn invalid_parameters() {
+        assert!(split(&[1; 32], 0, 5).is_err());
+        assert!(split(&[1; 32], 6, 5).is_err());
+        assert!(split(&[], 2, 3).is_err());
+        assert!(split(&[1; 65], 2, 3).is_err());
+        assert_eq!(split(&[1; 32], 1, 1).unwrap()[0].value, &[1; 32][..]);
+    }
+
+    #[test]
+    fn share_serialization() {
+        let shares = split(&[7; 32], 2, 3).unwrap();
+        let bytes = shares[1].to_bytes();
+        assert_eq!(bytes.len(), HEADER_SIZE + 32 + CHECKSUM_SIZE);
+
+        let share = Share::from_bytes(&bytes).unwrap();
+        assert_eq!(share.id(), shares[1].id());
+        assert_eq!(share.threshold(), 2);
+        assert_eq!(share.index(), 2);
+        assert_eq!(share.value, shares[1].value);
+
+        for i in 0..bytes.len() {
+            let mut corrupted = bytes.clone();
+            corrupted[i] ^= 1;
+            assert!(Share::from_bytes(&corrupted).is_err());
+        }
+        assert!(Share::from_bytes(&bytes[1..]).is_err());
+    }
+
+    #[cfg(feature = "ed25519")]
+    #[test]
+    fn seed_shares() {
+        let seed = crate::ed25519::Seed::new([0x5a; 32]);
+        let shares = split_seed(&seed, 2, 3).unwrap();
+        let combined = combine_seed(&shares[1..]).unwrap();
+        assert_eq!(combined.as_secret_slice(), seed.as_secret_slice());
+    }
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn encoded_shares() {
+        use crate::encoding::Hex;
+
+        let shares = split(&[9; 32], 2, 2).unwrap();
+        let encoded = shares[0].encode(&Hex::default());
+        let decoded = Share::decode(&encoded, &Hex::default()).unwrap();
+        assert_eq!(decoded.to_bytes(), shares[0].to_bytes());
+    }
+}
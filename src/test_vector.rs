@@ -23,6 +23,9 @@ pub struct TestVector {
     /// Message to be signed
     pub msg: &'static [u8],
 
+    /// Context string (i.e. Ed25519ctx and Ed25519ph's)
+    pub context: Option<&'static [u8]>,
+
     /// Expected signature
     pub sig: &'static [u8],
 
@@ -44,4 +47,10 @@ pub enum TestVectorAlgorithm {
 
     /// "edwards25519" elliptic curve
     Ed25519,
+
+    /// Ed25519ctx: Ed25519 with a context string
+    Ed25519ctx,
+
+    /// Ed25519ph: Ed25519 of a SHA-512 digest, with a context string
+    Ed25519ph,
 }
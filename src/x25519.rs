@@ -0,0 +1,85 @@
+//! X25519: Elliptic Curve Diffie-Hellman on the Montgomery form of Curve25519
+//!
+//! Described in RFC 7748: <https://tools.ietf.org/html/rfc7748>
+//!
+//! Ed25519 and X25519 keys live on (birationally equivalent forms of) the
+//! same curve, so Ed25519 identity keys can be converted to X25519 keys to
+//! set up encrypted channels: seeds with `SecretKey::from_ed25519_seed`, and
+//! public keys with providers' `Ed25519PublicKeyConverter`.
+//!
+//! # Example (with signatory-dalek)
+//!
+//! ```nobuild
+//! use signatory::{ed25519, x25519::{DiffieHellman, Ed25519PublicKeyConverter}};
+//! use signatory_dalek::X25519Secret;
+//!
+//! // Convert our Ed25519 identity seed to an X25519 secret
+//! let seed = ed25519::Seed::generate();
+//! let secret = X25519Secret::from(&seed);
+//!
+//! // Convert their Ed25519 identity public key to an X25519 public key
+//! let their_identity = ed25519::PublicKey::from_bytes(&their_identity_bytes).unwrap();
+//! let their_public_key = X25519Secret::convert_public_key(&their_identity).unwrap();
+//!
+//! let shared_secret = secret.diffie_hellman(&their_public_key).unwrap();
+//! ```
+
+mod public_key;
+mod secret_key;
+
+#[cfg(feature = "test-vectors")]
+#[macro_use]
+mod test_macros;
+
+pub use self::{
+    public_key::{PublicKey, PUBLIC_KEY_SIZE},
+    secret_key::{SecretKey, SECRET_KEY_SIZE},
+};
+
+use crate::{ed25519, public_key::PublicKeyed};
+use signature::Error;
+use zeroize::Zeroize;
+
+/// Size of an X25519 shared secret in bytes (256-bits)
+pub const SHARED_SECRET_SIZE: usize = 32;
+
+/// Secret shared by the two parties of a Diffie-Hellman key exchange, which
+/// should be hashed (e.g. with a KDF) before being used as a key
+pub struct SharedSecret([u8; SHARED_SECRET_SIZE]);
+
+impl SharedSecret {
+    /// Create a shared secret from a 32-byte array
+    pub fn new(bytes: [u8; SHARED_SECRET_SIZE]) -> Self {
+        SharedSecret(bytes)
+    }
+
+    /// Expose the secret values of the `SharedSecret` as a byte slice
+    pub fn as_secret_slice(&self) -> &[u8] {
+        self.0.as_ref()
+    }
+}
+
+impl Drop for SharedSecret {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+/// X25519 Diffie-Hellman key exchange (to be implemented by Signatory
+/// providers, with their X25519 secret key type)
+pub trait DiffieHellman: PublicKeyed<PublicKey> {
+    /// Compute the secret shared with the owner of the given public key,
+    /// returning an error if it is all zeroes (i.e. the public key is of
+    /// small order)
+    fn diffie_hellman(&self, public_key: &PublicKey) -> Result<SharedSecret, Error>;
+}
+
+/// Conversion of Ed25519 public keys to X25519 public keys (to be implemented
+/// by Signatory providers, with their X25519 secret key type)
+pub trait Ed25519PublicKeyConverter {
+    /// Convert an Ed25519 public key (i.e. an Edwards point) to the X25519
+    /// public key of the same secret scalar (i.e. its Montgomery-u
+    /// coordinate), returning an error for points which aren't valid or lie
+    /// outside the prime-order subgroup
+    fn convert_public_key(public_key: &ed25519::PublicKey) -> Result<PublicKey, Error>;
+}
@@ -0,0 +1,58 @@
+//! X25519 public keys
+
+use core::fmt::{self, Debug};
+
+/// Size of an X25519 public key in bytes (256-bits)
+pub const PUBLIC_KEY_SIZE: usize = 32;
+
+/// X25519 public keys: Montgomery-u coordinates of points on Curve25519
+#[derive(Copy, Clone, Eq, Hash, PartialEq, PartialOrd, Ord)]
+pub struct PublicKey(pub [u8; PUBLIC_KEY_SIZE]);
+
+impl PublicKey {
+    /// Create an X25519 public key from a 32-byte array
+    pub fn new(bytes: [u8; PUBLIC_KEY_SIZE]) -> Self {
+        PublicKey(bytes)
+    }
+
+    /// Create an X25519 public key from its serialized (Montgomery-u) form
+    pub fn from_bytes<B>(bytes: B) -> Option<Self>
+    where
+        B: AsRef<[u8]>,
+    {
+        if bytes.as_ref().len() == PUBLIC_KEY_SIZE {
+            let mut public_key = [0u8; PUBLIC_KEY_SIZE];
+            public_key.copy_from_slice(bytes.as_ref());
+            Some(PublicKey(public_key))
+        } else {
+            None
+        }
+    }
+
+    /// Obtain public key as a byte array reference
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8; PUBLIC_KEY_SIZE] {
+        &self.0
+    }
+
+    /// Convert public key into owned byte array
+    #[inline]
+    pub fn into_bytes(self) -> [u8; PUBLIC_KEY_SIZE] {
+        self.0
+    }
+}
+
+impl AsRef<[u8]> for PublicKey {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        self.0.as_ref()
+    }
+}
+
+impl Debug for PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "x25519::PublicKey({:?})", self.as_ref())
+    }
+}
+
+impl crate::public_key::PublicKey for PublicKey {}
@@ -0,0 +1,104 @@
+//! X25519 secret keys
+
+#[cfg(feature = "getrandom")]
+use getrandom::getrandom;
+use sha2::{Digest, Sha512};
+use zeroize::Zeroize;
+
+use crate::ed25519;
+
+/// Size of an X25519 secret key in bytes (256-bits)
+pub const SECRET_KEY_SIZE: usize = 32;
+
+/// X25519 secret keys: scalars which are "clamped" when used (i.e. their
+/// low three bits are cleared, the high bit cleared and the second-highest
+/// bit set)
+#[derive(Clone)]
+pub struct SecretKey([u8; SECRET_KEY_SIZE]);
+
+impl SecretKey {
+    /// Create an X25519 secret key from a 32-byte array
+    pub fn new(bytes: [u8; SECRET_KEY_SIZE]) -> Self {
+        SecretKey(bytes)
+    }
+
+    /// Generate a new X25519 secret key using the operating system's
+    /// cryptographically secure random number generator
+    #[cfg(feature = "getrandom")]
+    pub fn generate() -> Self {
+        let mut bytes = [0u8; SECRET_KEY_SIZE];
+        getrandom(&mut bytes[..]).expect("RNG failure!");
+        Self::new(bytes)
+    }
+
+    /// Create an X25519 secret key from a byte slice, returning `None` if the
+    /// slice is not the correct size (32-bytes)
+    pub fn from_bytes<B>(bytes: B) -> Option<Self>
+    where
+        B: AsRef<[u8]>,
+    {
+        if bytes.as_ref().len() == SECRET_KEY_SIZE {
+            let mut secret_key = [0u8; SECRET_KEY_SIZE];
+            secret_key.copy_from_slice(bytes.as_ref());
+            Some(SecretKey::new(secret_key))
+        } else {
+            None
+        }
+    }
+
+    /// Convert an Ed25519 seed to the X25519 secret key with the same scalar
+    /// (i.e. the clamped lower half of the seed's SHA-512 hash), as libsodium's
+    /// `crypto_sign_ed25519_sk_to_curve25519` does. Its public key is the
+    /// conversion of the seed's Ed25519 public key.
+    pub fn from_ed25519_seed(seed: &ed25519::Seed) -> Self {
+        let mut hash = [0u8; 64];
+        hash.copy_from_slice(&Sha512::digest(seed.as_secret_slice()));
+
+        let mut secret_key = [0u8; SECRET_KEY_SIZE];
+        secret_key.copy_from_slice(&hash[..SECRET_KEY_SIZE]);
+        secret_key[0] &= 248;
+        secret_key[31] &= 127;
+        secret_key[31] |= 64;
+        hash.zeroize();
+
+        Self::new(secret_key)
+    }
+
+    /// Expose the secret values of the `SecretKey` as a byte slice
+    pub fn as_secret_slice(&self) -> &[u8] {
+        self.0.as_ref()
+    }
+}
+
+impl Drop for SecretKey {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl From<[u8; SECRET_KEY_SIZE]> for SecretKey {
+    fn from(bytes: [u8; SECRET_KEY_SIZE]) -> Self {
+        SecretKey::new(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_ed25519_seed() {
+        // RFC 8032 test 1
+        let seed = ed25519::Seed::from_bytes(
+            b"\x9D\x61\xB1\x9D\xEF\xFD\x5A\x60\xBA\x84\x4A\xF4\x92\xEC\x2C\xC4\
+              \x44\x49\xC5\x69\x7B\x32\x69\x19\x70\x3B\xAC\x03\x1C\xAE\x7F\x60",
+        )
+        .unwrap();
+
+        assert_eq!(
+            SecretKey::from_ed25519_seed(&seed).as_secret_slice(),
+            &b"\x30\x7C\x83\x86\x4F\x28\x33\xCB\x42\x7A\x2E\xF1\xC0\x0A\x01\x3C\
+               \xFD\xFF\x27\x68\xD9\x80\xC0\xA3\xA5\x20\xF0\x06\x90\x4D\xE9\x4F"[..]
+        );
+    }
+}
@@ -0,0 +1,152 @@
+//! Macro for generating shared tests for all X25519 implementations
+
+/// Generate tests for X25519 Diffie-Hellman and Ed25519 key conversion, which
+/// every provider must pass with the same results
+#[macro_export]
+macro_rules! x25519_tests {
+    ($secret:ident) => {
+        use $crate::public_key::PublicKeyed as _;
+        use $crate::x25519::{self, DiffieHellman as _, Ed25519PublicKeyConverter as _};
+
+        /// RFC 8032 test 1 seed
+        const ED25519_SEED: &[u8] = b"\x9D\x61\xB1\x9D\xEF\xFD\x5A\x60\xBA\x84\x4A\xF4\x92\xEC\x2C\xC4\
+                                      \x44\x49\xC5\x69\x7B\x32\x69\x19\x70\x3B\xAC\x03\x1C\xAE\x7F\x60";
+
+        /// RFC 8032 test 1 public key
+        const ED25519_PUBLIC_KEY: &[u8] = b"\xD7\x5A\x98\x01\x82\xB1\x0A\xB7\xD5\x4B\xFE\xD3\xC9\x64\x07\x3A\
+                                            \x0E\xE1\x72\xF3\xDA\xA6\x23\x25\xAF\x02\x1A\x68\xF7\x07\x51\x1A";
+
+        /// X25519 public key of the RFC 8032 test 1 seed
+        const X25519_PUBLIC_KEY: &[u8] = b"\xD8\x5E\x07\xEC\x22\xB0\xAD\x88\x15\x37\xC2\xF4\x4D\x66\x2D\x1A\
+                                           \x14\x3C\xF8\x30\xC5\x7A\xCA\x43\x05\xD8\x5C\x7A\x90\xF6\xB6\x2E";
+
+        #[test]
+        fn rfc7748_diffie_hellman() {
+            // RFC 7748 section 6.1
+            let alice = $secret::from(
+                &x25519::SecretKey::from_bytes(
+                    b"\x77\x07\x6D\x0A\x73\x18\xA5\x7D\x3C\x16\xC1\x72\x51\xB2\x66\x45\
+                      \xDF\x4C\x2F\x87\xEB\xC0\x99\x2A\xB1\x77\xFB\xA5\x1D\xB9\x2C\x2A",
+                )
+                .unwrap(),
+            );
+            let bob = $secret::from(
+                &x25519::SecretKey::from_bytes(
+                    b"\x5D\xAB\x08\x7E\x62\x4A\x8A\x4B\x79\xE1\x7F\x8B\x83\x80\x0E\xE6\
+                      \x6F\x3B\xB1\x29\x26\x18\xB6\xFD\x1C\x2F\x8B\x27\xFF\x88\xE0\xEB",
+                )
+                .unwrap(),
+            );
+
+            let alice_public_key = alice.public_key().unwrap();
+            let bob_public_key = bob.public_key().unwrap();
+            assert_eq!(
+                alice_public_key.as_ref(),
+                &b"\x85\x20\xF0\x09\x89\x30\xA7\x54\x74\x8B\x7D\xDC\xB4\x3E\xF7\x5A\
+                   \x0D\xBF\x3A\x0D\x26\x38\x1A\xF4\xEB\xA4\xA9\x8E\xAA\x9B\x4E\x6A"[..]
+            );
+            assert_eq!(
+                bob_public_key.as_ref(),
+                &b"\xDE\x9E\xDB\x7D\x7B\x7D\xC1\xB4\xD3\x5B\x61\xC2\xEC\xE4\x35\x37\
+                   \x3F\x83\x43\xC8\x5B\x78\x67\x4D\xAD\xFC\x7E\x14\x6F\x88\x2B\x4F"[..]
+            );
+
+            let shared_secret = &b"\x4A\x5D\x9D\x5B\xA4\xCE\x2D\xE1\x72\x8E\x3B\xF4\x80\x35\x0F\x25\
+                                   \xE0\x7E\x21\xC9\x47\xD1\x9E\x33\x76\xF0\x9B\x3C\x1E\x16\x17\x42"[..];
+            assert_eq!(
+                alice
+                    .diffie_hellman(&bob_public_key)
+                    .unwrap()
+                    .as_secret_slice(),
+                shared_secret
+            );
+            assert_eq!(
+                bob.diffie_hellman(&alice_public_key)
+                    .unwrap()
+                    .as_secret_slice(),
+                shared_secret
+            );
+        }
+
+        #[test]
+        fn rejects_small_order_public_keys() {
+            let secret = $secret::from(&x25519::SecretKey::new([0x42; 32]));
+            for u in &[0u8, 1] {
+                let mut public_key = [0u8; 32];
+                public_key[0] = *u;
+                assert!(secret
+                    .diffie_hellman(&x25519::PublicKey::new(public_key))
+                    .is_err());
+            }
+        }
+
+        #[test]
+        fn converts_ed25519_keys() {
+            let seed = $crate::ed25519::Seed::from_bytes(ED25519_SEED).unwrap();
+            let from_seed = $secret::from(&seed).public_key().unwrap();
+            let from_secret_key = $secret::from(&x25519::SecretKey::from_ed25519_seed(&seed))
+                .public_key()
+                .unwrap();
+            let converted = $secret::convert_public_key(
+                &$crate::ed25519::PublicKey::from_bytes(ED25519_PUBLIC_KEY).unwrap(),
+            )
+            .unwrap();
+
+            assert_eq!(from_seed.as_ref(), X25519_PUBLIC_KEY);
+            assert_eq!(from_secret_key.as_ref(), X25519_PUBLIC_KEY);
+            assert_eq!(converted.as_ref(), X25519_PUBLIC_KEY);
+        }
+
+        #[test]
+        fn diffie_hellman_with_ed25519_keys() {
+            // RFC 8032 test 2
+            let their_seed = $crate::ed25519::Seed::from_bytes(
+                b"\x4C\xCD\x08\x9B\x28\xFF\x96\xDA\x9D\xB6\xC3\x46\xEC\x11\x4E\x0F\
+                  \x5B\x8A\x31\x9F\x35\xAB\xA6\x24\xDA\x8C\xF6\xED\x4F\xB8\xA6\xFB",
+            )
+            .unwrap();
+            let their_identity = $crate::ed25519::PublicKey::from_bytes(
+                b"\x3D\x40\x17\xC3\xE8\x43\x89\x5A\x92\xB7\x0A\xA7\x4D\x1B\x7E\xBC\
+                  \x9C\x98\x2C\xCF\x2E\xC4\x96\x8C\xC0\xCD\x55\xF1\x2A\xF4\x66\x0C",
+            )
+            .unwrap();
+
+            let ours = $secret::from(&$crate::ed25519::Seed::from_bytes(ED25519_SEED).unwrap());
+            let theirs = $secret::from(&their_seed);
+            let their_public_key = $secret::convert_public_key(&their_identity).unwrap();
+            let our_public_key = $secret::convert_public_key(
+                &$crate::ed25519::PublicKey::from_bytes(ED25519_PUBLIC_KEY).unwrap(),
+            )
+            .unwrap();
+
+            let shared_secret = &b"\x51\x66\xF2\x4A\x69\x18\x36\x8E\x2A\xF8\x31\xA4\xAF\xFA\xDD\x97\
+                                   \xAF\x0A\xC3\x26\xBD\xF1\x43\x59\x6C\x04\x59\x67\xCC\x00\x23\x0E"[..];
+            assert_eq!(
+                ours.diffie_hellman(&their_public_key)
+                    .unwrap()
+                    .as_secret_slice(),
+                shared_secret
+            );
+            assert_eq!(
+                theirs
+                    .diffie_hellman(&our_public_key)
+                    .unwrap()
+                    .as_secret_slice(),
+                shared_secret
+            );
+        }
+
+        #[test]
+        fn rejects_converting_small_order_ed25519_keys() {
+            // the identity and a point of order 4
+            for first_byte in &[1u8, 0] {
+                let mut public_key = [0u8; 32];
+                public_key[0] = *first_byte;
+                assert!(
+                    $secret::convert_public_key(&$crate::ed25519::PublicKey::new(public_key))
+                        .is_err()
+                );
+            }
+        }
+    };
+}